@@ -0,0 +1,747 @@
+//! 管理接口（`/admin/*`）的响应结构体
+//!
+//! Master 提供，供 pa_top 等运维工具通过 [`crate::client::MasterClient`] 读取
+
+use crate::latency::LatencyStats;
+use crate::{SystemSample, WorkerProfile};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// 标识操作人的请求头，写入审计日志
+pub const ACTOR_HEADER: &str = "x-pa-actor";
+
+/// 本机的操作人标识：`用户名@主机名`
+pub fn local_actor() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    match std::env::var("HOSTNAME") {
+        Ok(host) if !host.is_empty() => format!("{}@{}", user, host),
+        _ => user,
+    }
+}
+
+/// 全局统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsResponse {
+    /// 全局游标位置（下一个待分配的起始ID）
+    pub cursor: i64,
+
+    /// 运行中的任务数（已分配给Worker）
+    pub running_tasks: usize,
+
+    /// 待分配的任务数（超时回收或手动重新排队）
+    pub pending_tasks: usize,
+
+    /// 已完成（归档）的任务数
+    pub completed_tasks: i64,
+
+    /// 已发现的有效ID数
+    pub valid_results: i64,
+
+    /// 已完成的范围中没有得到结论（重试耗尽或被上游拒绝）、尚未重新排队的ID数
+    #[serde(default)]
+    pub unchecked_ids: i64,
+
+    /// 任务分发是否已暂停（旧版本Master不提供）
+    #[serde(default)]
+    pub dispatch_paused: bool,
+
+    /// 最近几分钟提交中上报的上游延迟（按请求数加权平均，没有上报时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream_latency: Option<LatencyStats>,
+}
+
+/// 一分钟内的扫描量和上游延迟
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsPoint {
+    /// 分钟的起点（UTC，`YYYY-MM-DD HH:MM:00`）
+    pub minute: String,
+
+    /// 提交的任务数
+    pub tasks: i64,
+
+    /// 扫描的ID数
+    pub ids_scanned: i64,
+
+    /// 发现的有效ID数
+    pub valid_found: i64,
+
+    /// 上游延迟：分位数为各提交按请求数加权的平均值，max 为其中的最大值（没有上报时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyStats>,
+}
+
+/// 已完成任务的耗时分布（从分配到提交），用于调整批次大小、目标时长和超时设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskDurations {
+    /// 统计的时间窗口（小时，按完成时间）
+    pub hours: u32,
+
+    /// 统计的任务数（不含显式ID列表任务和没有记录耗时的旧任务）
+    pub tasks: u64,
+
+    /// 耗时中位数（毫秒，没有任务时为 0，下同）
+    pub p50_ms: u64,
+
+    /// 耗时95分位
+    pub p95_ms: u64,
+
+    /// 最长耗时
+    pub max_ms: u64,
+
+    /// 按任务大小分组（按2的幂分档，从小到大），没有任务的档不返回
+    pub by_batch_size: Vec<BatchSizeTiming>,
+}
+
+/// 一档任务大小的耗时
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSizeTiming {
+    /// 本档任务大小的下限（ID数，含）
+    pub min_size: i64,
+
+    /// 本档任务大小的上限（ID数，含）
+    pub max_size: i64,
+
+    /// 任务数
+    pub tasks: u64,
+
+    /// 耗时中位数（毫秒）
+    pub p50_ms: u64,
+
+    /// 耗时95分位（毫秒）
+    pub p95_ms: u64,
+
+    /// 本档的平均扫描速度（总ID数 / 总耗时，ID/s）
+    pub ids_per_sec: f64,
+}
+
+/// 有运行中任务的Worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerInfo {
+    /// Worker的唯一标识符
+    pub worker_id: String,
+
+    /// 持有的任务ID
+    pub task_ids: Vec<i32>,
+
+    /// 距最近一次心跳的秒数（None 表示租约已失效）
+    pub last_heartbeat_secs: Option<u64>,
+
+    /// 各任务最近一次心跳上报的速度之和（ID/s，未上报时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+
+    /// Worker上报的主机信息和标签（未上报时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<WorkerProfile>,
+}
+
+/// 集群概览：在线的Worker及其任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterOverview {
+    /// 判定在线的时间窗口（秒）：期间有过请求或任务心跳的Worker视为在线
+    pub active_secs: u64,
+
+    /// 在线的Worker，按ID排序
+    pub workers: Vec<ClusterWorker>,
+
+    /// 在线Worker最近一次心跳上报的速度之和（ID/s）
+    pub total_speed: f64,
+
+    /// 各版本的在线Worker数（没有上报版本的计入 `unknown`）
+    pub versions: BTreeMap<String, usize>,
+}
+
+/// 集群概览中的一个Worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterWorker {
+    /// Worker的唯一标识符
+    pub worker_id: String,
+
+    /// 距最近一次请求或任务心跳的秒数
+    pub last_seen_secs: u64,
+
+    /// 持有的任务
+    pub tasks: Vec<TaskInfo>,
+
+    /// 各任务最近一次心跳上报的速度之和（ID/s，未上报时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+
+    /// Worker上报的主机信息、标签和版本（未上报时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<WorkerProfile>,
+
+    /// 最近一次随心跳上报的系统状态（Worker没有启用 `--report-system` 时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<WorkerSystemStatus>,
+}
+
+/// 一条告警规则的当前状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertStatus {
+    /// 规则名称
+    pub name: String,
+
+    /// 指标名称（如 `hits_per_hour`、`timeout_rate`）
+    pub metric: String,
+
+    /// 比较方式（`>`、`>=`、`<`、`<=`）
+    pub op: String,
+
+    /// 阈值
+    pub threshold: f64,
+
+    /// 条件需要持续满足的秒数
+    pub for_secs: u64,
+
+    /// 最近一次计算的指标值（还没有计算过或指标一直无法计算时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+
+    /// `ok`、`pending`（条件满足但持续时间不够）或 `firing`
+    pub state: String,
+
+    /// 进入当前 `pending` 或 `firing` 状态的秒数（`ok` 时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_secs: Option<u64>,
+}
+
+/// Worker主机最近一次上报的系统状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerSystemStatus {
+    /// 采样值
+    #[serde(flatten)]
+    pub sample: SystemSample,
+
+    /// 距采样的秒数
+    pub age_secs: u64,
+
+    /// 与上一次采样相比，网卡错误和丢包每秒增加的数量（只有一次采样时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub net_errors_per_sec: Option<f64>,
+}
+
+/// 任务队列中的任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskInfo {
+    /// 任务ID
+    pub task_id: i32,
+
+    /// 起始ID（包含）
+    pub start_id: i64,
+
+    /// 结束ID（包含）
+    pub end_id: i64,
+
+    /// 持有该任务的Worker（空字符串表示待分配）
+    pub worker_id: String,
+
+    /// 距最近一次心跳的秒数（None 表示租约已失效）
+    pub heartbeat_age_secs: Option<u64>,
+
+    /// 最近一次心跳上报的速度（ID/s，未上报时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+}
+
+/// 最近发现的有效ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentResult {
+    /// 有效ID
+    pub id: i64,
+
+    /// 发现时间（UTC）
+    pub found_at: String,
+}
+
+/// 命中响应标记的无效ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkedResult {
+    /// ID
+    pub id: i64,
+
+    /// 标记名称（如 removed、region_restricted）
+    pub label: String,
+
+    /// 最后一次上报的Worker
+    pub worker_id: String,
+
+    /// 首次以该标记出现的时间（UTC）
+    pub first_seen: String,
+
+    /// 最后一次出现的时间（UTC）
+    pub last_seen: String,
+}
+
+/// 一种标记的ID数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkedCount {
+    /// 标记名称
+    pub label: String,
+
+    /// 当前带有该标记的ID数
+    pub count: i64,
+}
+
+/// 命中响应标记的ID统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkedStats {
+    /// 各标记的ID数
+    pub labels: Vec<MarkedCount>,
+
+    /// 最近出现的ID
+    pub recent: Vec<MarkedResult>,
+}
+
+/// 任务分发状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DispatchStatus {
+    /// 任务分发是否已暂停
+    pub dispatch_paused: bool,
+}
+
+/// 开启或结束维护模式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceRequest {
+    /// true 开启，false 结束
+    pub enabled: bool,
+
+    /// 维护原因，会出现在返回给Worker的错误信息中
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// 建议Worker等待多少秒后重试（默认60）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+/// 维护模式状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    /// 是否在维护中
+    pub enabled: bool,
+
+    /// 维护原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// 已维护的秒数（不在维护中时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since_secs: Option<u64>,
+
+    /// 建议Worker等待的秒数（不在维护中时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+/// 任务批次大小的计算参数
+///
+/// 批次大小 = Worker的速度（没有速度时取 `default_speed`）× `target_runtime_secs`，
+/// 再限制在 `[min_batch_size, max_batch_size]` 之间。Master还没有测得速度的Worker先领取
+/// `calibration_batch_size` 个ID的校准批次，提交后按测得的速度计算。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchSettings {
+    /// 期望每个任务运行的秒数
+    pub target_runtime_secs: i64,
+
+    /// Worker未上报速度时假定的速度（req/s）
+    pub default_speed: i64,
+
+    /// 批次大小下限
+    pub min_batch_size: i64,
+
+    /// 批次大小上限
+    pub max_batch_size: i64,
+
+    /// 校准批次的大小（不受下限限制，0 表示不校准，旧版本Master不提供）
+    #[serde(default)]
+    pub calibration_batch_size: i64,
+}
+
+/// 修改批次参数的请求体（未给出的字段保持不变）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateBatchSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_runtime_secs: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_speed: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_batch_size: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_batch_size: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calibration_batch_size: Option<i64>,
+}
+
+impl BatchSettings {
+    /// 应用修改，返回新的参数
+    pub fn apply(&self, update: &UpdateBatchSettings) -> Self {
+        Self {
+            target_runtime_secs: update
+                .target_runtime_secs
+                .unwrap_or(self.target_runtime_secs),
+            default_speed: update.default_speed.unwrap_or(self.default_speed),
+            min_batch_size: update.min_batch_size.unwrap_or(self.min_batch_size),
+            max_batch_size: update.max_batch_size.unwrap_or(self.max_batch_size),
+            calibration_batch_size: update
+                .calibration_batch_size
+                .unwrap_or(self.calibration_batch_size),
+        }
+    }
+
+    /// 检查参数是否合理
+    pub fn check(&self) -> Result<(), String> {
+        if self.target_runtime_secs <= 0 {
+            return Err("期望运行时长必须大于0".to_string());
+        }
+        if self.default_speed <= 0 {
+            return Err("默认速度必须大于0".to_string());
+        }
+        if self.min_batch_size <= 0 {
+            return Err("批次大小下限必须大于0".to_string());
+        }
+        if self.max_batch_size < self.min_batch_size {
+            return Err(format!(
+                "批次大小上限 {} 不能小于下限 {}",
+                self.max_batch_size, self.min_batch_size
+            ));
+        }
+        if self.calibration_batch_size < 0 {
+            return Err("校准批次大小不能为负数".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 封禁Worker的请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanRequest {
+    /// 要封禁的Worker
+    pub worker_id: String,
+
+    /// 封禁原因
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// 被封禁的Worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BannedWorker {
+    /// Worker的唯一标识符
+    pub worker_id: String,
+
+    /// 封禁原因
+    pub reason: String,
+
+    /// 封禁时间（UTC）
+    pub banned_at: String,
+}
+
+/// 重新排队的请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequeueRequest {
+    /// 起始ID（包含）
+    pub start_id: i64,
+
+    /// 结束ID（包含）
+    pub end_id: i64,
+
+    /// 每个任务的大小（默认使用Master的拆分粒度）
+    #[serde(default)]
+    pub chunk: Option<i64>,
+}
+
+/// 重新排队的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequeueResponse {
+    /// 新建的待分配任务ID
+    pub task_ids: Vec<i32>,
+}
+
+/// 重新排队没有得到结论的范围的请求体
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequeueUncheckedRequest {
+    /// 每个任务的大小（默认使用Master的拆分粒度）
+    #[serde(default)]
+    pub chunk: Option<i64>,
+}
+
+/// 重新排队没有得到结论的范围的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequeueUncheckedResponse {
+    /// 重新排队的已完成范围数
+    pub ranges: usize,
+
+    /// 新建的待分配任务ID
+    pub task_ids: Vec<i32>,
+
+    /// 重新等待分配的候选ID数
+    pub candidates: u64,
+}
+
+/// 导入候选ID的请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportCandidatesRequest {
+    /// 候选ID
+    pub ids: Vec<i64>,
+}
+
+/// 解析候选ID文件中的一行
+///
+/// 每行一个ID；也接受 `init export` 导出的 CSV（取第一列）和 JSONL（取 `id` 字段）。
+/// 空行、`#` 开头的注释和 CSV 表头返回 None。
+pub fn parse_candidate_line(line: &str) -> Result<Option<i64>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with("id") {
+        return Ok(None);
+    }
+    let id = if line.starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(line).map_err(|e| e.to_string())?;
+        value["id"].as_i64().ok_or("缺少 id 字段")?
+    } else {
+        let field = line.split(',').next().unwrap_or(line).trim();
+        field
+            .parse()
+            .map_err(|e| format!("无效的ID {}: {}", field, e))?
+    };
+    if id < 0 {
+        return Err(format!("ID不能为负数: {}", id));
+    }
+    Ok(Some(id))
+}
+
+/// 导入候选ID的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportCandidatesResponse {
+    /// 加入等待分配队列的ID数（含重新打开的已完成ID）
+    pub queued: u64,
+
+    /// 已在等待分配或正在扫描、被跳过的ID数
+    pub skipped: u64,
+}
+
+/// 候选ID统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidateStats {
+    /// 等待分配
+    pub pending: i64,
+
+    /// 已分配给显式ID列表任务、正在扫描
+    pub assigned: i64,
+
+    /// 已完成扫描
+    pub checked: i64,
+}
+
+/// 一条审计日志
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// 自增ID
+    pub id: i64,
+
+    /// 操作人
+    pub actor: String,
+
+    /// 操作类型（如 pause_dispatch、set_cursor）
+    pub action: String,
+
+    /// 操作参数（JSON）
+    pub details: String,
+
+    /// 操作时间（UTC）
+    pub created_at: String,
+}
+
+/// API Key的角色，决定可以调用哪些路由
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyRole {
+    /// 只能调用 `/task/*`
+    #[default]
+    Worker,
+    /// 可以查看管理接口、GraphQL 和状态页面，不能执行修改操作
+    ReadOnly,
+    /// 可以调用所有管理接口
+    Admin,
+}
+
+impl ApiKeyRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiKeyRole::Worker => "worker",
+            ApiKeyRole::ReadOnly => "read_only",
+            ApiKeyRole::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for ApiKeyRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "worker" => Ok(ApiKeyRole::Worker),
+            "read_only" | "readonly" => Ok(ApiKeyRole::ReadOnly),
+            "admin" => Ok(ApiKeyRole::Admin),
+            other => Err(format!(
+                "未知的角色: {}（可选 worker、read_only、admin）",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ApiKeyRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// API Key信息（不含密钥本身）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyInfo {
+    /// API Key ID
+    pub key_id: i64,
+
+    /// 名称（如贡献者的名字）
+    pub name: String,
+
+    /// 角色
+    #[serde(default)]
+    pub role: ApiKeyRole,
+
+    /// 同时持有的任务数上限（None 表示不限）
+    pub max_concurrent_tasks: Option<i64>,
+
+    /// 每天（UTC）分配的ID数上限（None 表示不限）
+    pub max_ids_per_day: Option<i64>,
+
+    /// 是否已被吊销
+    pub revoked: bool,
+
+    /// 当前持有的任务数
+    pub running_tasks: i64,
+
+    /// 今天（UTC）已分配的ID数
+    pub ids_today: i64,
+
+    /// 创建时间（UTC）
+    pub created_at: String,
+}
+
+/// 创建API Key的请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateApiKeyRequest {
+    /// 名称
+    pub name: String,
+
+    /// 角色（默认 worker）
+    #[serde(default)]
+    pub role: ApiKeyRole,
+
+    /// 同时持有的任务数上限
+    #[serde(default)]
+    pub max_concurrent_tasks: Option<i64>,
+
+    /// 每天分配的ID数上限
+    #[serde(default)]
+    pub max_ids_per_day: Option<i64>,
+}
+
+/// 创建API Key的结果，密钥只在此时返回一次
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateApiKeyResponse {
+    /// API Key ID
+    pub key_id: i64,
+
+    /// 密钥，交给Worker使用
+    pub key: String,
+}
+
+/// 复查队列中的一条记录（被判定为可疑的提交）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationEntry {
+    /// 记录ID
+    pub id: i64,
+
+    /// 被标记的原任务ID
+    pub task_id: i64,
+
+    /// 起始ID（包含）
+    pub start_id: i64,
+
+    /// 结束ID（包含）
+    pub end_id: i64,
+
+    /// 提交该结果的Worker
+    pub worker_id: String,
+
+    /// 判定为可疑的原因
+    pub reason: String,
+
+    /// 原提交的有效ID数
+    pub valid_count: i64,
+
+    /// 复查任务ID
+    pub verify_task_id: Option<i64>,
+
+    /// 复查得到的有效ID数（复查完成前为 None）
+    pub verified_count: Option<i64>,
+
+    /// 状态：pending 或 done
+    pub status: String,
+
+    /// 标记时间（UTC）
+    pub created_at: String,
+
+    /// 复查完成时间（UTC）
+    pub completed_at: Option<String>,
+}
+
+/// Master抽查某个Worker提交结果的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpotCheckStats {
+    /// Worker ID
+    pub worker_id: String,
+
+    /// 抽查的ID数
+    pub checked: i64,
+
+    /// 抽查结论与Worker一致的ID数
+    pub agreed: i64,
+
+    /// 一致率（0~1）
+    pub agreement_rate: f64,
+
+    /// 最近一次抽查时间（UTC）
+    pub last_checked_at: Option<String>,
+}
+
+/// 覆盖图中的一个桶
+///
+/// 三个比例之和为1；重叠的已完成范围（如复查）只计一次以内，因此 `scanned` 不会超过1。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageBucket {
+    /// 桶的起始ID（包含）
+    pub start_id: i64,
+
+    /// 桶的结束ID（包含）
+    pub end_id: i64,
+
+    /// 已完成（归档）的ID比例
+    pub scanned: f64,
+
+    /// 正在被Worker扫描的ID比例
+    pub running: f64,
+
+    /// 尚未扫描的ID比例（包括待分配的任务）
+    pub unscanned: f64,
+}
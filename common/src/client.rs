@@ -0,0 +1,662 @@
+//! Master HTTP 客户端
+//!
+//! 封装请求关联ID、链路上下文传播、失败重试和 [`ApiResponse`] 的解包，
+//! 供 Worker 和运维工具（pa_top 等）调用 Master 的接口。
+//!
+//! 重试只在安全时进行：GET、DELETE 和标记为幂等的调用在网络错误、429 和 5xx 时重试；
+//! 其余调用只在连接失败（请求没有到达Master）时重试。重试间隔按指数退避并加入随机抖动，
+//! Master 返回 `Retry-After` 时按其建议等待。
+
+use crate::admin::{
+    AlertStatus, ApiKeyInfo, AuditEntry, BanRequest, BannedWorker, BatchSettings, CandidateStats,
+    ClusterOverview, CoverageBucket, CreateApiKeyRequest, CreateApiKeyResponse, DispatchStatus,
+    ImportCandidatesRequest, ImportCandidatesResponse, MaintenanceRequest, MaintenanceStatus,
+    MarkedStats, RecentResult, RequeueRequest, RequeueResponse, RequeueUncheckedRequest,
+    RequeueUncheckedResponse, SpotCheckStats, StatsPoint, StatsResponse, TaskDurations, TaskInfo,
+    UpdateBatchSettings, VerificationEntry, WorkerInfo, ACTOR_HEADER,
+};
+use crate::{routes, telemetry, ApiError, ApiResponse, ErrorCode, REQUEST_ID_HEADER};
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::{Method, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+use tracing::warn;
+
+/// 调用 Master 接口的错误
+#[derive(Debug)]
+pub enum ClientError {
+    /// 网络错误或响应无法解析
+    Http {
+        source: reqwest::Error,
+        request_id: String,
+    },
+
+    /// Master 返回了失败的响应
+    Api {
+        status: u16,
+        message: String,
+        code: Option<ErrorCode>,
+        /// 响应的 `Retry-After` 头（429/503 时Master建议的等待时长）
+        retry_after: Option<Duration>,
+        request_id: String,
+    },
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Http { source, request_id } => {
+                write!(f, "请求Master失败: {} (request_id={})", source, request_id)
+            }
+            ClientError::Api {
+                status,
+                message,
+                request_id,
+                ..
+            } => write!(
+                f,
+                "Master返回错误 {}: {} (request_id={})",
+                status, message, request_id
+            ),
+        }
+    }
+}
+
+impl ClientError {
+    /// 重试是否可能成功：网络错误、429 和 5xx 可以重试，其余 4xx 重试也不会成功
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            ClientError::Http { .. } => true,
+            ClientError::Api { status, .. } => *status == 429 || *status >= 500,
+        }
+    }
+
+    /// Master是否正在维护（任务接口暂不可用，已持有的任务不会因此超时）
+    pub fn is_maintenance(&self) -> bool {
+        matches!(
+            self,
+            ClientError::Api {
+                code: Some(ErrorCode::Maintenance),
+                ..
+            }
+        )
+    }
+
+    /// Master建议的等待时长（`Retry-After` 头）
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ClientError::Http { .. } => None,
+            ClientError::Api { retry_after, .. } => *retry_after,
+        }
+    }
+}
+
+/// 没有HTTP响应可参考时（如解析保存下来的响应）的转换：状态码记为0，没有 `Retry-After`，
+/// 使返回 [`ClientError`] 的函数可以对 [`ApiResponse::into_result`] 使用 `?`
+impl From<ApiError> for ClientError {
+    fn from(error: ApiError) -> Self {
+        ClientError::Api {
+            status: 0,
+            message: error.message,
+            code: error.code,
+            retry_after: None,
+            request_id: error.request_id.unwrap_or_default(),
+        }
+    }
+}
+
+impl ClientError {
+    /// 由失败响应中的错误和响应的状态码、`Retry-After` 构造，响应中没有请求关联ID时使用发送时生成的ID
+    pub fn from_api(
+        error: ApiError,
+        status: u16,
+        retry_after: Option<Duration>,
+        request_id: String,
+    ) -> Self {
+        ClientError::Api {
+            status,
+            message: error.message,
+            code: error.code,
+            retry_after,
+            request_id: error.request_id.unwrap_or(request_id),
+        }
+    }
+
+    /// 调用是否可以安全地重试：幂等的调用按 [`is_retryable`](Self::is_retryable) 判断，
+    /// 非幂等的调用只有连接失败时可以重试（请求没有到达Master）
+    fn can_retry(&self, idempotent: bool) -> bool {
+        match self {
+            ClientError::Http { source, .. } if source.is_connect() => true,
+            _ => idempotent && self.is_retryable(),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Http { source, .. } => Some(source),
+            ClientError::Api { .. } => None,
+        }
+    }
+}
+
+/// 调用失败时的重试策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最多尝试次数（含第一次），1 表示不重试
+    pub max_attempts: u32,
+
+    /// 第一次重试前的等待时长，之后每次翻倍
+    pub base_delay: Duration,
+
+    /// 单次等待的上限，Master 建议的 `Retry-After` 超过该值时不再重试，直接返回错误
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// 第 `retry` 次重试前的等待时长：指数退避，在 [一半, 全部] 之间随机抖动，
+    /// 避免大量Worker在Master恢复的同一时刻一起重试
+    fn backoff(&self, retry: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        exponential.mul_f64(rand::random_range(0.5..=1.0))
+    }
+}
+
+/// 单次调用的选项，覆盖客户端的默认设置
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CallOptions {
+    idempotent: bool,
+    retry: Option<RetryPolicy>,
+}
+
+impl CallOptions {
+    /// 标记调用是幂等的（重复执行与执行一次效果相同），收到错误响应后也可以重试
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
+    /// 本次调用使用的重试策略，不设置时使用客户端的策略
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+}
+
+/// Master HTTP 客户端
+#[derive(Clone)]
+pub struct MasterClient {
+    base_url: String,
+    client: reqwest::Client,
+    actor: Option<String>,
+    credentials: Option<Credentials>,
+    retry: RetryPolicy,
+}
+
+/// 管理接口的凭据
+#[derive(Clone)]
+enum Credentials {
+    /// HTTP Basic 认证（用户名, 密码）
+    Basic(String, String),
+    /// read_only 或 admin 角色的API Key
+    Token(String),
+}
+
+impl MasterClient {
+    /// 创建客户端，`base_url` 形如 `http://localhost:3000`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_client(base_url, reqwest::Client::new())
+    }
+
+    /// 使用已有的 reqwest 客户端（共享连接池、自定义超时等）
+    pub fn with_client(base_url: impl Into<String>, client: reqwest::Client) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            client,
+            actor: None,
+            credentials: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// 设置默认的重试策略（默认最多尝试3次）
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// 设置操作人，随每个请求发送，Master 写入审计日志
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// 设置管理凭据（Master 的 `--admin-user` / `--admin-password`），以 HTTP Basic 认证随每个请求发送
+    pub fn with_basic_auth(mut self, user: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::Basic(user.into(), password.into()));
+        self
+    }
+
+    /// 设置管理令牌（read_only 或 admin 角色的API Key），以 Bearer 令牌随每个请求发送
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.credentials = Some(Credentials::Token(token.into()));
+        self
+    }
+
+    /// Master 地址
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// 全局统计
+    pub async fn stats(&self) -> Result<StatsResponse, ClientError> {
+        self.get(routes::admin::STATS).await
+    }
+
+    /// 最近 `minutes` 分钟按分钟汇总的扫描量和上游延迟
+    pub async fn stats_history(&self, minutes: u32) -> Result<Vec<StatsPoint>, ClientError> {
+        self.get(&format!(
+            "{}?minutes={}",
+            routes::admin::STATS_HISTORY,
+            minutes
+        ))
+        .await
+    }
+
+    /// 有运行中任务的Worker
+    pub async fn workers(&self) -> Result<Vec<WorkerInfo>, ClientError> {
+        self.get(routes::admin::WORKERS).await
+    }
+
+    /// 集群概览：最近 `active_secs` 秒内在线的Worker及其任务、速度、版本和标签
+    pub async fn cluster(&self, active_secs: u64) -> Result<ClusterOverview, ClientError> {
+        self.get(&format!(
+            "{}?active_secs={}",
+            routes::admin::CLUSTER,
+            active_secs
+        ))
+        .await
+    }
+
+    /// 各告警规则的当前状态
+    pub async fn alerts(&self) -> Result<Vec<AlertStatus>, ClientError> {
+        self.get(routes::admin::ALERTS).await
+    }
+
+    /// 任务队列中的任务
+    pub async fn tasks(&self) -> Result<Vec<TaskInfo>, ClientError> {
+        self.get(routes::admin::TASKS).await
+    }
+
+    /// 最近 `hours` 小时内完成的任务的耗时分布，按任务大小分档
+    pub async fn task_durations(&self, hours: u32) -> Result<TaskDurations, ClientError> {
+        self.get(&format!(
+            "{}?hours={}",
+            routes::admin::TASK_DURATIONS,
+            hours
+        ))
+        .await
+    }
+
+    /// 最近发现的有效ID
+    pub async fn recent_results(&self, limit: u32) -> Result<Vec<RecentResult>, ClientError> {
+        self.get(&format!(
+            "{}?limit={}",
+            routes::admin::RECENT_RESULTS,
+            limit
+        ))
+        .await
+    }
+
+    /// 暂停任务分发
+    pub async fn pause_dispatch(&self) -> Result<DispatchStatus, ClientError> {
+        self.post(routes::admin::PAUSE_DISPATCH, &()).await
+    }
+
+    /// 恢复任务分发
+    pub async fn resume_dispatch(&self) -> Result<DispatchStatus, ClientError> {
+        self.post(routes::admin::RESUME_DISPATCH, &()).await
+    }
+
+    /// 维护模式状态
+    pub async fn maintenance(&self) -> Result<MaintenanceStatus, ClientError> {
+        self.get(routes::admin::MAINTENANCE).await
+    }
+
+    /// 开启或结束维护模式
+    pub async fn set_maintenance(
+        &self,
+        request: &MaintenanceRequest,
+    ) -> Result<MaintenanceStatus, ClientError> {
+        self.post(routes::admin::MAINTENANCE, request).await
+    }
+
+    /// 任务批次大小的计算参数
+    pub async fn batch_settings(&self) -> Result<BatchSettings, ClientError> {
+        self.get(routes::admin::BATCH).await
+    }
+
+    /// 修改任务批次大小的计算参数（未给出的字段保持不变）
+    pub async fn update_batch_settings(
+        &self,
+        update: &UpdateBatchSettings,
+    ) -> Result<BatchSettings, ClientError> {
+        self.post(routes::admin::BATCH, update).await
+    }
+
+    /// 被封禁的Worker
+    pub async fn bans(&self) -> Result<Vec<BannedWorker>, ClientError> {
+        self.get(routes::admin::BANS).await
+    }
+
+    /// 封禁Worker（其持有的任务会被回收重新分配）
+    pub async fn ban(&self, worker_id: &str, reason: Option<&str>) -> Result<String, ClientError> {
+        let request = BanRequest {
+            worker_id: worker_id.to_string(),
+            reason: reason.map(str::to_string),
+        };
+        self.post(routes::admin::BANS, &request).await
+    }
+
+    /// 解除Worker的封禁
+    pub async fn unban(&self, worker_id: &str) -> Result<String, ClientError> {
+        self.delete(&routes::fill(
+            routes::admin::BAN,
+            &encode_path_segment(worker_id),
+        ))
+        .await
+    }
+
+    /// 将已分配过的范围重新排队
+    pub async fn requeue(
+        &self,
+        start_id: i64,
+        end_id: i64,
+        chunk: Option<i64>,
+    ) -> Result<RequeueResponse, ClientError> {
+        let request = RequeueRequest {
+            start_id,
+            end_id,
+            chunk,
+        };
+        self.post(routes::admin::REQUEUE, &request).await
+    }
+
+    /// 将上报了没有结论的ID的已完成范围重新排队
+    pub async fn requeue_unchecked(
+        &self,
+        chunk: Option<i64>,
+    ) -> Result<RequeueUncheckedResponse, ClientError> {
+        let request = RequeueUncheckedRequest { chunk };
+        self.post(routes::admin::REQUEUE_UNCHECKED, &request).await
+    }
+
+    /// 候选ID统计
+    pub async fn candidate_stats(&self) -> Result<CandidateStats, ClientError> {
+        self.get(routes::admin::CANDIDATES).await
+    }
+
+    /// 批量导入候选ID
+    pub async fn import_candidates(
+        &self,
+        ids: Vec<i64>,
+    ) -> Result<ImportCandidatesResponse, ClientError> {
+        self.post(routes::admin::CANDIDATES, &ImportCandidatesRequest { ids })
+            .await
+    }
+
+    /// 所有API Key及其用量
+    pub async fn api_keys(&self) -> Result<Vec<ApiKeyInfo>, ClientError> {
+        self.get(routes::admin::KEYS).await
+    }
+
+    /// 创建API Key
+    pub async fn create_api_key(
+        &self,
+        request: &CreateApiKeyRequest,
+    ) -> Result<CreateApiKeyResponse, ClientError> {
+        self.post(routes::admin::KEYS, request).await
+    }
+
+    /// 吊销API Key
+    pub async fn revoke_api_key(&self, key_id: i64) -> Result<String, ClientError> {
+        self.delete(&routes::fill(routes::admin::KEY, &key_id.to_string()))
+            .await
+    }
+
+    /// 复查队列，可按状态（pending、done）过滤
+    pub async fn verification(
+        &self,
+        limit: u32,
+        status: Option<&str>,
+    ) -> Result<Vec<VerificationEntry>, ClientError> {
+        let mut path = format!("{}?limit={}", routes::admin::VERIFICATION, limit);
+        if let Some(status) = status {
+            path.push_str(&format!("&status={}", encode_path_segment(status)));
+        }
+        self.get(&path).await
+    }
+
+    /// 各Worker的抽查一致率，一致率低的在前
+    pub async fn spot_checks(&self) -> Result<Vec<SpotCheckStats>, ClientError> {
+        self.get(routes::admin::SPOT_CHECKS).await
+    }
+
+    /// 按 `bucket` 个ID一桶统计的覆盖情况，范围默认从0到游标
+    pub async fn coverage(
+        &self,
+        bucket: i64,
+        start: Option<i64>,
+        end: Option<i64>,
+    ) -> Result<Vec<CoverageBucket>, ClientError> {
+        let mut path = format!("{}?bucket={}", routes::admin::COVERAGE, bucket);
+        if let Some(start) = start {
+            path.push_str(&format!("&start={}", start));
+        }
+        if let Some(end) = end {
+            path.push_str(&format!("&end={}", end));
+        }
+        self.get(&path).await
+    }
+
+    /// 命中响应标记的ID：各标记的数量和最近出现的ID，可按标记过滤
+    pub async fn marked(
+        &self,
+        limit: u32,
+        label: Option<&str>,
+    ) -> Result<MarkedStats, ClientError> {
+        let mut path = format!("{}?limit={}", routes::admin::MARKED, limit);
+        if let Some(label) = label {
+            path.push_str(&format!("&label={}", encode_path_segment(label)));
+        }
+        self.get(&path).await
+    }
+
+    /// 最近的审计日志，可按操作类型过滤
+    pub async fn audit(
+        &self,
+        limit: u32,
+        action: Option<&str>,
+    ) -> Result<Vec<AuditEntry>, ClientError> {
+        let mut path = format!("{}?limit={}", routes::admin::AUDIT, limit);
+        if let Some(action) = action {
+            path.push_str(&format!("&action={}", encode_path_segment(action)));
+        }
+        self.get(&path).await
+    }
+
+    /// 发送 GET 请求并解包响应
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        self.call(
+            Method::GET,
+            path,
+            None::<&()>,
+            CallOptions::default().idempotent(),
+        )
+        .await
+    }
+
+    /// 发送 POST 请求并解包响应（按非幂等调用处理，只在连接失败时重试）
+    pub async fn post<Req: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &Req,
+    ) -> Result<T, ClientError> {
+        self.post_with(path, body, CallOptions::default()).await
+    }
+
+    /// 按给定的选项发送 POST 请求并解包响应
+    pub async fn post_with<Req: Serialize, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &Req,
+        options: CallOptions,
+    ) -> Result<T, ClientError> {
+        self.call(Method::POST, path, Some(body), options).await
+    }
+
+    /// 发送 DELETE 请求并解包响应
+    pub async fn delete<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        self.call(
+            Method::DELETE,
+            path,
+            None::<&()>,
+            CallOptions::default().idempotent(),
+        )
+        .await
+    }
+
+    /// 发送请求，按重试策略重试可以安全重试的失败
+    ///
+    /// 每次尝试使用新的请求关联ID，重试记录在日志中。
+    async fn call<Req: Serialize, T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Req>,
+        options: CallOptions,
+    ) -> Result<T, ClientError> {
+        let policy = options.retry.unwrap_or(self.retry);
+        let mut attempt = 1;
+        loop {
+            let (mut request, request_id) = self.request(method.clone(), path);
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+            let error = match send(request, request_id).await {
+                Err(e) if attempt < policy.max_attempts && e.can_retry(options.idempotent) => e,
+                result => return result,
+            };
+
+            let wait = error
+                .retry_after()
+                .unwrap_or_else(|| policy.backoff(attempt));
+            if wait > policy.max_delay {
+                return Err(error);
+            }
+            warn!(
+                "{} {} 失败 (第 {} 次): {}，{}ms 后重试",
+                method,
+                path,
+                attempt,
+                error,
+                wait.as_millis()
+            );
+            tokio::time::sleep(wait).await;
+            attempt += 1;
+        }
+    }
+
+    /// 构造请求，带上公共请求头和管理凭据
+    fn request(&self, method: Method, path: &str) -> (RequestBuilder, String) {
+        let (headers, request_id) = self.request_headers();
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path))
+            .headers(headers);
+        match &self.credentials {
+            Some(Credentials::Basic(user, password)) => {
+                request = request.basic_auth(user, Some(password));
+            }
+            Some(Credentials::Token(token)) => request = request.bearer_auth(token),
+            None => {}
+        }
+        (request, request_id)
+    }
+
+    /// 生成请求关联ID，并带上当前 span 的链路上下文和操作人
+    fn request_headers(&self) -> (HeaderMap, String) {
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let mut headers = HeaderMap::new();
+        telemetry::inject_context(&mut headers);
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            headers.insert(REQUEST_ID_HEADER, value);
+        }
+        if let Some(Ok(value)) = self.actor.as_deref().map(HeaderValue::from_str) {
+            headers.insert(ACTOR_HEADER, value);
+        }
+        (headers, request_id)
+    }
+}
+
+/// 对路径中的一段做百分号编码（Worker ID 可能包含任意字符）
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// 发送请求，将 `ApiResponse` 解包为数据或错误
+///
+/// 错误状态码的响应体同样按 `ApiResponse` 解析，保留错误码供调用方区分。
+pub async fn send<T: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    request_id: String,
+) -> Result<T, ClientError> {
+    let http_error = |source| ClientError::Http {
+        source,
+        request_id: request_id.clone(),
+    };
+
+    let response = request.send().await.map_err(http_error)?;
+    let status = response.status().as_u16();
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs);
+    let body: ApiResponse<T> = response.json().await.map_err(http_error)?;
+    body.into_result()
+        .map_err(|error| ClientError::from_api(error, status, retry_after, request_id))
+}
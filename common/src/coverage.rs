@@ -0,0 +1,101 @@
+//! 探测覆盖校验
+//!
+//! Worker提交结果时附带实际探测过的ID数（按结论分类）和这些ID的摘要，
+//! Master按任务范围重新计算摘要并比对，从而发现跳过了部分范围的Worker。
+//!
+//! 重试耗尽仍没有结论（`unknown`）或一直被上游拒绝（`blocked`）的ID也算探测过，
+//! 但它们不代表确认不存在，Master记录这两类的数量，之后可以重新排队检查。
+//!
+//! 摘要是每个ID经 splitmix64 混合后的回绕和，与探测顺序无关
+//! （Worker并发探测，完成顺序不固定）。
+
+use serde::{Deserialize, Serialize};
+
+/// 提交结果时附带的探测覆盖信息
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Coverage {
+    /// 判定为有效的ID数
+    pub valid: u64,
+
+    /// 判定为无效的ID数
+    pub invalid: u64,
+
+    /// 重试耗尽仍没有结论的ID数
+    pub unknown: u64,
+
+    /// 探测时被上游拒绝（403、429）的ID数
+    pub blocked: u64,
+
+    /// 探测过的ID的摘要
+    pub digest: u64,
+}
+
+/// 一个ID最终的探测结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdStatus {
+    /// 有效
+    Valid,
+
+    /// 确认无效
+    Invalid,
+
+    /// 重试耗尽仍没有结论
+    Unknown,
+
+    /// 最后一次探测被上游拒绝
+    Blocked,
+}
+
+impl Coverage {
+    /// 记录一个探测过的ID
+    pub fn record(&mut self, id: i64, status: IdStatus) {
+        match status {
+            IdStatus::Valid => self.valid += 1,
+            IdStatus::Invalid => self.invalid += 1,
+            IdStatus::Unknown => self.unknown += 1,
+            IdStatus::Blocked => self.blocked += 1,
+        }
+        self.digest = self.digest.wrapping_add(mix(id));
+    }
+
+    /// 探测过的ID总数
+    pub fn probed(&self) -> u64 {
+        self.valid + self.invalid + self.unknown + self.blocked
+    }
+
+    /// 没有得到确定结论的ID数
+    pub fn unchecked(&self) -> u64 {
+        self.unknown + self.blocked
+    }
+
+    /// 完整探测闭区间 [start_id, end_id] 时应得的摘要
+    pub fn expected_digest(start_id: i64, end_id: i64) -> u64 {
+        (start_id..=end_id).fold(0u64, |digest, id| digest.wrapping_add(mix(id)))
+    }
+}
+
+/// splitmix64 的混合函数
+fn mix(id: i64) -> u64 {
+    let mut z = (id as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unchecked_ids_count_as_probed() {
+        let mut coverage = Coverage::default();
+        coverage.record(1, IdStatus::Valid);
+        coverage.record(2, IdStatus::Invalid);
+        coverage.record(3, IdStatus::Unknown);
+        coverage.record(4, IdStatus::Blocked);
+        assert_eq!(coverage.probed(), 4);
+        assert_eq!(coverage.unchecked(), 2);
+        assert_eq!(coverage.digest, Coverage::expected_digest(1, 4));
+    }
+}
@@ -0,0 +1,187 @@
+//! ID类型
+//!
+//! 任务范围、结果表中的ID始终是整数（ID空间中的序号），ID类型决定序号如何对应到上游的标识：
+//! - `integer`：标识就是序号本身
+//! - `template`：按模板格式化，如 `C{n}` 配合补零宽度和校验位得到 `C00012347`
+//! - `list`：没有连续的ID空间，只扫描明确给出的候选ID，不推进全局游标
+//!
+//! ID类型属于整个扫描（Master启动时确定并保存在数据库中），随任务下发给Worker，
+//! 探测目标的模板中 `{id}` 替换为格式化后的标识，`{index}` 替换为序号。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 格式化模板中代表数字部分的占位符
+pub const NUMBER_PLACEHOLDER: &str = "{n}";
+
+/// ID类型
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IdKind {
+    /// 连续的整数
+    #[default]
+    Integer,
+
+    /// 按模板格式化的整数
+    Template {
+        /// 模板，`{n}` 替换为数字部分（如 `C{n}`）
+        template: String,
+
+        /// 数字部分补零到的宽度（含校验位，0 表示不补零）
+        #[serde(default, skip_serializing_if = "is_zero")]
+        width: usize,
+
+        /// 数字部分末尾附加的校验位
+        #[serde(default, skip_serializing_if = "Checksum::is_none")]
+        checksum: Checksum,
+    },
+
+    /// 明确给出的候选ID列表
+    List,
+}
+
+/// 校验位算法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Checksum {
+    /// 不附加校验位
+    #[default]
+    None,
+
+    /// Luhn（模10）校验位
+    Luhn,
+}
+
+fn is_zero(width: &usize) -> bool {
+    *width == 0
+}
+
+impl Checksum {
+    fn is_none(&self) -> bool {
+        *self == Checksum::None
+    }
+}
+
+impl FromStr for Checksum {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Checksum::None),
+            "luhn" => Ok(Checksum::Luhn),
+            other => Err(format!("未知的校验位算法: {}（可选 none、luhn）", other)),
+        }
+    }
+}
+
+impl IdKind {
+    /// 是否为连续的整数（旧版本的行为）
+    pub fn is_integer(&self) -> bool {
+        *self == IdKind::Integer
+    }
+
+    /// 是否有连续的ID空间（`list` 类型只扫描候选ID）
+    pub fn is_sequential(&self) -> bool {
+        !matches!(self, IdKind::List)
+    }
+
+    /// 检查ID类型的定义，返回错误说明
+    pub fn validate(&self) -> Result<(), String> {
+        if let IdKind::Template { template, .. } = self {
+            if !template.contains(NUMBER_PLACEHOLDER) {
+                return Err(format!(
+                    "ID模板必须包含 {}: {}",
+                    NUMBER_PLACEHOLDER, template
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 把序号格式化为上游的标识（`list` 类型的标识由候选表给出，这里按整数处理）
+    pub fn format(&self, index: i64) -> String {
+        match self {
+            IdKind::Integer | IdKind::List => index.to_string(),
+            IdKind::Template {
+                template,
+                width,
+                checksum,
+            } => {
+                let mut digits = index.to_string();
+                if *checksum == Checksum::Luhn {
+                    digits.push(luhn_digit(&digits));
+                }
+                template.replace(
+                    NUMBER_PLACEHOLDER,
+                    &format!("{:0>width$}", digits, width = *width),
+                )
+            }
+        }
+    }
+}
+
+impl fmt::Display for IdKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdKind::Integer => f.write_str("integer"),
+            IdKind::List => f.write_str("list"),
+            IdKind::Template {
+                template,
+                width,
+                checksum,
+            } => {
+                write!(f, "template {}", template)?;
+                if *width > 0 {
+                    write!(f, " 宽度 {}", width)?;
+                }
+                if *checksum == Checksum::Luhn {
+                    f.write_str(" 带 Luhn 校验位")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 计算数字串的 Luhn 校验位
+fn luhn_digit(digits: &str) -> char {
+    let sum: u32 = digits
+        .bytes()
+        .filter(u8::is_ascii_digit)
+        .rev()
+        .enumerate()
+        .map(|(i, byte)| {
+            let digit = u32::from(byte - b'0');
+            if i % 2 == 0 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                digit
+            }
+        })
+        .sum();
+    char::from_digit((10 - sum % 10) % 10, 10).unwrap_or('0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_formats_with_check_digit() {
+        let kind = IdKind::Template {
+            template: "C{n}".to_string(),
+            width: 9,
+            checksum: Checksum::Luhn,
+        };
+        assert!(kind.validate().is_ok());
+        assert_eq!(kind.format(1234), "C000012344");
+        assert_eq!(kind.format(7_992_739_871), "C79927398713");
+        assert_eq!(IdKind::Integer.format(42), "42");
+    }
+}
@@ -0,0 +1,63 @@
+//! 探测延迟统计
+//!
+//! Worker记录任务中每次探测请求（含重试）的耗时，提交结果时附带分位数，
+//! Master据此区分是个别Worker的网络慢，还是上游整体变慢。
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 一个任务中探测请求的延迟分布（毫秒）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyStats {
+    /// 请求数
+    pub count: u64,
+
+    /// 中位数
+    pub p50_ms: u64,
+
+    /// 90分位
+    pub p90_ms: u64,
+
+    /// 99分位
+    pub p99_ms: u64,
+
+    /// 最大值
+    pub max_ms: u64,
+}
+
+/// 延迟记录器，供并发的探测协程共用
+#[derive(Default)]
+pub struct LatencyRecorder {
+    samples: Mutex<Vec<u64>>,
+}
+
+impl LatencyRecorder {
+    /// 记录一次请求的耗时
+    pub fn record(&self, elapsed: Duration) {
+        let millis = u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        self.samples.lock().expect("延迟记录锁中毒").push(millis);
+    }
+
+    /// 计算分位数，没有记录时返回 None
+    pub fn stats(&self) -> Option<LatencyStats> {
+        let mut samples = self.samples.lock().expect("延迟记录锁中毒").clone();
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort_unstable();
+        // 最近秩法：第 ceil(p * n) 个样本
+        let percentile = |p: f64| {
+            let rank = (p * samples.len() as f64).ceil() as usize;
+            samples[rank.clamp(1, samples.len()) - 1]
+        };
+        Some(LatencyStats {
+            count: samples.len() as u64,
+            p50_ms: percentile(0.50),
+            p90_ms: percentile(0.90),
+            p99_ms: percentile(0.99),
+            max_ms: samples[samples.len() - 1],
+        })
+    }
+}
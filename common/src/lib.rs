@@ -1,9 +1,54 @@
 //! Common library for distributed crawler
 //! 定义Master和Worker之间共享的请求/响应结构体
+//!
+//! 滚动升级时Master和Worker会短暂运行不同版本，结构体的演进遵循以下约定：
+//! - 只有任务和Worker的标识（task_id、worker_id、范围等）是必填字段
+//! - 新增的字段都带 `#[serde(default)]`，缺少时取默认值；可选字段为 None 时不序列化
+//! - 不认识的字段被忽略（不使用 `deny_unknown_fields`），不认识的错误码解析为 [`ErrorCode::Unknown`]
+//!
+//! `tests/compat.rs` 用旧版本的请求/响应样例检查这些约定。
 
+use coverage::Coverage;
+use ids::IdKind;
+use latency::LatencyStats;
+use probe::TargetSpec;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 
+pub mod admin;
+pub mod client;
 pub mod code;
+pub mod coverage;
+pub mod ids;
+pub mod latency;
+pub mod probe;
+pub mod request;
+pub mod rolling_file;
+pub mod routes;
+pub mod runtime;
+pub mod telemetry;
+
+/// 请求关联ID的HTTP头，Master和Worker两端的日志通过它对应起来
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// 请求所属的Worker，Master把它记录到请求的日志字段中（`/task/*` 请求也可以只放在请求体里）
+pub const WORKER_ID_HEADER: &str = "x-pa-worker-id";
+
+/// 请求所属的任务，用法同 [`WORKER_ID_HEADER`]
+pub const TASK_ID_HEADER: &str = "x-pa-task-id";
+
+/// Worker的API Key以 `Authorization: Bearer <key>` 的形式发送
+pub const API_KEY_SCHEME: &str = "Bearer";
+
+/// Worker与Master之间的协议版本
+///
+/// - 1：探测内置的上游
+/// - 2：任务可以携带上游探测目标（[`AcquireTaskResponse::target`]）
+/// - 3：任务可以是显式ID列表（[`AcquireTaskResponse::ids`]）
+/// - 4：任务可以是限时任务（[`AcquireTaskResponse::scan_for_secs`]）
+/// - 5：一次可以领取多个任务（[`AcquireTaskRequest::max_tasks`]、[`AcquireTaskResponse::extra_tasks`]）
+pub const PROTOCOL_VERSION: u32 = 5;
 
 /// Worker向Master请求任务时的请求体
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,7 +58,72 @@ pub struct AcquireTaskRequest {
 
     /// Worker上一次任务的每秒处理速度（可选）
     /// 用于Master动态调整batch_size
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_performance: Option<u32>,
+
+    /// Worker上一次任务中上游请求出错（需要重试）的比例（可选）
+    /// 用于Master调整建议并发数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error_rate: Option<f64>,
+
+    /// Worker能接受的最大范围（ID数，可选）
+    /// 内存受限或按流量计费的Worker可以要求比按速度计算的更小的范围
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_batch_size: Option<i64>,
+
+    /// Worker的主机信息和标签（可选）
+    /// Master保存后在管理接口中展示，便于辨认各个Worker
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<WorkerProfile>,
+
+    /// 本次最多领取的任务数（可选，protocol v5），不设置时为1
+    /// Master按自身的上限截断，可分配的任务不足时返回的更少
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tasks: Option<u32>,
+
+    /// Worker支持的协议版本（[`PROTOCOL_VERSION`]，旧版本Worker不提供，视为1）
+    #[serde(default = "legacy_protocol")]
+    pub protocol: u32,
+}
+
+fn legacy_protocol() -> u32 {
+    1
+}
+
+/// Worker的主机信息和自定义标签
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerProfile {
+    /// 主机名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+
+    /// 所在地区（如 cn-sh、us-west）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+
+    /// 出口信息（如代理名称或出口IP）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress: Option<String>,
+
+    /// 自定义标签
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+
+    /// Worker的版本号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl WorkerProfile {
+    /// 供列表展示的简短描述：主机名、地区和出口，以 ` / ` 分隔
+    pub fn summary(&self) -> String {
+        [&self.hostname, &self.region, &self.egress]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
 }
 
 /// Master向Worker返回任务时的响应体
@@ -27,6 +137,79 @@ pub struct AcquireTaskResponse {
 
     /// 结束ID（包含）
     pub end_id: i64,
+
+    /// 本次分配的租约标识（分配时的Unix毫秒时间戳），提交结果时原样带回
+    #[serde(default)]
+    pub lease: i64,
+
+    /// Master对本次分配的签名，提交结果时原样带回
+    #[serde(default)]
+    pub signature: String,
+
+    /// Master建议的并发数上限（旧版本Master不提供）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggested_concurrency: Option<usize>,
+
+    /// 分给该Worker的请求速度上限（req/s），None 表示不限制，之后随心跳响应更新
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<f64>,
+
+    /// 本任务探测的上游（protocol v2），None 表示探测内置的上游
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<TargetSpec>,
+
+    /// ID类型：范围中的序号如何格式化为探测目标模板中的 `{id}`（protocol v2）
+    #[serde(default, skip_serializing_if = "IdKind::is_integer")]
+    pub id_kind: IdKind,
+
+    /// 显式ID列表（protocol v3），非空时只扫描这些ID，start_id/end_id 为其中的最小/最大值
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ids: Vec<i64>,
+
+    /// 限时任务（protocol v4）：从 start_id 起按顺序扫描该时长（秒）后停止，end_id 只是预留的上限，
+    /// 提交时在 [`SubmitResultRequest::scanned_to`] 中上报扫描到的位置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_for_secs: Option<u64>,
+
+    /// 请求了多个任务时（protocol v5）额外分配的任务，第一个任务即本结构体自身
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_tasks: Vec<AcquireTaskResponse>,
+}
+
+impl AcquireTaskResponse {
+    /// 展开为本次分配的所有任务，第一个为本结构体自身
+    pub fn into_tasks(mut self) -> Vec<AcquireTaskResponse> {
+        let extra = std::mem::take(&mut self.extra_tasks);
+        let mut tasks = Vec::with_capacity(extra.len() + 1);
+        tasks.push(self);
+        tasks.extend(extra);
+        tasks
+    }
+
+    /// 是否为显式ID列表任务
+    pub fn is_list(&self) -> bool {
+        !self.ids.is_empty()
+    }
+
+    /// 是否为限时任务
+    pub fn is_timed(&self) -> bool {
+        self.scan_for_secs.is_some()
+    }
+
+    /// 要扫描的ID数（限时任务为预留的上限）
+    pub fn id_count(&self) -> i64 {
+        if self.is_list() {
+            self.ids.len() as i64
+        } else {
+            self.end_id - self.start_id + 1
+        }
+    }
+
+    /// 按顺序产出要扫描的ID
+    pub fn scan_ids(&self) -> impl Iterator<Item = i64> + '_ {
+        let range = (!self.is_list()).then_some(self.start_id..=self.end_id);
+        range.into_iter().flatten().chain(self.ids.iter().copied())
+    }
 }
 
 /// Worker向Master发送心跳的请求体
@@ -37,6 +220,96 @@ pub struct HeartbeatRequest {
 
     /// Worker的唯一标识符
     pub worker_id: String,
+
+    /// 自上次心跳以来每秒完成探测的ID数（可选）
+    /// Master据此在长任务运行期间了解Worker的实际速度，并发现停滞的任务
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+
+    /// Worker主机的系统状态（Worker启用 `--report-system` 时上报）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<SystemSample>,
+}
+
+/// Worker主机的系统状态采样，用于在任务开始超时之前发现内存不足或网卡异常的机器
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SystemSample {
+    /// 最近 1 分钟的平均负载
+    #[serde(default)]
+    pub load1: f64,
+
+    /// CPU 核数
+    #[serde(default)]
+    pub cpus: u32,
+
+    /// 内存总量（KB）
+    #[serde(default)]
+    pub mem_total_kb: u64,
+
+    /// 可用内存（KB）
+    #[serde(default)]
+    pub mem_available_kb: u64,
+
+    /// 已使用的交换空间（KB）
+    #[serde(default)]
+    pub swap_used_kb: u64,
+
+    /// 所有网卡累计的收发错误数（不含 lo）
+    #[serde(default)]
+    pub net_errors: u64,
+
+    /// 所有网卡累计的丢包数（不含 lo）
+    #[serde(default)]
+    pub net_drops: u64,
+}
+
+/// Master对心跳的响应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "HeartbeatPayload")]
+pub struct HeartbeatResponse {
+    /// 任务已不再属于该Worker（被重新分配或已完成），Worker应立即停止扫描
+    #[serde(default)]
+    pub abort: bool,
+
+    /// 需要停止的原因
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// 分给该Worker的请求速度上限（req/s），None 表示不限制
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<f64>,
+}
+
+/// 心跳响应的反序列化形式：旧版本Master的心跳响应只是一条字符串消息，视为不需要停止
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HeartbeatPayload {
+    Response {
+        #[serde(default)]
+        abort: bool,
+        #[serde(default)]
+        reason: Option<String>,
+        #[serde(default)]
+        rate_limit: Option<f64>,
+    },
+    Message(#[allow(dead_code)] String),
+}
+
+impl From<HeartbeatPayload> for HeartbeatResponse {
+    fn from(payload: HeartbeatPayload) -> Self {
+        match payload {
+            HeartbeatPayload::Response {
+                abort,
+                reason,
+                rate_limit,
+            } => Self {
+                abort,
+                reason,
+                rate_limit,
+            },
+            HeartbeatPayload::Message(_) => Self::default(),
+        }
+    }
 }
 
 /// Worker向Master提交结果的请求体
@@ -47,6 +320,104 @@ pub struct SubmitResultRequest {
 
     /// 发现的有效ID列表
     pub valid_ids: Vec<i64>,
+
+    /// Worker的唯一标识符（与获取任务时相同）
+    #[serde(default)]
+    pub worker_id: String,
+
+    /// 获取任务时返回的租约标识
+    #[serde(default)]
+    pub lease: i64,
+
+    /// 获取任务时返回的签名
+    #[serde(default)]
+    pub signature: String,
+
+    /// 实际探测的ID数和摘要（旧版本Worker不提供）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<Coverage>,
+
+    /// 探测请求的延迟分布（旧版本Worker不提供）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyStats>,
+
+    /// 幂等键：同一次提交重试时保持不变，Master据此返回首次提交的结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+
+    /// 命中响应标记的无效ID（如已下架、地区限制）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub marked: Vec<MarkedId>,
+
+    /// 限时任务扫描到的最大ID，[start_id, scanned_to] 已全部探测；None 表示扫描了整个范围
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scanned_to: Option<i64>,
+}
+
+/// 命中响应标记（[`probe::ResponseMarker`]）的无效ID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkedId {
+    /// ID
+    pub id: i64,
+
+    /// 标记名称
+    pub label: String,
+}
+
+/// Worker在任务执行过程中提前上报已发现的有效ID的请求体
+///
+/// 只写入有效ID，不完成任务；任务完成时仍需通过 [`SubmitResultRequest`] 提交全部结果。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialResultRequest {
+    /// 任务ID
+    pub task_id: i32,
+
+    /// 自上次上报以来新发现的有效ID
+    pub valid_ids: Vec<i64>,
+
+    /// Worker的唯一标识符（与获取任务时相同）
+    pub worker_id: String,
+
+    /// 获取任务时返回的租约标识
+    #[serde(default)]
+    pub lease: i64,
+
+    /// 获取任务时返回的签名
+    #[serde(default)]
+    pub signature: String,
+}
+
+/// Worker上传有效ID原始响应的请求体（原始响应存档模式）
+///
+/// 在提交结果之前按批上传，与提前上报相同，需要任务仍属于该Worker。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveResponsesRequest {
+    /// 任务ID
+    pub task_id: i32,
+
+    /// Worker的唯一标识符（与获取任务时相同）
+    pub worker_id: String,
+
+    /// 获取任务时返回的租约标识
+    #[serde(default)]
+    pub lease: i64,
+
+    /// 获取任务时返回的签名
+    #[serde(default)]
+    pub signature: String,
+
+    /// 有效ID的原始响应
+    pub responses: Vec<RawResponse>,
+}
+
+/// 一个有效ID的原始响应
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RawResponse {
+    /// 有效ID
+    pub id: i64,
+
+    /// gzip 压缩后按标准 base64 编码的响应体
+    pub body: String,
 }
 
 /// Worker向Master释放任务的请求体（用于优雅退出）
@@ -59,7 +430,60 @@ pub struct ReleaseTaskRequest {
     pub worker_id: String,
 }
 
+/// 错误码
+///
+/// 与错误信息一起返回，调用方据此区分错误类型，而不需要解析错误信息文本。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// 请求过于频繁，稍后重试（响应带 `Retry-After` 头）
+    RateLimited,
+
+    /// 请求体无法解析或未通过校验，重试也不会成功
+    InvalidRequest,
+
+    /// 缺少API Key，或API Key无效、已被吊销
+    Unauthorized,
+
+    /// 凭据有效但角色不允许调用该接口（如只读令牌执行修改操作、管理令牌调用 `/task/*`）
+    Forbidden,
+
+    /// API Key的配额已用完（并发任务数或当天的ID数）
+    QuotaExceeded,
+
+    /// 提交结果时的任务分配签名无效（任务并未以此租约分配给该Worker）
+    InvalidSignature,
+
+    /// 提交的任务已被重新分配给其他Worker或已由其他Worker完成，结果作废，不应重试
+    TaskConflict,
+
+    /// Worker已被封禁
+    Banned,
+
+    /// 任务分发已暂停，稍后重试（响应带 `Retry-After` 头）
+    DispatchPaused,
+
+    /// 暂时没有可分配的任务，稍后重试（响应带 `Retry-After` 头）
+    NoTaskAvailable,
+
+    /// 请求的任务、Worker、API Key或接口不存在
+    NotFound,
+
+    /// 当前节点为备用节点，不处理写请求，稍后重试或改为请求主节点（响应带 `Retry-After` 头）
+    NotLeader,
+
+    /// Master正在维护，任务接口暂不可用，按 `Retry-After` 等待后重试，已持有的任务不会因此超时
+    Maintenance,
+
+    /// 当前版本不认识的错误码（新版本Master返回的）
+    #[serde(other)]
+    Unknown,
+}
+
 /// Master向Worker返回的通用响应
+///
+/// `/task/*` 与 `/admin/*` 的所有响应都使用该结构：成功时状态码为 2xx 且 `success` 为 true；
+/// 请求方的问题返回 4xx，Master自身的问题返回 5xx；需要稍后重试的 429/503 带 `Retry-After` 头。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     /// 是否成功
@@ -70,6 +494,14 @@ pub struct ApiResponse<T> {
 
     /// 错误信息（如果有）
     pub error: Option<String>,
+
+    /// 请求关联ID（仅在错误响应中携带）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+
+    /// 错误码（仅在部分错误响应中携带）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
 }
 
 impl<T> ApiResponse<T> {
@@ -79,6 +511,8 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            request_id: None,
+            code: None,
         }
     }
 
@@ -88,6 +522,84 @@ impl<T> ApiResponse<T> {
             success: false,
             data: None,
             error: Some(msg),
+            request_id: None,
+            code: None,
+        }
+    }
+
+    /// 附带请求关联ID
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// 附带错误码
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// 解包为数据或错误：`success` 为 true 且带有数据时返回数据，否则返回 [`ApiError`]
+    pub fn into_result(self) -> Result<T, ApiError> {
+        match self {
+            ApiResponse {
+                success: true,
+                data: Some(data),
+                ..
+            } => Ok(data),
+            ApiResponse {
+                error,
+                code,
+                request_id,
+                ..
+            } => Err(ApiError {
+                message: error.unwrap_or_else(|| "响应中没有数据".to_string()),
+                code,
+                request_id,
+            }),
         }
     }
 }
+
+/// 失败的 [`ApiResponse`] 中携带的错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    /// 错误信息
+    pub message: String,
+
+    /// 错误码（部分错误响应不带）
+    pub code: Option<ErrorCode>,
+
+    /// 请求关联ID
+    pub request_id: Option<String>,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.request_id {
+            Some(request_id) => write!(f, "{} (request_id={})", self.message, request_id),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_task_scans_only_its_ids() {
+        let mut task: AcquireTaskResponse =
+            serde_json::from_str(r#"{"task_id": 9, "start_id": 5, "end_id": 8, "ids": [5, 6, 8]}"#)
+                .unwrap();
+        assert!(task.is_list());
+        assert_eq!(task.id_count(), 3);
+        assert_eq!(task.scan_ids().collect::<Vec<_>>(), vec![5, 6, 8]);
+
+        task.ids.clear();
+        assert_eq!(task.id_count(), 4);
+        assert_eq!(task.scan_ids().collect::<Vec<_>>(), vec![5, 6, 7, 8]);
+    }
+}
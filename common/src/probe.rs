@@ -0,0 +1,370 @@
+//! 上游探测
+//!
+//! 检查单个ID在上游是否存在。Worker扫描任务时使用，
+//! Master的抽查也使用同一个实现，保证两边的判定一致。
+//!
+//! 默认探测内置的上游（[`check_id`]）；Master配置了 [`TargetSpec`] 时随任务下发，
+//! Worker按其中的地址、请求头、请求体模板和判定规则探测（[`check_target`]）。
+//! 模板中的 `{id}` 替换为按ID类型（[`crate::ids::IdKind`]）格式化后的标识，`{index}` 替换为序号。
+//!
+//! 判定规则可以列出若干响应标记（[`ResponseMarker`]），如“已下架”“地区限制”：
+//! 命中标记的ID同样视为无效，但带上标记名称随结果提交，Master单独保存，用于追踪下架情况。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 一次探测的结论
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// ID 有效，值为上游响应体的原始内容（供原始响应存档使用）
+    Valid(Vec<u8>),
+
+    /// ID 无效（上游确认不存在）
+    Invalid,
+
+    /// ID 无效，但响应命中了值得记录的标记（如已下架、地区限制），值为标记名称
+    Marked(String),
+
+    /// 结论不确定（appId 不匹配、请求超时、连接失败或上游出错），需要重试
+    Retry,
+
+    /// 上游拒绝了请求（403、429），需要稍后重试
+    Blocked,
+}
+
+impl Outcome {
+    /// 是否得到了确定的结论
+    pub fn is_conclusive(&self) -> bool {
+        matches!(
+            self,
+            Outcome::Valid(_) | Outcome::Invalid | Outcome::Marked(_)
+        )
+    }
+}
+
+/// 按响应状态码判断上游是否拒绝服务或出错，返回 None 时继续解析响应体
+fn classify_status(status: reqwest::StatusCode) -> Option<Outcome> {
+    if status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+    {
+        Some(Outcome::Blocked)
+    } else if status.is_server_error() {
+        Some(Outcome::Retry)
+    } else {
+        None
+    }
+}
+
+/// 读取响应体并按 JSON 解析，同时保留原始内容；读取超时时需要重试，其他失败视为ID无效
+async fn read_json(response: reqwest::Response) -> Result<(Vec<u8>, serde_json::Value), Outcome> {
+    let raw = match response.bytes().await {
+        Ok(raw) => raw.to_vec(),
+        Err(e) if e.is_timeout() => return Err(Outcome::Retry),
+        Err(_) => return Err(Outcome::Invalid),
+    };
+    let value = serde_json::from_slice(&raw).map_err(|_| Outcome::Invalid)?;
+    Ok((raw, value))
+}
+
+/// 模板中代表当前ID（格式化后的标识）的占位符
+pub const ID_PLACEHOLDER: &str = "{id}";
+
+/// 模板中代表当前ID序号的占位符
+pub const INDEX_PLACEHOLDER: &str = "{index}";
+
+/// 上游探测目标（protocol v2），模板中的 `{id}`、`{index}` 替换为当前ID
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetSpec {
+    /// 名称，用于日志
+    #[serde(default)]
+    pub name: String,
+
+    /// 请求地址模板
+    pub url: String,
+
+    /// 请求方法（默认 POST）
+    #[serde(default = "default_method")]
+    pub method: String,
+
+    /// 请求头，值也可以使用模板
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+
+    /// 请求体模板（没有时不发送请求体），未设置 Content-Type 时按 JSON 发送
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_template: Option<String>,
+
+    /// 判定规则
+    pub validation: ValidationRules,
+}
+
+/// 根据上游响应判定ID是否有效的规则
+///
+/// 响应体为空、不是 JSON 或 `field` 不存在时ID无效；`field` 存在时，
+/// 没有 `expected` 则ID有效，有 `expected` 时与其相等才有效，不相等说明上游返回了别的记录，需要重试。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationRules {
+    /// 响应 JSON 中标识记录的字段（JSON Pointer，如 `/appId`）
+    pub field: String,
+
+    /// 字段的期望值模板（字符串比较）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+
+    /// 值得单独记录的无效响应，按顺序匹配，先于有效判定检查
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<ResponseMarker>,
+}
+
+/// 值得单独记录的无效响应（如已下架、地区限制）
+///
+/// `status` 和 `field` 至少设置一个，设置的条件都满足时命中：
+/// 只有 `status` 时按状态码匹配（先于 403/429/5xx 的重试处理）；
+/// 有 `field` 时要求响应 JSON 中存在该字段，设置了 `equals` 时还要求字段值相等。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseMarker {
+    /// 标记名称（如 `removed`、`region_restricted`），随结果提交
+    pub label: String,
+
+    /// HTTP状态码
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// 响应 JSON 中的字段（JSON Pointer）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+
+    /// 字段的值（字符串比较），不设置时字段存在（且不为 null）即可
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<String>,
+}
+
+/// 标记名称的最大长度
+pub const MAX_MARKER_LABEL_LEN: usize = 64;
+
+impl ResponseMarker {
+    /// 检查标记定义是否可用，返回错误说明
+    pub fn validate(&self) -> Result<(), String> {
+        if self.label.trim().is_empty() || self.label.len() > MAX_MARKER_LABEL_LEN {
+            return Err(format!(
+                "标记名称不能为空且不能超过 {} 个字节: {:?}",
+                MAX_MARKER_LABEL_LEN, self.label
+            ));
+        }
+        match &self.field {
+            Some(field) if !field.starts_with('/') => Err(format!(
+                "标记 {} 的字段必须是以 / 开头的 JSON Pointer: {}",
+                self.label, field
+            )),
+            None if self.status.is_none() => {
+                Err(format!("标记 {} 必须设置 status 或 field", self.label))
+            }
+            None if self.equals.is_some() => {
+                Err(format!("标记 {} 设置了 equals 但没有 field", self.label))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 响应是否命中该标记，`body` 为 None 时只匹配没有 `field` 的标记
+    fn matches(&self, status: u16, body: Option<&serde_json::Value>) -> bool {
+        if self.status.is_some_and(|expected| expected != status) {
+            return false;
+        }
+        match (&self.field, body) {
+            (None, None) => true,
+            (Some(field), Some(body)) => {
+                match body.pointer(field).filter(|value| !value.is_null()) {
+                    None => false,
+                    Some(value) => self
+                        .equals
+                        .as_ref()
+                        .is_none_or(|equals| *equals == field_text(value)),
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// 第一个命中的标记
+fn matched_marker(
+    markers: &[ResponseMarker],
+    status: u16,
+    body: Option<&serde_json::Value>,
+) -> Option<Outcome> {
+    markers
+        .iter()
+        .find(|marker| marker.matches(status, body))
+        .map(|marker| Outcome::Marked(marker.label.clone()))
+}
+
+/// 字段值的字符串形式：字符串取其内容，其他类型取 JSON 文本
+fn field_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+impl TargetSpec {
+    /// 检查目标定义是否可用，返回错误说明
+    pub fn validate(&self) -> Result<(), String> {
+        let url = render(&self.url, "0", 0);
+        let parsed = reqwest::Url::parse(&url).map_err(|e| format!("无效的地址 {}: {}", url, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("地址必须是 http 或 https: {}", url));
+        }
+        reqwest::Method::from_bytes(self.method.as_bytes())
+            .map_err(|_| format!("无效的请求方法: {}", self.method))?;
+        for (name, value) in &self.headers {
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("无效的请求头名称: {}", name))?;
+            reqwest::header::HeaderValue::from_str(&render(value, "0", 0))
+                .map_err(|_| format!("请求头 {} 的值无效", name))?;
+        }
+        if !self.validation.field.starts_with('/') {
+            return Err(format!(
+                "判定字段必须是以 / 开头的 JSON Pointer: {}",
+                self.validation.field
+            ));
+        }
+        for marker in &self.validation.markers {
+            marker.validate()?;
+        }
+        Ok(())
+    }
+
+    /// 日志中显示的名称，没有设置时使用地址
+    pub fn label(&self) -> &str {
+        if self.name.is_empty() {
+            &self.url
+        } else {
+            &self.name
+        }
+    }
+}
+
+/// 把模板中的 `{id}` 替换为标识，`{index}` 替换为序号
+pub fn render(template: &str, id: &str, index: i64) -> String {
+    template
+        .replace(ID_PLACEHOLDER, id)
+        .replace(INDEX_PLACEHOLDER, &index.to_string())
+}
+
+/// 按目标定义检查标识为 `id`、序号为 `index` 的ID是否有效，返回值同 [`check_id`]
+pub async fn check_target(
+    client: &reqwest::Client,
+    target: &TargetSpec,
+    id: &str,
+    index: i64,
+) -> Outcome {
+    let method =
+        reqwest::Method::from_bytes(target.method.as_bytes()).unwrap_or(reqwest::Method::POST);
+    let mut request = client.request(method, render(&target.url, id, index));
+    let mut has_content_type = false;
+    for (name, value) in &target.headers {
+        has_content_type |= name.eq_ignore_ascii_case("content-type");
+        request = request.header(name.as_str(), render(value, id, index));
+    }
+    if let Some(body) = &target.body_template {
+        if !has_content_type {
+            request = request.header("Content-Type", "application/json");
+        }
+        request = request.body(render(body, id, index));
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        // 超时或连接失败（包括DNS解析失败）不代表ID无效，需要重试
+        Err(e) if e.is_timeout() || e.is_connect() => return Outcome::Retry,
+        Err(_) => return Outcome::Invalid,
+    };
+    let rules = &target.validation;
+    let status = response.status();
+    if let Some(outcome) = matched_marker(&rules.markers, status.as_u16(), None) {
+        return outcome;
+    }
+    if let Some(outcome) = classify_status(status) {
+        return outcome;
+    }
+    let (raw, value) = match read_json(response).await {
+        Ok(parsed) => parsed,
+        Err(outcome) => return outcome,
+    };
+    if let Some(outcome) = matched_marker(&rules.markers, status.as_u16(), Some(&value)) {
+        return outcome;
+    }
+    let Some(field) = value.pointer(&rules.field).filter(|field| !field.is_null()) else {
+        return Outcome::Invalid;
+    };
+    let Some(expected) = &rules.expected else {
+        return Outcome::Valid(raw);
+    };
+    if field_text(field) == render(expected, id, index) {
+        Outcome::Valid(raw)
+    } else {
+        Outcome::Retry // 返回了别的记录，需要重试
+    }
+}
+
+/// 检查ID是否有效
+/// 返回值：
+/// - `Valid` - ID 有效
+/// - `Invalid` - ID 无效
+/// - `Marked` - ID 无效，响应命中了目标定义中的标记（内置上游没有标记，不会返回）
+/// - `Retry` - appId 不匹配、请求超时、连接失败或上游出错，需要重试
+/// - `Blocked` - 上游拒绝了请求（403、429），需要稍后重试
+pub async fn check_id(client: &reqwest::Client, id: i64) -> Outcome {
+    let app_id = format!("C{}", id);
+    let body = serde_json::json!({
+        "appId": app_id,
+        "locale": "zh_CN",
+        "countryCode": "CN",
+        "orderApp": 1
+    });
+
+    let token = crate::code::GLOBAL_CODE_MANAGER.get_full_token().await;
+    let response = client
+        .post("https://web-drcn.hispace.dbankcloud.com/edge/webedge/appinfo")
+        .header("Content-Type", "application/json")
+        .header("User-Agent", crate::code::USER_AGENT.to_string())
+        .header("interface-code", token.interface_code)
+        .header("identity-id", token.identity_id)
+        .json(&body)
+        .send()
+        .await;
+
+    match response {
+        Ok(resp) => {
+            if let Some(outcome) = classify_status(resp.status()) {
+                return outcome;
+            }
+            if resp.content_length().unwrap_or(0) == 0 {
+                return Outcome::Invalid;
+            }
+            let (raw, value) = match read_json(resp).await {
+                Ok(parsed) => parsed,
+                Err(outcome) => return outcome,
+            };
+            let Some(value) = value.as_object() else {
+                return Outcome::Invalid;
+            };
+            if !value.contains_key("appId") {
+                return Outcome::Invalid;
+            }
+            let response_app_id = value.get("appId").and_then(|v| v.as_str());
+            match response_app_id {
+                Some(v) if v == app_id => Outcome::Valid(raw),
+                Some(_) => Outcome::Retry, // appId 不匹配，需要重试
+                None => Outcome::Invalid,
+            }
+        }
+        // 超时或连接失败（包括DNS解析失败）不代表ID无效，需要重试
+        Err(e) if e.is_timeout() || e.is_connect() => Outcome::Retry,
+        Err(_) => Outcome::Invalid,
+    }
+}
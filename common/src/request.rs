@@ -0,0 +1,324 @@
+//! Worker请求体的构造和基本检查
+//!
+//! Worker、运维工具和测试通过这里的构造方法组装请求，租约、签名、协议版本等字段从同一处填入，
+//! 不会在某个调用处漏掉。`check` 是Master收到请求时也会执行的基本检查，Worker可以在发送前
+//! 先检查，配置错误（如空的 worker_id）在启动时就能发现，而不是每次请求都被拒绝。
+
+use crate::coverage::Coverage;
+use crate::latency::LatencyStats;
+use crate::{
+    AcquireTaskRequest, AcquireTaskResponse, ArchiveResponsesRequest, HeartbeatRequest, MarkedId,
+    PartialResultRequest, RawResponse, ReleaseTaskRequest, SubmitResultRequest, SystemSample,
+    WorkerProfile, PROTOCOL_VERSION,
+};
+
+/// worker_id 的最大长度
+pub const MAX_WORKER_ID_LEN: usize = 128;
+
+/// 检查 worker_id：不能为空，不能超过 [`MAX_WORKER_ID_LEN`]
+pub fn check_worker_id(worker_id: &str) -> Result<(), String> {
+    if worker_id.trim().is_empty() {
+        return Err("worker_id不能为空".to_string());
+    }
+    if worker_id.len() > MAX_WORKER_ID_LEN {
+        return Err(format!("worker_id长度不能超过 {}", MAX_WORKER_ID_LEN));
+    }
+    Ok(())
+}
+
+fn check_task_id(task_id: i32) -> Result<(), String> {
+    if task_id <= 0 {
+        return Err(format!("无效的task_id: {}", task_id));
+    }
+    Ok(())
+}
+
+impl AcquireTaskRequest {
+    /// 当前协议版本的获取任务请求，其余字段为空
+    pub fn new(worker_id: impl Into<String>) -> Self {
+        Self {
+            worker_id: worker_id.into(),
+            last_performance: None,
+            last_error_rate: None,
+            max_batch_size: None,
+            profile: None,
+            max_tasks: None,
+            protocol: PROTOCOL_VERSION,
+        }
+    }
+
+    /// 上一个任务的速度（req/s），0 表示还没有测得速度，不上报
+    pub fn with_performance(mut self, speed: impl Into<Option<u32>>) -> Self {
+        self.last_performance = speed.into().filter(|speed| *speed > 0);
+        self
+    }
+
+    /// 上一个任务中上游请求出错的比例
+    pub fn with_error_rate(mut self, rate: impl Into<Option<f64>>) -> Self {
+        self.last_error_rate = rate.into();
+        self
+    }
+
+    /// 能接受的最大范围（ID数）
+    pub fn with_max_batch_size(mut self, max: impl Into<Option<i64>>) -> Self {
+        self.max_batch_size = max.into();
+        self
+    }
+
+    /// 主机信息和标签
+    pub fn with_profile(mut self, profile: WorkerProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// 本次最多领取的任务数，1 与不设置相同
+    pub fn with_max_tasks(mut self, max: u32) -> Self {
+        self.max_tasks = (max != 1).then_some(max);
+        self
+    }
+
+    /// 基本检查
+    pub fn check(&self) -> Result<(), String> {
+        check_worker_id(&self.worker_id)?;
+        if let Some(rate) = self.last_error_rate {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(format!("无效的错误率: {}", rate));
+            }
+        }
+        if matches!(self.max_batch_size, Some(max) if max <= 0) {
+            return Err("范围上限必须大于0".to_string());
+        }
+        if self.max_tasks == Some(0) {
+            return Err("领取的任务数必须大于0".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl HeartbeatRequest {
+    pub fn new(task_id: i32, worker_id: impl Into<String>) -> Self {
+        Self {
+            task_id,
+            worker_id: worker_id.into(),
+            speed: None,
+            system: None,
+        }
+    }
+
+    /// 自上次心跳以来的速度（ID/s）
+    pub fn with_speed(mut self, speed: impl Into<Option<f64>>) -> Self {
+        self.speed = speed.into();
+        self
+    }
+
+    /// 主机的系统状态
+    pub fn with_system(mut self, system: impl Into<Option<SystemSample>>) -> Self {
+        self.system = system.into();
+        self
+    }
+
+    /// 基本检查
+    pub fn check(&self) -> Result<(), String> {
+        check_worker_id(&self.worker_id)?;
+        check_task_id(self.task_id)?;
+        if let Some(speed) = self.speed {
+            if !speed.is_finite() || speed < 0.0 {
+                return Err(format!("无效的速度: {}", speed));
+            }
+        }
+        if let Some(system) = &self.system {
+            if !system.load1.is_finite() || system.load1 < 0.0 {
+                return Err(format!("无效的系统负载: {}", system.load1));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SubmitResultRequest {
+    /// 提交任务的结果，任务ID、租约和签名取自分配的任务
+    pub fn new(
+        task: &AcquireTaskResponse,
+        worker_id: impl Into<String>,
+        valid_ids: Vec<i64>,
+    ) -> Self {
+        Self {
+            task_id: task.task_id,
+            valid_ids,
+            worker_id: worker_id.into(),
+            lease: task.lease,
+            signature: task.signature.clone(),
+            coverage: None,
+            latency: None,
+            idempotency_key: None,
+            marked: Vec::new(),
+            scanned_to: None,
+        }
+    }
+
+    /// 实际探测的ID数和摘要
+    pub fn with_coverage(mut self, coverage: Coverage) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    /// 探测请求的延迟分布
+    pub fn with_latency(mut self, latency: impl Into<Option<LatencyStats>>) -> Self {
+        self.latency = latency.into();
+        self
+    }
+
+    /// 幂等键，重试同一次提交时保持不变
+    pub fn with_idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    /// 命中响应标记的无效ID
+    pub fn with_marked(mut self, marked: Vec<MarkedId>) -> Self {
+        self.marked = marked;
+        self
+    }
+
+    /// 限时任务扫描到的位置
+    pub fn with_scanned_to(mut self, scanned_to: impl Into<Option<i64>>) -> Self {
+        self.scanned_to = scanned_to.into();
+        self
+    }
+
+    /// 基本检查（有效ID数的上限由Master决定，不在这里检查）
+    pub fn check(&self) -> Result<(), String> {
+        check_worker_id(&self.worker_id)?;
+        check_task_id(self.task_id)?;
+        if let Some(id) = self.valid_ids.iter().find(|id| **id < 0) {
+            return Err(format!("无效的有效ID: {}", id));
+        }
+        if let Some(key) = &self.idempotency_key {
+            if key.trim().is_empty() {
+                return Err("幂等键不能为空".to_string());
+            }
+        }
+        if let Some(latency) = &self.latency {
+            let ordered = latency.p50_ms <= latency.p90_ms
+                && latency.p90_ms <= latency.p99_ms
+                && latency.p99_ms <= latency.max_ms;
+            if !ordered {
+                return Err("延迟分位数必须递增".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PartialResultRequest {
+    /// 提前上报任务中新发现的有效ID，任务ID、租约和签名取自分配的任务
+    pub fn new(
+        task: &AcquireTaskResponse,
+        worker_id: impl Into<String>,
+        valid_ids: Vec<i64>,
+    ) -> Self {
+        Self {
+            task_id: task.task_id,
+            valid_ids,
+            worker_id: worker_id.into(),
+            lease: task.lease,
+            signature: task.signature.clone(),
+        }
+    }
+
+    /// 基本检查（有效ID数的上限由Master决定，不在这里检查）
+    pub fn check(&self) -> Result<(), String> {
+        check_worker_id(&self.worker_id)?;
+        check_task_id(self.task_id)?;
+        if let Some(id) = self.valid_ids.iter().find(|id| **id < 0) {
+            return Err(format!("无效的有效ID: {}", id));
+        }
+        Ok(())
+    }
+}
+
+impl ArchiveResponsesRequest {
+    /// 上传任务中有效ID的原始响应，任务ID、租约和签名取自分配的任务
+    pub fn new(
+        task: &AcquireTaskResponse,
+        worker_id: impl Into<String>,
+        responses: Vec<RawResponse>,
+    ) -> Self {
+        Self {
+            task_id: task.task_id,
+            worker_id: worker_id.into(),
+            lease: task.lease,
+            signature: task.signature.clone(),
+            responses,
+        }
+    }
+
+    /// 基本检查（单次上传的响应数上限由Master决定，不在这里检查）
+    pub fn check(&self) -> Result<(), String> {
+        check_worker_id(&self.worker_id)?;
+        check_task_id(self.task_id)?;
+        if self.responses.is_empty() {
+            return Err("没有要存档的响应".to_string());
+        }
+        if let Some(response) = self.responses.iter().find(|response| response.id < 0) {
+            return Err(format!("无效的有效ID: {}", response.id));
+        }
+        Ok(())
+    }
+}
+
+impl ReleaseTaskRequest {
+    pub fn new(task_id: i32, worker_id: impl Into<String>) -> Self {
+        Self {
+            task_id,
+            worker_id: worker_id.into(),
+        }
+    }
+
+    /// 基本检查
+    pub fn check(&self) -> Result<(), String> {
+        check_worker_id(&self.worker_id)?;
+        check_task_id(self.task_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_builders_fill_task_fields() {
+        let task: AcquireTaskResponse = serde_json::from_str(
+            r#"{"task_id": 8, "start_id": 2000, "end_id": 2999, "lease": 1760000000000, "signature": "c2lnbmF0dXJl"}"#,
+        )
+        .unwrap();
+        let submit = SubmitResultRequest::new(&task, "worker-1", vec![task.start_id])
+            .with_idempotency_key("key-1");
+        assert_eq!(submit.task_id, task.task_id);
+        assert_eq!(submit.lease, task.lease);
+        assert_eq!(submit.signature, task.signature);
+        assert!(submit.check().is_ok());
+        assert!(SubmitResultRequest::new(&task, "", Vec::new())
+            .check()
+            .is_err());
+        assert!(
+            SubmitResultRequest::new(&task, "w".repeat(MAX_WORKER_ID_LEN + 1), Vec::new())
+                .check()
+                .is_err()
+        );
+
+        let acquire = AcquireTaskRequest::new("worker-1").with_performance(0);
+        assert_eq!(acquire.last_performance, None);
+        assert_eq!(acquire.protocol, PROTOCOL_VERSION);
+        assert!(acquire.check().is_ok());
+        assert!(AcquireTaskRequest::new(" ").check().is_err());
+        assert!(AcquireTaskRequest::new("worker-1")
+            .with_max_batch_size(0)
+            .check()
+            .is_err());
+        assert!(HeartbeatRequest::new(task.task_id, "worker-1")
+            .with_speed(f64::NAN)
+            .check()
+            .is_err());
+    }
+}
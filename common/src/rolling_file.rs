@@ -0,0 +1,214 @@
+//! 带轮转和保留策略的日志文件
+//!
+//! 按时间（每小时/每天）或按大小轮转，轮转后的文件以时间戳为后缀，
+//! 例如 `pa_master.log.20250101-000000`，只保留最近的若干个。
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 轮转策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// 不轮转
+    Never,
+    /// 每小时轮转（UTC整点）
+    Hourly,
+    /// 每天轮转（UTC零点）
+    Daily,
+    /// 文件超过指定字节数时轮转
+    Size(u64),
+}
+
+impl FromStr for Rotation {
+    type Err = String;
+
+    /// 支持 `never`、`hourly`、`daily` 以及 `100MB`、`512KB`、`1GB` 形式的大小
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        match lower.as_str() {
+            "never" => return Ok(Rotation::Never),
+            "hourly" => return Ok(Rotation::Hourly),
+            "daily" => return Ok(Rotation::Daily),
+            _ => {}
+        }
+
+        let (number, unit) = match lower.find(|c: char| !c.is_ascii_digit()) {
+            Some(index) => lower.split_at(index),
+            None => (lower.as_str(), ""),
+        };
+        let multiplier = match unit {
+            "" | "b" => 1,
+            "kb" | "k" => 1024,
+            "mb" | "m" => 1024 * 1024,
+            "gb" | "g" => 1024 * 1024 * 1024,
+            _ => return Err(format!("无法识别的轮转策略: {}", s)),
+        };
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("无法识别的轮转策略: {}", s))?;
+        if number == 0 {
+            return Err("轮转大小必须大于0".to_string());
+        }
+
+        Ok(Rotation::Size(number * multiplier))
+    }
+}
+
+impl std::fmt::Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rotation::Never => write!(f, "never"),
+            Rotation::Hourly => write!(f, "hourly"),
+            Rotation::Daily => write!(f, "daily"),
+            Rotation::Size(bytes) => write!(f, "{}B", bytes),
+        }
+    }
+}
+
+impl Rotation {
+    /// 时间所在的轮转周期编号（按大小轮转或不轮转时恒为0）
+    fn period_of(&self, time: SystemTime) -> u64 {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        match self {
+            Rotation::Hourly => secs / 3600,
+            Rotation::Daily => secs / 86400,
+            Rotation::Never | Rotation::Size(_) => 0,
+        }
+    }
+}
+
+/// 带轮转的日志文件写入器
+pub struct RollingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    max_files: usize,
+    file: File,
+    size: u64,
+    period: u64,
+}
+
+impl RollingFile {
+    /// 打开（或创建）日志文件
+    ///
+    /// `max_files` 为保留的历史文件数，0 表示全部保留。
+    pub fn open(path: impl AsRef<Path>, rotation: Rotation, max_files: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let metadata = file.metadata()?;
+        let period = rotation.period_of(metadata.modified().unwrap_or_else(|_| SystemTime::now()));
+
+        Ok(Self {
+            path,
+            rotation,
+            max_files,
+            file,
+            size: metadata.len(),
+            period,
+        })
+    }
+
+    /// 写入 `incoming` 字节前是否需要轮转
+    fn should_roll(&self, incoming: usize) -> bool {
+        match self.rotation {
+            Rotation::Never => false,
+            Rotation::Size(limit) => self.size > 0 && self.size + incoming as u64 > limit,
+            Rotation::Hourly | Rotation::Daily => {
+                self.rotation.period_of(SystemTime::now()) != self.period
+            }
+        }
+    }
+
+    /// 执行轮转：重命名当前文件，打开新文件，清理过期文件
+    fn roll(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        let suffix = chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut rotated = self.rotated_path(&suffix);
+        let mut counter = 1;
+        while rotated.exists() {
+            rotated = self.rotated_path(&format!("{}.{}", suffix, counter));
+            counter += 1;
+        }
+        fs::rename(&self.path, &rotated)?;
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        self.period = self.rotation.period_of(SystemTime::now());
+
+        self.cleanup()
+    }
+
+    fn rotated_path(&self, suffix: &str) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(suffix);
+        self.path.with_file_name(name)
+    }
+
+    /// 删除超出保留数量的历史文件
+    fn cleanup(&self) -> io::Result<()> {
+        if self.max_files == 0 {
+            return Ok(());
+        }
+
+        let dir = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let prefix = format!(
+            "{}.",
+            self.path.file_name().unwrap_or_default().to_string_lossy()
+        );
+
+        let mut rotated: Vec<PathBuf> = fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .map(|entry| entry.path())
+            .collect();
+
+        // 后缀是时间戳，按文件名排序即按时间排序
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.max_files);
+        for path in &rotated[..excess] {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.should_roll(buf.len()) {
+            if let Err(e) = self.roll() {
+                eprintln!("日志文件轮转失败: {}", e);
+                // 避免每次写入都重试轮转
+                self.period = self.rotation.period_of(SystemTime::now());
+                self.size = 0;
+            }
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -0,0 +1,106 @@
+//! HTTP 接口的路径
+//!
+//! Master的路由和 [`crate::client::MasterClient`]、Worker的调用都使用这里的常量，
+//! 接口改名时两端一起改变，不会出现一端改了路径、另一端还在请求旧路径的情况。
+//!
+//! 带路径参数的接口按 axum 的写法（`{name}`）定义，客户端用 [`fill`] 填入参数。
+
+/// Worker调用的接口（`/task/*`）
+pub mod task {
+    /// 所有Worker接口的路径前缀
+    pub const PREFIX: &str = "/task";
+
+    /// 获取任务
+    pub const ACQUIRE: &str = "/task/acquire";
+
+    /// 任务心跳
+    pub const HEARTBEAT: &str = "/task/heartbeat";
+
+    /// 提交任务结果
+    pub const SUBMIT: &str = "/task/submit";
+
+    /// 提前上报已发现的有效ID
+    pub const RESULTS: &str = "/task/results";
+
+    /// 上传有效ID的原始响应
+    pub const RESPONSES: &str = "/task/responses";
+
+    /// 主动释放任务
+    pub const RELEASE: &str = "/task/release";
+}
+
+/// 管理接口（`/admin/*`）
+pub mod admin {
+    /// 所有管理接口的路径前缀
+    pub const PREFIX: &str = "/admin";
+
+    pub const STATS: &str = "/admin/stats";
+    pub const STATS_HISTORY: &str = "/admin/stats/history";
+    pub const WORKERS: &str = "/admin/workers";
+    pub const CLUSTER: &str = "/admin/cluster";
+    pub const ALERTS: &str = "/admin/alerts";
+    pub const TASKS: &str = "/admin/tasks";
+    pub const TASK_DURATIONS: &str = "/admin/tasks/durations";
+    pub const RECENT_RESULTS: &str = "/admin/results/recent";
+    pub const MARKED: &str = "/admin/marked";
+    pub const PAUSE_DISPATCH: &str = "/admin/dispatch/pause";
+    pub const RESUME_DISPATCH: &str = "/admin/dispatch/resume";
+    pub const MAINTENANCE: &str = "/admin/maintenance";
+    pub const BATCH: &str = "/admin/batch";
+    pub const BANS: &str = "/admin/bans";
+    pub const BAN: &str = "/admin/bans/{worker_id}";
+    pub const REQUEUE: &str = "/admin/requeue";
+    pub const REQUEUE_UNCHECKED: &str = "/admin/requeue-unchecked";
+    pub const CANDIDATES: &str = "/admin/candidates";
+    pub const AUDIT: &str = "/admin/audit";
+    pub const KEYS: &str = "/admin/keys";
+    pub const KEY: &str = "/admin/keys/{key_id}";
+    pub const VERIFICATION: &str = "/admin/verification";
+    pub const SPOT_CHECKS: &str = "/admin/spot-checks";
+    pub const COVERAGE: &str = "/admin/coverage";
+}
+
+/// GraphQL 查询
+pub const GRAPHQL: &str = "/graphql";
+
+/// Prometheus 指标
+pub const METRICS: &str = "/metrics";
+
+/// 存活检查
+pub const HEALTHZ: &str = "/healthz";
+
+/// 就绪检查
+pub const READYZ: &str = "/readyz";
+
+/// 把路径中第一个 `{name}` 形式的参数替换为 `value`（调用方负责编码），路径中没有参数时原样返回
+pub fn fill(path: &str, value: &str) -> String {
+    match (path.find('{'), path.find('}')) {
+        (Some(start), Some(end)) if start < end => {
+            format!("{}{}{}", &path[..start], value, &path[end + 1..])
+        }
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_routes_are_stable() {
+        // 滚动升级期间旧版本Worker仍按原来的路径请求
+        assert_eq!(task::ACQUIRE, "/task/acquire");
+        assert_eq!(task::HEARTBEAT, "/task/heartbeat");
+        assert_eq!(task::SUBMIT, "/task/submit");
+        assert_eq!(task::RESULTS, "/task/results");
+        assert_eq!(task::RESPONSES, "/task/responses");
+        assert_eq!(task::RELEASE, "/task/release");
+    }
+
+    #[test]
+    fn route_parameters_are_filled() {
+        assert_eq!(fill(admin::BAN, "worker%201"), "/admin/bans/worker%201");
+        assert_eq!(fill(admin::KEY, "7"), "/admin/keys/7");
+        assert_eq!(fill(admin::STATS, "7"), "/admin/stats");
+    }
+}
@@ -0,0 +1,45 @@
+//! Tokio 运行时配置
+//!
+//! 默认的工作线程数取 [`std::thread::available_parallelism`]，在容器中它可能读到宿主机的核数，
+//! 或者在 CPU 配额很小的机器上开出过多线程。Master和Worker提供 `--worker-threads` 和
+//! `--max-blocking-threads` 覆盖默认值，解析完命令行后再用 [`RuntimeConfig::build`] 创建运行时。
+
+use std::io;
+use std::num::NonZeroUsize;
+use tokio::runtime::{Builder, Runtime};
+
+/// Tokio 阻塞线程池的默认上限（与 Tokio 自身的默认值相同）
+const DEFAULT_MAX_BLOCKING_THREADS: usize = 512;
+
+/// 运行时参数，None 表示使用默认值
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RuntimeConfig {
+    /// 异步工作线程数
+    pub worker_threads: Option<NonZeroUsize>,
+    /// 阻塞线程池（`spawn_blocking`、文件 IO 等）的线程数上限
+    pub max_blocking_threads: Option<NonZeroUsize>,
+}
+
+impl RuntimeConfig {
+    /// 实际使用的工作线程数
+    pub fn worker_threads(&self) -> usize {
+        self.worker_threads
+            .or_else(|| std::thread::available_parallelism().ok())
+            .map_or(1, NonZeroUsize::get)
+    }
+
+    /// 实际使用的阻塞线程数上限
+    pub fn max_blocking_threads(&self) -> usize {
+        self.max_blocking_threads
+            .map_or(DEFAULT_MAX_BLOCKING_THREADS, NonZeroUsize::get)
+    }
+
+    /// 创建多线程运行时
+    pub fn build(&self) -> io::Result<Runtime> {
+        Builder::new_multi_thread()
+            .worker_threads(self.worker_threads())
+            .max_blocking_threads(self.max_blocking_threads())
+            .enable_all()
+            .build()
+    }
+}
@@ -0,0 +1,211 @@
+//! 日志与链路追踪初始化
+//!
+//! Master和Worker共用：
+//! - 终端日志（人类可读格式或 JSON 格式）
+//! - 可选的日志文件输出（按时间或大小轮转）
+//! - 可选的 OTLP 链路追踪导出（Jaeger / Tempo 等）
+//! - 通过 W3C `traceparent` 头在Worker和Master之间传播链路上下文
+
+use crate::rolling_file::{RollingFile, Rotation};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::Context;
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+use reqwest::header::HeaderMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::{DefaultFields, Writer};
+use tracing_subscriber::fmt::FormatFields;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+/// 日志输出格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// 人类可读的文本格式
+    #[default]
+    Text,
+    /// 每行一个 JSON 对象，便于 Loki / ELK 采集
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("未知的日志格式: {}（可选 text、json）", other)),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogFormat::Text => write!(f, "text"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// 日志与链路追踪配置
+pub struct TelemetryConfig<'a> {
+    /// 服务名（上报到链路追踪后端时使用）
+    pub service_name: &'static str,
+
+    /// 日志输出格式
+    pub log_format: LogFormat,
+
+    /// OTLP HTTP 导出地址（如 `http://localhost:4318/v1/traces`），None 表示不导出
+    pub otlp_endpoint: Option<&'a str>,
+
+    /// 日志文件（在终端输出之外额外写入），None 表示不写文件
+    pub log_file: Option<LogFileConfig<'a>>,
+}
+
+/// 日志文件配置
+pub struct LogFileConfig<'a> {
+    /// 日志文件路径
+    pub path: &'a Path,
+
+    /// 轮转策略
+    pub rotation: Rotation,
+
+    /// 保留的历史文件数（0 表示全部保留）
+    pub max_files: usize,
+}
+
+/// 日志与链路追踪守卫，drop 时刷新日志文件并关闭导出器
+pub struct TelemetryGuard {
+    provider: Option<SdkTracerProvider>,
+    _file_guard: Option<WorkerGuard>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("关闭链路追踪导出器失败: {}", e);
+            }
+        }
+    }
+}
+
+/// 初始化全局日志与链路追踪
+///
+/// 返回的守卫需要一直持有到进程退出。
+pub fn init_tracing(
+    config: &TelemetryConfig,
+) -> Result<TelemetryGuard, Box<dyn std::error::Error>> {
+    let provider = match config.otlp_endpoint {
+        Some(endpoint) => {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(endpoint)
+                .build()?;
+
+            let provider = SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(
+                    Resource::builder()
+                        .with_service_name(config.service_name)
+                        .build(),
+                )
+                .build();
+
+            opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+            Some(provider)
+        }
+        None => None,
+    };
+
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(config.service_name))
+    });
+
+    let mut layers = vec![fmt_layer(config.log_format, std::io::stdout, true)];
+
+    let file_guard = match &config.log_file {
+        Some(file) => {
+            let writer = RollingFile::open(file.path, file.rotation, file.max_files)?;
+            let (writer, guard) = tracing_appender::non_blocking(writer);
+            layers.push(fmt_layer(config.log_format, writer, false));
+            Some(guard)
+        }
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(layers)
+        .with(otel_layer)
+        .try_init()?;
+
+    Ok(TelemetryGuard {
+        provider,
+        _file_guard: file_guard,
+    })
+}
+
+/// 按日志格式构造输出层
+///
+/// JSON 格式中带上所在 span 的字段（worker_id、task_id、request_id 等）
+fn fmt_layer<S, W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+    W: for<'writer> tracing_subscriber::fmt::MakeWriter<'writer> + Send + Sync + 'static,
+{
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(ansi);
+    match format {
+        LogFormat::Text if !ansi => layer.fmt_fields(PlainFields::default()).boxed(),
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+    }
+}
+
+/// 将当前 span 的链路上下文写入请求头
+pub fn inject_context(headers: &mut HeaderMap) {
+    let context = tracing::Span::current().context();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(headers))
+    });
+}
+
+/// 从请求头中提取上游的链路上下文
+pub fn extract_context(headers: &HeaderMap) -> Context {
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(headers))
+    })
+}
+
+/// 将 span 的父上下文设置为请求头中携带的上游上下文
+pub fn set_parent_from_headers(span: &tracing::Span, headers: &HeaderMap) {
+    let _ = span.set_parent(extract_context(headers));
+}
+
+/// 不带颜色的字段格式化器
+///
+/// span 字段的格式化结果按格式化器类型缓存在 span 上，终端层与文件层
+/// 如果都用 `DefaultFields` 会共享同一份（带 ANSI 颜色的）结果，
+/// 所以文件层使用单独的类型。
+#[derive(Default)]
+struct PlainFields(DefaultFields);
+
+impl<'writer> FormatFields<'writer> for PlainFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        self.0.format_fields(writer, fields)
+    }
+}
@@ -0,0 +1,355 @@
+//! 请求/响应结构体的兼容性检查
+//!
+//! `fixtures/` 中是旧版本（`_v1`）和假想的新版本（`_future`）发出的请求/响应，
+//! 滚动升级期间当前版本必须能解析它们。
+
+use common::admin::StatsResponse;
+use common::client::ClientError;
+use common::coverage::Coverage;
+use common::ids::{Checksum, IdKind};
+use common::latency::LatencyStats;
+use common::probe::{self, ResponseMarker, TargetSpec, ValidationRules};
+use common::{
+    AcquireTaskRequest, AcquireTaskResponse, ApiResponse, ArchiveResponsesRequest, ErrorCode,
+    HeartbeatRequest, HeartbeatResponse, MarkedId, PartialResultRequest, RawResponse,
+    ReleaseTaskRequest, SubmitResultRequest, SystemSample, WorkerProfile,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+fn parse<T: DeserializeOwned>(json: &str) -> T {
+    serde_json::from_str(json).expect("样例应能解析")
+}
+
+/// 序列化后再解析，结果应与原值序列化的结果相同
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> serde_json::Value {
+    let json = serde_json::to_value(value).unwrap();
+    let parsed: T = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&parsed).unwrap(), json);
+    json
+}
+
+#[test]
+fn old_acquire_request() {
+    let request: AcquireTaskRequest = parse(include_str!("fixtures/acquire_request_v1.json"));
+    assert_eq!(request.worker_id, "worker-1");
+    assert_eq!(request.last_performance, None);
+    assert_eq!(request.max_batch_size, None);
+    assert!(request.profile.is_none());
+    assert_eq!(request.max_tasks, None);
+    assert_eq!(request.protocol, 1);
+}
+
+#[test]
+fn old_acquire_response() {
+    let response: ApiResponse<AcquireTaskResponse> =
+        parse(include_str!("fixtures/acquire_response_v1.json"));
+    let task = response.into_result().unwrap();
+    assert_eq!((task.task_id, task.start_id, task.end_id), (7, 1000, 1999));
+    assert_eq!(task.lease, 0);
+    assert!(task.signature.is_empty());
+    assert_eq!(task.suggested_concurrency, None);
+    assert_eq!(task.rate_limit, None);
+    assert!(task.target.is_none());
+    assert!(task.id_kind.is_integer());
+    assert!(!task.is_list());
+    assert!(!task.is_timed());
+    assert_eq!(task.id_count(), 1000);
+    assert_eq!(task.into_tasks().len(), 1);
+}
+
+#[test]
+fn batch_acquire_response() {
+    let response: ApiResponse<AcquireTaskResponse> =
+        parse(include_str!("fixtures/acquire_response_v5.json"));
+    let tasks = response.into_result().unwrap().into_tasks();
+    let ids: Vec<_> = tasks.iter().map(|task| task.task_id).collect();
+    assert_eq!(ids, [7, 8, 9]);
+    assert!(tasks.iter().all(|task| task.extra_tasks.is_empty()));
+    assert_eq!(tasks[1].rate_limit, Some(12.5));
+    assert!(tasks[2].is_list());
+}
+
+#[test]
+fn acquire_response_with_target() {
+    let response: ApiResponse<AcquireTaskResponse> =
+        parse(include_str!("fixtures/acquire_response_v2.json"));
+    let target = response.into_result().unwrap().target.unwrap();
+    assert_eq!(target.label(), "mirror-1");
+    assert_eq!(target.method, "POST");
+    assert!(target.validate().is_ok());
+    let body = probe::render(target.body_template.as_deref().unwrap(), "2001", 2001);
+    assert_eq!(body, r#"{"appId": "C2001"}"#);
+    assert_eq!(target.validation.expected.as_deref(), Some("C{id}"));
+    assert!(target.validation.markers.is_empty());
+}
+
+#[test]
+fn response_markers_need_a_condition() {
+    let marker: ResponseMarker = parse(r#"{"label": "removed", "field": "/offShelf"}"#);
+    assert!(marker.validate().is_ok());
+    let marker: ResponseMarker = parse(r#"{"label": "region_restricted", "status": 451}"#);
+    assert!(marker.validate().is_ok());
+    let marker: ResponseMarker = parse(r#"{"label": "removed"}"#);
+    assert!(marker.validate().is_err());
+    let marker: ResponseMarker = parse(r#"{"label": "removed", "field": "offShelf"}"#);
+    assert!(marker.validate().is_err());
+}
+
+#[test]
+fn future_acquire_response_ignores_unknown_fields() {
+    let response: ApiResponse<AcquireTaskResponse> =
+        parse(include_str!("fixtures/acquire_response_future.json"));
+    let task = response.into_result().unwrap();
+    assert_eq!(task.task_id, 7);
+    assert_eq!(task.lease, 1_760_000_000_000);
+    assert_eq!(task.rate_limit, Some(12.5));
+}
+
+#[test]
+fn old_heartbeat_response_is_not_an_abort() {
+    let response: ApiResponse<HeartbeatResponse> =
+        parse(include_str!("fixtures/heartbeat_response_v1.json"));
+    let heartbeat = response.into_result().unwrap();
+    assert!(!heartbeat.abort);
+    assert_eq!(heartbeat.reason, None);
+}
+
+#[test]
+fn heartbeat_response_without_rate_limit() {
+    let response: ApiResponse<HeartbeatResponse> =
+        parse(include_str!("fixtures/heartbeat_response_v2.json"));
+    let heartbeat = response.into_result().unwrap();
+    assert!(heartbeat.abort);
+    assert_eq!(heartbeat.reason.as_deref(), Some("任务已重新分配"));
+    assert_eq!(heartbeat.rate_limit, None);
+}
+
+#[test]
+fn old_submit_request() {
+    let request: SubmitResultRequest = parse(include_str!("fixtures/submit_request_v1.json"));
+    assert_eq!(request.valid_ids, vec![1001, 1500]);
+    assert!(request.worker_id.is_empty());
+    assert_eq!(request.lease, 0);
+    assert!(request.coverage.is_none());
+    assert!(request.latency.is_none());
+    assert!(request.idempotency_key.is_none());
+    assert!(request.marked.is_empty());
+    assert!(request.scanned_to.is_none());
+}
+
+#[test]
+fn coverage_without_unchecked_counts() {
+    let request: SubmitResultRequest = parse(include_str!("fixtures/submit_request_v2.json"));
+    let coverage = request.coverage.unwrap();
+    assert_eq!((coverage.unknown, coverage.blocked), (0, 0));
+    assert_eq!(coverage.probed(), 1000);
+}
+
+#[test]
+fn old_partial_result_request() {
+    let request: PartialResultRequest =
+        parse(include_str!("fixtures/partial_result_request_v1.json"));
+    assert_eq!(request.worker_id, "worker-1");
+    assert_eq!(request.lease, 0);
+    assert!(request.signature.is_empty());
+}
+
+#[test]
+fn old_error_response() {
+    let response: ApiResponse<AcquireTaskResponse> =
+        parse(include_str!("fixtures/error_response_v1.json"));
+    let error = response.into_result().unwrap_err();
+    assert_eq!(error.message, "没有可用的任务");
+    assert_eq!(error.code, None);
+    assert_eq!(error.request_id, None);
+}
+
+#[test]
+fn future_error_code_is_unknown() {
+    let response: ApiResponse<AcquireTaskResponse> =
+        parse(include_str!("fixtures/error_response_future.json"));
+    let error = response.into_result().unwrap_err();
+    assert_eq!(error.code, Some(ErrorCode::Unknown));
+    assert_eq!(error.request_id.as_deref(), Some("req-2"));
+}
+
+#[test]
+fn api_error_converts_to_client_error() {
+    fn task(json: &str) -> Result<AcquireTaskResponse, ClientError> {
+        let response: ApiResponse<AcquireTaskResponse> = parse(json);
+        Ok(response.into_result()?)
+    }
+    assert!(task(include_str!("fixtures/acquire_response_v1.json")).is_ok());
+
+    let error = task(include_str!("fixtures/error_response_future.json")).unwrap_err();
+    assert!(!error.is_retryable());
+    assert_eq!(error.retry_after(), None);
+    match error {
+        ClientError::Api {
+            status,
+            message,
+            code,
+            request_id,
+            ..
+        } => {
+            assert_eq!(status, 0);
+            assert_eq!(message, "上游维护中");
+            assert_eq!(code, Some(ErrorCode::Unknown));
+            assert_eq!(request_id, "req-2");
+        }
+        other => panic!("应转换为 Api 错误: {:?}", other),
+    }
+}
+
+#[test]
+fn old_stats_response() {
+    let stats: StatsResponse = parse(include_str!("fixtures/stats_v1.json"));
+    assert_eq!(stats.cursor, 5000);
+    assert!(!stats.dispatch_paused);
+    assert!(stats.upstream_latency.is_none());
+    assert_eq!(stats.unchecked_ids, 0);
+}
+
+#[test]
+fn round_trip_full_payloads() {
+    let profile = WorkerProfile {
+        hostname: Some("host-1".to_string()),
+        region: Some("cn-east".to_string()),
+        egress: None,
+        labels: BTreeMap::from([("rack".to_string(), "a1".to_string())]),
+        version: Some("0.1.0".to_string()),
+    };
+    round_trip(&AcquireTaskRequest {
+        worker_id: "worker-1".to_string(),
+        last_performance: Some(120),
+        last_error_rate: Some(0.02),
+        max_batch_size: Some(5000),
+        profile: Some(profile),
+        max_tasks: Some(4),
+        protocol: common::PROTOCOL_VERSION,
+    });
+    round_trip(&AcquireTaskResponse {
+        task_id: 7,
+        start_id: 1000,
+        end_id: 1999,
+        lease: 1_760_000_000_000,
+        signature: "c2lnbmF0dXJl".to_string(),
+        suggested_concurrency: Some(32),
+        rate_limit: Some(12.5),
+        target: Some(TargetSpec {
+            name: String::new(),
+            url: "https://example.com/item/{id}".to_string(),
+            method: "GET".to_string(),
+            headers: BTreeMap::new(),
+            body_template: None,
+            validation: ValidationRules {
+                field: "/data/id".to_string(),
+                expected: None,
+                markers: vec![ResponseMarker {
+                    label: "removed".to_string(),
+                    status: None,
+                    field: Some("/data/status".to_string()),
+                    equals: Some("offline".to_string()),
+                }],
+            },
+        }),
+        id_kind: IdKind::Template {
+            template: "C{n}".to_string(),
+            width: 9,
+            checksum: Checksum::Luhn,
+        },
+        ids: vec![1003, 1500, 1999],
+        scan_for_secs: Some(300),
+        extra_tasks: vec![parse::<ApiResponse<AcquireTaskResponse>>(include_str!(
+            "fixtures/acquire_response_v1.json"
+        ))
+        .into_result()
+        .unwrap()],
+    });
+    round_trip(&HeartbeatRequest {
+        task_id: 7,
+        worker_id: "worker-1".to_string(),
+        speed: Some(98.5),
+        system: Some(SystemSample {
+            load1: 1.5,
+            cpus: 8,
+            mem_total_kb: 16_000_000,
+            mem_available_kb: 4_000_000,
+            swap_used_kb: 1024,
+            net_errors: 3,
+            net_drops: 12,
+        }),
+    });
+    round_trip(&HeartbeatResponse {
+        abort: true,
+        reason: Some("任务已重新分配".to_string()),
+        rate_limit: Some(3.0),
+    });
+    round_trip(&SubmitResultRequest {
+        task_id: 7,
+        valid_ids: vec![1001, 1500],
+        worker_id: "worker-1".to_string(),
+        lease: 1_760_000_000_000,
+        signature: "c2lnbmF0dXJl".to_string(),
+        coverage: Some(Coverage {
+            valid: 2,
+            invalid: 995,
+            unknown: 2,
+            blocked: 1,
+            digest: 42,
+        }),
+        latency: Some(LatencyStats {
+            count: 1000,
+            p50_ms: 40,
+            p90_ms: 80,
+            p99_ms: 200,
+            max_ms: 450,
+        }),
+        idempotency_key: Some("key-1".to_string()),
+        marked: vec![MarkedId {
+            id: 1002,
+            label: "removed".to_string(),
+        }],
+        scanned_to: Some(1800),
+    });
+    round_trip(&ArchiveResponsesRequest {
+        task_id: 7,
+        worker_id: "worker-1".to_string(),
+        lease: 1_760_000_000_000,
+        signature: "c2lnbmF0dXJl".to_string(),
+        responses: vec![RawResponse {
+            id: 1001,
+            body: "H4sIAAAAAAAAA6tWSixILMpUslIyNDAwVKoFAJxD4A4RAAAA".to_string(),
+        }],
+    });
+    round_trip(&ReleaseTaskRequest {
+        task_id: 7,
+        worker_id: "worker-1".to_string(),
+    });
+    round_trip(
+        &ApiResponse::<String>::error("没有可用的任务".to_string())
+            .with_code(ErrorCode::NoTaskAvailable),
+    );
+}
+
+#[test]
+fn optional_fields_are_omitted() {
+    let json = round_trip(&AcquireTaskRequest {
+        worker_id: "worker-1".to_string(),
+        last_performance: None,
+        last_error_rate: None,
+        max_batch_size: None,
+        profile: None,
+        max_tasks: None,
+        protocol: 1,
+    });
+    assert_eq!(
+        json,
+        serde_json::json!({"worker_id": "worker-1", "protocol": 1})
+    );
+
+    let json = round_trip(&HeartbeatResponse::default());
+    assert_eq!(json, serde_json::json!({"abort": false}));
+}
@@ -1,5 +1,5 @@
--- 分布式ID扫描系统数据库初始化脚本
--- SQLite
+-- 初始表结构
+-- 使用 IF NOT EXISTS，兼容迁移机制引入之前由 init_database 创建的数据库
 
 -- 1. global_cursor表: 存储全局任务分配进度
 CREATE TABLE IF NOT EXISTS global_cursor (
@@ -33,4 +33,4 @@ CREATE TABLE IF NOT EXISTS valid_results (
 );
 
 -- 在found_at上创建索引，便于按时间查询
-CREATE INDEX IF NOT EXISTS idx_valid_results_found_at ON valid_results(found_at);
\ No newline at end of file
+CREATE INDEX IF NOT EXISTS idx_valid_results_found_at ON valid_results(found_at);
@@ -0,0 +1,15 @@
+-- 已完成任务的归档
+-- submit 时从 task_queue 移入，记录哪些范围已经扫描完成，用于覆盖率检查和统计
+
+CREATE TABLE task_archive (
+    task_id INTEGER PRIMARY KEY,
+    start_id INTEGER NOT NULL,
+    end_id INTEGER NOT NULL,
+    worker_id TEXT NOT NULL,
+    valid_count INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL,
+    completed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_task_archive_start_id ON task_archive(start_id);
+CREATE INDEX idx_task_archive_completed_at ON task_archive(completed_at);
@@ -0,0 +1,8 @@
+-- 被封禁的Worker
+-- 封禁后 acquire 和心跳都会被拒绝，持有的任务会被回收重新分配
+
+CREATE TABLE banned_workers (
+    worker_id TEXT PRIMARY KEY,
+    reason TEXT NOT NULL DEFAULT '',
+    banned_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,9 @@
+-- 主节点租约（主备高可用）
+-- 只有一行，持有租约的节点负责任务分发，租约过期后备用节点接管
+
+CREATE TABLE master_lease (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    holder TEXT NOT NULL,
+    expires_at DATETIME NOT NULL,
+    acquired_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- 管理操作审计日志
+-- 记录暂停/恢复分发、修改游标、封禁、重新排队、清空等操作的操作人和参数
+
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    details TEXT NOT NULL DEFAULT '{}',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_audit_log_action ON audit_log(action);
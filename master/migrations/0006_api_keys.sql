@@ -0,0 +1,24 @@
+-- Worker的API Key和配额
+-- 密钥只保存 SHA-256 摘要；task_queue.key_id 记录任务由哪个API Key领取，用于统计并发任务数
+
+CREATE TABLE api_keys (
+    key_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    max_concurrent_tasks INTEGER,
+    max_ids_per_day INTEGER,
+    revoked INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 每个API Key每天（UTC）分配的ID数
+CREATE TABLE api_key_usage (
+    key_id INTEGER NOT NULL,
+    day TEXT NOT NULL,
+    ids_assigned INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, day)
+);
+
+ALTER TABLE task_queue ADD COLUMN key_id INTEGER;
+
+CREATE INDEX idx_task_queue_key_id ON task_queue(key_id);
@@ -0,0 +1,8 @@
+-- Master使用的密钥（如任务分配签名密钥）
+-- 未通过命令行指定时首次启动自动生成，主备节点共用同一个数据库，因此使用同一个密钥
+
+CREATE TABLE server_secrets (
+    name TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
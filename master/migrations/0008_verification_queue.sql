@@ -0,0 +1,23 @@
+-- 待复查的可疑提交
+-- 提交结果明显不合理时（完成过快、命中率与相邻范围差异过大等）记录在这里，
+-- 并为该范围创建一个复查任务，由其他Worker重新扫描后比对结果
+
+CREATE TABLE verification_queue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    task_id INTEGER NOT NULL,
+    start_id INTEGER NOT NULL,
+    end_id INTEGER NOT NULL,
+    worker_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    valid_count INTEGER NOT NULL,
+    verify_task_id INTEGER,
+    verified_count INTEGER,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'done')),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    completed_at DATETIME
+);
+
+CREATE INDEX idx_verification_queue_status ON verification_queue(status);
+
+-- 复查任务指向对应的 verification_queue 记录
+ALTER TABLE task_queue ADD COLUMN verification_id INTEGER;
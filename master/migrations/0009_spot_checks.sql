@@ -0,0 +1,20 @@
+-- Master抽查Worker提交结果的统计
+-- 每个Worker的抽查次数和结论一致的次数，以及结论不一致的明细
+
+CREATE TABLE spot_checks (
+    worker_id TEXT PRIMARY KEY,
+    checked INTEGER NOT NULL DEFAULT 0,
+    agreed INTEGER NOT NULL DEFAULT 0,
+    last_checked_at DATETIME
+);
+
+CREATE TABLE spot_check_mismatches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    worker_id TEXT NOT NULL,
+    task_id INTEGER NOT NULL,
+    item_id INTEGER NOT NULL,
+    claimed_valid BOOLEAN NOT NULL,
+    checked_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_spot_check_mismatches_worker_id ON spot_check_mismatches(worker_id);
@@ -0,0 +1,11 @@
+-- 带幂等键的提交结果
+-- Worker在超时后重试提交时，Master据此返回首次提交的结果，而不是“任务不存在”
+
+CREATE TABLE submit_outcomes (
+    idempotency_key TEXT PRIMARY KEY,
+    task_id INTEGER NOT NULL,
+    message TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_submit_outcomes_created_at ON submit_outcomes(created_at);
@@ -0,0 +1,11 @@
+-- Worker上报的主机信息和标签
+-- 每个Worker只保留最近一次上报的内容，labels 为 JSON 对象
+
+CREATE TABLE worker_profiles (
+    worker_id TEXT PRIMARY KEY,
+    hostname TEXT,
+    region TEXT,
+    egress TEXT,
+    labels TEXT NOT NULL DEFAULT '{}',
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
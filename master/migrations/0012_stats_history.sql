@@ -0,0 +1,14 @@
+-- 按分钟汇总的扫描量和上游延迟，用于对照命中率的变化和上游变慢、封禁等事件
+-- 每分钟一行，提交结果时累加；延迟分位数按请求数加权累加，读取时除以 latency_requests 得到平均值
+
+CREATE TABLE stats_history (
+    minute DATETIME PRIMARY KEY,
+    tasks INTEGER NOT NULL DEFAULT 0,
+    ids_scanned INTEGER NOT NULL DEFAULT 0,
+    valid_found INTEGER NOT NULL DEFAULT 0,
+    latency_requests INTEGER NOT NULL DEFAULT 0,
+    p50_ms_sum INTEGER NOT NULL DEFAULT 0,
+    p90_ms_sum INTEGER NOT NULL DEFAULT 0,
+    p99_ms_sum INTEGER NOT NULL DEFAULT 0,
+    max_ms INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,8 @@
+-- 压缩后的有效ID：连续的一段ID按范围保存，found_at 为其中最晚的发现时间
+-- 范围互不重叠也不相邻；valid_results 中不会再出现被范围覆盖的ID
+
+CREATE TABLE valid_ranges (
+    start_id INTEGER PRIMARY KEY,
+    end_id INTEGER NOT NULL,
+    found_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,4 @@
+-- API Key的角色：worker 只能调用 /task/*，read_only 可以查看管理接口，admin 可以调用所有管理接口
+-- 已有的API Key都是Worker使用的
+
+ALTER TABLE api_keys ADD COLUMN role TEXT NOT NULL DEFAULT 'worker';
@@ -0,0 +1,8 @@
+-- 整个扫描的设置（如ID类型），首次启动时写入
+-- 结果表中的ID是序号，设置改变后已有的结果会被按新的方式解读，因此启动时检查与命令行是否一致
+
+CREATE TABLE job_settings (
+    name TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
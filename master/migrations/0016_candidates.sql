@@ -0,0 +1,18 @@
+-- 候选ID：运维给出的待扫描ID（复查指定的ID、非连续的ID空间）
+-- 调度时先于全局游标分配，每次取出一批作为一个显式ID列表任务
+
+CREATE TABLE candidates (
+    id INTEGER PRIMARY KEY,
+    -- 分配到的任务，NULL 表示尚未分配
+    task_id INTEGER,
+    -- 所在任务提交完成的时间
+    checked_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_candidates_task_id ON candidates(task_id);
+
+-- 显式ID列表任务只扫描 candidates 中分配给它的ID，start_id/end_id 为其中的最小/最大值，
+-- 统计覆盖范围、推算游标时需要排除
+ALTER TABLE task_queue ADD COLUMN list_task INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE task_archive ADD COLUMN list_task INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- 提交时上报的没有得到结论的ID数：重试耗尽仍不确定（unknown）、被上游拒绝（blocked）
+-- 这些ID不代表确认不存在，requeued_at 记录该范围重新排队检查的时间
+
+ALTER TABLE task_archive ADD COLUMN unknown_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE task_archive ADD COLUMN blocked_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE task_archive ADD COLUMN requeued_at DATETIME;
@@ -0,0 +1,13 @@
+-- 命中响应标记的无效ID（如已下架、地区限制）：与单纯不存在的ID区分，用于追踪下架情况
+-- 同一个ID再次提交时更新标记和最后出现时间，标记变化时 first_seen 重新计时
+
+CREATE TABLE marked_results (
+    id INTEGER PRIMARY KEY,
+    label TEXT NOT NULL,
+    task_id INTEGER,
+    worker_id TEXT NOT NULL DEFAULT '',
+    first_seen DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_marked_results_label ON marked_results(label, last_seen);
@@ -0,0 +1,13 @@
+-- 原始响应存档：有效ID的上游响应体（gzip 压缩）保存在 --response-archive 目录中，
+-- 这里记录每个ID最新一份存档的位置，以后增加新的元数据字段时据此重新解析
+
+CREATE TABLE raw_responses (
+    id INTEGER PRIMARY KEY,
+    -- 存档在存储中的位置（相对路径）
+    blob_key TEXT NOT NULL,
+    -- 压缩后的字节数
+    size INTEGER NOT NULL,
+    task_id INTEGER,
+    worker_id TEXT NOT NULL DEFAULT '',
+    stored_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
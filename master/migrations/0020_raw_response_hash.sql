@@ -0,0 +1,6 @@
+-- 原始响应按内容哈希去重：内容相同的响应共用一个对象，
+-- 之前按ID保存的记录 content_hash 为空，blob_key 仍指向原来的文件
+
+ALTER TABLE raw_responses ADD COLUMN content_hash TEXT;
+
+CREATE INDEX idx_raw_responses_content_hash ON raw_responses (content_hash);
@@ -0,0 +1,3 @@
+-- Worker随主机信息上报的版本号，供集群概览展示（旧版本的Worker不上报，为 NULL）
+
+ALTER TABLE worker_profiles ADD COLUMN version TEXT;
@@ -0,0 +1,8 @@
+-- 租约到期时间（Unix 毫秒），只由Master按自己的时钟写入；0 表示已释放或尚未分配
+-- 超时判定不再比较 last_heartbeat 字符串，last_heartbeat 只用于展示
+
+ALTER TABLE task_queue ADD COLUMN lease_expires_at INTEGER NOT NULL DEFAULT 0;
+
+UPDATE task_queue
+SET lease_expires_at = CAST((julianday(last_heartbeat) - 2440587.5) * 86400000 AS INTEGER) + 60000
+WHERE worker_id != '' AND last_heartbeat IS NOT NULL;
@@ -0,0 +1,4 @@
+-- 限时任务（--dispatch-mode duration）的扫描时长（秒），普通任务为 NULL
+-- 限时任务的 end_id 只是预留的上限，提交时截断到Worker扫描到的位置；被重新分配后按普通任务处理
+
+ALTER TABLE task_queue ADD COLUMN scan_for_secs INTEGER;
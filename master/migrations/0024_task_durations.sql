@@ -0,0 +1,4 @@
+-- 任务从分配到提交的耗时（毫秒），用于统计任务时长分布
+-- 旧的归档记录和没有带回租约的提交为 NULL
+
+ALTER TABLE task_archive ADD COLUMN duration_ms INTEGER;
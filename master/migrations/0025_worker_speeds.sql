@@ -0,0 +1,10 @@
+-- Master测得的各Worker速度（ID/s）：提交时按任务的ID数和从分配到提交的耗时计算
+-- speed 为平滑后的速度，用于计算批次大小；重启后从该表恢复，Worker重启后的第一次获取任务也能使用
+
+CREATE TABLE worker_speeds (
+    worker_id TEXT PRIMARY KEY,
+    speed REAL NOT NULL,
+    last_speed REAL NOT NULL,
+    samples INTEGER NOT NULL DEFAULT 1,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,1347 @@
+//! 管理接口
+//!
+//! `/admin/*` 下的接口，供 pa_top、admin 等运维工具查看和控制Master。
+//! 运行中的任务直接读内存视图，其余统计查询数据库。
+
+use crate::durations;
+use crate::history;
+use crate::maintenance;
+use crate::metrics::timed_sql;
+use crate::validate::ValidJson;
+use crate::{api_error, AppState};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use common::admin::{
+    AlertStatus, ApiKeyInfo, AuditEntry, BanRequest, BannedWorker, BatchSettings, CandidateStats,
+    ClusterOverview, ClusterWorker, CoverageBucket, CreateApiKeyRequest, CreateApiKeyResponse,
+    DispatchStatus, ImportCandidatesRequest, ImportCandidatesResponse, MaintenanceRequest,
+    MaintenanceStatus, MarkedStats, RecentResult, RequeueRequest, RequeueResponse,
+    RequeueUncheckedRequest, RequeueUncheckedResponse, SpotCheckStats, StatsPoint, StatsResponse,
+    TaskDurations, TaskInfo, UpdateBatchSettings, VerificationEntry, WorkerInfo, ACTOR_HEADER,
+};
+use common::routes;
+use common::{ApiResponse, ErrorCode};
+use master::audit;
+use master::candidates;
+use master::marked;
+use master::queue::{self, RequeueError};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::FromRow;
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// 最近结果接口单次最多返回的条数
+const MAX_RECENT_RESULTS: u32 = 1000;
+
+/// 审计日志接口单次最多返回的条数
+const MAX_AUDIT_ENTRIES: u32 = 1000;
+
+/// 复查队列接口单次最多返回的条数
+const MAX_VERIFICATION_ENTRIES: u32 = 1000;
+
+/// 覆盖图默认每个桶的ID数
+const DEFAULT_COVERAGE_BUCKET: i64 = 1_000_000;
+
+/// 覆盖图接口单次最多返回的桶数
+const MAX_COVERAGE_BUCKETS: i64 = 10_000;
+
+/// 扫描历史接口默认返回的分钟数
+const DEFAULT_HISTORY_MINUTES: u32 = 60;
+
+/// 扫描历史接口最多返回的分钟数（与历史的保留时长一致）
+const MAX_HISTORY_MINUTES: u32 = 30 * 24 * 60;
+
+/// 任务耗时统计默认的时间窗口（小时）
+const DEFAULT_DURATION_HOURS: u32 = 24;
+
+/// 任务耗时统计最长的时间窗口（小时）
+const MAX_DURATION_HOURS: u32 = 30 * 24;
+
+/// 集群概览默认的在线判定窗口（秒）
+const DEFAULT_ACTIVE_SECS: u64 = 60;
+
+/// 集群概览最长的在线判定窗口（秒）
+const MAX_ACTIVE_SECS: u64 = 24 * 3600;
+
+/// 全局统计中的上游延迟取最近几分钟的提交
+const RECENT_LATENCY_MINUTES: u32 = 5;
+
+type AdminResponse<T> = (StatusCode, Json<ApiResponse<T>>);
+
+/// 管理接口路由
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route(routes::admin::STATS, get(stats))
+        .route(routes::admin::STATS_HISTORY, get(stats_history))
+        .route(routes::admin::WORKERS, get(workers))
+        .route(routes::admin::CLUSTER, get(cluster))
+        .route(routes::admin::ALERTS, get(alerts))
+        .route(routes::admin::TASKS, get(tasks))
+        .route(routes::admin::TASK_DURATIONS, get(task_durations))
+        .route(routes::admin::RECENT_RESULTS, get(recent_results))
+        .route(routes::admin::MARKED, get(marked_results))
+        .route(routes::admin::PAUSE_DISPATCH, post(pause_dispatch))
+        .route(routes::admin::RESUME_DISPATCH, post(resume_dispatch))
+        .route(
+            routes::admin::MAINTENANCE,
+            get(maintenance_status).post(set_maintenance),
+        )
+        .route(
+            routes::admin::BATCH,
+            get(batch_settings).post(update_batch_settings),
+        )
+        .route(routes::admin::BANS, get(list_bans).post(ban_worker))
+        .route(routes::admin::BAN, delete(unban_worker))
+        .route(routes::admin::REQUEUE, post(requeue))
+        .route(routes::admin::REQUEUE_UNCHECKED, post(requeue_unchecked))
+        .route(
+            routes::admin::CANDIDATES,
+            get(candidate_stats).post(import_candidates),
+        )
+        .route(routes::admin::AUDIT, get(audit_log))
+        .route(routes::admin::KEYS, get(list_api_keys).post(create_api_key))
+        .route(routes::admin::KEY, delete(revoke_api_key))
+        .route(routes::admin::VERIFICATION, get(verification_queue))
+        .route(routes::admin::SPOT_CHECKS, get(spot_checks))
+        .route(routes::admin::COVERAGE, get(coverage))
+}
+
+/// 数据库错误转为500响应
+fn internal_error<T>(e: sqlx::Error) -> AdminResponse<T> {
+    error!("管理接口查询失败: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(api_error(format!("数据库错误: {}", e))),
+    )
+}
+
+/// 记录审计日志，操作人取自请求头
+///
+/// 写入失败只记录错误，不影响已经完成的操作。
+async fn audit(state: &AppState, headers: &HeaderMap, action: &str, details: serde_json::Value) {
+    let actor = headers
+        .get(ACTOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .unwrap_or("unknown");
+    if let Err(e) = timed_sql(audit::record(&state.db_pool, actor, action, details)).await {
+        error!("写入审计日志失败（{} {}）: {}", actor, action, e);
+    }
+}
+
+/// 全局统计
+/// GET /admin/stats
+async fn stats(State(state): State<Arc<AppState>>) -> AdminResponse<StatsResponse> {
+    match collect_stats(&state).await {
+        Ok(stats) => (StatusCode::OK, Json(ApiResponse::success(stats))),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 收集全局统计（管理接口和其他只读页面共用）
+pub async fn collect_stats(state: &AppState) -> Result<StatsResponse, sqlx::Error> {
+    let (cursor, completed_tasks, valid_results, unchecked_ids) = timed_sql(
+        sqlx::query_as::<_, (i64, i64, i64, i64)>(
+            r#"
+            SELECT
+                (SELECT next_start_id FROM global_cursor WHERE id = 1),
+                (SELECT COUNT(*) FROM task_archive),
+                (SELECT COUNT(*) FROM valid_results)
+                    + (SELECT COALESCE(SUM(end_id - start_id + 1), 0) FROM valid_ranges),
+                (SELECT COALESCE(SUM(unknown_count + blocked_count), 0)
+                    FROM task_archive WHERE requeued_at IS NULL)
+            "#,
+        )
+        .fetch_one(&state.read_pool),
+    )
+    .await?;
+
+    let upstream_latency = timed_sql(history::recent_latency(
+        &state.read_pool,
+        RECENT_LATENCY_MINUTES,
+    ))
+    .await?;
+
+    let tasks = state.running.snapshot().await;
+    let pending_tasks = tasks
+        .iter()
+        .filter(|task| task.worker_id.is_empty())
+        .count();
+
+    Ok(StatsResponse {
+        cursor,
+        running_tasks: tasks.len() - pending_tasks,
+        pending_tasks,
+        completed_tasks,
+        valid_results,
+        unchecked_ids,
+        dispatch_paused: state.dispatch_paused.load(Ordering::Relaxed),
+        upstream_latency,
+    })
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    /// 返回最近多少分钟（默认60）
+    minutes: Option<u32>,
+}
+
+/// 按分钟汇总的扫描量和上游延迟
+/// GET /admin/stats/history?minutes=60
+async fn stats_history(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HistoryQuery>,
+) -> AdminResponse<Vec<StatsPoint>> {
+    let minutes = query
+        .minutes
+        .unwrap_or(DEFAULT_HISTORY_MINUTES)
+        .clamp(1, MAX_HISTORY_MINUTES);
+    match timed_sql(history::fetch(&state.read_pool, minutes)).await {
+        Ok(points) => (StatusCode::OK, Json(ApiResponse::success(points))),
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct DurationsQuery {
+    /// 统计最近多少小时完成的任务（默认24）
+    hours: Option<u32>,
+}
+
+/// 已完成任务的耗时分布，按任务大小分档
+/// GET /admin/tasks/durations?hours=24
+async fn task_durations(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<DurationsQuery>,
+) -> AdminResponse<TaskDurations> {
+    let hours = query
+        .hours
+        .unwrap_or(DEFAULT_DURATION_HOURS)
+        .clamp(1, MAX_DURATION_HOURS);
+    match timed_sql(durations::fetch(&state.read_pool, hours)).await {
+        Ok(durations) => (StatusCode::OK, Json(ApiResponse::success(durations))),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 有运行中任务的Worker
+/// GET /admin/workers
+async fn workers(State(state): State<Arc<AppState>>) -> AdminResponse<Vec<WorkerInfo>> {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(collect_workers(&state).await)),
+    )
+}
+
+/// 持有任务的Worker，按ID排序
+pub async fn collect_workers(state: &AppState) -> Vec<WorkerInfo> {
+    let mut workers: BTreeMap<String, WorkerInfo> = BTreeMap::new();
+    for task in state.running.snapshot().await {
+        if task.worker_id.is_empty() {
+            continue;
+        }
+
+        let age = task.heartbeat_age().map(|age| age.as_secs());
+        let worker = workers
+            .entry(task.worker_id.clone())
+            .or_insert_with(|| WorkerInfo {
+                worker_id: task.worker_id.clone(),
+                task_ids: Vec::new(),
+                last_heartbeat_secs: None,
+                speed: None,
+                profile: None,
+            });
+        worker.task_ids.push(task.task_id);
+        // 取所有任务中最近的一次心跳
+        worker.last_heartbeat_secs = match (worker.last_heartbeat_secs, age) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if let Some(speed) = task.speed {
+            *worker.speed.get_or_insert(0.0) += speed;
+        }
+    }
+
+    for worker in workers.values_mut() {
+        worker.profile = state.profiles.get(&worker.worker_id).await;
+    }
+
+    workers.into_values().collect()
+}
+
+/// 集群概览查询参数
+#[derive(Deserialize)]
+struct ClusterQuery {
+    /// 在线判定窗口（秒，默认60）
+    active_secs: Option<u64>,
+}
+
+/// 集群概览：在线的Worker及其任务、速度、版本、标签和系统状态
+/// GET /admin/cluster?active_secs=60
+async fn cluster(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ClusterQuery>,
+) -> AdminResponse<ClusterOverview> {
+    let active_secs = query
+        .active_secs
+        .unwrap_or(DEFAULT_ACTIVE_SECS)
+        .min(MAX_ACTIVE_SECS);
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            collect_cluster(&state, active_secs).await,
+        )),
+    )
+}
+
+/// 各告警规则的当前状态（未配置 `--alert-rules` 时为空列表）
+/// GET /admin/alerts
+async fn alerts(State(state): State<Arc<AppState>>) -> AdminResponse<Vec<AlertStatus>> {
+    let status = state
+        .alerts
+        .as_ref()
+        .map(|engine| engine.status())
+        .unwrap_or_default();
+    (StatusCode::OK, Json(ApiResponse::success(status)))
+}
+
+/// 最近 `active_secs` 秒内有过请求或任务心跳的Worker
+///
+/// 在线Worker持有的任务全部列出，包括租约已经超时的任务。
+pub async fn collect_cluster(state: &AppState, active_secs: u64) -> ClusterOverview {
+    let within = Duration::from_secs(active_secs);
+    let new_worker = |worker_id: &str, last_seen_secs: u64| ClusterWorker {
+        worker_id: worker_id.to_string(),
+        last_seen_secs,
+        tasks: Vec::new(),
+        speed: None,
+        profile: None,
+        system: None,
+    };
+
+    let mut workers: BTreeMap<String, ClusterWorker> = BTreeMap::new();
+    for (worker_id, age) in state.presence.seen_within(within) {
+        let worker = new_worker(&worker_id, age.as_secs());
+        workers.insert(worker_id, worker);
+    }
+
+    for task in state.running.snapshot().await {
+        if task.worker_id.is_empty() {
+            continue;
+        }
+        let age = task.heartbeat_age();
+        let heartbeat_recent = age.is_some_and(|age| age <= within);
+        if !heartbeat_recent && !workers.contains_key(&task.worker_id) {
+            continue;
+        }
+
+        let age_secs = age.map(|age| age.as_secs());
+        let worker = workers
+            .entry(task.worker_id.clone())
+            .or_insert_with(|| new_worker(&task.worker_id, age_secs.unwrap_or(active_secs)));
+        if let Some(age_secs) = age_secs.filter(|_| heartbeat_recent) {
+            worker.last_seen_secs = worker.last_seen_secs.min(age_secs);
+        }
+        if let Some(speed) = task.speed {
+            *worker.speed.get_or_insert(0.0) += speed;
+        }
+        worker.tasks.push(TaskInfo {
+            task_id: task.task_id,
+            start_id: task.start_id,
+            end_id: task.end_id,
+            worker_id: task.worker_id,
+            heartbeat_age_secs: age_secs,
+            speed: task.speed,
+        });
+    }
+
+    let mut versions: BTreeMap<String, usize> = BTreeMap::new();
+    for worker in workers.values_mut() {
+        worker.profile = state.profiles.get(&worker.worker_id).await;
+        worker.system = state.system_samples.get(&worker.worker_id);
+        let version = worker
+            .profile
+            .as_ref()
+            .and_then(|profile| profile.version.clone())
+            .unwrap_or_else(|| "unknown".to_string());
+        *versions.entry(version).or_default() += 1;
+    }
+
+    ClusterOverview {
+        active_secs,
+        total_speed: workers
+            .values()
+            .filter_map(|worker| worker.speed)
+            .fold(0.0, |total, speed| total + speed),
+        workers: workers.into_values().collect(),
+        versions,
+    }
+}
+
+/// 任务队列中的任务
+/// GET /admin/tasks
+async fn tasks(State(state): State<Arc<AppState>>) -> AdminResponse<Vec<TaskInfo>> {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(collect_tasks(&state).await)),
+    )
+}
+
+/// 任务队列中的任务
+pub async fn collect_tasks(state: &AppState) -> Vec<TaskInfo> {
+    state
+        .running
+        .snapshot()
+        .await
+        .into_iter()
+        .map(|task| TaskInfo {
+            task_id: task.task_id,
+            start_id: task.start_id,
+            end_id: task.end_id,
+            heartbeat_age_secs: task.heartbeat_age().map(|age| age.as_secs()),
+            worker_id: task.worker_id,
+            speed: task.speed,
+        })
+        .collect()
+}
+
+/// 最近结果查询参数
+#[derive(Deserialize)]
+struct RecentQuery {
+    /// 返回条数（默认20）
+    limit: Option<u32>,
+}
+
+/// 最近发现的有效ID
+/// GET /admin/results/recent?limit=20
+async fn recent_results(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RecentQuery>,
+) -> AdminResponse<Vec<RecentResult>> {
+    let limit = query.limit.unwrap_or(20).min(MAX_RECENT_RESULTS);
+    match fetch_recent_results(&state, limit).await {
+        Ok(results) => (StatusCode::OK, Json(ApiResponse::success(results))),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 最近发现的有效ID，按发现时间倒序
+pub async fn fetch_recent_results(
+    state: &AppState,
+    limit: u32,
+) -> Result<Vec<RecentResult>, sqlx::Error> {
+    let rows = timed_sql(
+        sqlx::query_as::<_, (i64, String)>(
+            "SELECT id, found_at FROM valid_results ORDER BY found_at DESC, id DESC LIMIT ?",
+        )
+        .bind(limit)
+        .fetch_all(&state.read_pool),
+    )
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, found_at)| RecentResult { id, found_at })
+        .collect())
+}
+
+/// 命中标记的ID查询参数
+#[derive(Deserialize)]
+struct MarkedQuery {
+    /// 返回的最近ID条数（默认20）
+    limit: Option<u32>,
+
+    /// 只返回该标记的ID
+    label: Option<String>,
+}
+
+/// 命中响应标记的ID：各标记的数量和最近出现的ID
+/// GET /admin/marked?limit=20&label=removed
+async fn marked_results(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<MarkedQuery>,
+) -> AdminResponse<MarkedStats> {
+    let limit = query.limit.unwrap_or(20).min(MAX_RECENT_RESULTS);
+    let labels = match timed_sql(marked::counts(&state.read_pool)).await {
+        Ok(labels) => labels,
+        Err(e) => return internal_error(e),
+    };
+    match timed_sql(marked::recent(
+        &state.read_pool,
+        limit,
+        query.label.as_deref(),
+    ))
+    .await
+    {
+        Ok(recent) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(MarkedStats { labels, recent })),
+        ),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 暂停任务分发
+/// POST /admin/dispatch/pause
+async fn pause_dispatch(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AdminResponse<DispatchStatus> {
+    state.dispatch_paused.store(true, Ordering::Relaxed);
+    warn!("任务分发已暂停");
+    audit(&state, &headers, "pause_dispatch", json!({})).await;
+    dispatch_status(&state)
+}
+
+/// 恢复任务分发
+/// POST /admin/dispatch/resume
+async fn resume_dispatch(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AdminResponse<DispatchStatus> {
+    state.dispatch_paused.store(false, Ordering::Relaxed);
+    info!("任务分发已恢复");
+    audit(&state, &headers, "resume_dispatch", json!({})).await;
+    dispatch_status(&state)
+}
+
+fn dispatch_status(state: &AppState) -> AdminResponse<DispatchStatus> {
+    let status = DispatchStatus {
+        dispatch_paused: state.dispatch_paused.load(Ordering::Relaxed),
+    };
+    (StatusCode::OK, Json(ApiResponse::success(status)))
+}
+
+/// 维护模式状态
+/// GET /admin/maintenance
+async fn maintenance_status(
+    State(state): State<Arc<AppState>>,
+) -> AdminResponse<MaintenanceStatus> {
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(state.maintenance.status())),
+    )
+}
+
+/// 开启或结束维护模式
+/// POST /admin/maintenance
+async fn set_maintenance(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ValidJson(request): ValidJson<MaintenanceRequest>,
+) -> AdminResponse<MaintenanceStatus> {
+    if request.enabled {
+        let retry_after = request
+            .retry_after_secs
+            .map_or(maintenance::DEFAULT_RETRY_AFTER, Duration::from_secs);
+        let reason = request.reason.clone().filter(|reason| !reason.is_empty());
+        if state.maintenance.begin(reason, retry_after) {
+            info!("维护模式参数已更新: {:?}", request.reason);
+        } else {
+            warn!(
+                "已进入维护模式，任务接口暂停服务: {}",
+                request.reason.as_deref().unwrap_or("未说明原因")
+            );
+        }
+    } else if let Some(duration) = state.maintenance.end() {
+        let extended = state.running.extend_leases(duration).await;
+        // 数据库中的到期时间只在重启或主备切换时用到，同样顺延
+        let result = timed_sql(
+            sqlx::query(
+                "UPDATE task_queue SET lease_expires_at = lease_expires_at + ? WHERE lease_expires_at > 0",
+            )
+            .bind(duration.as_millis() as i64)
+            .execute(&state.db_pool),
+        )
+        .await;
+        if let Err(e) = result {
+            error!("顺延数据库中的租约失败: {}", e);
+        }
+        warn!(
+            "已退出维护模式（持续 {}），{} 个任务的租约已顺延",
+            humantime::format_duration(Duration::from_secs(duration.as_secs())),
+            extended
+        );
+    }
+    audit(
+        &state,
+        &headers,
+        "set_maintenance",
+        json!({
+            "enabled": request.enabled,
+            "reason": request.reason,
+            "retry_after_secs": request.retry_after_secs,
+        }),
+    )
+    .await;
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(state.maintenance.status())),
+    )
+}
+
+/// 任务批次大小的计算参数
+/// GET /admin/batch
+async fn batch_settings(State(state): State<Arc<AppState>>) -> AdminResponse<BatchSettings> {
+    let settings = *state.batch_settings.read().expect("批次参数锁中毒");
+    (StatusCode::OK, Json(ApiResponse::success(settings)))
+}
+
+/// 修改任务批次大小的计算参数（只在内存中生效，重启后恢复为启动参数）
+/// POST /admin/batch
+async fn update_batch_settings(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ValidJson(update): ValidJson<UpdateBatchSettings>,
+) -> AdminResponse<BatchSettings> {
+    let (previous, settings) = {
+        let mut current = state.batch_settings.write().expect("批次参数锁中毒");
+        let settings = current.apply(&update);
+        if let Err(message) = settings.check() {
+            warn!("批次参数无效: {}", message);
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(api_error(message).with_code(ErrorCode::InvalidRequest)),
+            );
+        }
+        (std::mem::replace(&mut *current, settings), settings)
+    };
+
+    info!("批次参数已修改: {:?} -> {:?}", previous, settings);
+    audit(
+        &state,
+        &headers,
+        "update_batch_settings",
+        json!({ "previous": previous, "current": settings }),
+    )
+    .await;
+    (StatusCode::OK, Json(ApiResponse::success(settings)))
+}
+
+/// 被封禁的Worker
+/// GET /admin/bans
+async fn list_bans(State(state): State<Arc<AppState>>) -> AdminResponse<Vec<BannedWorker>> {
+    let rows = timed_sql(
+        sqlx::query_as::<_, (String, String, String)>(
+            "SELECT worker_id, reason, banned_at FROM banned_workers ORDER BY banned_at",
+        )
+        .fetch_all(&state.read_pool),
+    )
+    .await;
+
+    match rows {
+        Ok(rows) => {
+            let bans = rows
+                .into_iter()
+                .map(|(worker_id, reason, banned_at)| BannedWorker {
+                    worker_id,
+                    reason,
+                    banned_at,
+                })
+                .collect();
+            (StatusCode::OK, Json(ApiResponse::success(bans)))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 封禁Worker，并回收其持有的任务
+/// POST /admin/bans
+async fn ban_worker(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ValidJson(req): ValidJson<BanRequest>,
+) -> AdminResponse<String> {
+    let reason = req.reason.unwrap_or_default();
+    let newly = match timed_sql(state.bans.ban(&state.db_pool, &req.worker_id, &reason)).await {
+        Ok(newly) => newly,
+        Err(e) => return internal_error(e),
+    };
+
+    // 使其租约立即失效，下一轮回收时重新排队
+    let expired = state.running.expire_worker(&req.worker_id).await;
+    warn!(
+        "Worker {} 已被封禁（原因: {}），{} 个任务等待回收",
+        req.worker_id,
+        reason,
+        expired.len()
+    );
+    audit(
+        &state,
+        &headers,
+        "ban_worker",
+        json!({ "worker_id": req.worker_id, "reason": reason, "expired_tasks": expired }),
+    )
+    .await;
+
+    let message = if newly {
+        format!("已封禁，{} 个任务将被重新分配", expired.len())
+    } else {
+        "该Worker已在封禁名单中".to_string()
+    };
+    (StatusCode::OK, Json(ApiResponse::success(message)))
+}
+
+/// 解除Worker的封禁
+/// DELETE /admin/bans/{worker_id}
+async fn unban_worker(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(worker_id): Path<String>,
+) -> AdminResponse<String> {
+    match timed_sql(state.bans.unban(&state.db_pool, &worker_id)).await {
+        Ok(true) => {
+            info!("Worker {} 已解除封禁", worker_id);
+            audit(
+                &state,
+                &headers,
+                "unban_worker",
+                json!({ "worker_id": worker_id }),
+            )
+            .await;
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success("已解除封禁".to_string())),
+            )
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(
+                api_error(format!("Worker {} 不在封禁名单中", worker_id))
+                    .with_code(ErrorCode::NotFound),
+            ),
+        ),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 将已分配过的范围重新排队
+/// POST /admin/requeue
+async fn requeue(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ValidJson(req): ValidJson<RequeueRequest>,
+) -> AdminResponse<RequeueResponse> {
+    let chunk = req.chunk.unwrap_or(state.config.split_size);
+    // 拆分粒度为0表示不拆分
+    let chunk = if chunk == 0 {
+        req.end_id.saturating_sub(req.start_id).saturating_add(1)
+    } else {
+        chunk
+    };
+
+    let tasks = match timed_sql(queue::requeue_range(
+        &state.db_pool,
+        req.start_id,
+        req.end_id,
+        chunk,
+    ))
+    .await
+    {
+        Ok(tasks) => tasks,
+        Err(RequeueError::Database(e)) => return internal_error(e),
+        Err(e) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(api_error(e.to_string()).with_code(ErrorCode::InvalidRequest)),
+            )
+        }
+    };
+
+    let task_ids: Vec<i32> = tasks.iter().map(|task| task.task_id).collect();
+    for task in tasks {
+        state.running.insert(task).await;
+    }
+    info!(
+        "范围 [{}, {}] 已重新排队，共 {} 个任务",
+        req.start_id,
+        req.end_id,
+        task_ids.len()
+    );
+    audit(
+        &state,
+        &headers,
+        "requeue",
+        json!({ "start_id": req.start_id, "end_id": req.end_id, "chunk": chunk, "tasks": task_ids.len() }),
+    )
+    .await;
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(RequeueResponse { task_ids })),
+    )
+}
+
+/// 将上报了没有结论的ID的已完成范围重新排队
+/// POST /admin/requeue-unchecked
+async fn requeue_unchecked(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ValidJson(req): ValidJson<RequeueUncheckedRequest>,
+) -> AdminResponse<RequeueUncheckedResponse> {
+    // 拆分粒度为0表示不拆分
+    let chunk = match req.chunk.unwrap_or(state.config.split_size) {
+        0 => i64::MAX,
+        chunk => chunk,
+    };
+
+    let requeue = match timed_sql(queue::requeue_unchecked(&state.db_pool, chunk)).await {
+        Ok(requeue) => requeue,
+        Err(RequeueError::Database(e)) => return internal_error(e),
+        Err(e) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(api_error(e.to_string()).with_code(ErrorCode::InvalidRequest)),
+            )
+        }
+    };
+
+    let task_ids: Vec<i32> = requeue.tasks.iter().map(|task| task.task_id).collect();
+    for task in requeue.tasks {
+        state.running.insert(task).await;
+    }
+    info!(
+        "{} 个范围中没有结论的ID已重新排队：新建 {} 个任务，重新打开 {} 个候选ID",
+        requeue.ranges,
+        task_ids.len(),
+        requeue.candidates
+    );
+    audit(
+        &state,
+        &headers,
+        "requeue_unchecked",
+        json!({ "ranges": requeue.ranges, "chunk": chunk, "tasks": task_ids.len(), "candidates": requeue.candidates }),
+    )
+    .await;
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(RequeueUncheckedResponse {
+            ranges: requeue.ranges,
+            task_ids,
+            candidates: requeue.candidates,
+        })),
+    )
+}
+
+/// 候选ID统计
+/// GET /admin/candidates
+async fn candidate_stats(State(state): State<Arc<AppState>>) -> AdminResponse<CandidateStats> {
+    match timed_sql(candidates::counts(&state.read_pool)).await {
+        Ok((pending, assigned, checked)) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(CandidateStats {
+                pending,
+                assigned,
+                checked,
+            })),
+        ),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 批量导入候选ID，调度时先于全局游标以显式ID列表任务扫描
+/// POST /admin/candidates
+async fn import_candidates(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ValidJson(req): ValidJson<ImportCandidatesRequest>,
+) -> AdminResponse<ImportCandidatesResponse> {
+    let mut tx = match timed_sql(state.db_pool.begin()).await {
+        Ok(tx) => tx,
+        Err(e) => return internal_error(e),
+    };
+    let mut queued = 0;
+    for id in &req.ids {
+        match timed_sql(candidates::insert(&mut tx, *id)).await {
+            Ok(true) => queued += 1,
+            Ok(false) => {}
+            Err(e) => return internal_error(e),
+        }
+    }
+    if let Err(e) = timed_sql(tx.commit()).await {
+        return internal_error(e);
+    }
+
+    let skipped = req.ids.len() as u64 - queued;
+    info!(
+        "已导入 {} 个候选ID（{} 个已在等待或扫描中）",
+        queued, skipped
+    );
+    audit(
+        &state,
+        &headers,
+        "import_candidates",
+        json!({ "ids": req.ids.len(), "queued": queued }),
+    )
+    .await;
+
+    (
+        StatusCode::OK,
+        Json(ApiResponse::success(ImportCandidatesResponse {
+            queued,
+            skipped,
+        })),
+    )
+}
+
+/// 审计日志查询参数
+#[derive(Deserialize)]
+struct AuditQuery {
+    /// 返回条数
+    limit: Option<u32>,
+
+    /// 只返回该类型的操作
+    action: Option<String>,
+}
+
+/// 最近的审计日志
+/// GET /admin/audit?limit=50&action=set_cursor
+async fn audit_log(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditQuery>,
+) -> AdminResponse<Vec<AuditEntry>> {
+    let limit = query.limit.unwrap_or(50).min(MAX_AUDIT_ENTRIES);
+    match timed_sql(audit::recent(
+        &state.read_pool,
+        limit,
+        query.action.as_deref(),
+    ))
+    .await
+    {
+        Ok(entries) => (StatusCode::OK, Json(ApiResponse::success(entries))),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 所有API Key及其今天的用量
+/// GET /admin/keys
+async fn list_api_keys(State(state): State<Arc<AppState>>) -> AdminResponse<Vec<ApiKeyInfo>> {
+    let rows = timed_sql(
+        sqlx::query_as::<_, (i64, String, String, Option<i64>, Option<i64>, bool, i64, i64, String)>(
+            r#"
+            SELECT k.key_id, k.name, k.role, k.max_concurrent_tasks, k.max_ids_per_day, k.revoked,
+                   (SELECT COUNT(*) FROM task_queue t WHERE t.key_id = k.key_id AND t.worker_id != ''),
+                   COALESCE((SELECT u.ids_assigned FROM api_key_usage u
+                             WHERE u.key_id = k.key_id AND u.day = date('now')), 0),
+                   k.created_at
+            FROM api_keys k
+            ORDER BY k.key_id
+            "#,
+        )
+        .fetch_all(&state.read_pool),
+    )
+    .await;
+
+    match rows {
+        Ok(rows) => {
+            let keys = rows
+                .into_iter()
+                .map(
+                    |(
+                        key_id,
+                        name,
+                        role,
+                        max_concurrent_tasks,
+                        max_ids_per_day,
+                        revoked,
+                        running_tasks,
+                        ids_today,
+                        created_at,
+                    )| ApiKeyInfo {
+                        key_id,
+                        name,
+                        role: role.parse().unwrap_or_default(),
+                        max_concurrent_tasks,
+                        max_ids_per_day,
+                        revoked,
+                        running_tasks,
+                        ids_today,
+                        created_at,
+                    },
+                )
+                .collect();
+            (StatusCode::OK, Json(ApiResponse::success(keys)))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 创建API Key，密钥只在响应中出现这一次
+/// POST /admin/keys
+async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ValidJson(req): ValidJson<CreateApiKeyRequest>,
+) -> AdminResponse<CreateApiKeyResponse> {
+    let name = req.name.trim();
+    let created = timed_sql(state.api_keys.create(
+        &state.db_pool,
+        name,
+        req.role,
+        req.max_concurrent_tasks,
+        req.max_ids_per_day,
+    ))
+    .await;
+
+    match created {
+        Ok((key_id, key)) => {
+            info!("已创建API Key {} ({}, {})", key_id, name, req.role);
+            audit(
+                &state,
+                &headers,
+                "create_api_key",
+                json!({
+                    "key_id": key_id,
+                    "name": name,
+                    "role": req.role,
+                    "max_concurrent_tasks": req.max_concurrent_tasks,
+                    "max_ids_per_day": req.max_ids_per_day,
+                }),
+            )
+            .await;
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success(CreateApiKeyResponse { key_id, key })),
+            )
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 吊销API Key，已分配给它的任务照常完成
+/// DELETE /admin/keys/{key_id}
+async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(key_id): Path<i64>,
+) -> AdminResponse<String> {
+    match timed_sql(state.api_keys.revoke(&state.db_pool, key_id)).await {
+        Ok(true) => {
+            warn!("API Key {} 已被吊销", key_id);
+            audit(
+                &state,
+                &headers,
+                "revoke_api_key",
+                json!({ "key_id": key_id }),
+            )
+            .await;
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success("已吊销".to_string())),
+            )
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(
+                api_error(format!("API Key {} 不存在或已被吊销", key_id))
+                    .with_code(ErrorCode::NotFound),
+            ),
+        ),
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 复查队列查询参数
+#[derive(Deserialize)]
+struct VerificationQuery {
+    /// 返回条数
+    limit: Option<u32>,
+
+    /// 只返回该状态的记录（pending、done）
+    status: Option<String>,
+}
+
+/// 复查队列的一行
+#[derive(FromRow)]
+struct VerificationRow {
+    id: i64,
+    task_id: i64,
+    start_id: i64,
+    end_id: i64,
+    worker_id: String,
+    reason: String,
+    valid_count: i64,
+    verify_task_id: Option<i64>,
+    verified_count: Option<i64>,
+    status: String,
+    created_at: String,
+    completed_at: Option<String>,
+}
+
+impl From<VerificationRow> for VerificationEntry {
+    fn from(row: VerificationRow) -> Self {
+        Self {
+            id: row.id,
+            task_id: row.task_id,
+            start_id: row.start_id,
+            end_id: row.end_id,
+            worker_id: row.worker_id,
+            reason: row.reason,
+            valid_count: row.valid_count,
+            verify_task_id: row.verify_task_id,
+            verified_count: row.verified_count,
+            status: row.status,
+            created_at: row.created_at,
+            completed_at: row.completed_at,
+        }
+    }
+}
+
+/// 复查队列（被判定为可疑的提交），最新的在前
+/// GET /admin/verification?limit=50&status=pending
+async fn verification_queue(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<VerificationQuery>,
+) -> AdminResponse<Vec<VerificationEntry>> {
+    let limit = query.limit.unwrap_or(50).min(MAX_VERIFICATION_ENTRIES);
+    let rows = timed_sql(
+        sqlx::query_as::<_, VerificationRow>(
+            r#"
+            SELECT id, task_id, start_id, end_id, worker_id, reason, valid_count,
+                   verify_task_id, verified_count, status, created_at, completed_at
+            FROM verification_queue
+            WHERE ? IS NULL OR status = ?
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&query.status)
+        .bind(&query.status)
+        .bind(limit)
+        .fetch_all(&state.read_pool),
+    )
+    .await;
+
+    match rows {
+        Ok(rows) => {
+            let entries = rows.into_iter().map(VerificationEntry::from).collect();
+            (StatusCode::OK, Json(ApiResponse::success(entries)))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+/// 各Worker的抽查一致率，一致率低的在前
+/// GET /admin/spot-checks
+async fn spot_checks(State(state): State<Arc<AppState>>) -> AdminResponse<Vec<SpotCheckStats>> {
+    let rows = timed_sql(
+        sqlx::query_as::<_, (String, i64, i64, Option<String>)>(
+            r#"
+            SELECT worker_id, checked, agreed, last_checked_at
+            FROM spot_checks
+            ORDER BY CAST(agreed AS REAL) / MAX(checked, 1), checked DESC
+            "#,
+        )
+        .fetch_all(&state.read_pool),
+    )
+    .await;
+
+    match rows {
+        Ok(rows) => {
+            let stats = rows
+                .into_iter()
+                .map(
+                    |(worker_id, checked, agreed, last_checked_at)| SpotCheckStats {
+                        worker_id,
+                        checked,
+                        agreed,
+                        agreement_rate: agreed as f64 / checked.max(1) as f64,
+                        last_checked_at,
+                    },
+                )
+                .collect();
+            (StatusCode::OK, Json(ApiResponse::success(stats)))
+        }
+        Err(e) => internal_error(e),
+    }
+}
+
+#[derive(Deserialize)]
+struct CoverageQuery {
+    /// 每个桶的ID数（默认1000000）
+    bucket: Option<i64>,
+
+    /// 统计范围的起始ID（默认0）
+    start: Option<i64>,
+
+    /// 统计范围的结束ID（包含，默认为游标的前一个ID）
+    end: Option<i64>,
+}
+
+/// 按桶统计的覆盖情况：已完成的比例取自归档表，正在扫描的比例取自运行中的任务
+/// GET /admin/coverage?bucket=1000000&start=0&end=99999999
+async fn coverage(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CoverageQuery>,
+) -> AdminResponse<Vec<CoverageBucket>> {
+    let invalid = |message: String| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(api_error(message).with_code(ErrorCode::InvalidRequest)),
+        )
+    };
+    let bucket = query.bucket.unwrap_or(DEFAULT_COVERAGE_BUCKET);
+    if bucket <= 0 {
+        return invalid("bucket 必须大于0".to_string());
+    }
+    let start = query.start.unwrap_or(0);
+    if start < 0 {
+        return invalid("start 不能为负数".to_string());
+    }
+
+    // 1. 确定统计范围，按桶对齐
+    let end = match query.end {
+        Some(end) => end,
+        None => {
+            let cursor = timed_sql(
+                sqlx::query_scalar::<_, i64>(
+                    "SELECT next_start_id FROM global_cursor WHERE id = 1",
+                )
+                .fetch_one(&state.read_pool),
+            )
+            .await;
+            match cursor {
+                Ok(cursor) => cursor - 1,
+                Err(e) => return internal_error(e),
+            }
+        }
+    };
+    let first = start / bucket;
+    let last = end.div_euclid(bucket);
+    if last < first {
+        return (StatusCode::OK, Json(ApiResponse::success(Vec::new())));
+    }
+    if last - first >= MAX_COVERAGE_BUCKETS {
+        return invalid(format!(
+            "桶数 {} 超过上限 {}，请增大 bucket 或缩小范围",
+            last - first + 1,
+            MAX_COVERAGE_BUCKETS
+        ));
+    }
+    let range_start = first * bucket;
+    let range_end = (last + 1).saturating_mul(bucket).saturating_sub(1);
+    let mut scanned = vec![0i64; (last - first + 1) as usize];
+    let mut running = vec![0i64; scanned.len()];
+
+    // 2. 与统计范围相交的已完成范围：复查、补扫和合并会留下重叠的归档，先合并重叠的区间再按桶累加，
+    // 同一段ID扫描多次只算一次
+    let archived = timed_sql(
+        sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT start_id, end_id
+            FROM task_archive
+            WHERE end_id >= ?1 AND start_id <= ?2 AND list_task = 0
+            ORDER BY start_id
+            "#,
+        )
+        .bind(range_start)
+        .bind(range_end)
+        .fetch_all(&state.read_pool),
+    )
+    .await;
+    match archived {
+        Ok(rows) => add_merged_ranges(&mut scanned, first, bucket, rows),
+        Err(e) => return internal_error(e),
+    }
+
+    // 3. 已分配给Worker的任务（待分配的任务算作未扫描，显式ID列表任务不是连续的范围）
+    let mut assigned: Vec<(i64, i64)> = state
+        .running
+        .snapshot()
+        .await
+        .into_iter()
+        .filter(|task| !task.worker_id.is_empty() && !task.list)
+        .map(|task| (task.start_id, task.end_id))
+        .collect();
+    assigned.sort_unstable();
+    add_merged_ranges(&mut running, first, bucket, assigned);
+
+    let buckets = scanned
+        .iter()
+        .zip(&running)
+        .enumerate()
+        .map(|(i, (&scanned, &running))| {
+            let start_id = (first + i as i64) * bucket;
+            let size = bucket as f64;
+            let scanned = (scanned as f64 / size).min(1.0);
+            let running = (running as f64 / size).min(1.0 - scanned);
+            CoverageBucket {
+                start_id,
+                end_id: start_id.saturating_add(bucket - 1),
+                scanned,
+                running,
+                unscanned: 1.0 - scanned - running,
+            }
+        })
+        .collect();
+    (StatusCode::OK, Json(ApiResponse::success(buckets)))
+}
+
+/// 合并按 start_id 排序的闭区间中重叠或相邻的部分，再累加到各桶
+fn add_merged_ranges(
+    counts: &mut [i64],
+    first: i64,
+    bucket: i64,
+    sorted: impl IntoIterator<Item = (i64, i64)>,
+) {
+    let mut merged: Option<(i64, i64)> = None;
+    for (start_id, end_id) in sorted {
+        merged = match merged {
+            Some((merged_start, merged_end)) if start_id <= merged_end.saturating_add(1) => {
+                Some((merged_start, merged_end.max(end_id)))
+            }
+            Some((merged_start, merged_end)) => {
+                add_range(counts, first, bucket, merged_start, merged_end);
+                Some((start_id, end_id))
+            }
+            None => Some((start_id, end_id)),
+        };
+    }
+    if let Some((start_id, end_id)) = merged {
+        add_range(counts, first, bucket, start_id, end_id);
+    }
+}
+
+/// 把闭区间 [start_id, end_id] 中落在统计范围内的ID数累加到各桶，`counts[0]` 对应第 `first` 个桶
+fn add_range(counts: &mut [i64], first: i64, bucket: i64, start_id: i64, end_id: i64) {
+    let range_end = (first + counts.len() as i64)
+        .saturating_mul(bucket)
+        .saturating_sub(1);
+    let mut id = start_id.max(first * bucket);
+    let end_id = end_id.min(range_end);
+    while id <= end_id {
+        let index = id.div_euclid(bucket);
+        let bucket_end = (index + 1)
+            .saturating_mul(bucket)
+            .saturating_sub(1)
+            .min(end_id);
+        counts[(index - first) as usize] += bucket_end - id + 1;
+        id = bucket_end + 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_ranges_are_counted_once() {
+        // 桶大小10，统计 [0, 29]：[0, 4] 被扫描了两次，[8, 14] 与 [10, 12] 重叠
+        let mut counts = vec![0; 3];
+        add_merged_ranges(
+            &mut counts,
+            0,
+            10,
+            vec![(0, 4), (0, 4), (8, 14), (10, 12), (25, 40)],
+        );
+        assert_eq!(counts, vec![7, 5, 5]);
+    }
+}
@@ -0,0 +1,134 @@
+//! 管理接口的访问控制
+//!
+//! 设置 `--admin-password` 后，管理接口、GraphQL、状态页面和前端面板都要求认证：
+//! - HTTP Basic 认证，用户名为 `--admin-user`，拥有全部权限。浏览器访问时会弹出登录框，之后由浏览器自动携带凭据
+//! - `Authorization: Bearer <key>` 携带 read_only 或 admin 角色的API Key。
+//!   read_only 只能执行只读请求（GET 和 GraphQL 查询），适合交给监控系统；admin 拥有全部权限
+//!
+//! worker 角色的API Key不能调用这些路由，反过来 read_only、admin 角色的API Key也不能调用 `/task/*`。
+//!
+//! 没有设置 `--admin-password` 时：携带的令牌仍然按角色检查；创建过 read_only 或 admin 角色的令牌后，
+//! 管理接口要求携带令牌，否则创建只读令牌并不能阻止其他人执行修改操作。
+
+use crate::{api_error, AppState};
+use axum::{
+    extract::{Request, State},
+    http::{
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::admin::ApiKeyRole;
+use common::{routes, ErrorCode, API_KEY_SCHEME};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tracing::warn;
+
+/// 浏览器登录框中显示的 realm
+const REALM: &str = r#"Basic realm="pa_master", charset="UTF-8""#;
+
+/// 校验管理凭据和令牌角色
+///
+/// 携带令牌时总是按角色检查；未设置 `--admin-password` 且没有管理令牌时，不带凭据的请求直接放行。
+pub async fn require_admin(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let authorization = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+
+    // 1. 管理令牌：按角色检查
+    if let Some(token) = authorization.and_then(|value| value.strip_prefix(API_KEY_SCHEME)) {
+        let Some(key) = state.api_keys.lookup(token.trim()).await else {
+            warn!("无效或已吊销的管理令牌: {} {}", req.method(), req.uri());
+            return unauthorized("令牌无效或已被吊销");
+        };
+        let allowed = match key.role {
+            ApiKeyRole::Admin => true,
+            ApiKeyRole::ReadOnly => is_read_only(req.method(), req.uri().path()),
+            ApiKeyRole::Worker => false,
+        };
+        if !allowed {
+            warn!(
+                "{} 角色的API Key {} 无权调用: {} {}",
+                key.role,
+                key.key_id,
+                req.method(),
+                req.uri()
+            );
+            return forbidden(&format!("{} 角色的令牌无权调用该接口", key.role));
+        }
+        return next.run(req).await;
+    }
+
+    let Some(password) = &state.config.admin_password else {
+        if state.api_keys.has_management_keys().await {
+            warn!("没有携带管理令牌: {} {}", req.method(), req.uri());
+            return unauthorized("已创建管理令牌，需要携带 read_only 或 admin 角色的令牌");
+        }
+        return next.run(req).await;
+    };
+    let Some(authorization) = authorization else {
+        return unauthorized("需要管理凭据（HTTP Basic 认证或管理令牌）");
+    };
+
+    // 2. HTTP Basic 认证
+    match parse_basic(authorization) {
+        Some((user, given)) if user == state.config.admin_user && digest_eq(&given, password) => {
+            next.run(req).await
+        }
+        Some((user, _)) => {
+            warn!("管理凭据错误: user={} {} {}", user, req.method(), req.uri());
+            unauthorized("用户名或密码错误")
+        }
+        None => unauthorized("需要管理凭据（HTTP Basic 认证或管理令牌）"),
+    }
+}
+
+/// 请求是否只读：GET、HEAD 和 GraphQL 查询（schema 中没有修改操作）
+pub fn is_read_only(method: &Method, path: &str) -> bool {
+    matches!(*method, Method::GET | Method::HEAD) || path == routes::GRAPHQL
+}
+
+/// 解析 `Basic base64(user:password)`
+fn parse_basic(value: &str) -> Option<(String, String)> {
+    let (scheme, encoded) = value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+}
+
+/// 比较摘要而不是原文，比较耗时与密码内容无关
+fn digest_eq(given: &str, expected: &str) -> bool {
+    Sha256::digest(given.as_bytes()) == Sha256::digest(expected.as_bytes())
+}
+
+fn forbidden(message: &str) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(api_error::<()>(message.to_string()).with_code(ErrorCode::Forbidden)),
+    )
+        .into_response()
+}
+
+fn unauthorized(message: &str) -> Response {
+    let mut response = (
+        StatusCode::UNAUTHORIZED,
+        Json(api_error::<()>(message.to_string()).with_code(ErrorCode::Unauthorized)),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(WWW_AUTHENTICATE, HeaderValue::from_static(REALM));
+    response
+}
@@ -0,0 +1,405 @@
+//! 告警规则
+//!
+//! `--alert-rules FILE` 指定一个 JSON 数组，每条规则对一项指标设置阈值：
+//!
+//! ```json
+//! [
+//!   {"name": "hit_rate_low", "metric": "hits_per_hour", "op": "<", "threshold": 10, "for": "15m"},
+//!   {"name": "timeouts", "metric": "timeout_rate", "op": ">", "threshold": 0.2},
+//!   {"name": "fleet_shrunk", "metric": "active_workers", "op": "<", "threshold": 3, "for": "5m"}
+//! ]
+//! ```
+//!
+//! Master按 `--alert-interval` 定期计算各项指标：条件持续满足 `for`（默认 0s）后规则触发，
+//! 通过告警通知（webhook 或邮件）发送 `alert_firing`；条件不再满足时发送 `alert_resolved`。
+//! 指标暂时无法计算时（例如最近没有任何任务，超时率没有意义）保持规则原来的状态。
+//! 主备模式下只由主节点计算。
+
+use crate::metrics::Metrics;
+use crate::{admin, history, AppState};
+use common::admin::AlertStatus;
+use serde::{Deserialize, Deserializer};
+use serde_json::json;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// 超时率、提交数等比例类指标的统计窗口
+const RATE_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+/// 判定 Worker 在线的时间窗口
+const ACTIVE_WINDOW_SECS: u64 = 60;
+
+/// 规则名称的最大长度
+const MAX_RULE_NAME_LEN: usize = 64;
+
+/// 可以设置告警的指标
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMetric {
+    /// 最近 60 分钟发现的有效ID数
+    HitsPerHour,
+    /// 最近 5 分钟平均每秒扫描的ID数
+    IdsPerSecond,
+    /// 最近 15 分钟超时被回收的任务占（回收 + 提交）的比例
+    TimeoutRate,
+    /// 最近 60 分钟完成的任务中重试耗尽或被上游拒绝的ID比例
+    UncheckedRate,
+    /// 最近 60 秒内有过请求或心跳的 Worker 数
+    ActiveWorkers,
+    /// 有 Worker 持有的任务数
+    RunningTasks,
+    /// 等待分配的任务数
+    PendingTasks,
+    /// 最近 5 分钟上游延迟的 p90（毫秒）
+    UpstreamP90Ms,
+}
+
+impl AlertMetric {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::HitsPerHour => "hits_per_hour",
+            Self::IdsPerSecond => "ids_per_second",
+            Self::TimeoutRate => "timeout_rate",
+            Self::UncheckedRate => "unchecked_rate",
+            Self::ActiveWorkers => "active_workers",
+            Self::RunningTasks => "running_tasks",
+            Self::PendingTasks => "pending_tasks",
+            Self::UpstreamP90Ms => "upstream_p90_ms",
+        }
+    }
+}
+
+/// 比较方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Comparison {
+    #[serde(rename = ">")]
+    Above,
+    #[serde(rename = ">=")]
+    AtLeast,
+    #[serde(rename = "<")]
+    Below,
+    #[serde(rename = "<=")]
+    AtMost,
+}
+
+impl Comparison {
+    fn matches(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Self::Above => value > threshold,
+            Self::AtLeast => value >= threshold,
+            Self::Below => value < threshold,
+            Self::AtMost => value <= threshold,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Above => ">",
+            Self::AtLeast => ">=",
+            Self::Below => "<",
+            Self::AtMost => "<=",
+        })
+    }
+}
+
+/// 一条告警规则
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// 规则名称，出现在通知中
+    pub name: String,
+    pub metric: AlertMetric,
+    pub op: Comparison,
+    pub threshold: f64,
+    /// 条件需要持续满足多久才触发
+    #[serde(rename = "for", default, deserialize_with = "parse_duration")]
+    pub for_duration: Duration,
+}
+
+fn parse_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    humantime::parse_duration(&text).map_err(serde::de::Error::custom)
+}
+
+/// 从文件加载规则并检查
+pub fn load_rules(path: &Path) -> Result<Vec<AlertRule>, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("无法读取告警规则 {}: {}", path.display(), e))?;
+    let rules: Vec<AlertRule> = serde_json::from_str(&text)
+        .map_err(|e| format!("告警规则 {} 格式错误: {}", path.display(), e))?;
+
+    let mut names = std::collections::HashSet::new();
+    for rule in &rules {
+        if rule.name.is_empty() || rule.name.len() > MAX_RULE_NAME_LEN {
+            return Err(format!(
+                "告警规则名称长度必须在 1 到 {} 之间: {:?}",
+                MAX_RULE_NAME_LEN, rule.name
+            ));
+        }
+        if !names.insert(rule.name.as_str()) {
+            return Err(format!("告警规则名称重复: {}", rule.name));
+        }
+        if !rule.threshold.is_finite() {
+            return Err(format!("告警规则 {} 的阈值无效", rule.name));
+        }
+    }
+    Ok(rules)
+}
+
+/// 规则的当前状态
+#[derive(Debug, Clone, Copy, Default)]
+struct RuleState {
+    /// 条件从何时开始满足（None 表示不满足）
+    pending_since: Option<Instant>,
+    /// 从何时开始触发（None 表示未触发）
+    firing_since: Option<Instant>,
+    /// 最近一次计算的指标值
+    value: Option<f64>,
+}
+
+/// 告警规则及其状态
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    states: Mutex<Vec<RuleState>>,
+    /// 回收计数的采样，用于计算窗口内的超时数
+    reaped_samples: Mutex<VecDeque<(Instant, u64)>>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        let states = vec![RuleState::default(); rules.len()];
+        Self {
+            rules,
+            states: Mutex::new(states),
+            reaped_samples: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 各规则的当前状态
+    pub fn status(&self) -> Vec<AlertStatus> {
+        let states = self.states.lock().expect("告警状态锁中毒");
+        self.rules
+            .iter()
+            .zip(states.iter())
+            .map(|(rule, state)| {
+                let (status, since) = match (state.firing_since, state.pending_since) {
+                    (Some(at), _) => ("firing", Some(at)),
+                    (None, Some(at)) => ("pending", Some(at)),
+                    (None, None) => ("ok", None),
+                };
+                AlertStatus {
+                    name: rule.name.clone(),
+                    metric: rule.metric.name().to_string(),
+                    op: rule.op.to_string(),
+                    threshold: rule.threshold,
+                    for_secs: rule.for_duration.as_secs(),
+                    value: state.value,
+                    state: status.to_string(),
+                    since_secs: since.map(|at| at.elapsed().as_secs()),
+                }
+            })
+            .collect()
+    }
+
+    /// 失去主节点身份时清空状态，由新的主节点重新计算
+    fn reset(&self) {
+        let mut states = self.states.lock().expect("告警状态锁中毒");
+        states.fill(RuleState::default());
+        self.reaped_samples.lock().expect("告警状态锁中毒").clear();
+    }
+
+    /// 记录当前的回收计数，返回统计窗口内新增的回收数
+    fn reaped_in_window(&self, reaped: u64) -> u64 {
+        let now = Instant::now();
+        let mut samples = self.reaped_samples.lock().expect("告警状态锁中毒");
+        samples.push_back((now, reaped));
+        while samples
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > RATE_WINDOW)
+        {
+            samples.pop_front();
+        }
+        let oldest = samples.front().map(|(_, count)| *count).unwrap_or(reaped);
+        reaped.saturating_sub(oldest)
+    }
+}
+
+/// 规则状态的变化
+enum Transition {
+    Fired,
+    Resolved,
+}
+
+/// 启动后台告警计算
+pub fn spawn_alerts(state: Arc<AppState>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let Some(engine) = &state.alerts else {
+                return;
+            };
+            if !state.leadership.is_leader() {
+                engine.reset();
+                continue;
+            }
+            match collect_metrics(&state, engine).await {
+                Ok(values) => evaluate(&state, engine, &values).await,
+                Err(e) => error!("计算告警指标失败: {}", e),
+            }
+        }
+    });
+}
+
+/// 计算规则用到的指标，无法计算的指标不出现在结果中
+async fn collect_metrics(
+    state: &AppState,
+    engine: &AlertEngine,
+) -> Result<BTreeMap<AlertMetric, f64>, sqlx::Error> {
+    let mut values = BTreeMap::new();
+
+    let points = history::fetch(&state.read_pool, 60).await?;
+    values.insert(
+        AlertMetric::HitsPerHour,
+        points.iter().map(|point| point.valid_found).sum::<i64>() as f64,
+    );
+    let recent_scanned: i64 = points
+        .iter()
+        .rev()
+        .take(5)
+        .map(|point| point.ids_scanned)
+        .sum();
+    values.insert(AlertMetric::IdsPerSecond, recent_scanned as f64 / 300.0);
+
+    let window_minutes = (RATE_WINDOW.as_secs() / 60) as usize;
+    let submitted: i64 = points
+        .iter()
+        .rev()
+        .take(window_minutes)
+        .map(|point| point.tasks)
+        .sum();
+    let reaped = engine.reaped_in_window(state.metrics.tasks_reaped.load(Ordering::Relaxed));
+    let finished = reaped + submitted.max(0) as u64;
+    if finished > 0 {
+        values.insert(AlertMetric::TimeoutRate, reaped as f64 / finished as f64);
+    }
+
+    let (scanned, unchecked): (Option<i64>, Option<i64>) = sqlx::query_as(
+        r#"
+        SELECT SUM(end_id - start_id + 1), SUM(unknown_count + blocked_count)
+        FROM task_archive
+        WHERE list_task = 0 AND completed_at >= datetime('now', '-60 minutes')
+        "#,
+    )
+    .fetch_one(&state.read_pool)
+    .await?;
+    if let (Some(scanned), Some(unchecked)) = (scanned, unchecked) {
+        if scanned > 0 {
+            values.insert(
+                AlertMetric::UncheckedRate,
+                unchecked as f64 / scanned as f64,
+            );
+        }
+    }
+
+    let cluster = admin::collect_cluster(state, ACTIVE_WINDOW_SECS).await;
+    values.insert(AlertMetric::ActiveWorkers, cluster.workers.len() as f64);
+
+    let tasks = state.running.snapshot().await;
+    let pending = tasks
+        .iter()
+        .filter(|task| task.worker_id.is_empty())
+        .count();
+    values.insert(AlertMetric::PendingTasks, pending as f64);
+    values.insert(AlertMetric::RunningTasks, (tasks.len() - pending) as f64);
+
+    if let Some(latency) = history::recent_latency(&state.read_pool, 5).await? {
+        values.insert(AlertMetric::UpstreamP90Ms, latency.p90_ms as f64);
+    }
+
+    Ok(values)
+}
+
+/// 按最新的指标更新规则状态，发送触发和恢复通知
+async fn evaluate(state: &AppState, engine: &AlertEngine, values: &BTreeMap<AlertMetric, f64>) {
+    let now = Instant::now();
+    let mut transitions = Vec::new();
+    {
+        let mut states = engine.states.lock().expect("告警状态锁中毒");
+        for (index, (rule, rule_state)) in engine.rules.iter().zip(states.iter_mut()).enumerate() {
+            let Some(&value) = values.get(&rule.metric) else {
+                continue;
+            };
+            rule_state.value = Some(value);
+
+            if rule.op.matches(value, rule.threshold) {
+                let since = *rule_state.pending_since.get_or_insert(now);
+                if rule_state.firing_since.is_none()
+                    && now.duration_since(since) >= rule.for_duration
+                {
+                    rule_state.firing_since = Some(now);
+                    transitions.push((index, Transition::Fired, value));
+                }
+            } else {
+                rule_state.pending_since = None;
+                if rule_state.firing_since.take().is_some() {
+                    transitions.push((index, Transition::Resolved, value));
+                }
+            }
+        }
+    }
+
+    for (index, transition, value) in transitions {
+        let rule = &engine.rules[index];
+        let details = json!({
+            "rule": rule.name,
+            "metric": rule.metric.name(),
+            "op": rule.op.to_string(),
+            "threshold": rule.threshold,
+            "value": value,
+        });
+        let (kind, text) = match transition {
+            Transition::Fired => {
+                Metrics::incr(&state.metrics.alerts_fired);
+                let text = format!(
+                    "告警 {} 触发: {} = {} {} {}",
+                    rule.name,
+                    rule.metric.name(),
+                    format_value(value),
+                    rule.op,
+                    rule.threshold
+                );
+                warn!("{}", text);
+                ("alert_firing", text)
+            }
+            Transition::Resolved => {
+                let text = format!(
+                    "告警 {} 已恢复: {} = {}",
+                    rule.name,
+                    rule.metric.name(),
+                    format_value(value)
+                );
+                info!("{}", text);
+                ("alert_resolved", text)
+            }
+        };
+        if let Some(notifier) = &state.notifier {
+            notifier.send(kind, &text, details).await;
+        }
+    }
+}
+
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.4}", value)
+    }
+}
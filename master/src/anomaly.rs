@@ -0,0 +1,250 @@
+//! 可疑提交检测
+//!
+//! 提交结果时做几项简单的统计检查，不合理的提交照常接收，
+//! 但会记入 `verification_queue`，并为该范围创建复查任务由其他Worker重新扫描：
+//! - 覆盖校验不符：Worker上报的探测数、有效数或摘要与任务范围对不上
+//! - 完成过快：按签名中的分配时间计算的实际速度远超Worker上报的速度
+//!   （Worker的第一个任务上报的是配置的初始速度，不做这项检查）
+//! - 历史高密度区域中没有发现任何有效ID
+//! - 命中率与相邻已完成范围差异过大
+//!
+//! 复查任务本身不再做检测，完成后把复查结果写回对应的记录。
+
+use common::coverage::Coverage;
+use master::queue;
+use master::running::RunningTask;
+use sqlx::SqliteConnection;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 记录的Worker数超过该值时清空上报速度
+const MAX_TRACKED_WORKERS: usize = 4096;
+
+/// 检测参数（各项为0时关闭对应的检查）
+pub struct AnomalyConfig {
+    /// 实际速度超过上报速度的倍数
+    pub speed_factor: f64,
+
+    /// 命中率与相邻范围相差的倍数（0 同时关闭高密度区域零命中的检查）
+    pub rate_factor: f64,
+
+    /// 预期（或实际）命中数至少为多少时才比较命中率，避免小样本误报
+    pub min_expected: f64,
+
+    /// 向两侧各取多少个ID范围内的已完成任务作为参照
+    pub window: i64,
+}
+
+/// Worker上报的速度
+#[derive(Clone, Copy, Default)]
+struct ReportedSpeed {
+    /// 最近一次 acquire 时上报的速度
+    speed: u32,
+    /// 是否已经提交过任务（之后上报的速度才是实测值）
+    measured: bool,
+}
+
+/// 可疑提交检测器
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    reported_speed: Mutex<HashMap<String, ReportedSpeed>>,
+}
+
+/// 一次提交的检测输入
+pub struct Submission<'a> {
+    pub task_id: i32,
+    pub start_id: i64,
+    pub end_id: i64,
+    pub worker_id: &'a str,
+    pub valid_count: i64,
+    /// 从分配到提交经过的毫秒数
+    pub elapsed_ms: i64,
+    /// Worker上报的探测覆盖信息
+    pub coverage: Option<Coverage>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            reported_speed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 记录Worker在 acquire 时上报的速度
+    pub fn record_speed(&self, worker_id: &str, speed: Option<u32>) {
+        let Some(speed) = speed.filter(|speed| *speed > 0) else {
+            return;
+        };
+        let mut reported = self.reported_speed.lock().expect("速度记录锁中毒");
+        if reported.len() >= MAX_TRACKED_WORKERS && !reported.contains_key(worker_id) {
+            reported.clear();
+        }
+        reported.entry(worker_id.to_string()).or_default().speed = speed;
+    }
+
+    /// 检查一次提交，可疑时返回原因
+    pub async fn check(
+        &self,
+        conn: &mut SqliteConnection,
+        submission: &Submission<'_>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let ids = submission.end_id - submission.start_id + 1;
+
+        // 1. 探测覆盖
+        if let Some(coverage) = &submission.coverage {
+            if coverage.probed() != ids as u64 {
+                return Ok(Some(format!(
+                    "覆盖校验不符: 只探测了 {}/{} 个ID",
+                    coverage.probed(),
+                    ids
+                )));
+            }
+            if coverage.valid != submission.valid_count as u64 {
+                return Ok(Some(format!(
+                    "覆盖校验不符: 探测到 {} 个有效ID，提交了 {} 个",
+                    coverage.valid, submission.valid_count
+                )));
+            }
+            if coverage.digest != Coverage::expected_digest(submission.start_id, submission.end_id)
+            {
+                return Ok(Some("覆盖校验不符: 探测过的ID与任务范围不一致".to_string()));
+            }
+        }
+
+        // 2. 完成速度
+        if self.config.speed_factor > 0.0 {
+            let reported = self
+                .reported_speed
+                .lock()
+                .expect("速度记录锁中毒")
+                .get_mut(submission.worker_id)
+                .and_then(|reported| {
+                    let measured = std::mem::replace(&mut reported.measured, true);
+                    (measured && reported.speed > 0).then_some(reported.speed)
+                });
+            if let Some(reported) = reported {
+                let secs = submission.elapsed_ms.max(1) as f64 / 1000.0;
+                let actual = ids as f64 / secs;
+                if actual > reported as f64 * self.config.speed_factor {
+                    return Ok(Some(format!(
+                        "完成过快: 实际 {:.0} ids/s，上报速度 {} ids/s",
+                        actual, reported
+                    )));
+                }
+            }
+        }
+
+        // 3. 与相邻已完成范围的命中率比较
+        if self.config.rate_factor <= 0.0 {
+            return Ok(None);
+        }
+        let (neighbor_hits, neighbor_ids): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT COALESCE(SUM(valid_count), 0), COALESCE(SUM(end_id - start_id + 1), 0)
+            FROM task_archive
+            WHERE end_id >= ? AND start_id <= ? AND task_id != ? AND list_task = 0
+            "#,
+        )
+        .bind(submission.start_id.saturating_sub(self.config.window))
+        .bind(submission.end_id.saturating_add(self.config.window))
+        .bind(submission.task_id)
+        .fetch_one(conn)
+        .await?;
+
+        // 参照范围太小时不做判断
+        if neighbor_ids < ids {
+            return Ok(None);
+        }
+
+        let expected = neighbor_hits as f64 / neighbor_ids as f64 * ids as f64;
+        let valid = submission.valid_count as f64;
+        if valid.max(expected) < self.config.min_expected {
+            return Ok(None);
+        }
+
+        if submission.valid_count == 0 {
+            return Ok(Some(format!(
+                "历史高密度区域中没有发现有效ID（预期约 {:.1} 个）",
+                expected
+            )));
+        }
+
+        let factor = self.config.rate_factor;
+        if valid > expected * factor || valid * factor < expected {
+            return Ok(Some(format!(
+                "命中率与相邻范围差异过大: 发现 {} 个，预期约 {:.1} 个",
+                submission.valid_count, expected
+            )));
+        }
+
+        Ok(None)
+    }
+}
+
+/// 将可疑提交记入复查队列，并为该范围创建复查任务
+pub async fn enqueue(
+    conn: &mut SqliteConnection,
+    submission: &Submission<'_>,
+    reason: &str,
+) -> Result<RunningTask, sqlx::Error> {
+    let verification_id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO verification_queue (task_id, start_id, end_id, worker_id, reason, valid_count)
+        VALUES (?, ?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(submission.task_id)
+    .bind(submission.start_id)
+    .bind(submission.end_id)
+    .bind(submission.worker_id)
+    .bind(reason)
+    .bind(submission.valid_count)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let task_id = queue::insert_pending(&mut *conn, submission.start_id, submission.end_id).await?;
+    sqlx::query("UPDATE task_queue SET verification_id = ? WHERE task_id = ?")
+        .bind(verification_id)
+        .bind(task_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE verification_queue SET verify_task_id = ? WHERE id = ?")
+        .bind(task_id)
+        .bind(verification_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(RunningTask {
+        task_id,
+        start_id: submission.start_id,
+        end_id: submission.end_id,
+        worker_id: String::new(),
+        lease_expires: None,
+        verification: true,
+        list: false,
+        speed: None,
+        stalled_since: None,
+    })
+}
+
+/// 复查任务完成，写回复查得到的有效ID数
+pub async fn complete(
+    conn: &mut SqliteConnection,
+    verification_id: i64,
+    verified_count: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE verification_queue
+        SET status = 'done', verified_count = ?, completed_at = CURRENT_TIMESTAMP
+        WHERE id = ?
+        "#,
+    )
+    .bind(verified_count)
+    .bind(verification_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,322 @@
+//! Worker的API Key和配额
+//!
+//! 社区贡献的Worker各自使用一个API Key，可以单独限制配额、随时吊销：
+//! - 同时持有的任务数上限
+//! - 每天（UTC）分配的ID数上限
+//!
+//! 密钥只在创建时返回一次，数据库中只保存 SHA-256 摘要。
+//! 启用 `--require-api-key` 后，`/task/*` 接口必须携带有效的API Key；
+//! 未启用时不带API Key的请求照常处理，带了的仍然会校验并计入配额。
+//!
+//! 多个Worker可以共用一个API Key。同一个Key的配额检查、领取任务和记录用量持有该Key的配额锁依次进行
+//! （见 [`ApiKeys::lock_quota`]），并发的请求不会同时通过检查而超出配额；只有主节点分配任务，进程内的锁即可保证这一点。
+//!
+//! API Key带有角色：`/task/*` 只接受 worker 角色，read_only 和 admin 角色的令牌用于管理接口（见 [`crate::admin_auth`]）。
+
+use crate::{api_error, AppState};
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::admin::ApiKeyRole;
+use common::{ErrorCode, API_KEY_SCHEME};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard, RwLock};
+use tracing::warn;
+
+/// 并发任务数达到上限时建议Worker等待的时长
+const CONCURRENCY_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// 一个有效的API Key
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub key_id: i64,
+    pub name: String,
+    pub role: ApiKeyRole,
+    pub max_concurrent_tasks: Option<i64>,
+    pub max_ids_per_day: Option<i64>,
+}
+
+/// 配额检查结果
+pub enum Quota {
+    /// 可以分配，新切分的范围不能超过给出的ID数
+    Allowed(i64),
+    /// 配额已用完，附带原因和建议的等待时长
+    Exceeded(String, Duration),
+}
+
+/// 有效API Key的内存缓存（按摘要索引，不含已吊销的）
+#[derive(Default)]
+pub struct ApiKeys {
+    keys: RwLock<HashMap<String, ApiKey>>,
+
+    /// 每个Key的配额锁（key_id -> 锁）
+    quota_locks: Mutex<HashMap<i64, Arc<AsyncMutex<()>>>>,
+}
+
+impl ApiKeys {
+    /// 从数据库加载未吊销的API Key
+    pub async fn load(pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+        let keys = Self::default();
+        keys.reload(pool).await?;
+        Ok(keys)
+    }
+
+    /// 从数据库重新加载（其他节点可能修改过）
+    pub async fn reload(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        let rows = sqlx::query_as::<_, (i64, String, String, String, Option<i64>, Option<i64>)>(
+            "SELECT key_id, name, key_hash, role, max_concurrent_tasks, max_ids_per_day FROM api_keys WHERE revoked = 0",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        *self.keys.write().await = rows
+            .into_iter()
+            .map(
+                |(key_id, name, hash, role, max_concurrent_tasks, max_ids_per_day)| {
+                    (
+                        hash,
+                        ApiKey {
+                            key_id,
+                            name,
+                            // 不认识的角色按权限最小的 worker 处理
+                            role: role.parse().unwrap_or_default(),
+                            max_concurrent_tasks,
+                            max_ids_per_day,
+                        },
+                    )
+                },
+            )
+            .collect();
+        Ok(())
+    }
+
+    /// 查找密钥对应的API Key
+    pub async fn lookup(&self, key: &str) -> Option<ApiKey> {
+        self.keys.read().await.get(&hash_key(key)).cloned()
+    }
+
+    /// 取得API Key的配额锁，持有期间同一个Key的其他请求不能检查配额
+    ///
+    /// 检查配额、领取任务和记录用量需要在持有锁时完成，否则共用一个Key的多个Worker可能同时通过检查。
+    pub async fn lock_quota(&self, key_id: i64) -> OwnedMutexGuard<()> {
+        let lock = Arc::clone(
+            self.quota_locks
+                .lock()
+                .expect("配额锁表锁中毒")
+                .entry(key_id)
+                .or_default(),
+        );
+        lock.lock_owned().await
+    }
+
+    /// 是否有未吊销的 read_only 或 admin 角色的API Key
+    pub async fn has_management_keys(&self) -> bool {
+        self.keys
+            .read()
+            .await
+            .values()
+            .any(|key| key.role != ApiKeyRole::Worker)
+    }
+
+    /// 创建API Key，返回 (key_id, 密钥)
+    pub async fn create(
+        &self,
+        pool: &SqlitePool,
+        name: &str,
+        role: ApiKeyRole,
+        max_concurrent_tasks: Option<i64>,
+        max_ids_per_day: Option<i64>,
+    ) -> Result<(i64, String), sqlx::Error> {
+        let key = format!(
+            "pa_{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        let hash = hash_key(&key);
+
+        let key_id: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO api_keys (name, key_hash, role, max_concurrent_tasks, max_ids_per_day)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING key_id
+            "#,
+        )
+        .bind(name)
+        .bind(&hash)
+        .bind(role.as_str())
+        .bind(max_concurrent_tasks)
+        .bind(max_ids_per_day)
+        .fetch_one(pool)
+        .await?;
+
+        self.keys.write().await.insert(
+            hash,
+            ApiKey {
+                key_id,
+                name: name.to_string(),
+                role,
+                max_concurrent_tasks,
+                max_ids_per_day,
+            },
+        );
+        Ok((key_id, key))
+    }
+
+    /// 吊销API Key，返回是否确实吊销了一个有效的Key
+    pub async fn revoke(&self, pool: &SqlitePool, key_id: i64) -> Result<bool, sqlx::Error> {
+        let result =
+            sqlx::query("UPDATE api_keys SET revoked = 1 WHERE key_id = ? AND revoked = 0")
+                .bind(key_id)
+                .execute(pool)
+                .await?;
+        self.keys
+            .write()
+            .await
+            .retain(|_, key| key.key_id != key_id);
+        self.quota_locks
+            .lock()
+            .expect("配额锁表锁中毒")
+            .remove(&key_id);
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// 密钥的 SHA-256 摘要（十六进制）
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// 检查API Key的配额，`batch_size` 为本次打算分配的ID数
+///
+/// 调用方需要持有 [`ApiKeys::lock_quota`] 返回的锁，直到领取的任务和用量都已写入数据库。
+pub async fn check_quota(
+    pool: &SqlitePool,
+    key: &ApiKey,
+    batch_size: i64,
+) -> Result<Quota, sqlx::Error> {
+    if let Some(max) = key.max_concurrent_tasks {
+        let running: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM task_queue WHERE key_id = ? AND worker_id != ''",
+        )
+        .bind(key.key_id)
+        .fetch_one(pool)
+        .await?;
+        if running >= max {
+            return Ok(Quota::Exceeded(
+                format!("同时持有的任务数已达上限 {}", max),
+                CONCURRENCY_RETRY_AFTER,
+            ));
+        }
+    }
+
+    let Some(max) = key.max_ids_per_day else {
+        return Ok(Quota::Allowed(batch_size));
+    };
+    let used = ids_today(pool, key.key_id).await?;
+    if used >= max {
+        return Ok(Quota::Exceeded(
+            format!("今天分配的ID数已达上限 {}", max),
+            until_next_day(),
+        ));
+    }
+    Ok(Quota::Allowed(batch_size.min(max - used)))
+}
+
+/// 距离下一个UTC零点（每日配额重置）的时长
+fn until_next_day() -> Duration {
+    let now = chrono::Utc::now();
+    let tomorrow = (now.date_naive() + chrono::Days::new(1)).and_time(chrono::NaiveTime::MIN);
+    (tomorrow - now.naive_utc()).to_std().unwrap_or_default()
+}
+
+/// 今天（UTC）已分配给API Key的ID数
+pub async fn ids_today(pool: &SqlitePool, key_id: i64) -> Result<i64, sqlx::Error> {
+    let used: Option<i64> = sqlx::query_scalar(
+        "SELECT ids_assigned FROM api_key_usage WHERE key_id = ? AND day = date('now')",
+    )
+    .bind(key_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(used.unwrap_or(0))
+}
+
+/// 记录分配给API Key的ID数
+pub async fn record_usage(pool: &SqlitePool, key_id: i64, ids: i64) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO api_key_usage (key_id, day, ids_assigned)
+        VALUES (?, date('now'), ?)
+        ON CONFLICT(key_id, day) DO UPDATE SET ids_assigned = ids_assigned + excluded.ids_assigned
+        "#,
+    )
+    .bind(key_id)
+    .bind(ids)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// 中间件：校验 `/task/*` 请求携带的API Key，有效时放入请求扩展供处理函数检查配额
+pub async fn authenticate(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix(API_KEY_SCHEME))
+        .map(|value| value.trim().to_string());
+
+    match token {
+        Some(token) => match state.api_keys.lookup(&token).await {
+            Some(key) if key.role != ApiKeyRole::Worker => {
+                warn!(
+                    "{} 角色的API Key {} 不能调用Worker接口: {}",
+                    key.role,
+                    key.key_id,
+                    req.uri()
+                );
+                return forbidden("该API Key不是Worker角色，不能调用 /task/*");
+            }
+            Some(key) => {
+                req.extensions_mut().insert(key);
+            }
+            None => {
+                warn!("无效或已吊销的API Key: {} {}", req.method(), req.uri());
+                return unauthorized("API Key无效或已被吊销");
+            }
+        },
+        None if state.config.require_api_key => {
+            return unauthorized("缺少API Key（Authorization: Bearer <key>）");
+        }
+        None => {}
+    }
+
+    next.run(req).await
+}
+
+fn forbidden(message: &str) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(api_error::<()>(message.to_string()).with_code(ErrorCode::Forbidden)),
+    )
+        .into_response()
+}
+
+fn unauthorized(message: &str) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(api_error::<()>(message.to_string()).with_code(ErrorCode::Unauthorized)),
+    )
+        .into_response()
+}
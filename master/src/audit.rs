@@ -0,0 +1,56 @@
+//! 管理操作审计日志
+//!
+//! Master 的管理接口和 init 工具执行的修改操作都写入 audit_log 表，
+//! 出问题时可以查到是谁、在什么时候、用什么参数做了什么。
+
+use common::admin::AuditEntry;
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// 写入一条审计日志
+pub async fn record<'c>(
+    executor: impl SqliteExecutor<'c>,
+    actor: &str,
+    action: &str,
+    details: serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO audit_log (actor, action, details) VALUES (?, ?, ?)")
+        .bind(actor)
+        .bind(action)
+        .bind(details.to_string())
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// 最近的审计日志（新的在前），可按操作类型过滤
+pub async fn recent(
+    pool: &SqlitePool,
+    limit: u32,
+    action: Option<&str>,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, String, String, String, String)>(
+        r#"
+        SELECT id, actor, action, details, created_at
+        FROM audit_log
+        WHERE ? IS NULL OR action = ?
+        ORDER BY id DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(action)
+    .bind(action)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(id, actor, action, details, created_at)| AuditEntry {
+            id,
+            actor,
+            action,
+            details,
+            created_at,
+        })
+        .collect())
+}
@@ -0,0 +1,147 @@
+//! 定期备份
+//!
+//! 扫描进度和结果都只保存在一个 SQLite 文件里，磁盘损坏就意味着重新扫描数亿个ID。
+//! 备份任务定期用 `VACUUM INTO` 在线生成一致的数据库快照（不阻塞Worker的读写），
+//! 写入备份目录，并只保留最近的若干份。配置了上传目标时，备份完成后会再推送到
+//! S3 兼容存储或 WebDAV。
+
+mod upload;
+
+pub use upload::{UploadTarget, Uploader};
+
+use crate::db_pool;
+use crate::metrics::Metrics;
+use crate::AppState;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// 备份文件名前缀，清理时只处理带此前缀的文件
+const BACKUP_PREFIX: &str = "pa_master-";
+
+/// 备份文件扩展名
+const BACKUP_EXTENSION: &str = ".db";
+
+/// 启动后台备份任务
+///
+/// 第一次备份在一个间隔之后执行，避免每次重启都立即生成一份。
+/// 上传失败不影响本地备份，只记录日志和指标。
+pub fn spawn_backup(
+    state: Arc<AppState>,
+    dir: PathBuf,
+    interval: Duration,
+    keep: usize,
+    uploader: Option<Uploader>,
+) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        loop {
+            ticker.tick().await;
+            // 主备模式下只由主节点备份
+            if !state.leadership.is_leader() {
+                continue;
+            }
+            match backup_once(&state, &dir, keep).await {
+                Ok(path) => {
+                    Metrics::incr(&state.metrics.backups_completed);
+                    info!("数据库已备份到 {}", path.display());
+
+                    if let Some(uploader) = &uploader {
+                        match uploader.upload(&path).await {
+                            Ok(()) => {
+                                Metrics::incr(&state.metrics.backup_uploads_completed);
+                                info!("备份已上传到 {}", uploader.target());
+                            }
+                            Err(e) => {
+                                Metrics::incr(&state.metrics.backup_uploads_failed);
+                                error!("上传备份到 {} 失败: {}", uploader.target(), e);
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    Metrics::incr(&state.metrics.backups_failed);
+                    error!("数据库备份失败: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// 执行一次备份并清理过期备份，返回备份文件路径
+async fn backup_once(
+    state: &AppState,
+    dir: &Path,
+    keep: usize,
+) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    fs::create_dir_all(dir)?;
+
+    let name = format!(
+        "{}{}{}",
+        BACKUP_PREFIX,
+        chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+        BACKUP_EXTENSION
+    );
+    let path = dir.join(&name);
+    // 先写入临时文件，完成后再改名，避免留下不完整的备份被当作最新的一份
+    let temp = dir.join(format!("{}.tmp", name));
+    if temp.exists() {
+        fs::remove_file(&temp)?;
+    }
+
+    let start = Instant::now();
+    // 1. VACUUM INTO 在一个读事务中复制整个数据库，期间写入不受影响；数据库较大时耗时较长，不受语句超时限制
+    let mut conn = db_pool::acquire_unbounded(&state.db_pool).await?;
+    sqlx::query("VACUUM INTO ?")
+        .bind(temp.to_string_lossy().as_ref())
+        .execute(&mut *conn)
+        .await?;
+    drop(conn);
+    fs::rename(&temp, &path)?;
+
+    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    info!(
+        "备份完成: {} 字节，耗时 {:.1}s",
+        size,
+        start.elapsed().as_secs_f64()
+    );
+
+    // 2. 清理超出保留数量的旧备份
+    let removed = prune_backups(dir, keep)?;
+    if removed > 0 {
+        info!("已删除 {} 个过期备份", removed);
+    }
+
+    Ok(path)
+}
+
+/// 删除超出保留数量的旧备份，返回删除的数量（keep 为0时全部保留）
+fn prune_backups(dir: &Path, keep: usize) -> io::Result<usize> {
+    if keep == 0 {
+        return Ok(0);
+    }
+
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with(BACKUP_PREFIX) && name.ends_with(BACKUP_EXTENSION)
+        })
+        .map(|entry| entry.path())
+        .collect();
+
+    // 文件名中的时间戳是定长的，按文件名排序即按时间排序
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    for path in &backups[..excess] {
+        if let Err(e) = fs::remove_file(path) {
+            warn!("删除过期备份 {} 失败: {}", path.display(), e);
+        }
+    }
+
+    Ok(excess)
+}
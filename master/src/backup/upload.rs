@@ -0,0 +1,307 @@
+//! 备份远程上传
+//!
+//! 备份生成后可以额外推送到 S3 兼容存储（AWS S3、MinIO 等）或 WebDAV 服务，
+//! 防止整台机器损坏时本地备份一起丢失。
+//!
+//! 每份备份会同时上传一个 `sha256sum` 格式的校验文件：
+//! - S3：请求签名中携带内容的 SHA-256，服务端校验不一致会拒绝写入
+//! - WebDAV：上传后重新下载并比对 SHA-256
+
+use crate::blob::{uri_encode, S3Location, EMPTY_SHA256};
+use reqwest::header::CONTENT_LENGTH;
+use reqwest::{Body, Client, Method, RequestBuilder, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio_util::io::ReaderStream;
+use tracing::{info, warn};
+
+/// 两次重试之间的最长等待时间
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// 上传目标
+pub enum UploadTarget {
+    /// S3 兼容存储（路径风格访问：`{endpoint}/{bucket}/{key}`）
+    S3(S3Location),
+    /// WebDAV 目录
+    WebDav {
+        base_url: Url,
+        username: Option<String>,
+        password: Option<String>,
+    },
+}
+
+impl UploadTarget {
+    /// 根据上传地址构造上传目标
+    ///
+    /// - `s3://bucket/prefix`：凭据从 `AWS_ACCESS_KEY_ID`、`AWS_SECRET_ACCESS_KEY` 环境变量读取
+    /// - `http(s)://host/path`：WebDAV，凭据从 `PA_BACKUP_WEBDAV_USER`、`PA_BACKUP_WEBDAV_PASSWORD` 读取
+    pub fn parse(url: &str, s3_endpoint: &str, s3_region: &str) -> Result<Self, String> {
+        if let Some(location) = S3Location::parse(url, s3_endpoint, s3_region)? {
+            return Ok(UploadTarget::S3(location));
+        }
+
+        let mut base_url = Url::parse(url).map_err(|e| format!("无效的上传地址 {}: {}", url, e))?;
+        if !matches!(base_url.scheme(), "http" | "https") {
+            return Err(format!(
+                "不支持的上传地址: {}（可选 s3://bucket/prefix 或 WebDAV 的 http(s) 地址）",
+                url
+            ));
+        }
+        // 保证以 `/` 结尾，拼接文件名时不会替换掉最后一级目录
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        Ok(UploadTarget::WebDav {
+            base_url,
+            username: std::env::var("PA_BACKUP_WEBDAV_USER").ok(),
+            password: std::env::var("PA_BACKUP_WEBDAV_PASSWORD").ok(),
+        })
+    }
+}
+
+impl fmt::Display for UploadTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadTarget::S3(location) => location.fmt(f),
+            UploadTarget::WebDav { base_url, .. } => write!(f, "{}", base_url),
+        }
+    }
+}
+
+/// 上传失败
+#[derive(Debug)]
+pub enum UploadError {
+    /// 读取本地文件失败
+    Io(std::io::Error),
+    /// 请求失败
+    Http(reqwest::Error),
+    /// 服务端返回了错误状态码
+    Status { status: StatusCode, body: String },
+    /// 上传后的校验不一致
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadError::Io(e) => write!(f, "读取备份文件失败: {}", e),
+            UploadError::Http(e) => write!(f, "请求失败: {}", e),
+            UploadError::Status { status, body } => write!(f, "服务端返回 {}: {}", status, body),
+            UploadError::ChecksumMismatch { expected, actual } => {
+                write!(f, "校验失败: 期望 {}，实际 {}", expected, actual)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+impl From<std::io::Error> for UploadError {
+    fn from(e: std::io::Error) -> Self {
+        UploadError::Io(e)
+    }
+}
+
+impl From<reqwest::Error> for UploadError {
+    fn from(e: reqwest::Error) -> Self {
+        UploadError::Http(e)
+    }
+}
+
+/// 备份上传器
+pub struct Uploader {
+    client: Client,
+    target: UploadTarget,
+    retries: u32,
+}
+
+impl Uploader {
+    pub fn new(target: UploadTarget, retries: u32) -> Self {
+        Self {
+            client: Client::new(),
+            target,
+            retries,
+        }
+    }
+
+    pub fn target(&self) -> &UploadTarget {
+        &self.target
+    }
+
+    /// 上传一份备份及其校验文件，失败时按指数退避重试
+    pub async fn upload(&self, path: &Path) -> Result<(), UploadError> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        // 1. 计算本地文件的 SHA-256
+        let (sha256, size) = file_sha256(path).await?;
+        info!("备份 {} 的 SHA-256: {}", name, sha256);
+
+        // 2. 上传备份文件
+        let mut attempt = 0;
+        loop {
+            match self.upload_file(path, &name, &sha256, size).await {
+                Ok(()) => break,
+                Err(e) if attempt < self.retries => {
+                    attempt += 1;
+                    let delay = retry_delay(attempt);
+                    warn!(
+                        "上传备份 {} 失败（第 {} 次重试，{}s 后）: {}",
+                        name,
+                        attempt,
+                        delay.as_secs(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // 3. 上传校验文件（sha256sum 格式，可直接用 `sha256sum -c` 校验）
+        let checksum = format!("{}  {}\n", sha256, name).into_bytes();
+        let checksum_sha256 = hex::encode(Sha256::digest(&checksum));
+        let checksum_name = format!("{}.sha256", name);
+        let mut attempt = 0;
+        loop {
+            let request = self.put_request(&checksum_name, &checksum_sha256, checksum.len() as u64);
+            match send(request.body(checksum.clone())).await {
+                Ok(_) => break,
+                Err(e) if attempt < self.retries => {
+                    attempt += 1;
+                    warn!("上传校验文件 {} 失败: {}", checksum_name, e);
+                    tokio::time::sleep(retry_delay(attempt)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 上传一次备份文件并校验
+    async fn upload_file(
+        &self,
+        path: &Path,
+        name: &str,
+        sha256: &str,
+        size: u64,
+    ) -> Result<(), UploadError> {
+        let file = tokio::fs::File::open(path).await?;
+        let body = Body::wrap_stream(ReaderStream::new(file));
+        send(self.put_request(name, sha256, size).body(body)).await?;
+
+        match &self.target {
+            // S3 已按签名中的 x-amz-content-sha256 校验过内容，这里只确认对象大小
+            UploadTarget::S3(_) => {
+                let response = send(self.s3_request(Method::HEAD, name, EMPTY_SHA256)).await?;
+                let remote_size = response
+                    .headers()
+                    .get(CONTENT_LENGTH)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse::<u64>().ok());
+                if remote_size != Some(size) {
+                    return Err(UploadError::ChecksumMismatch {
+                        expected: format!("{} 字节", size),
+                        actual: remote_size
+                            .map(|size| format!("{} 字节", size))
+                            .unwrap_or_else(|| "未知大小".to_string()),
+                    });
+                }
+            }
+            // WebDAV 没有内容校验，重新下载一遍比对
+            UploadTarget::WebDav { .. } => {
+                let mut response = send(self.webdav_request(Method::GET, name)).await?;
+                let mut hasher = Sha256::new();
+                while let Some(chunk) = response.chunk().await? {
+                    hasher.update(&chunk);
+                }
+                let actual = hex::encode(hasher.finalize());
+                if actual != sha256 {
+                    return Err(UploadError::ChecksumMismatch {
+                        expected: sha256.to_string(),
+                        actual,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 构造上传请求（不含请求体）
+    fn put_request(&self, name: &str, sha256: &str, size: u64) -> RequestBuilder {
+        let request = match &self.target {
+            UploadTarget::S3(_) => self.s3_request(Method::PUT, name, sha256),
+            UploadTarget::WebDav { .. } => self.webdav_request(Method::PUT, name),
+        };
+        request.header(CONTENT_LENGTH, size)
+    }
+
+    fn webdav_request(&self, method: Method, name: &str) -> RequestBuilder {
+        let UploadTarget::WebDav {
+            base_url,
+            username,
+            password,
+        } = &self.target
+        else {
+            unreachable!("非 WebDAV 目标");
+        };
+
+        let url = base_url
+            .join(&uri_encode(name, false))
+            .expect("文件名拼接失败");
+        let request = self.client.request(method, url);
+        match username {
+            Some(username) => request.basic_auth(username, password.as_ref()),
+            None => request,
+        }
+    }
+
+    fn s3_request(&self, method: Method, name: &str, payload_sha256: &str) -> RequestBuilder {
+        let UploadTarget::S3(location) = &self.target else {
+            unreachable!("非 S3 目标");
+        };
+        location.request(&self.client, method, name, payload_sha256)
+    }
+}
+
+/// 发送请求，非 2xx 状态码视为失败
+async fn send(request: RequestBuilder) -> Result<reqwest::Response, UploadError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(UploadError::Status { status, body })
+    }
+}
+
+/// 计算文件的 SHA-256（十六进制）和大小
+async fn file_sha256(path: &Path) -> std::io::Result<(String, u64)> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1024 * 1024];
+    let mut size = 0;
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((hex::encode(hasher.finalize()), size))
+}
+
+/// 第 n 次重试前的等待时间：2s、4s、8s……最长 60s
+fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempt)).min(MAX_RETRY_DELAY)
+}
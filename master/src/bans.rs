@@ -0,0 +1,66 @@
+//! Worker封禁名单
+//!
+//! 持久化在 banned_workers 表中，内存中保留一份集合供热路径检查。
+
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use tokio::sync::RwLock;
+
+/// 封禁名单
+#[derive(Default)]
+pub struct BannedWorkers {
+    workers: RwLock<HashSet<String>>,
+}
+
+impl BannedWorkers {
+    /// 从数据库加载封禁名单
+    pub async fn load(pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+        let workers: Vec<String> = sqlx::query_scalar("SELECT worker_id FROM banned_workers")
+            .fetch_all(pool)
+            .await?;
+        Ok(Self {
+            workers: RwLock::new(workers.into_iter().collect()),
+        })
+    }
+
+    /// 从数据库重新加载封禁名单（其他节点可能修改过）
+    pub async fn reload(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        let workers: Vec<String> = sqlx::query_scalar("SELECT worker_id FROM banned_workers")
+            .fetch_all(pool)
+            .await?;
+        *self.workers.write().await = workers.into_iter().collect();
+        Ok(())
+    }
+
+    /// Worker是否已被封禁
+    pub async fn is_banned(&self, worker_id: &str) -> bool {
+        self.workers.read().await.contains(worker_id)
+    }
+
+    /// 封禁Worker，返回是否为新封禁
+    pub async fn ban(
+        &self,
+        pool: &SqlitePool,
+        worker_id: &str,
+        reason: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result =
+            sqlx::query("INSERT OR IGNORE INTO banned_workers (worker_id, reason) VALUES (?, ?)")
+                .bind(worker_id)
+                .bind(reason)
+                .execute(pool)
+                .await?;
+        self.workers.write().await.insert(worker_id.to_string());
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 解除封禁，返回是否确实处于封禁状态
+    pub async fn unban(&self, pool: &SqlitePool, worker_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM banned_workers WHERE worker_id = ?")
+            .bind(worker_id)
+            .execute(pool)
+            .await?;
+        self.workers.write().await.remove(worker_id);
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,10 +1,25 @@
 //! Master 节点初始化工具
 //! 用于管理任务队列的初始化和重置
 
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand};
+use common::admin::{local_actor, parse_candidate_line};
+use master::audit;
+use master::candidates;
+use master::queue::{self, RequeueError};
+use master::results;
+use master::running::{self, TASK_TIMEOUT};
+use master::schema;
+use master::shard::{self, Shard, ShardSpec};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Acquire, FromRow};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
 use std::str::FromStr;
-use tracing::info;
+use std::time::Duration;
+use tracing::{info, warn};
 
 #[derive(Parser)]
 #[command(
@@ -17,28 +32,206 @@ struct Cli {
     #[arg(short = 'd', long, default_value = "master.db")]
     database_url: String,
 
+    /// 操作人（写入审计日志），默认为当前用户
+    #[arg(long, global = true, default_value_t = local_actor())]
+    actor: String,
+
     #[command(subcommand)]
     command: Commands,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    /// 初始化数据库（创建表）
+    /// 初始化数据库（执行schema迁移）
     InitDb,
 
     /// 设置全局游标位置
-    #[command(about = "设置扫描起始 ID")]
+    #[command(
+        about = "设置扫描起始 ID",
+        group(ArgGroup::new("source").required(true).args(["start_id", "from_max_result", "from_max_task_end"]))
+    )]
     SetCursor {
         /// 起始 ID
         #[arg(value_name = "START_ID")]
-        start_id: i64,
+        start_id: Option<i64>,
+
+        /// 设为已知最大有效ID的下一个（游标表丢失或被误重置后恢复）
+        #[arg(long)]
+        from_max_result: bool,
+
+        /// 设为已分配过的任务（队列和归档）最大结束ID的下一个
+        #[arg(long)]
+        from_max_task_end: bool,
     },
 
     /// 重置任务队列（清空所有待执行任务）
     ResetQueue,
 
     /// 显示当前状态
-    Status,
+    Status {
+        /// 以 JSON 格式输出（便于脚本和监控采集）
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// 持续刷新显示状态（游标推进速度、任务和结果速率）
+    Watch {
+        /// 刷新间隔（如 2s、500ms、1m）
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "2s")]
+        interval: Duration,
+    },
+
+    /// 按区段统计有效ID的密度，找出密集区和空白区
+    Density {
+        /// 区段大小
+        #[arg(long, default_value_t = 1_000_000)]
+        bucket: i64,
+    },
+
+    /// 列出任务队列中的任务
+    Tasks {
+        /// 只显示心跳已超时的任务
+        #[arg(long)]
+        stale: bool,
+
+        /// 只显示指定 Worker 的任务
+        #[arg(long, value_name = "WORKER_ID")]
+        worker: Option<String>,
+
+        /// 最多显示的任务数
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+
+    /// 查看单个任务的详情
+    Task {
+        /// 任务 ID
+        #[arg(value_name = "TASK_ID")]
+        task_id: i64,
+    },
+
+    /// 将指定范围重新放回任务队列（例如上游异常期间扫描过的区段）
+    Requeue {
+        /// 起始 ID（包含）
+        #[arg(long)]
+        start: i64,
+
+        /// 结束 ID（包含）
+        #[arg(long)]
+        end: i64,
+
+        /// 每个任务的大小，超过时拆分为多个任务
+        #[arg(long, default_value_t = 10000)]
+        chunk: i64,
+    },
+
+    /// 清理心跳早已超时的任务（默认重新排队）
+    Prune {
+        /// 心跳超过该时长未更新的任务才会被处理（如 30m、1h、2d）
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "1h")]
+        older_than: Duration,
+
+        /// 直接删除任务而不是重新排队（范围将不会被重新扫描）
+        #[arg(long)]
+        delete: bool,
+
+        /// 只打印将要处理的任务，不修改数据库
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// 压缩数据库（完整性检查 + VACUUM + ANALYZE）
+    Compact,
+
+    /// 把连续的有效ID压缩为范围（移到 valid_ranges 表），之后可用 compact 回收空间
+    CompactResults {
+        /// 连续ID至少为多少个才按范围保存（与已有范围相邻的不受限制）
+        #[arg(long, default_value_t = 16)]
+        min_run: i64,
+
+        /// 只压缩发现超过该时长的ID（如 1h），默认全部
+        #[arg(long, value_parser = humantime::parse_duration, default_value = "0s")]
+        min_age: Duration,
+    },
+
+    /// 导出有效结果
+    Export {
+        /// 导出格式
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+
+        /// 输出文件
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+
+        /// 只导出该时间之后发现的结果（如 "2025-01-01 00:00:00"，UTC），用于增量备份
+        #[arg(long, value_name = "DATETIME")]
+        since: Option<String>,
+    },
+
+    /// 生成数据库快照（Master 运行时也可以执行），加 --sanitize 时脱敏后用于公开分享
+    Snapshot {
+        /// 输出文件（不能已存在）
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+
+        /// 把 Worker ID 替换为加盐的哈希，并清空审计日志、API Key、密钥、Worker主机信息等运维表
+        #[arg(long)]
+        sanitize: bool,
+    },
+
+    /// 导入有效结果
+    Import {
+        /// 输入文件（按扩展名识别格式）
+        #[arg(long = "in", value_name = "FILE")]
+        input: PathBuf,
+
+        /// 输入格式（默认按扩展名识别）
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+
+        /// 合并到已有结果中（不加该标志时要求结果表为空）
+        #[arg(long)]
+        merge: bool,
+    },
+
+    /// 合并另一个 Master 的数据库
+    Merge {
+        /// 另一个数据库文件路径
+        #[arg(long, value_name = "OTHER_DB")]
+        other: PathBuf,
+
+        /// 同时把对方未完成的任务范围导入为待分配任务
+        #[arg(long)]
+        with_tasks: bool,
+
+        /// 导入任务时每个任务的大小，超过时拆分为多个任务
+        #[arg(long, default_value_t = 10000)]
+        chunk: i64,
+    },
+
+    /// 导入候选ID，调度时先于全局游标以显式ID列表任务扫描
+    #[command(group(ArgGroup::new("source").required(true).args(["input", "from_db"])))]
+    ImportCandidates {
+        /// 输入文件：每行一个ID，也接受 export 导出的 CSV / JSONL
+        #[arg(long = "in", value_name = "FILE")]
+        input: Option<PathBuf>,
+
+        /// 导入另一个扫描（Master 数据库）的全部有效ID，例如换用新的判定规则重新检查
+        #[arg(long, value_name = "OTHER_DB")]
+        from_db: Option<PathBuf>,
+    },
+
+    /// 检查数据库完整性和任务范围的一致性（发现问题时返回非零退出码）
+    Check {
+        /// 按该分片检查覆盖空档（如 2/4），其他分片的块不算空档；默认使用 Master 最近一次启动时的分片配置
+        #[arg(long)]
+        shard: Option<ShardSpec>,
+
+        /// 分片的块大小，与 --shard 一起使用
+        #[arg(long, requires = "shard", default_value = "1000000")]
+        shard_block_size: i64,
+    },
 
     /// 清空所有数据（包括已完成的结果）
     #[command(about = "危险操作：清空所有数据")]
@@ -49,11 +242,21 @@ enum Commands {
     },
 }
 
+/// 结果导出格式
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ExportFormat {
+    /// 带表头的 CSV（id,found_at）
+    Csv,
+    /// 每行一个 JSON 对象
+    Jsonl,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // 初始化日志
+    // 初始化日志（输出到 stderr，避免混入命令的标准输出）
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
         .init();
 
     // 解析命令行参数
@@ -83,97 +286,209 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 执行相应的命令
     match cli.command {
         Commands::InitDb => init_db(&pool).await?,
-        Commands::SetCursor { start_id } => set_cursor(&pool, start_id).await?,
-        Commands::ResetQueue => reset_queue(&pool).await?,
-        Commands::Status => show_status(&pool).await?,
-        Commands::Clear { force } => clear_all(&pool, force).await?,
+        Commands::SetCursor {
+            start_id,
+            from_max_result,
+            from_max_task_end: _,
+        } => {
+            let start_id = match start_id {
+                Some(start_id) => start_id,
+                None => cursor_from_data(&pool, from_max_result).await?,
+            };
+            let previous = set_cursor(&pool, start_id).await?;
+            let details = json!({ "from": previous, "to": start_id });
+            record_audit(&pool, &cli.actor, "set_cursor", details).await;
+        }
+        Commands::ResetQueue => {
+            let deleted = reset_queue(&pool).await?;
+            let details = json!({ "deleted_tasks": deleted });
+            record_audit(&pool, &cli.actor, "reset_queue", details).await;
+        }
+        Commands::Status { json } => show_status(&pool, json).await?,
+        Commands::Watch { interval } => watch_status(&pool, interval).await?,
+        Commands::Density { bucket } => density_report(&pool, bucket).await?,
+        Commands::Tasks {
+            stale,
+            worker,
+            limit,
+        } => list_tasks(&pool, stale, worker.as_deref(), limit).await?,
+        Commands::Task { task_id } => show_task(&pool, task_id).await?,
+        Commands::Requeue { start, end, chunk } => {
+            requeue_range(&pool, start, end, chunk).await?;
+            let details = json!({ "start_id": start, "end_id": end, "chunk": chunk });
+            record_audit(&pool, &cli.actor, "requeue", details).await;
+        }
+        Commands::Prune {
+            older_than,
+            delete,
+            dry_run,
+        } => {
+            prune_tasks(&pool, older_than, delete, dry_run).await?;
+            if !dry_run {
+                let details = json!({
+                    "older_than": humantime::format_duration(older_than).to_string(),
+                    "delete": delete,
+                });
+                record_audit(&pool, &cli.actor, "prune", details).await;
+            }
+        }
+        Commands::Compact => compact(&pool, &cli.database_url).await?,
+        Commands::CompactResults { min_run, min_age } => {
+            compact_results(&pool, min_run, min_age).await?
+        }
+        Commands::Export { format, out, since } => {
+            export_results(&pool, format, &out, since.as_deref()).await?
+        }
+        Commands::Snapshot { out, sanitize } => snapshot(&pool, &out, sanitize).await?,
+        Commands::Import {
+            input,
+            format,
+            merge,
+        } => {
+            import_results(&pool, &input, format, merge).await?;
+            let details = json!({ "input": input, "merge": merge });
+            record_audit(&pool, &cli.actor, "import", details).await;
+        }
+        Commands::Merge {
+            other,
+            with_tasks,
+            chunk,
+        } => {
+            merge_database(&pool, &other, with_tasks, chunk).await?;
+            let details = json!({ "other": other, "with_tasks": with_tasks });
+            record_audit(&pool, &cli.actor, "merge", details).await;
+        }
+        Commands::ImportCandidates { input, from_db } => {
+            let (queued, skipped) = match (&input, &from_db) {
+                (Some(input), _) => import_candidates(&pool, input).await?,
+                (None, Some(other)) => import_candidates_from_db(&pool, other).await?,
+                (None, None) => unreachable!("clap 保证至少提供一个来源"),
+            };
+            println!(
+                "✓ 已导入 {} 个候选ID（{} 个已在等待或扫描中）",
+                queued, skipped
+            );
+            let details = json!({ "input": input, "from_db": from_db, "queued": queued });
+            record_audit(&pool, &cli.actor, "import_candidates", details).await;
+        }
+        Commands::Check {
+            shard,
+            shard_block_size,
+        } => {
+            let shard = match shard {
+                Some(_) if shard_block_size <= 0 => {
+                    eprintln!("--shard-block-size 必须大于0");
+                    std::process::exit(1);
+                }
+                Some(spec) => Some(Shard {
+                    spec,
+                    block_size: shard_block_size,
+                }),
+                None => shard::load(&pool).await?,
+            };
+            check(&pool, shard).await?
+        }
+        Commands::Clear { force } => {
+            clear_all(&pool, force).await?;
+            record_audit(&pool, &cli.actor, "clear", json!({})).await;
+        }
     }
 
     Ok(())
 }
 
-/// 初始化数据库
+/// 记录审计日志
+///
+/// 数据库可能还没有执行过迁移（没有 audit_log 表），写入失败只给出警告。
+async fn record_audit(
+    pool: &sqlx::SqlitePool,
+    actor: &str,
+    action: &str,
+    details: serde_json::Value,
+) {
+    if let Err(e) = audit::record(pool, actor, action, details).await {
+        warn!("写入审计日志失败: {}", e);
+    }
+}
+
+/// 初始化数据库（执行迁移）
 async fn init_db(pool: &sqlx::SqlitePool) -> Result<(), Box<dyn std::error::Error>> {
     info!("初始化数据库...");
 
-    // 创建 global_cursor 表
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS global_cursor (
-            id INTEGER PRIMARY KEY,
-            next_start_id INTEGER NOT NULL
-        )",
-    )
-    .execute(pool)
-    .await?;
-
-    // 初始化全局游标
-    sqlx::query("INSERT OR IGNORE INTO global_cursor (id, next_start_id) VALUES (1, 0)")
-        .execute(pool)
-        .await?;
-
-    // 创建 task_queue 表
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS task_queue (
-            task_id INTEGER PRIMARY KEY AUTOINCREMENT,
-            start_id INTEGER NOT NULL,
-            end_id INTEGER NOT NULL,
-            worker_id TEXT NOT NULL,
-            status TEXT NOT NULL DEFAULT 'running',
-            last_heartbeat DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )",
-    )
-    .execute(pool)
-    .await?;
-
-    // 创建索引
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_task_queue_last_heartbeat ON task_queue(last_heartbeat)",
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_task_queue_status ON task_queue(status)")
-        .execute(pool)
-        .await?;
-
-    // 创建 valid_results 表
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS valid_results (
-            id INTEGER PRIMARY KEY,
-            found_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )",
-    )
-    .execute(pool)
-    .await?;
-
-    // 创建索引
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_valid_results_found_at ON valid_results(found_at)")
-        .execute(pool)
-        .await?;
+    let before = schema::schema_version(pool).await?;
+    let after = schema::migrate(pool).await?;
 
-    info!("数据库初始化成功");
+    if before == after {
+        info!("✓ 数据库已是最新 (schema版本 {})", after);
+    } else {
+        info!("✓ 数据库初始化成功 (schema版本 {} -> {})", before, after);
+    }
     Ok(())
 }
 
-/// 设置全局游标
+/// 设置全局游标，返回原来的游标位置
 async fn set_cursor(
     pool: &sqlx::SqlitePool,
     start_id: i64,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<i64, Box<dyn std::error::Error>> {
     info!("设置游标位置: {}", start_id);
 
+    let previous: i64 = sqlx::query_scalar("SELECT next_start_id FROM global_cursor WHERE id = 1")
+        .fetch_one(pool)
+        .await?;
     sqlx::query("UPDATE global_cursor SET next_start_id = ? WHERE id = 1")
         .bind(start_id)
         .execute(pool)
         .await?;
 
-    info!("✓ 游标已更新为 {}", start_id);
-    Ok(())
+    info!("✓ 游标已从 {} 更新为 {}", previous, start_id);
+    Ok(previous)
+}
+
+/// 根据已有数据推算游标：已知最大ID的下一个
+///
+/// `from_results` 为 true 时取最大有效ID，否则取任务队列和归档中最大的结束ID（不含显式ID列表任务）。
+async fn cursor_from_data(
+    pool: &sqlx::SqlitePool,
+    from_results: bool,
+) -> Result<i64, Box<dyn std::error::Error>> {
+    let (source, max_id): (&str, Option<i64>) = if from_results {
+        ("有效结果", results::max_id(pool).await?)
+    } else {
+        let max_id = sqlx::query_scalar(
+            "SELECT MAX(end_id) FROM (SELECT end_id FROM task_queue WHERE list_task = 0 UNION ALL SELECT end_id FROM task_archive WHERE list_task = 0)",
+        )
+        .fetch_one(pool)
+        .await?;
+        ("任务范围", max_id)
+    };
+
+    let Some(max_id) = max_id else {
+        eprintln!("没有{}数据，无法推算游标位置", source);
+        std::process::exit(1);
+    };
+
+    let current: (i64,) = sqlx::query_as("SELECT next_start_id FROM global_cursor WHERE id = 1")
+        .fetch_one(pool)
+        .await?;
+    let start_id = max_id + 1;
+    info!(
+        "{}中最大的ID为 {}，游标 {} -> {}",
+        source, max_id, current.0, start_id
+    );
+    if start_id < current.0 {
+        warn!(
+            "新游标小于当前游标，{} 到 {} 之间的范围将被重新分配",
+            start_id,
+            current.0 - 1
+        );
+    }
+
+    Ok(start_id)
 }
 
-/// 重置任务队列
-async fn reset_queue(pool: &sqlx::SqlitePool) -> Result<(), Box<dyn std::error::Error>> {
+/// 重置任务队列，返回删除的任务数
+async fn reset_queue(pool: &sqlx::SqlitePool) -> Result<i64, Box<dyn std::error::Error>> {
     info!("重置任务队列...");
 
     let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM task_queue")
@@ -181,13 +496,34 @@ async fn reset_queue(pool: &sqlx::SqlitePool) -> Result<(), Box<dyn std::error::
         .await?;
 
     sqlx::query("DELETE FROM task_queue").execute(pool).await?;
+    // 显式ID列表任务中的候选ID重新等待分配
+    candidates::release_orphaned(pool).await?;
 
     info!("✓ 任务队列已清空 (删除了 {} 个任务)", count);
-    Ok(())
+    Ok(count)
 }
 
-/// 显示当前状态
-async fn show_status(pool: &sqlx::SqlitePool) -> Result<(), Box<dyn std::error::Error>> {
+/// 状态统计
+#[derive(Serialize)]
+struct StatusReport {
+    /// 全局游标位置
+    cursor: i64,
+    /// 总任务数
+    total_tasks: i64,
+    /// 运行中的任务数
+    running_tasks: i64,
+    /// 待分配的任务数
+    pending_tasks: i64,
+    /// 已完成（归档）的任务数
+    completed_tasks: i64,
+    /// 已扫描结果数
+    valid_results: i64,
+    /// 尚未完成扫描的候选ID数（等待分配和扫描中）
+    open_candidates: i64,
+}
+
+/// 收集状态统计
+async fn collect_status(pool: &sqlx::SqlitePool) -> Result<StatusReport, sqlx::Error> {
     // 获取游标位置
     let cursor: (i64,) = sqlx::query_as("SELECT next_start_id FROM global_cursor WHERE id = 1")
         .fetch_one(pool)
@@ -203,23 +539,1414 @@ async fn show_status(pool: &sqlx::SqlitePool) -> Result<(), Box<dyn std::error::
             .fetch_one(pool)
             .await?;
 
-    // 获取已扫描的结果数
-    let result_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM valid_results")
+    let pending_count: (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM task_queue WHERE status = 'pending'")
+            .fetch_one(pool)
+            .await?;
+
+    let completed_count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM task_archive")
         .fetch_one(pool)
         .await?;
 
+    // 获取已扫描的结果数（含压缩为范围的结果）
+    let result_count = results::count(pool).await?;
+
+    let (pending_candidates, assigned_candidates, _) = candidates::counts(pool).await?;
+
+    Ok(StatusReport {
+        cursor: cursor.0,
+        total_tasks: task_count.0,
+        running_tasks: running_count.0,
+        pending_tasks: pending_count.0,
+        completed_tasks: completed_count.0,
+        valid_results: result_count,
+        open_candidates: pending_candidates + assigned_candidates,
+    })
+}
+
+/// 显示当前状态
+async fn show_status(
+    pool: &sqlx::SqlitePool,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let report = collect_status(pool).await?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_status_table(&report);
+    println!();
+
+    Ok(())
+}
+
+/// 打印状态表格
+fn print_status_table(report: &StatusReport) {
     println!("\n╔════════════════════════════════════════╗");
     println!("║         Master 节点任务状态            ║");
     println!("╠════════════════════════════════════════╣");
-    println!("║ 全局游标位置:  {:<22} ║", cursor.0);
-    println!("║ 总任务数:      {:<22} ║", task_count.0);
-    println!("║ 运行中的任务:  {:<22} ║", running_count.0);
-    println!("║ 已扫描结果:    {:<22} ║", result_count.0);
-    println!("╚════════════════════════════════════════╝\n");
+    println!("║ 全局游标位置:  {:<22} ║", report.cursor);
+    println!("║ 总任务数:      {:<22} ║", report.total_tasks);
+    println!("║ 运行中的任务:  {:<22} ║", report.running_tasks);
+    println!("║ 待分配的任务:  {:<22} ║", report.pending_tasks);
+    println!("║ 已完成的任务:  {:<22} ║", report.completed_tasks);
+    println!("║ 已扫描结果:    {:<22} ║", report.valid_results);
+    println!("║ 待扫描候选ID:  {:<22} ║", report.open_candidates);
+    println!("╚════════════════════════════════════════╝");
+}
+
+/// 持续刷新显示状态
+async fn watch_status(
+    pool: &sqlx::SqlitePool,
+    interval: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if interval.is_zero() {
+        eprintln!("刷新间隔必须大于 0");
+        std::process::exit(1);
+    }
+
+    let mut ticker = tokio::time::interval(interval);
+    let mut previous: Option<(StatusReport, std::time::Instant)> = None;
+
+    loop {
+        ticker.tick().await;
+        let report = collect_status(pool).await?;
+        let now = std::time::Instant::now();
+
+        // 清屏并把光标移到左上角
+        print!("\x1b[2J\x1b[H");
+        println!("{}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+        print_status_table(&report);
+
+        match &previous {
+            Some((last, at)) => {
+                let secs = now.duration_since(*at).as_secs_f64();
+                print_rate("游标推进", report.cursor - last.cursor, secs, "ID");
+                print_rate(
+                    "完成任务",
+                    report.completed_tasks - last.completed_tasks,
+                    secs,
+                    "个",
+                );
+                print_rate(
+                    "有效结果",
+                    report.valid_results - last.valid_results,
+                    secs,
+                    "个",
+                );
+            }
+            None => println!("  （等待下一次刷新以计算速率）"),
+        }
+        println!(
+            "\n刷新间隔 {}，按 Ctrl+C 退出",
+            humantime::format_duration(interval)
+        );
+        std::io::stdout().flush()?;
+
+        previous = Some((report, now));
+    }
+}
+
+/// 打印一项增量及其速率
+fn print_rate(label: &str, delta: i64, secs: f64, unit: &str) {
+    println!(
+        "  {}:  {:+}（{:.2} {}/s）",
+        label,
+        delta,
+        delta as f64 / secs,
+        unit
+    );
+}
+
+/// 密度条形图的最大宽度
+const DENSITY_BAR_WIDTH: usize = 30;
+
+/// 按区段统计有效ID的密度
+async fn density_report(
+    pool: &sqlx::SqlitePool,
+    bucket: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if bucket <= 0 {
+        eprintln!("--bucket 必须大于 0");
+        std::process::exit(1);
+    }
+
+    // 已扫描空间：从最早的结果/任务范围到游标
+    let cursor: (i64,) = sqlx::query_as("SELECT next_start_id FROM global_cursor WHERE id = 1")
+        .fetch_one(pool)
+        .await?;
+    let cursor = cursor.0;
+    let lowest: Option<i64> = sqlx::query_scalar(
+        r#"
+        SELECT MIN(low) FROM (
+            SELECT MIN(id) AS low FROM valid_results
+            UNION ALL SELECT MIN(start_id) FROM valid_ranges
+            UNION ALL SELECT MIN(start_id) FROM task_archive
+            UNION ALL SELECT MIN(start_id) FROM task_queue
+        )
+        "#,
+    )
+    .fetch_one(pool)
+    .await?;
+    let Some(lowest) = lowest.filter(|&lowest| lowest < cursor) else {
+        println!("还没有已扫描的范围");
+        return Ok(());
+    };
+
+    // 区段按 bucket 对齐（负数ID也向下取整）
+    let first_bucket = lowest.div_euclid(bucket);
+    let last_bucket = (cursor - 1).div_euclid(bucket);
+
+    let hits: Vec<(i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT (id - ((id % ?1) + ?1) % ?1) / ?1 AS bucket, COUNT(*)
+        FROM valid_results
+        WHERE id < ?2
+        GROUP BY bucket
+        "#,
+    )
+    .bind(bucket)
+    .bind(cursor)
+    .fetch_all(pool)
+    .await?;
+    let mut hits: std::collections::HashMap<i64, i64> = hits.into_iter().collect();
+
+    // 压缩为范围的结果按区段拆开计入
+    let ranges: Vec<(i64, i64)> = sqlx::query_as(
+        "SELECT start_id, MIN(end_id, ?1 - 1) FROM valid_ranges WHERE start_id < ?1",
+    )
+    .bind(cursor)
+    .fetch_all(pool)
+    .await?;
+    for (start_id, end_id) in ranges {
+        let mut start = start_id;
+        while start <= end_id {
+            let index = start.div_euclid(bucket);
+            let end = (index * bucket + bucket - 1).min(end_id);
+            *hits.entry(index).or_default() += end - start + 1;
+            start = end + 1;
+        }
+    }
+
+    // (区段起始, 区段结束, 命中数, 每百万ID命中数)
+    let rows: Vec<(i64, i64, i64, f64)> = (first_bucket..=last_bucket)
+        .map(|index| {
+            let start = (index * bucket).max(lowest);
+            let end = (index * bucket + bucket - 1).min(cursor - 1);
+            let count = hits.get(&index).copied().unwrap_or(0);
+            let density = count as f64 * 1_000_000.0 / (end - start + 1) as f64;
+            (start, end, count, density)
+        })
+        .collect();
+
+    let total_hits: i64 = rows.iter().map(|row| row.2).sum();
+    let average = total_hits as f64 * 1_000_000.0 / (cursor - lowest) as f64;
+    let max_density = rows.iter().map(|row| row.3).fold(0.0, f64::max);
+
+    // 中文字符占两列宽，表头的宽度相应减少
+    println!(
+        "{:>18}  {:>18}  {:>8}  {:>7}",
+        "起始ID", "结束ID", "命中", "每百万"
+    );
+    for &(start, end, count, density) in &rows {
+        let width = if max_density > 0.0 {
+            (density / max_density * DENSITY_BAR_WIDTH as f64).round() as usize
+        } else {
+            0
+        };
+        let marker = if count == 0 {
+            " ·"
+        } else if density >= average * 2.0 {
+            " ★"
+        } else {
+            ""
+        };
+        println!(
+            "{:>20}  {:>20}  {:>10}  {:>10.1}  {}{}",
+            start,
+            end,
+            count,
+            density,
+            "█".repeat(width),
+            marker
+        );
+    }
+
+    let empty = rows.iter().filter(|row| row.2 == 0).count();
+    let dense = rows
+        .iter()
+        .filter(|row| row.2 > 0 && row.3 >= average * 2.0)
+        .count();
+
+    // 最长的连续空白区
+    let mut longest: Option<(i64, i64)> = None;
+    let mut run: Option<(i64, i64)> = None;
+    for &(start, end, count, _) in &rows {
+        run = match (count, run) {
+            (0, Some((run_start, _))) => Some((run_start, end)),
+            (0, None) => Some((start, end)),
+            _ => None,
+        };
+        if let Some((run_start, run_end)) = run {
+            if longest.is_none_or(|(s, e)| run_end - run_start > e - s) {
+                longest = Some((run_start, run_end));
+            }
+        }
+    }
+
+    println!();
+    println!("已扫描范围:   [{}, {}]", lowest, cursor - 1);
+    println!("有效ID总数:   {}", total_hits);
+    println!("平均密度:     {:.1} / 百万ID", average);
+    println!("密集区段 ★:   {}（密度达到平均值的 2 倍以上）", dense);
+    println!("空白区段 ·:   {} / {}", empty, rows.len());
+    if let Some((start, end)) = longest {
+        println!(
+            "最长空白区:   [{}, {}]（{} 个ID）",
+            start,
+            end,
+            end - start + 1
+        );
+    }
 
     Ok(())
 }
 
+/// 任务队列中的一行（附带计算出的时长）
+#[derive(FromRow)]
+struct TaskRow {
+    task_id: i64,
+    start_id: i64,
+    end_id: i64,
+    worker_id: String,
+    status: String,
+    last_heartbeat: String,
+    /// 租约到期时间（Unix 毫秒，0 表示已释放或尚未分配）
+    lease_expires_at: i64,
+    created_at: String,
+    /// 距最后一次心跳的秒数（只用于展示，超时以租约到期时间为准）
+    heartbeat_age: i64,
+    /// 距创建的秒数
+    age: i64,
+}
+
+const TASK_COLUMNS: &str = r#"
+    task_id, start_id, end_id, worker_id, status, last_heartbeat, lease_expires_at, created_at,
+    CAST((julianday('now') - julianday(last_heartbeat)) * 86400 AS INTEGER) AS heartbeat_age,
+    CAST((julianday('now') - julianday(created_at)) * 86400 AS INTEGER) AS age
+"#;
+
+/// 列出任务队列
+async fn list_tasks(
+    pool: &sqlx::SqlitePool,
+    stale: bool,
+    worker: Option<&str>,
+    limit: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let sql = format!(
+        r#"
+        SELECT {} FROM task_queue
+        WHERE (?1 = 0 OR lease_expires_at <= ?2)
+          AND (?3 IS NULL OR worker_id = ?3)
+        ORDER BY lease_expires_at ASC, task_id ASC
+        LIMIT ?4
+        "#,
+        TASK_COLUMNS
+    );
+    let now = running::now_millis();
+    let tasks: Vec<TaskRow> = sqlx::query_as(&sql)
+        .bind(stale)
+        .bind(now)
+        .bind(worker)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+    if tasks.is_empty() {
+        println!("没有符合条件的任务");
+        return Ok(());
+    }
+
+    print_task_table(&tasks, now);
+    println!("\n共 {} 个任务（⚠ 表示租约已超时）", tasks.len());
+
+    Ok(())
+}
+
+/// 以表格形式打印任务列表
+fn print_task_table(tasks: &[TaskRow], now: i64) {
+    // 中文字符占两列宽，表头的宽度相应减少
+    println!(
+        "{:>6}  {:>18}  {:>18}  {:<8}  {:<24}  {:>6}  {:>6}",
+        "任务ID", "起始ID", "结束ID", "状态", "Worker", "心跳", "创建"
+    );
+    for task in tasks {
+        let worker_id = if task.worker_id.is_empty() {
+            "-"
+        } else {
+            task.worker_id.as_str()
+        };
+        let marker = if task.lease_expires_at <= now {
+            " ⚠"
+        } else {
+            ""
+        };
+        println!(
+            "{:>8}  {:>20}  {:>20}  {:<10}  {:<24}  {:>8}  {:>8}{}",
+            task.task_id,
+            task.start_id,
+            task.end_id,
+            task.status,
+            worker_id,
+            format_age(task.heartbeat_age),
+            format_age(task.age),
+            marker
+        );
+    }
+}
+
+/// 显示单个任务的详情
+async fn show_task(
+    pool: &sqlx::SqlitePool,
+    task_id: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let sql = format!("SELECT {} FROM task_queue WHERE task_id = ?", TASK_COLUMNS);
+    let task: Option<TaskRow> = sqlx::query_as(&sql)
+        .bind(task_id)
+        .fetch_optional(pool)
+        .await?;
+
+    let Some(task) = task else {
+        eprintln!("任务 {} 不存在（可能已完成提交）", task_id);
+        std::process::exit(1);
+    };
+
+    // 范围内已经提交过的有效ID（任务被拆分或重新分配后可能已有部分结果）
+    let found = results::count_between(pool, task.start_id, task.end_id).await?;
+
+    let expired = task.lease_expires_at <= running::now_millis();
+
+    println!("任务 ID:      {}", task.task_id);
+    println!("范围:         {} - {}", task.start_id, task.end_id);
+    println!("大小:         {}", task.end_id - task.start_id + 1);
+    println!("状态:         {}", task.status);
+    println!(
+        "Worker:       {}",
+        if task.worker_id.is_empty() {
+            "（未分配）"
+        } else {
+            task.worker_id.as_str()
+        }
+    );
+    println!(
+        "最后心跳:     {} UTC（{}前{}）",
+        task.last_heartbeat,
+        format_age(task.heartbeat_age),
+        if expired { "，已超时" } else { "" }
+    );
+    println!(
+        "创建时间:     {} UTC（{}前）",
+        task.created_at,
+        format_age(task.age)
+    );
+    println!("范围内结果:   {}", found);
+
+    Ok(())
+}
+
+/// 将指定范围重新排队
+async fn requeue_range(
+    pool: &sqlx::SqlitePool,
+    start_id: i64,
+    end_id: i64,
+    chunk: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tasks = match queue::requeue_range(pool, start_id, end_id, chunk).await {
+        Ok(tasks) => tasks,
+        Err(RequeueError::Database(e)) => return Err(e.into()),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    info!(
+        "✓ 范围 [{}, {}] 已重新排队，共 {} 个任务",
+        start_id,
+        end_id,
+        tasks.len()
+    );
+    Ok(())
+}
+
+/// 清理心跳早已超时的任务
+async fn prune_tasks(
+    pool: &sqlx::SqlitePool,
+    older_than: Duration,
+    delete: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // 只处理仍有归属的任务，已回收的待分配任务本来就在等待重新分配。
+    // 心跳早于 older_than 即租约到期时间早于 now - older_than + TASK_TIMEOUT
+    let sql = format!(
+        r#"
+        SELECT {} FROM task_queue
+        WHERE worker_id != '' AND lease_expires_at < ?
+        ORDER BY lease_expires_at ASC, task_id ASC
+        "#,
+        TASK_COLUMNS
+    );
+    let now = running::now_millis();
+    let tasks: Vec<TaskRow> = sqlx::query_as(&sql)
+        .bind(now - older_than.as_millis() as i64 + TASK_TIMEOUT.as_millis() as i64)
+        .fetch_all(pool)
+        .await?;
+
+    if tasks.is_empty() {
+        println!(
+            "没有心跳超过 {} 的任务",
+            humantime::format_duration(older_than)
+        );
+        return Ok(());
+    }
+
+    print_task_table(&tasks, now);
+    let action = if delete { "删除" } else { "重新排队" };
+
+    if dry_run {
+        println!(
+            "\n[dry-run] 将{} {} 个任务，未做任何修改",
+            action,
+            tasks.len()
+        );
+        return Ok(());
+    }
+
+    // 以 worker_id 和租约到期时间为条件，跳过期间恢复心跳或被重新认领的任务
+    let mut tx = pool.begin().await?;
+    let mut affected = 0;
+    for task in &tasks {
+        let result = if delete {
+            sqlx::query(
+                "DELETE FROM task_queue WHERE task_id = ? AND worker_id = ? AND lease_expires_at = ?",
+            )
+            .bind(task.task_id)
+            .bind(&task.worker_id)
+            .bind(task.lease_expires_at)
+            .execute(&mut *tx)
+            .await?
+        } else {
+            sqlx::query(
+                r#"
+                UPDATE task_queue SET worker_id = '', status = 'pending', lease_expires_at = 0
+                WHERE task_id = ? AND worker_id = ? AND lease_expires_at = ?
+                "#,
+            )
+            .bind(task.task_id)
+            .bind(&task.worker_id)
+            .bind(task.lease_expires_at)
+            .execute(&mut *tx)
+            .await?
+        };
+        affected += result.rows_affected();
+    }
+    if delete {
+        // 被删除的显式ID列表任务中的候选ID重新等待分配
+        candidates::release_orphaned(&mut *tx).await?;
+    }
+    tx.commit().await?;
+
+    println!("\n✓ 已{} {} 个任务", action, affected);
+    if affected < tasks.len() as u64 {
+        println!(
+            "  {} 个任务在处理期间恢复了心跳或已被修改，已跳过",
+            tasks.len() as u64 - affected
+        );
+    }
+
+    Ok(())
+}
+
+/// 压缩数据库
+async fn compact(pool: &sqlx::SqlitePool, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // 1. 完整性检查，损坏的数据库不做 VACUUM
+    info!("检查数据库完整性...");
+    let problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await?;
+    if problems.len() != 1 || problems[0] != "ok" {
+        eprintln!("✗ 完整性检查失败，已中止压缩:");
+        for problem in &problems {
+            eprintln!("  {}", problem);
+        }
+        std::process::exit(1);
+    }
+
+    let before = database_size(path);
+    let free_pages: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+        .fetch_one(pool)
+        .await?;
+    let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+        .fetch_one(pool)
+        .await?;
+
+    // 2. VACUUM 重建数据库文件，回收空闲页
+    info!("执行 VACUUM（数据库较大时需要一段时间）...");
+    sqlx::query("VACUUM").execute(pool).await?;
+
+    // 3. ANALYZE 更新查询规划器的统计信息
+    info!("执行 ANALYZE...");
+    sqlx::query("ANALYZE").execute(pool).await?;
+
+    // 4. 把 WAL 合并回主文件并截断，文件大小才能反映压缩结果
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await?;
+
+    let after = database_size(path);
+
+    println!("✓ 压缩完成");
+    println!("  完整性检查:   ok");
+    println!(
+        "  空闲页:       {} ({})",
+        free_pages,
+        format_bytes((free_pages * page_size) as u64)
+    );
+    println!("  压缩前大小:   {}", format_bytes(before));
+    println!("  压缩后大小:   {}", format_bytes(after));
+    println!(
+        "  释放空间:     {}",
+        format_bytes(before.saturating_sub(after))
+    );
+
+    Ok(())
+}
+
+/// 把连续的有效ID压缩为范围
+async fn compact_results(
+    pool: &sqlx::SqlitePool,
+    min_run: i64,
+    min_age: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if min_run < 1 {
+        eprintln!("--min-run 必须大于 0");
+        std::process::exit(1);
+    }
+
+    info!("压缩有效ID（最短游程 {}）...", min_run);
+    let stats = results::compact(pool, min_run, min_age).await?;
+    let (singles, ranges): (i64, i64) = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM valid_results), (SELECT COUNT(*) FROM valid_ranges)",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    println!("✓ 压缩完成");
+    println!("  本次压缩:     {} 段，共 {} 个ID", stats.runs, stats.ids);
+    println!("  单独保存:     {} 个ID", singles);
+    println!("  范围:         {} 段", ranges);
+    println!("  使用 compact 回收数据库文件中的空闲空间");
+
+    Ok(())
+}
+
+/// 数据库文件（含 WAL 文件）占用的字节数
+fn database_size(path: &str) -> u64 {
+    [path.to_string(), format!("{}-wal", path)]
+        .iter()
+        .filter_map(|file| std::fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// 将字节数格式化为易读的大小，如 `512 B`、`1.5 MB`
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// 脱敏快照中清空的运维表：认证信息、密钥、审计记录、Worker的主机信息和按Worker的统计
+const OPERATIONAL_TABLES: &[&str] = &[
+    "audit_log",
+    "api_keys",
+    "api_key_usage",
+    "server_secrets",
+    "master_lease",
+    "banned_workers",
+    "submit_outcomes",
+    "worker_profiles",
+    "worker_speeds",
+    "spot_checks",
+    "spot_check_mismatches",
+];
+
+/// 生成数据库快照
+///
+/// 先用 `VACUUM INTO` 复制出一致的快照（不阻塞 Master 的读写），脱敏后再 VACUUM 一次，
+/// 被删除的内容不会残留在空闲页中。先写入临时文件，完成后再改名，不会留下没有脱敏完的快照。
+async fn snapshot(
+    pool: &sqlx::SqlitePool,
+    out: &std::path::Path,
+    sanitize: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if out.exists() {
+        eprintln!("输出文件已存在: {}", out.display());
+        std::process::exit(1);
+    }
+    let temp = PathBuf::from(format!("{}.tmp", out.display()));
+    if temp.exists() {
+        std::fs::remove_file(&temp)?;
+    }
+
+    info!("生成快照: {}", out.display());
+    sqlx::query("VACUUM INTO ?")
+        .bind(temp.to_string_lossy().as_ref())
+        .execute(pool)
+        .await?;
+
+    if sanitize {
+        let snapshot = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(SqliteConnectOptions::new().filename(&temp))
+            .await?;
+        let result = sanitize_snapshot(&snapshot).await;
+        snapshot.close().await;
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e);
+        }
+    }
+    std::fs::rename(&temp, out)?;
+
+    let size = std::fs::metadata(out).map(|m| m.len()).unwrap_or(0);
+    println!("✓ 快照已生成: {} ({})", out.display(), format_bytes(size));
+    if sanitize {
+        println!("  已脱敏: Worker ID 已替换为哈希，运维表已清空");
+    }
+    Ok(())
+}
+
+/// 脱敏快照：替换所有表中的 Worker ID，清空运维表，最后 VACUUM
+///
+/// 哈希使用本次随机生成、不保存的盐，同一个 Worker 在快照内的ID一致，但无法通过猜测原ID还原，
+/// 也无法与其他快照关联。
+async fn sanitize_snapshot(pool: &sqlx::SqlitePool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tx = pool.begin().await?;
+
+    for table in OPERATIONAL_TABLES {
+        let exists: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?",
+        )
+        .bind(table)
+        .fetch_one(&mut *tx)
+        .await?;
+        if exists > 0 {
+            let removed = sqlx::query(&format!("DELETE FROM {}", table))
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            info!("清空 {}: {} 行", table, removed);
+        }
+    }
+    // API Key 已清空，任务上记录的 Key 没有意义
+    sqlx::query("UPDATE task_queue SET key_id = NULL")
+        .execute(&mut *tx)
+        .await?;
+
+    // 有 worker_id 列的表（包括以后新增的表）
+    let tables: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT m.name FROM sqlite_master AS m, pragma_table_info(m.name) AS p
+        WHERE m.type = 'table' AND p.name = 'worker_id'
+        "#,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let salt: [u8; 16] = rand::random();
+    let mut renamed = std::collections::HashMap::new();
+    for table in &tables {
+        let workers: Vec<String> = sqlx::query_scalar(&format!(
+            "SELECT DISTINCT worker_id FROM {} WHERE worker_id != ''",
+            table
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+        for worker in workers {
+            let hashed = renamed
+                .entry(worker.clone())
+                .or_insert_with(|| {
+                    let digest = Sha256::new()
+                        .chain_update(salt)
+                        .chain_update(worker.as_bytes())
+                        .finalize();
+                    format!("worker-{}", &hex::encode(digest)[..16])
+                })
+                .clone();
+            sqlx::query(&format!(
+                "UPDATE {} SET worker_id = ? WHERE worker_id = ?",
+                table
+            ))
+            .bind(hashed)
+            .bind(&worker)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    info!(
+        "已替换 {} 个表中 {} 个 Worker 的ID",
+        tables.len(),
+        renamed.len()
+    );
+
+    tx.commit().await?;
+    sqlx::query("VACUUM").execute(pool).await?;
+    Ok(())
+}
+
+/// 导出/导入的一条有效结果
+#[derive(FromRow, Serialize, Deserialize)]
+struct ResultRecord {
+    id: i64,
+    found_at: String,
+}
+
+/// 每次从数据库读取的结果数
+const EXPORT_PAGE_SIZE: i64 = 10000;
+
+/// 导出有效结果
+async fn export_results(
+    pool: &sqlx::SqlitePool,
+    format: ExportFormat,
+    out: &std::path::Path,
+    since: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = BufWriter::new(std::fs::File::create(out)?);
+    if format == ExportFormat::Csv {
+        writeln!(writer, "id,found_at")?;
+    }
+
+    // 压缩为范围的结果按ID顺序展开，穿插在逐个保存的结果之间（范围的 found_at 为其中最晚的发现时间）
+    let ranges: Vec<(i64, i64, String)> = sqlx::query_as(
+        r#"
+        SELECT start_id, end_id, found_at FROM valid_ranges
+        WHERE ? IS NULL OR found_at > ?
+        ORDER BY start_id
+        "#,
+    )
+    .bind(since)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    let mut ranges = ranges.into_iter().peekable();
+
+    // 按 id 分页读取，避免一次把全部结果读入内存
+    let mut last_id = i64::MIN;
+    let mut total = 0;
+    loop {
+        let page: Vec<ResultRecord> = sqlx::query_as(
+            r#"
+            SELECT id, found_at FROM valid_results
+            WHERE id > ? AND (? IS NULL OR found_at > ?)
+            ORDER BY id
+            LIMIT ?
+            "#,
+        )
+        .bind(last_id)
+        .bind(since)
+        .bind(since)
+        .bind(EXPORT_PAGE_SIZE)
+        .fetch_all(pool)
+        .await?;
+
+        for record in &page {
+            while let Some((start_id, end_id, found_at)) =
+                ranges.next_if(|(start_id, _, _)| *start_id < record.id)
+            {
+                total += write_range(&mut writer, format, start_id, end_id, found_at)?;
+            }
+            write_record(&mut writer, format, record)?;
+        }
+        total += page.len();
+
+        match page.last() {
+            Some(record) if page.len() as i64 == EXPORT_PAGE_SIZE => last_id = record.id,
+            _ => break,
+        }
+    }
+    for (start_id, end_id, found_at) in ranges {
+        total += write_range(&mut writer, format, start_id, end_id, found_at)?;
+    }
+    writer.flush()?;
+
+    info!("✓ 已导出 {} 条结果到 {}", total, out.display());
+    Ok(())
+}
+
+/// 写入一条导出记录
+fn write_record(
+    writer: &mut impl Write,
+    format: ExportFormat,
+    record: &ResultRecord,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        ExportFormat::Csv => writeln!(writer, "{},{}", record.id, record.found_at)?,
+        ExportFormat::Jsonl => writeln!(writer, "{}", serde_json::to_string(record)?)?,
+    }
+    Ok(())
+}
+
+/// 把一个范围逐个ID写入，返回写入的条数
+fn write_range(
+    writer: &mut impl Write,
+    format: ExportFormat,
+    start_id: i64,
+    end_id: i64,
+    found_at: String,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut record = ResultRecord {
+        id: start_id,
+        found_at,
+    };
+    for id in start_id..=end_id {
+        record.id = id;
+        write_record(writer, format, &record)?;
+    }
+    Ok((end_id - start_id + 1) as usize)
+}
+
+/// 导入有效结果
+async fn import_results(
+    pool: &sqlx::SqlitePool,
+    input: &std::path::Path,
+    format: Option<ExportFormat>,
+    merge: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let format = match format {
+        Some(format) => format,
+        None => match input.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => ExportFormat::Csv,
+            Some("jsonl") | Some("json") => ExportFormat::Jsonl,
+            _ => {
+                eprintln!("无法从扩展名识别格式，请使用 --format 指定");
+                std::process::exit(1);
+            }
+        },
+    };
+
+    if !merge {
+        let existing = results::count(pool).await?;
+        if existing > 0 {
+            eprintln!("结果表中已有 {} 条记录，使用 --merge 合并导入", existing);
+            std::process::exit(1);
+        }
+    }
+
+    let reader = BufReader::new(std::fs::File::open(input)?);
+    let mut tx = pool.begin().await?;
+    let mut imported = 0;
+    let mut duplicates = 0;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || (format == ExportFormat::Csv && index == 0 && line.starts_with("id"))
+        {
+            continue;
+        }
+
+        let record = parse_record(line, format)
+            .map_err(|e| format!("第 {} 行格式错误: {}", index + 1, e))?;
+
+        // 与 submit 一致：已存在的ID保持不变
+        if results::insert(&mut tx, record.id, Some(&record.found_at)).await? {
+            imported += 1;
+        } else {
+            duplicates += 1;
+        }
+    }
+
+    tx.commit().await?;
+
+    info!(
+        "✓ 已导入 {} 条结果（{} 条已存在，已跳过）",
+        imported, duplicates
+    );
+    Ok(())
+}
+
+/// 解析导入文件中的一行
+fn parse_record(line: &str, format: ExportFormat) -> Result<ResultRecord, String> {
+    match format {
+        ExportFormat::Csv => {
+            let (id, found_at) = line.split_once(',').ok_or("缺少 found_at 列")?;
+            Ok(ResultRecord {
+                id: id.trim().parse().map_err(|e| format!("无效的ID: {}", e))?,
+                found_at: found_at.trim().to_string(),
+            })
+        }
+        ExportFormat::Jsonl => serde_json::from_str(line).map_err(|e| e.to_string()),
+    }
+}
+
+/// 合并另一个 Master 的数据库
+async fn merge_database(
+    pool: &sqlx::SqlitePool,
+    other: &std::path::Path,
+    with_tasks: bool,
+    chunk: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !other.exists() {
+        eprintln!("数据库文件不存在: {}", other.display());
+        std::process::exit(1);
+    }
+    info!("合并数据库: {}", other.display());
+
+    // ATTACH 只对当前连接生效，且不能在事务中执行
+    let mut conn = pool.acquire().await?;
+    sqlx::query("ATTACH DATABASE ? AS other")
+        .bind(other.to_string_lossy().as_ref())
+        .execute(&mut *conn)
+        .await?;
+
+    let mut tx = conn.begin().await?;
+
+    // 1. 合并有效结果（按ID去重，已存在或已被范围覆盖的保持不变）
+    let mut merged_results = sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO valid_results (id, found_at)
+        SELECT id, found_at FROM other.valid_results AS theirs
+        WHERE NOT EXISTS (
+            SELECT 1 FROM (
+                SELECT end_id FROM valid_ranges WHERE start_id <= theirs.id
+                ORDER BY start_id DESC LIMIT 1
+            ) WHERE end_id >= theirs.id
+        )
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // 对方压缩为范围的结果逐个展开写入，之后可以重新压缩（对方是旧版本数据库时可能没有该表）
+    let has_ranges: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM other.sqlite_master WHERE type = 'table' AND name = 'valid_ranges'",
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if has_ranges.0 > 0 {
+        let ranges: Vec<(i64, i64, String)> =
+            sqlx::query_as("SELECT start_id, end_id, found_at FROM other.valid_ranges")
+                .fetch_all(&mut *tx)
+                .await?;
+        for (start_id, end_id, found_at) in ranges {
+            for id in start_id..=end_id {
+                if results::insert(&mut tx, id, Some(&found_at)).await? {
+                    merged_results += 1;
+                }
+            }
+        }
+    }
+
+    // 2. 游标取两者中较大的一个
+    let ours: (i64,) = sqlx::query_as("SELECT next_start_id FROM global_cursor WHERE id = 1")
+        .fetch_one(&mut *tx)
+        .await?;
+    let theirs: (i64,) =
+        sqlx::query_as("SELECT next_start_id FROM other.global_cursor WHERE id = 1")
+            .fetch_one(&mut *tx)
+            .await?;
+    let cursor = ours.0.max(theirs.0);
+    sqlx::query("UPDATE global_cursor SET next_start_id = ? WHERE id = 1")
+        .bind(cursor)
+        .execute(&mut *tx)
+        .await?;
+
+    // 3. 合并对方的已完成任务归档（对方是旧版本数据库时可能没有该表）
+    let has_archive: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM other.sqlite_master WHERE type = 'table' AND name = 'task_archive'",
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let mut merged_archive = 0;
+    if has_archive.0 > 0 {
+        // 对方是旧版本数据库时可能没有后来增加的列，按默认值补齐
+        let mut columns = Vec::new();
+        for (column, default) in [
+            ("list_task", "0"),
+            ("unknown_count", "0"),
+            ("blocked_count", "0"),
+            ("duration_ms", "NULL"),
+        ] {
+            let present = other_has_column(&mut tx, "task_archive", column).await?;
+            columns.push(if present { column } else { default });
+        }
+        // 已经合并过的归档（范围、Worker和完成时间都相同）不重复插入，重复合并不会产生重复的记录
+        merged_archive = sqlx::query(&format!(
+            r#"
+            INSERT INTO task_archive
+                (start_id, end_id, worker_id, valid_count, created_at, completed_at,
+                 list_task, unknown_count, blocked_count, duration_ms)
+            SELECT start_id, end_id, worker_id, valid_count, created_at, completed_at, {}, {}, {}, {}
+            FROM other.task_archive AS theirs
+            WHERE NOT EXISTS (
+                SELECT 1 FROM task_archive AS ours
+                WHERE ours.start_id = theirs.start_id AND ours.end_id = theirs.end_id
+                  AND ours.completed_at = theirs.completed_at AND ours.worker_id = theirs.worker_id
+            )
+            "#,
+            columns[0], columns[1], columns[2], columns[3]
+        ))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+
+    // 4. 对方未完成的任务作为待分配任务导入
+    // 显式ID列表任务的范围只是候选ID的最小/最大值，不能当作连续范围，改为导入其中的候选ID
+    let has_list_tasks = other_has_column(&mut tx, "task_queue", "list_task").await?;
+    let range_filter = if has_list_tasks {
+        "WHERE list_task = 0"
+    } else {
+        ""
+    };
+    let ranges: Vec<(i64, i64)> = sqlx::query_as(&format!(
+        "SELECT start_id, end_id FROM other.task_queue {} ORDER BY start_id",
+        range_filter
+    ))
+    .fetch_all(&mut *tx)
+    .await?;
+    let list_tasks: i64 = if has_list_tasks {
+        sqlx::query_scalar("SELECT COUNT(*) FROM other.task_queue WHERE list_task != 0")
+            .fetch_one(&mut *tx)
+            .await?
+    } else {
+        0
+    };
+    let mut imported_tasks = 0;
+    let mut imported_candidates = 0;
+    if with_tasks {
+        for &(start_id, end_id) in &ranges {
+            for (chunk_start, chunk_end) in queue::split_range(start_id, end_id, chunk) {
+                queue::insert_pending(&mut tx, chunk_start, chunk_end).await?;
+                imported_tasks += 1;
+            }
+        }
+        if list_tasks > 0 {
+            // 本库已有的候选ID保持原状态（已扫描过的不需要再扫描）
+            imported_candidates = sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO candidates (id)
+                SELECT id FROM other.candidates
+                WHERE task_id IN (SELECT task_id FROM other.task_queue WHERE list_task != 0)
+                "#,
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+    }
+
+    tx.commit().await?;
+    sqlx::query("DETACH DATABASE other")
+        .execute(&mut *conn)
+        .await?;
+
+    println!("✓ 合并完成");
+    println!("  新增结果:     {}", merged_results);
+    println!("  游标:         {} / {} -> {}", ours.0, theirs.0, cursor);
+    println!("  新增归档:     {}", merged_archive);
+    let unfinished = ranges.len() as i64 + list_tasks;
+    if with_tasks {
+        println!(
+            "  导入任务:     {} 个范围，拆分为 {} 个任务",
+            ranges.len(),
+            imported_tasks
+        );
+        if list_tasks > 0 {
+            println!(
+                "  导入候选ID:   {} 个ID列表任务，新增 {} 个候选ID",
+                list_tasks, imported_candidates
+            );
+        }
+    } else if unfinished > 0 {
+        println!(
+            "  ⚠ 对方还有 {} 个未完成的任务，这些范围不会被扫描，使用 --with-tasks 导入",
+            unfinished
+        );
+    }
+
+    Ok(())
+}
+
+/// 附加的数据库 `other` 中的表是否有某一列（旧版本数据库可能没有后来增加的列）
+async fn other_has_column(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    column: &str,
+) -> Result<bool, sqlx::Error> {
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info(?, 'other') WHERE name = ?")
+            .bind(table)
+            .bind(column)
+            .fetch_one(conn)
+            .await?;
+    Ok(count > 0)
+}
+
+/// 从文件导入候选ID，返回 (加入等待队列数, 跳过数)
+async fn import_candidates(
+    pool: &sqlx::SqlitePool,
+    input: &std::path::Path,
+) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    let reader = BufReader::new(std::fs::File::open(input)?);
+    let mut tx = pool.begin().await?;
+    let (mut queued, mut skipped) = (0, 0);
+
+    for (index, line) in reader.lines().enumerate() {
+        let id = parse_candidate_line(&line?)
+            .map_err(|e| format!("第 {} 行格式错误: {}", index + 1, e))?;
+        let Some(id) = id else {
+            continue;
+        };
+        if candidates::insert(&mut tx, id).await? {
+            queued += 1;
+        } else {
+            skipped += 1;
+        }
+    }
+
+    tx.commit().await?;
+    Ok((queued, skipped))
+}
+
+/// 把另一个 Master 数据库中的全部有效ID导入为候选ID，返回 (加入等待队列数, 跳过数)
+async fn import_candidates_from_db(
+    pool: &sqlx::SqlitePool,
+    other: &std::path::Path,
+) -> Result<(u64, u64), Box<dyn std::error::Error>> {
+    if !other.exists() {
+        eprintln!("数据库文件不存在: {}", other.display());
+        std::process::exit(1);
+    }
+    info!("从数据库导入候选ID: {}", other.display());
+
+    // ATTACH 只对当前连接生效，且不能在事务中执行
+    let mut conn = pool.acquire().await?;
+    sqlx::query("ATTACH DATABASE ? AS other")
+        .bind(other.to_string_lossy().as_ref())
+        .execute(&mut *conn)
+        .await?;
+
+    let mut tx = conn.begin().await?;
+    let mut ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM other.valid_results")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    // 对方压缩为范围的结果逐个展开（对方是旧版本数据库时可能没有该表）
+    let has_ranges: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM other.sqlite_master WHERE type = 'table' AND name = 'valid_ranges'",
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if has_ranges.0 > 0 {
+        let ranges: Vec<(i64, i64)> =
+            sqlx::query_as("SELECT start_id, end_id FROM other.valid_ranges")
+                .fetch_all(&mut *tx)
+                .await?;
+        for (start_id, end_id) in ranges {
+            ids.extend(start_id..=end_id);
+        }
+    }
+
+    let (mut queued, mut skipped) = (0, 0);
+    for id in ids {
+        if candidates::insert(&mut tx, id).await? {
+            queued += 1;
+        } else {
+            skipped += 1;
+        }
+    }
+
+    tx.commit().await?;
+    sqlx::query("DETACH DATABASE other")
+        .execute(&mut *conn)
+        .await?;
+
+    Ok((queued, skipped))
+}
+
+/// 最多逐条列出的问题数
+const CHECK_MAX_LISTED: usize = 20;
+
+/// 检查数据库完整性和任务范围的一致性
+async fn check(
+    pool: &sqlx::SqlitePool,
+    shard: Option<Shard>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failed = false;
+
+    // 1. SQLite 完整性检查
+    let problems: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await?;
+    if problems.len() == 1 && problems[0] == "ok" {
+        println!("✓ 完整性检查通过");
+    } else {
+        failed = true;
+        println!("✗ 完整性检查失败:");
+        print_limited(&problems);
+    }
+
+    if let Some(shard) = &shard {
+        println!(
+            "- 按分片 {}（块大小 {}）检查，其他分片的块不算覆盖空档",
+            shard.spec, shard.block_size
+        );
+    }
+
+    let cursor: (i64,) = sqlx::query_as("SELECT next_start_id FROM global_cursor WHERE id = 1")
+        .fetch_one(pool)
+        .await?;
+    let cursor = cursor.0;
+
+    // 显式ID列表任务的范围只是候选ID的最小/最大值，不参与范围检查
+    let tasks: Vec<(i64, i64, i64)> = sqlx::query_as(
+        "SELECT task_id, start_id, end_id FROM task_queue WHERE list_task = 0 ORDER BY start_id, task_id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // 2. 任务范围互相重叠（同一段ID会被扫描多次）
+    let mut overlaps = Vec::new();
+    let mut furthest: Option<(i64, i64)> = None; // (task_id, end_id)
+    for &(task_id, start_id, end_id) in &tasks {
+        if let Some((other_id, other_end)) = furthest {
+            if start_id <= other_end {
+                overlaps.push(format!(
+                    "任务 {} [{}, {}] 与任务 {} 重叠",
+                    task_id, start_id, end_id, other_id
+                ));
+            }
+        }
+        if furthest.is_none_or(|(_, other_end)| end_id > other_end) {
+            furthest = Some((task_id, end_id));
+        }
+    }
+    if overlaps.is_empty() {
+        println!("✓ 任务范围无重叠");
+    } else {
+        failed = true;
+        println!("✗ {} 处任务范围重叠:", overlaps.len());
+        print_limited(&overlaps);
+    }
+
+    // 3. 任务超出全局游标（游标被回拨后，这些范围会被再次分配）
+    let beyond: Vec<String> = tasks
+        .iter()
+        .filter(|&&(_, _, end_id)| end_id >= cursor)
+        .map(|&(task_id, start_id, end_id)| format!("任务 {} [{}, {}]", task_id, start_id, end_id))
+        .collect();
+    if beyond.is_empty() {
+        println!("✓ 没有超出游标 {} 的任务", cursor);
+    } else {
+        failed = true;
+        println!("✗ {} 个任务超出了游标 {}:", beyond.len(), cursor);
+        print_limited(&beyond);
+    }
+
+    // 4. 覆盖空档：游标之前既没有归档也不在队列中的范围（任务被删除或游标被跳过）
+    // 分片模式下游标会跳过其他分片的块，这些块不算空档
+    let mut covered: Vec<(i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT start_id, end_id FROM task_archive WHERE list_task = 0
+        UNION ALL
+        SELECT start_id, end_id FROM task_queue WHERE list_task = 0
+        ORDER BY start_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    covered.retain(|&(start_id, _)| start_id < cursor);
+
+    let mut gaps = Vec::new();
+    let mut missing: i64 = 0;
+    if let Some(&(first_start, _)) = covered.first() {
+        let mut covered_end = first_start - 1;
+        for &(start_id, end_id) in covered.iter().chain(std::iter::once(&(cursor, cursor))) {
+            if start_id > covered_end + 1 {
+                let gap = (covered_end + 1, start_id - 1);
+                let parts = match &shard {
+                    Some(shard) => shard.owned_parts(gap.0, gap.1),
+                    None => vec![gap],
+                };
+                for (gap_start, gap_end) in parts {
+                    gaps.push(format!("[{}, {}]", gap_start, gap_end));
+                    missing += gap_end - gap_start + 1;
+                }
+            }
+            covered_end = covered_end.max(end_id);
+        }
+    }
+    match covered.first() {
+        None => println!("- 没有归档记录，跳过覆盖检查"),
+        Some(_) if gaps.is_empty() => {
+            println!("✓ 从 {} 到游标 {} 之间没有覆盖空档", covered[0].0, cursor)
+        }
+        Some(_) => {
+            failed = true;
+            println!(
+                "✗ {} 处覆盖空档，共 {} 个ID未被扫描（可用 init requeue 补扫）:",
+                gaps.len(),
+                missing
+            );
+            print_limited(&gaps);
+        }
+    }
+
+    if failed {
+        println!("\n检查发现问题");
+        std::process::exit(1);
+    }
+    println!("\n检查通过");
+    Ok(())
+}
+
+/// 打印问题列表，超过 CHECK_MAX_LISTED 条时省略
+fn print_limited(items: &[String]) {
+    for item in items.iter().take(CHECK_MAX_LISTED) {
+        println!("    {}", item);
+    }
+    if items.len() > CHECK_MAX_LISTED {
+        println!("    ... 以及另外 {} 条", items.len() - CHECK_MAX_LISTED);
+    }
+}
+
+/// 将秒数格式化为易读的时长，如 `45s`、`12m30s`、`3h05m`、`2d04h`
+fn format_age(secs: i64) -> String {
+    let secs = secs.max(0);
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else if secs < 86400 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}d{:02}h", secs / 86400, secs % 86400 / 3600)
+    }
+}
+
 /// 清空所有数据
 async fn clear_all(pool: &sqlx::SqlitePool, force: bool) -> Result<(), Box<dyn std::error::Error>> {
     if !force {
@@ -233,11 +1960,30 @@ async fn clear_all(pool: &sqlx::SqlitePool, force: bool) -> Result<(), Box<dyn s
     sqlx::query("DELETE FROM valid_results")
         .execute(pool)
         .await?;
+    sqlx::query("DELETE FROM valid_ranges")
+        .execute(pool)
+        .await?;
     sqlx::query("DELETE FROM task_queue").execute(pool).await?;
+    sqlx::query("DELETE FROM candidates").execute(pool).await?;
+    sqlx::query("DELETE FROM marked_results")
+        .execute(pool)
+        .await?;
+    // 覆盖率、异常检测的相邻命中率和报告都读取归档，不清空时会继续统计旧扫描的范围
+    sqlx::query("DELETE FROM task_archive")
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM verification_queue")
+        .execute(pool)
+        .await?;
+    // 存储中的响应对象不在数据库里，需要另外清理 --response-archive 指向的目录或存储桶
+    sqlx::query("DELETE FROM raw_responses")
+        .execute(pool)
+        .await?;
     sqlx::query("UPDATE global_cursor SET next_start_id = 0 WHERE id = 1")
         .execute(pool)
         .await?;
 
     info!("✓ 所有数据已成功清空");
+    info!("原始响应存档的对象仍保留在存储中，需要时手动删除");
     Ok(())
 }
@@ -0,0 +1,376 @@
+//! 扫描报告生成工具
+//!
+//! 读取 Master 数据库，生成一份独立的 HTML 报告：发现数量随时间的变化、各ID区段的命中密度、
+//! Worker 贡献排行和未确认比例。图表是内联的 SVG，不依赖外部脚本或样式，
+//! 每轮扫描结束后可以直接发布。
+
+use askama::Template;
+use chrono::DateTime;
+use clap::Parser;
+use master::results;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::info;
+
+/// 已完成任务及其扫描的ID数
+///
+/// 显式ID列表任务的 start_id/end_id 只是候选ID的最小/最大值，ID数按分配给它的候选ID计算
+const TASKS: &str = r#"
+    WITH tasks AS (
+        SELECT task_id, start_id, end_id, worker_id, valid_count, unknown_count, blocked_count,
+               completed_at, list_task,
+               CASE WHEN list_task = 0 THEN end_id - start_id + 1
+                    ELSE (SELECT COUNT(*) FROM candidates c WHERE c.task_id = task_archive.task_id)
+               END AS size
+        FROM task_archive
+    )
+"#;
+
+type SummaryRow = (
+    i64,
+    Option<i64>,
+    Option<i64>,
+    Option<i64>,
+    i64,
+    Option<String>,
+    Option<String>,
+);
+
+/// 图表的宽度和高度（像素）
+const CHART_WIDTH: f64 = 800.0;
+const CHART_HEIGHT: f64 = 200.0;
+
+/// 图表左侧留给纵轴刻度的宽度
+const CHART_MARGIN: f64 = 60.0;
+
+#[derive(Parser)]
+#[command(
+    name = "report",
+    about = "生成扫描报告",
+    long_about = "读取 Master 数据库，生成包含图表的独立 HTML 报告"
+)]
+struct Cli {
+    /// 数据库文件路径（如：master.db 或 ./data/master.db）
+    #[arg(short = 'd', long, default_value = "master.db")]
+    database_url: String,
+
+    /// 输出的 HTML 文件
+    #[arg(short, long, default_value = "report.html")]
+    output: PathBuf,
+
+    /// 报告标题
+    #[arg(long, default_value = "扫描报告")]
+    title: String,
+
+    /// 时间图表中每根柱子覆盖的时长（如 10m、1h、1d）
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "1h")]
+    interval: Duration,
+
+    /// 命中密度图把已扫描的ID空间分成多少段
+    #[arg(long, default_value_t = 50)]
+    range_buckets: i64,
+
+    /// 贡献排行显示的 Worker 数
+    #[arg(long, default_value_t = 20)]
+    top: i64,
+}
+
+#[derive(Template)]
+#[template(path = "report.html")]
+struct ReportPage {
+    title: String,
+    generated_at: String,
+    summary: Summary,
+    interval: String,
+    discoveries_chart: String,
+    unchecked_chart: String,
+    density_chart: String,
+    density_width: i64,
+    workers: Vec<WorkerRow>,
+}
+
+struct Summary {
+    valid_results: i64,
+    tasks: i64,
+    ids_scanned: i64,
+    hit_rate: String,
+    unchecked_rate: String,
+    workers: i64,
+    first_completed: String,
+    last_completed: String,
+}
+
+struct WorkerRow {
+    rank: usize,
+    worker_id: String,
+    tasks: i64,
+    ids_scanned: i64,
+    valid_found: i64,
+    hit_rate: String,
+    unchecked_rate: String,
+    last_seen: String,
+}
+
+/// 图表中的一根柱子
+struct Bar {
+    label: String,
+    value: f64,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
+
+    let cli = Cli::parse();
+    if cli.interval.as_secs() == 0 {
+        return Err("--interval 至少为 1 秒".into());
+    }
+    if cli.range_buckets <= 0 {
+        return Err("--range-buckets 必须大于 0".into());
+    }
+
+    // 只读打开，报告可以在 Master 运行时生成
+    let database_url = format!("sqlite:{}", cli.database_url);
+    let connect_options = SqliteConnectOptions::from_str(&database_url)?.read_only(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(connect_options)
+        .await?;
+    info!("连接到数据库: {}", cli.database_url);
+
+    let summary = summary(&pool).await?;
+    let interval_secs = cli.interval.as_secs() as i64;
+    let (discoveries, unchecked) = timeline(&pool, interval_secs).await?;
+    let (density, density_width) = density(&pool, cli.range_buckets).await?;
+    let workers = workers(&pool, cli.top).await?;
+
+    let page = ReportPage {
+        title: cli.title,
+        generated_at: chrono::Utc::now()
+            .format("%Y-%m-%d %H:%M:%S UTC")
+            .to_string(),
+        summary,
+        interval: humantime::format_duration(cli.interval).to_string(),
+        discoveries_chart: bar_chart(&discoveries, |value| format!("{:.0}", value)),
+        unchecked_chart: bar_chart(&unchecked, |value| format!("{:.2}%", value)),
+        density_chart: bar_chart(&density, |value| format!("{:.3}%", value)),
+        density_width,
+        workers,
+    };
+    std::fs::write(&cli.output, page.render()?)?;
+    println!("报告已写入 {}", cli.output.display());
+    Ok(())
+}
+
+/// 汇总数据
+async fn summary(pool: &SqlitePool) -> Result<Summary, sqlx::Error> {
+    let valid_results = results::count(pool).await?;
+    let (tasks, ids_scanned, valid_found, unchecked, workers, first, last): SummaryRow =
+        sqlx::query_as(&format!(
+            r#"
+        {TASKS}
+        SELECT COUNT(*), SUM(size), SUM(valid_count), SUM(unknown_count + blocked_count),
+               COUNT(DISTINCT worker_id), MIN(completed_at), MAX(completed_at)
+        FROM tasks
+        "#
+        ))
+        .fetch_one(pool)
+        .await?;
+    let ids_scanned = ids_scanned.unwrap_or(0);
+
+    Ok(Summary {
+        valid_results,
+        tasks,
+        ids_scanned,
+        hit_rate: percent(valid_found.unwrap_or(0), ids_scanned),
+        unchecked_rate: percent(unchecked.unwrap_or(0), ids_scanned),
+        workers,
+        first_completed: first.unwrap_or_else(|| "-".to_string()),
+        last_completed: last.unwrap_or_else(|| "-".to_string()),
+    })
+}
+
+/// 按完成时间分段：每段发现的有效ID数，以及没有结论的ID占比
+async fn timeline(
+    pool: &SqlitePool,
+    interval_secs: i64,
+) -> Result<(Vec<Bar>, Vec<Bar>), sqlx::Error> {
+    let rows: Vec<(i64, i64, i64, i64)> = sqlx::query_as(&format!(
+        r#"
+        {TASKS}
+        SELECT (CAST(strftime('%s', completed_at) AS INTEGER) / ?1) * ?1 AS bucket,
+               SUM(size), SUM(valid_count), SUM(unknown_count + blocked_count)
+        FROM tasks
+        GROUP BY bucket
+        ORDER BY bucket
+        "#
+    ))
+    .bind(interval_secs)
+    .fetch_all(pool)
+    .await?;
+
+    let mut discoveries = Vec::with_capacity(rows.len());
+    let mut unchecked = Vec::with_capacity(rows.len());
+    for (bucket, size, valid_found, unchecked_count) in rows {
+        let label = DateTime::from_timestamp(bucket, 0)
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        unchecked.push(Bar {
+            label: label.clone(),
+            value: ratio(unchecked_count, size) * 100.0,
+        });
+        discoveries.push(Bar {
+            label,
+            value: valid_found as f64,
+        });
+    }
+    Ok((discoveries, unchecked))
+}
+
+/// 把已扫描的ID空间均分为 `buckets` 段，统计每段的有效ID占比，返回各段和段的大小
+///
+/// 显式ID列表任务不计入；任务按起始ID归入所在的段。
+async fn density(pool: &SqlitePool, buckets: i64) -> Result<(Vec<Bar>, i64), sqlx::Error> {
+    let (min_id, max_id): (Option<i64>, Option<i64>) =
+        sqlx::query_as("SELECT MIN(start_id), MAX(end_id) FROM task_archive WHERE list_task = 0")
+            .fetch_one(pool)
+            .await?;
+    let (Some(min_id), Some(max_id)) = (min_id, max_id) else {
+        return Ok((Vec::new(), 0));
+    };
+    let width = ((max_id - min_id + 1) + buckets - 1) / buckets;
+
+    let rows: Vec<(i64, i64, i64)> = sqlx::query_as(&format!(
+        r#"
+        {TASKS}
+        SELECT (start_id - ?1) / ?2 AS bucket, SUM(size), SUM(valid_count)
+        FROM tasks
+        WHERE list_task = 0
+        GROUP BY bucket
+        ORDER BY bucket
+        "#
+    ))
+    .bind(min_id)
+    .bind(width)
+    .fetch_all(pool)
+    .await?;
+
+    let bars = rows
+        .into_iter()
+        .map(|(bucket, size, valid_found)| Bar {
+            label: format!("{}+", min_id + bucket * width),
+            value: ratio(valid_found, size) * 100.0,
+        })
+        .collect();
+    Ok((bars, width))
+}
+
+/// 按发现的有效ID数排序的 Worker 贡献排行
+async fn workers(pool: &SqlitePool, top: i64) -> Result<Vec<WorkerRow>, sqlx::Error> {
+    let rows: Vec<(String, i64, i64, i64, i64, String)> = sqlx::query_as(&format!(
+        r#"
+        {TASKS}
+        SELECT worker_id, COUNT(*), SUM(size), SUM(valid_count),
+               SUM(unknown_count + blocked_count), MAX(completed_at)
+        FROM tasks
+        GROUP BY worker_id
+        ORDER BY SUM(valid_count) DESC, SUM(size) DESC
+        LIMIT ?
+        "#
+    ))
+    .bind(top)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .enumerate()
+        .map(
+            |(index, (worker_id, tasks, ids_scanned, valid_found, unchecked, last_seen))| {
+                WorkerRow {
+                    rank: index + 1,
+                    worker_id,
+                    tasks,
+                    ids_scanned,
+                    valid_found,
+                    hit_rate: percent(valid_found, ids_scanned),
+                    unchecked_rate: percent(unchecked, ids_scanned),
+                    last_seen,
+                }
+            },
+        )
+        .collect())
+}
+
+fn ratio(part: i64, total: i64) -> f64 {
+    if total > 0 {
+        part as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
+fn percent(part: i64, total: i64) -> String {
+    if total > 0 {
+        format!("{:.3}%", ratio(part, total) * 100.0)
+    } else {
+        "-".to_string()
+    }
+}
+
+/// 生成柱状图（SVG），鼠标悬停在柱子上显示标签和数值
+fn bar_chart(bars: &[Bar], format: impl Fn(f64) -> String) -> String {
+    if bars.is_empty() {
+        return r#"<p class="muted">没有数据</p>"#.to_string();
+    }
+
+    let max = bars.iter().map(|bar| bar.value).fold(0.0, f64::max);
+    let plot_width = CHART_WIDTH - CHART_MARGIN;
+    let slot = plot_width / bars.len() as f64;
+    let bar_width = (slot * 0.8).max(1.0);
+    let height = CHART_HEIGHT + 20.0;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {CHART_WIDTH} {height}" width="{CHART_WIDTH}" height="{height}">"#
+    );
+    // 纵轴刻度：0 和最大值
+    let _ = write!(
+        svg,
+        r##"<line x1="{CHART_MARGIN}" y1="0" x2="{CHART_MARGIN}" y2="{CHART_HEIGHT}" stroke="#999"/><line x1="{CHART_MARGIN}" y1="{CHART_HEIGHT}" x2="{CHART_WIDTH}" y2="{CHART_HEIGHT}" stroke="#999"/><text x="{x}" y="12" text-anchor="end" font-size="11">{max}</text><text x="{x}" y="{CHART_HEIGHT}" text-anchor="end" font-size="11">0</text>"##,
+        x = CHART_MARGIN - 4.0,
+        max = format(max),
+    );
+    for (index, bar) in bars.iter().enumerate() {
+        let bar_height = if max > 0.0 {
+            bar.value / max * (CHART_HEIGHT - 16.0)
+        } else {
+            0.0
+        };
+        let x = CHART_MARGIN + index as f64 * slot + (slot - bar_width) / 2.0;
+        let _ = write!(
+            svg,
+            r##"<rect x="{x:.1}" y="{y:.1}" width="{bar_width:.1}" height="{bar_height:.1}" fill="#4a7fb5"><title>{label}: {value}</title></rect>"##,
+            y = CHART_HEIGHT - bar_height,
+            label = bar.label,
+            value = format(bar.value),
+        );
+    }
+    // 横轴只标出首尾，避免标签重叠
+    let _ = write!(
+        svg,
+        r#"<text x="{CHART_MARGIN}" y="{y}" font-size="11">{first}</text><text x="{CHART_WIDTH}" y="{y}" text-anchor="end" font-size="11">{last}</text></svg>"#,
+        y = height - 4.0,
+        first = bars[0].label,
+        last = bars[bars.len() - 1].label,
+    );
+    svg
+}
@@ -0,0 +1,120 @@
+//! 对象存储
+//!
+//! 原始响应存档等大块数据通过 [`BlobStore`] 写入，存储后端按Master的配置选择：
+//! - 本地目录：对象名即相对路径
+//! - S3 兼容存储（`s3://bucket/prefix`）：对象名拼在前缀之后
+//!
+//! 对象名由调用方决定，后端只负责按名字读写。
+
+mod s3;
+
+pub use s3::{uri_encode, S3Location, S3Store, EMPTY_SHA256};
+
+use reqwest::StatusCode;
+use std::fmt;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+/// 存储操作返回的 future
+pub type BlobFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, BlobError>> + Send + 'a>>;
+
+/// 对象存储后端
+pub trait BlobStore: fmt::Display + Send + Sync {
+    /// 写入对象，已存在时覆盖
+    fn put<'a>(&'a self, key: &'a str, data: &'a [u8]) -> BlobFuture<'a, ()>;
+
+    /// 对象是否已经存在
+    fn exists<'a>(&'a self, key: &'a str) -> BlobFuture<'a, bool>;
+}
+
+/// 根据配置打开存储后端：`s3://bucket/prefix` 使用 S3 兼容存储，其余按本地目录处理
+pub fn open(
+    location: &str,
+    s3_endpoint: &str,
+    s3_region: &str,
+) -> Result<Box<dyn BlobStore>, String> {
+    if let Some(location) = S3Location::parse(location, s3_endpoint, s3_region)? {
+        return Ok(Box::new(S3Store::new(location)));
+    }
+    let store = FsStore::open(Path::new(location))
+        .map_err(|e| format!("无法创建存储目录 {}: {}", location, e))?;
+    Ok(Box::new(store))
+}
+
+/// 存储操作失败
+#[derive(Debug)]
+pub enum BlobError {
+    /// 本地读写失败
+    Io(std::io::Error),
+    /// 请求失败
+    Http(reqwest::Error),
+    /// 服务端返回了错误状态码
+    Status { status: StatusCode, body: String },
+}
+
+impl fmt::Display for BlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlobError::Io(e) => write!(f, "读写失败: {}", e),
+            BlobError::Http(e) => write!(f, "请求失败: {}", e),
+            BlobError::Status { status, body } => write!(f, "服务端返回 {}: {}", status, body),
+        }
+    }
+}
+
+impl std::error::Error for BlobError {}
+
+impl From<std::io::Error> for BlobError {
+    fn from(e: std::io::Error) -> Self {
+        BlobError::Io(e)
+    }
+}
+
+impl From<reqwest::Error> for BlobError {
+    fn from(e: reqwest::Error) -> Self {
+        BlobError::Http(e)
+    }
+}
+
+/// 保存在本地目录中的对象
+pub struct FsStore {
+    dir: PathBuf,
+}
+
+impl FsStore {
+    /// 使用目录 `dir`，不存在时创建
+    pub fn open(dir: &Path) -> std::io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+}
+
+impl BlobStore for FsStore {
+    /// 先写入临时文件再改名，读取方不会看到写了一半的文件
+    fn put<'a>(&'a self, key: &'a str, data: &'a [u8]) -> BlobFuture<'a, ()> {
+        Box::pin(async move {
+            let path = self.dir.join(key);
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let mut tmp = path.clone().into_os_string();
+            tmp.push(".tmp");
+            tokio::fs::write(&tmp, data).await?;
+            tokio::fs::rename(&tmp, &path).await?;
+            Ok(())
+        })
+    }
+
+    fn exists<'a>(&'a self, key: &'a str) -> BlobFuture<'a, bool> {
+        Box::pin(async move { Ok(tokio::fs::try_exists(self.dir.join(key)).await?) })
+    }
+}
+
+impl fmt::Display for FsStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.dir.display())
+    }
+}
@@ -0,0 +1,214 @@
+//! S3 兼容存储（AWS S3、MinIO 等）
+//!
+//! 使用路径风格访问（`{endpoint}/{bucket}/{key}`），请求按 AWS Signature V4 签名，
+//! 凭据从 `AWS_ACCESS_KEY_ID`、`AWS_SECRET_ACCESS_KEY` 环境变量读取。
+//! 备份上传和原始响应存档共用这里的签名逻辑。
+
+use super::{BlobError, BlobFuture, BlobStore};
+use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH};
+use reqwest::{Client, Method, RequestBuilder, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// 空内容的 SHA-256（签名 HEAD、GET 请求时使用）
+pub const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// S3 中的一个位置：bucket 加上对象名前缀
+pub struct S3Location {
+    endpoint: Url,
+    bucket: String,
+    prefix: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Location {
+    /// 解析 `s3://bucket/prefix` 形式的地址，不是 `s3://` 开头时返回 `Ok(None)`
+    pub fn parse(url: &str, endpoint: &str, region: &str) -> Result<Option<Self>, String> {
+        let Some(rest) = url.strip_prefix("s3://") else {
+            return Ok(None);
+        };
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(format!("S3 地址缺少 bucket: {}", url));
+        }
+        let endpoint =
+            Url::parse(endpoint).map_err(|e| format!("无效的 S3 endpoint {}: {}", endpoint, e))?;
+        let access_key = std::env::var("AWS_ACCESS_KEY_ID")
+            .map_err(|_| "访问 S3 需要设置 AWS_ACCESS_KEY_ID 环境变量".to_string())?;
+        let secret_key = std::env::var("AWS_SECRET_ACCESS_KEY")
+            .map_err(|_| "访问 S3 需要设置 AWS_SECRET_ACCESS_KEY 环境变量".to_string())?;
+
+        Ok(Some(Self {
+            endpoint,
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            region: region.to_string(),
+            access_key,
+            secret_key,
+        }))
+    }
+
+    /// 构造带 AWS Signature V4 签名的请求，`name` 是相对于前缀的对象名
+    pub fn request(
+        &self,
+        client: &Client,
+        method: Method,
+        name: &str,
+        payload_sha256: &str,
+    ) -> RequestBuilder {
+        let key = if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, name)
+        };
+        let base_path = self.endpoint.path().trim_end_matches('/');
+        let path = format!(
+            "{}/{}/{}",
+            base_path,
+            uri_encode(&self.bucket, false),
+            uri_encode(&key, true)
+        );
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+        // 1. 规范请求
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, host, payload_sha256, amz_date, signed_headers, payload_sha256
+        );
+
+        // 2. 待签名字符串
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        // 3. 派生签名密钥并签名
+        let mut key = hmac_sha256(
+            format!("AWS4{}", self.secret_key).as_bytes(),
+            date.as_bytes(),
+        );
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        let signature = hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        );
+
+        client
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_sha256)
+            .header("x-amz-date", amz_date)
+            .header(AUTHORIZATION, authorization)
+    }
+}
+
+impl fmt::Display for S3Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.prefix.is_empty() {
+            write!(f, "s3://{}", self.bucket)
+        } else {
+            write!(f, "s3://{}/{}", self.bucket, self.prefix)
+        }
+    }
+}
+
+/// 保存在 S3 兼容存储中的对象
+pub struct S3Store {
+    client: Client,
+    location: S3Location,
+}
+
+impl S3Store {
+    pub fn new(location: S3Location) -> Self {
+        Self {
+            client: Client::new(),
+            location,
+        }
+    }
+}
+
+impl BlobStore for S3Store {
+    fn put<'a>(&'a self, key: &'a str, data: &'a [u8]) -> BlobFuture<'a, ()> {
+        Box::pin(async move {
+            let sha256 = hex::encode(Sha256::digest(data));
+            let request = self
+                .location
+                .request(&self.client, Method::PUT, key, &sha256)
+                .header(CONTENT_LENGTH, data.len())
+                .body(data.to_vec());
+            let response = request.send().await?;
+            check_status(response).await
+        })
+    }
+
+    fn exists<'a>(&'a self, key: &'a str) -> BlobFuture<'a, bool> {
+        Box::pin(async move {
+            let request = self
+                .location
+                .request(&self.client, Method::HEAD, key, EMPTY_SHA256);
+            let response = request.send().await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(false);
+            }
+            check_status(response).await?;
+            Ok(true)
+        })
+    }
+}
+
+impl fmt::Display for S3Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.location.fmt(f)
+    }
+}
+
+/// 非 2xx 状态码视为失败
+async fn check_status(response: reqwest::Response) -> Result<(), BlobError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(BlobError::Status { status, body })
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    use hmac::{Hmac, Mac};
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// 按 S3 的规则对路径做 URI 编码，`keep_slash` 为 true 时保留 `/`
+pub fn uri_encode(input: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
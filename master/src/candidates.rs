@@ -0,0 +1,113 @@
+//! 候选ID
+//!
+//! 运维给出的待扫描ID保存在 candidates 表中。调度时先于全局游标分配：每次按ID顺序取出一批
+//! 尚未分配的候选ID，作为一个显式ID列表任务（`task_queue.list_task = 1`），候选ID记下所属的任务。
+//! 任务超时后原样重新分配给其他Worker，提交完成时记录完成时间。
+//!
+//! 候选ID通过管理接口 `POST /admin/candidates` 或 `init import-candidates` 批量导入。
+
+use sqlx::{SqliteConnection, SqliteExecutor};
+
+/// 加入一个候选ID，返回是否加入了等待分配的队列
+///
+/// 已完成扫描的候选ID重新打开（再次导入表示需要重新检查），等待分配或正在扫描的保持不变。
+pub async fn insert(conn: &mut SqliteConnection, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO candidates (id) VALUES (?)
+        ON CONFLICT (id) DO UPDATE SET task_id = NULL, checked_at = NULL
+        WHERE checked_at IS NOT NULL
+        "#,
+    )
+    .bind(id)
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// 各状态的候选ID数：(等待分配, 扫描中, 已完成)
+pub async fn counts<'e>(executor: impl SqliteExecutor<'e>) -> Result<(i64, i64, i64), sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT COALESCE(SUM(task_id IS NULL), 0),
+               COALESCE(SUM(task_id IS NOT NULL AND checked_at IS NULL), 0),
+               COALESCE(SUM(checked_at IS NOT NULL), 0)
+        FROM candidates
+        "#,
+    )
+    .fetch_one(executor)
+    .await
+}
+
+/// 按ID顺序取出至多 `limit` 个尚未分配的候选ID
+pub async fn pending_batch(
+    conn: &mut SqliteConnection,
+    limit: i64,
+) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT id FROM candidates WHERE task_id IS NULL ORDER BY id LIMIT ?")
+        .bind(limit)
+        .fetch_all(conn)
+        .await
+}
+
+/// 把闭区间 [start_id, end_id] 内尚未分配的候选ID分配给任务，返回分配的个数
+pub async fn assign(
+    conn: &mut SqliteConnection,
+    task_id: i32,
+    start_id: i64,
+    end_id: i64,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE candidates SET task_id = ? WHERE task_id IS NULL AND id BETWEEN ? AND ?",
+    )
+    .bind(task_id)
+    .bind(start_id)
+    .bind(end_id)
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// 分配给任务的候选ID（按ID排序）
+pub async fn of_task<'e>(
+    executor: impl SqliteExecutor<'e>,
+    task_id: i32,
+) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT id FROM candidates WHERE task_id = ? ORDER BY id")
+        .bind(task_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// 所属任务已被删除（清空队列、删除超时任务）且尚未完成的候选ID放回等待队列，返回放回的个数
+pub async fn release_orphaned<'e>(executor: impl SqliteExecutor<'e>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        r#"
+        UPDATE candidates SET task_id = NULL
+        WHERE task_id IS NOT NULL AND checked_at IS NULL
+          AND task_id NOT IN (SELECT task_id FROM task_queue)
+        "#,
+    )
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// 重新打开任务中的候选ID，返回重新等待分配的数量（任务中有没有得到结论的ID时重新检查）
+pub async fn reopen(conn: &mut SqliteConnection, task_id: i32) -> Result<u64, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE candidates SET task_id = NULL, checked_at = NULL WHERE task_id = ?")
+            .bind(task_id)
+            .execute(conn)
+            .await?;
+    Ok(result.rows_affected())
+}
+
+/// 任务提交完成，记录其中候选ID的完成时间
+pub async fn complete(conn: &mut SqliteConnection, task_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE candidates SET checked_at = CURRENT_TIMESTAMP WHERE task_id = ?")
+        .bind(task_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! 有效ID的定期压缩
+//!
+//! 启用 `--compact-interval` 后，后台定期把连续的有效ID移到 valid_ranges 表中按范围保存
+//! （见 [`master::results::compact`]），命中密集的ID空间中结果表可以缩小几个数量级。
+
+use crate::AppState;
+use master::results;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+/// 只压缩发现超过该时长的ID，最近的结果仍可在 `/admin/results/recent` 中看到
+pub const COMPACT_MIN_AGE: Duration = Duration::from_secs(3600);
+
+/// 启动后台压缩任务
+pub fn spawn_compaction(state: Arc<AppState>, interval: Duration, min_run: i64) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        loop {
+            ticker.tick().await;
+            // 主备模式下只由主节点压缩
+            if !state.leadership.is_leader() {
+                continue;
+            }
+            let started = Instant::now();
+            match results::compact(&state.db_pool, min_run, COMPACT_MIN_AGE).await {
+                Ok(stats) if stats.runs > 0 => info!(
+                    "已压缩有效ID: {} 段共 {} 个ID，耗时 {:.1}s",
+                    stats.runs,
+                    stats.ids,
+                    started.elapsed().as_secs_f64()
+                ),
+                Ok(_) => {}
+                Err(e) => error!("压缩有效ID失败: {}", e),
+            }
+        }
+    });
+}
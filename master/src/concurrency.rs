@@ -0,0 +1,127 @@
+//! 服务端并发建议
+//!
+//! Worker获取任务时上报速度和上一个任务的上游错误率，Master汇总后在 acquire 响应中
+//! 给出建议并发数，Worker以此作为并发宽度的上限。建议值每个调整周期更新一次：
+//! - 近期上游错误率的平均值超过 `--max-upstream-error-rate` 时减半
+//! - 设置了 `--target-throughput` 且全体Worker上报的速度之和超过目标时按比例下调
+//!   （任务运行期间以心跳上报的实时速度为准）
+//! - 否则加一，直到 `--max-worker-concurrency`
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 两次调整建议值之间的最小间隔
+const ADJUST_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 超过该时长没有上报的Worker不再参与统计
+const REPORT_TTL: Duration = Duration::from_secs(300);
+
+/// 调整参数
+pub struct ConcurrencyConfig {
+    /// 建议并发数的上限
+    pub max_concurrency: usize,
+
+    /// 全体Worker的目标总速度（req/s），0 表示不限制
+    pub target_throughput: f64,
+
+    /// 可接受的上游错误率，超过时减半建议值
+    pub max_error_rate: f64,
+}
+
+/// Worker最近一次上报
+struct Report {
+    speed: Option<u32>,
+    error_rate: Option<f64>,
+    at: Instant,
+}
+
+struct AdvisorState {
+    suggested: usize,
+    last_adjusted: Instant,
+    reports: HashMap<String, Report>,
+}
+
+/// 并发建议
+pub struct ConcurrencyAdvisor {
+    config: ConcurrencyConfig,
+    state: Mutex<AdvisorState>,
+}
+
+impl ConcurrencyAdvisor {
+    pub fn new(config: ConcurrencyConfig) -> Self {
+        let suggested = config.max_concurrency.max(1);
+        Self {
+            config,
+            state: Mutex::new(AdvisorState {
+                suggested,
+                last_adjusted: Instant::now(),
+                reports: HashMap::new(),
+            }),
+        }
+    }
+
+    /// 记录Worker的上报，返回当前的建议并发数
+    pub fn suggest(&self, worker_id: &str, speed: Option<u32>, error_rate: Option<f64>) -> usize {
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("并发建议锁中毒");
+        state.reports.insert(
+            worker_id.to_string(),
+            Report {
+                speed,
+                error_rate,
+                at: now,
+            },
+        );
+
+        if now.duration_since(state.last_adjusted) >= ADJUST_INTERVAL {
+            state
+                .reports
+                .retain(|_, report| now.duration_since(report.at) < REPORT_TTL);
+            state.suggested = self.adjust(state.suggested, &state.reports);
+            state.last_adjusted = now;
+        }
+        state.suggested
+    }
+
+    /// 记录Worker在心跳中上报的实时速度（错误率沿用 acquire 时的上报）
+    pub fn record_live_speed(&self, worker_id: &str, speed: f64) {
+        let now = Instant::now();
+        let mut state = self.state.lock().expect("并发建议锁中毒");
+        let report = state
+            .reports
+            .entry(worker_id.to_string())
+            .or_insert(Report {
+                speed: None,
+                error_rate: None,
+                at: now,
+            });
+        report.speed = Some(speed.round() as u32);
+        report.at = now;
+    }
+
+    /// 根据近期上报计算新的建议值
+    fn adjust(&self, current: usize, reports: &HashMap<String, Report>) -> usize {
+        let error_rates: Vec<f64> = reports.values().filter_map(|r| r.error_rate).collect();
+        let error_rate = if error_rates.is_empty() {
+            0.0
+        } else {
+            error_rates.iter().sum::<f64>() / error_rates.len() as f64
+        };
+        let throughput: f64 = reports
+            .values()
+            .filter_map(|r| r.speed)
+            .map(f64::from)
+            .sum();
+
+        let target = self.config.target_throughput;
+        let suggested = if error_rate > self.config.max_error_rate {
+            current / 2
+        } else if target > 0.0 && throughput > target {
+            (current as f64 * target / throughput) as usize
+        } else {
+            current + 1
+        };
+        suggested.clamp(1, self.config.max_concurrency.max(1))
+    }
+}
@@ -0,0 +1,24 @@
+//! Master 配置文件
+//!
+//! `--config master.toml` 指定，保存不方便放在命令行上的设置（如 SMTP 密码）。
+//! 其余启动参数仍然通过命令行或环境变量传入。
+
+use crate::notify::SmtpConfig;
+use serde::Deserialize;
+use std::path::Path;
+
+/// 配置文件内容，各节都是可选的
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// 告警邮件
+    pub smtp: Option<SmtpConfig>,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("读取配置文件 {} 失败: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("解析配置文件 {} 失败: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,87 @@
+//! 数据库连接池
+//!
+//! 连接数、获取连接的超时和单条语句的超时都可以通过命令行调整：部署在小机器上时减少连接数，
+//! Worker很多时增加连接数。语句超时用 SQLite 的 progress handler 实现：新建连接和每次从池中取出连接时
+//! 设置截止时间，超过后正在执行的语句被中断并返回错误。事务中的多条语句共用同一个截止时间。
+//!
+//! 统计、导出和面板查询使用另一个只读连接池（`--db-read-connections`），同样由 [`connect`] 创建，
+//! Master以 WAL 模式打开数据库，读连接不会阻塞任务接口的写入，耗时的分析查询也不会占满写连接池。
+//!
+//! 连接池的使用情况和被中断的语句数通过 `GET /metrics` 暴露（见 [`crate::metrics`]）。
+
+use sqlx::pool::PoolConnection;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool, SqlitePoolOptions};
+use sqlx::Sqlite;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// progress handler 的调用间隔（SQLite 虚拟机指令数）
+const PROGRESS_OPS: i32 = 1000;
+
+/// 连接池参数
+#[derive(Debug, Clone, Copy)]
+pub struct PoolSettings {
+    /// 最大连接数
+    pub max_connections: u32,
+    /// 等待空闲连接的超时
+    pub acquire_timeout: Duration,
+    /// 单条语句（事务中为整个事务）的执行超时，None 表示不限制
+    pub statement_timeout: Option<Duration>,
+}
+
+/// 创建连接池，`timed_out` 统计因超时被中断的语句数
+pub async fn connect(
+    options: SqliteConnectOptions,
+    settings: PoolSettings,
+    timed_out: Arc<AtomicU64>,
+) -> Result<SqlitePool, sqlx::Error> {
+    let mut pool_options = SqlitePoolOptions::new()
+        .max_connections(settings.max_connections)
+        .acquire_timeout(settings.acquire_timeout);
+    if let Some(timeout) = settings.statement_timeout {
+        // 新建的连接不经过 before_acquire，第一次使用前同样要设置截止时间
+        let counter = Arc::clone(&timed_out);
+        pool_options = pool_options
+            .after_connect(move |conn, _| {
+                let timed_out = Arc::clone(&counter);
+                Box::pin(async move { set_deadline(conn, timeout, timed_out).await })
+            })
+            .before_acquire(move |conn, _| {
+                let timed_out = Arc::clone(&timed_out);
+                Box::pin(async move {
+                    set_deadline(conn, timeout, timed_out).await?;
+                    Ok(true)
+                })
+            });
+    }
+    pool_options.connect_with(options).await
+}
+
+/// 设置连接上语句的截止时间，超过后中断正在执行的语句
+async fn set_deadline(
+    conn: &mut SqliteConnection,
+    timeout: Duration,
+    timed_out: Arc<AtomicU64>,
+) -> Result<(), sqlx::Error> {
+    let deadline = Instant::now() + timeout;
+    conn.lock_handle()
+        .await?
+        .set_progress_handler(PROGRESS_OPS, move || {
+            if Instant::now() < deadline {
+                return true;
+            }
+            timed_out.fetch_add(1, Ordering::Relaxed);
+            false
+        });
+    Ok(())
+}
+
+/// 取出一个不受语句超时限制的连接，用于备份等预期耗时很长的操作
+///
+/// 连接归还后，下次取出时会重新设置超时。
+pub async fn acquire_unbounded(pool: &SqlitePool) -> Result<PoolConnection<Sqlite>, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    conn.lock_handle().await?.remove_progress_handler();
+    Ok(conn)
+}
@@ -0,0 +1,70 @@
+//! 任务耗时分布
+//!
+//! 提交结果时在 task_archive 中记录任务从分配到提交的耗时，管理接口据此统计最近一段时间的
+//! 分位数，并按任务大小分档，供运维对照 `--batch-target-runtime`、`--stall-timeout` 等设置调整。
+//! 显式ID列表任务的ID不连续、耗时和范围大小无关，不参与统计。
+
+use common::admin::{BatchSizeTiming, TaskDurations};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+
+/// 最近 `hours` 小时内完成的任务的耗时分布
+pub async fn fetch(pool: &SqlitePool, hours: u32) -> Result<TaskDurations, sqlx::Error> {
+    let rows: Vec<(i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT end_id - start_id + 1, duration_ms
+        FROM task_archive
+        WHERE duration_ms IS NOT NULL AND list_task = 0
+          AND completed_at >= datetime('now', ?)
+        "#,
+    )
+    .bind(format!("-{} hours", hours))
+    .fetch_all(pool)
+    .await?;
+
+    let mut all: Vec<u64> = rows.iter().map(|&(_, ms)| ms.max(0) as u64).collect();
+    all.sort_unstable();
+
+    // 按 floor(log2(size)) 分档
+    let mut buckets: BTreeMap<u32, (Vec<u64>, i64)> = BTreeMap::new();
+    for &(size, ms) in &rows {
+        let size = size.max(1);
+        let bucket = buckets.entry(size.ilog2()).or_default();
+        bucket.0.push(ms.max(0) as u64);
+        bucket.1 += size;
+    }
+
+    let by_batch_size = buckets
+        .into_iter()
+        .map(|(exp, (mut samples, ids))| {
+            samples.sort_unstable();
+            let total_ms: u64 = samples.iter().sum();
+            BatchSizeTiming {
+                min_size: 1 << exp,
+                max_size: (1 << (exp + 1)) - 1,
+                tasks: samples.len() as u64,
+                p50_ms: percentile(&samples, 0.50),
+                p95_ms: percentile(&samples, 0.95),
+                ids_per_sec: ids as f64 * 1000.0 / total_ms.max(1) as f64,
+            }
+        })
+        .collect();
+
+    Ok(TaskDurations {
+        hours,
+        tasks: all.len() as u64,
+        p50_ms: percentile(&all, 0.50),
+        p95_ms: percentile(&all, 0.95),
+        max_ms: all.last().copied().unwrap_or(0),
+        by_batch_size,
+    })
+}
+
+/// 最近秩法：第 ceil(p * n) 个样本，`sorted` 已升序，为空时返回 0
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
@@ -0,0 +1,389 @@
+//! GraphQL 查询接口
+//!
+//! 开启 `--graphql` 后在 `/graphql` 提供只读的 GraphQL 查询，覆盖有效结果、任务、Worker
+//! 和按分钟的扫描历史，支持按条件过滤和嵌套查询（如 Worker 持有的任务、任务范围内的结果），
+//! 一次请求即可取到管理接口需要多次调用才能拼出的数据。
+//!
+//! POST `/graphql` 接收标准的 `{"query": ..., "variables": ...}` 请求体；
+//! GET `/graphql?query=...` 同样可以查询，不带参数时返回 GraphiQL 调试页面。
+
+use crate::metrics::timed_sql;
+use crate::{admin, history, AppState};
+use async_graphql::http::{parse_query_string, GraphiQLSource};
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, EmptySubscription, Object, Result, Schema, SimpleObject,
+};
+use axum::{
+    extract::{Extension, RawQuery},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use common::latency::LatencyStats;
+use common::routes;
+use master::results;
+use std::sync::Arc;
+
+/// 查询嵌套的最大深度
+const MAX_DEPTH: usize = 8;
+
+/// 单次查询的最大复杂度
+const MAX_COMPLEXITY: usize = 1000;
+
+/// 结果列表单次最多返回的条数
+const MAX_RESULTS: i32 = 1000;
+
+/// 扫描历史最多返回的分钟数（与历史的保留时长一致）
+const MAX_HISTORY_MINUTES: i32 = 30 * 24 * 60;
+
+pub type PaSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// GraphQL 路由
+pub fn router(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let schema = Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish();
+    Router::new()
+        .route(routes::GRAPHQL, get(graphql_get).post(graphql_post))
+        .layer(Extension(schema))
+}
+
+/// 执行查询
+/// POST /graphql
+async fn graphql_post(
+    Extension(schema): Extension<PaSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+/// 带 query 参数时执行查询，否则返回 GraphiQL 页面
+/// GET /graphql
+async fn graphql_get(
+    Extension(schema): Extension<PaSchema>,
+    RawQuery(query): RawQuery,
+) -> Response {
+    let Some(query) = query.filter(|query| !query.is_empty()) else {
+        return Html(GraphiQLSource::build().endpoint(routes::GRAPHQL).finish()).into_response();
+    };
+    match parse_query_string(&query) {
+        Ok(request) => Json(schema.execute(request).await).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, format!("无效的查询参数: {}", e)).into_response(),
+    }
+}
+
+fn app_state<'a>(ctx: &Context<'a>) -> &'a AppState {
+    ctx.data_unchecked::<Arc<AppState>>()
+}
+
+/// 查询入口
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// 全局统计
+    async fn stats(&self, ctx: &Context<'_>) -> Result<Stats> {
+        let stats = admin::collect_stats(app_state(ctx)).await?;
+        Ok(Stats {
+            cursor: stats.cursor,
+            running_tasks: stats.running_tasks as i64,
+            pending_tasks: stats.pending_tasks as i64,
+            completed_tasks: stats.completed_tasks,
+            valid_results: stats.valid_results,
+            dispatch_paused: stats.dispatch_paused,
+            upstream_latency: stats.upstream_latency.map(Latency::from),
+        })
+    }
+
+    /// 任务队列中的任务，可按Worker过滤，`pending` 为 true 时只返回待分配的任务
+    async fn tasks(
+        &self,
+        ctx: &Context<'_>,
+        worker_id: Option<String>,
+        pending: Option<bool>,
+    ) -> Vec<Task> {
+        admin::collect_tasks(app_state(ctx))
+            .await
+            .into_iter()
+            .filter(|task| worker_id.as_ref().is_none_or(|id| &task.worker_id == id))
+            .filter(|task| pending.is_none_or(|pending| task.worker_id.is_empty() == pending))
+            .map(Task::from)
+            .collect()
+    }
+
+    /// 按ID查询任务队列中的任务
+    async fn task(&self, ctx: &Context<'_>, task_id: i32) -> Option<Task> {
+        admin::collect_tasks(app_state(ctx))
+            .await
+            .into_iter()
+            .find(|task| task.task_id == task_id)
+            .map(Task::from)
+    }
+
+    /// 持有任务的Worker，可按地区过滤
+    async fn workers(&self, ctx: &Context<'_>, region: Option<String>) -> Vec<Worker> {
+        admin::collect_workers(app_state(ctx))
+            .await
+            .into_iter()
+            .map(Worker::from)
+            .filter(|worker| region.is_none() || worker.region == region)
+            .collect()
+    }
+
+    /// 按ID查询Worker
+    async fn worker(&self, ctx: &Context<'_>, worker_id: String) -> Option<Worker> {
+        admin::collect_workers(app_state(ctx))
+            .await
+            .into_iter()
+            .find(|worker| worker.worker_id == worker_id)
+            .map(Worker::from)
+    }
+
+    /// 按ID升序列出有效ID，范围为 (after, end]，用于分页遍历
+    async fn results(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<i64>,
+        end: Option<i64>,
+        #[graphql(default = 100)] limit: i32,
+    ) -> Result<Vec<ValidResult>> {
+        let state = app_state(ctx);
+        list_results(state, after.unwrap_or(-1), end.unwrap_or(i64::MAX), limit).await
+    }
+
+    /// 最近发现的有效ID，按发现时间倒序
+    async fn recent_results(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] limit: i32,
+    ) -> Result<Vec<ValidResult>> {
+        let limit = limit.clamp(0, MAX_RESULTS) as u32;
+        let results = admin::fetch_recent_results(app_state(ctx), limit).await?;
+        Ok(results
+            .into_iter()
+            .map(|result| ValidResult {
+                id: result.id,
+                found_at: result.found_at,
+            })
+            .collect())
+    }
+
+    /// 最近 `minutes` 分钟的扫描历史，按时间升序
+    async fn history(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 60)] minutes: i32,
+    ) -> Result<Vec<StatsPoint>> {
+        let minutes = minutes.clamp(1, MAX_HISTORY_MINUTES) as u32;
+        let points = timed_sql(history::fetch(&app_state(ctx).read_pool, minutes)).await?;
+        Ok(points
+            .into_iter()
+            .map(|point| StatsPoint {
+                minute: point.minute,
+                tasks: point.tasks,
+                ids_scanned: point.ids_scanned,
+                valid_found: point.valid_found,
+                latency: point.latency.map(Latency::from),
+            })
+            .collect())
+    }
+}
+
+/// 列出 (after, end] 内的有效ID
+async fn list_results(
+    state: &AppState,
+    after: i64,
+    end: i64,
+    limit: i32,
+) -> Result<Vec<ValidResult>> {
+    let limit = i64::from(limit.clamp(0, MAX_RESULTS));
+    let rows = timed_sql(results::list(&state.read_pool, after, end, limit)).await?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, found_at)| ValidResult { id, found_at })
+        .collect())
+}
+
+/// 全局统计
+#[derive(SimpleObject)]
+struct Stats {
+    /// 下一个待分配的起始ID
+    cursor: i64,
+    /// 已分配给Worker的任务数
+    running_tasks: i64,
+    /// 等待分配的任务数
+    pending_tasks: i64,
+    /// 已完成的任务数
+    completed_tasks: i64,
+    /// 有效ID总数
+    valid_results: i64,
+    /// 任务分发是否已暂停
+    dispatch_paused: bool,
+    /// 最近几分钟提交中Worker上报的上游延迟
+    upstream_latency: Option<Latency>,
+}
+
+/// 上游请求延迟的分位数
+#[derive(SimpleObject)]
+struct Latency {
+    /// 请求数
+    count: i64,
+    p50_ms: i64,
+    p90_ms: i64,
+    p99_ms: i64,
+    max_ms: i64,
+}
+
+impl From<LatencyStats> for Latency {
+    fn from(stats: LatencyStats) -> Self {
+        let clamp = |value: u64| i64::try_from(value).unwrap_or(i64::MAX);
+        Self {
+            count: clamp(stats.count),
+            p50_ms: clamp(stats.p50_ms),
+            p90_ms: clamp(stats.p90_ms),
+            p99_ms: clamp(stats.p99_ms),
+            max_ms: clamp(stats.max_ms),
+        }
+    }
+}
+
+/// 任务队列中的任务
+#[derive(SimpleObject)]
+#[graphql(complex)]
+struct Task {
+    task_id: i32,
+    /// 起始ID（包含）
+    start_id: i64,
+    /// 结束ID（包含）
+    end_id: i64,
+    /// 持有任务的Worker，待分配时为 null
+    worker_id: Option<String>,
+    /// 距上次心跳的秒数
+    heartbeat_age_secs: Option<i64>,
+    /// 心跳上报的速度（ids/s）
+    speed: Option<f64>,
+}
+
+impl From<common::admin::TaskInfo> for Task {
+    fn from(task: common::admin::TaskInfo) -> Self {
+        Self {
+            task_id: task.task_id,
+            start_id: task.start_id,
+            end_id: task.end_id,
+            worker_id: Some(task.worker_id).filter(|id| !id.is_empty()),
+            heartbeat_age_secs: task.heartbeat_age_secs.map(|secs| secs as i64),
+            speed: task.speed,
+        }
+    }
+}
+
+#[ComplexObject]
+impl Task {
+    /// 任务范围内已入库的有效ID数（包括以前扫描过的部分）
+    async fn valid_count(&self, ctx: &Context<'_>) -> Result<i64> {
+        let state = app_state(ctx);
+        let count = timed_sql(results::count_between(
+            &state.read_pool,
+            self.start_id,
+            self.end_id,
+        ))
+        .await?;
+        Ok(count)
+    }
+
+    /// 任务范围内已入库的有效ID
+    async fn results(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 100)] limit: i32,
+    ) -> Result<Vec<ValidResult>> {
+        list_results(app_state(ctx), self.start_id - 1, self.end_id, limit).await
+    }
+}
+
+/// 持有任务的Worker
+#[derive(SimpleObject)]
+#[graphql(complex)]
+struct Worker {
+    worker_id: String,
+    #[graphql(skip)]
+    task_ids: Vec<i32>,
+    /// 所有任务中最近一次心跳距今的秒数
+    last_heartbeat_secs: Option<i64>,
+    /// 各任务上报速度之和（ids/s）
+    speed: Option<f64>,
+    /// 主机名
+    hostname: Option<String>,
+    /// 所在地区
+    region: Option<String>,
+    /// 出口信息
+    egress: Option<String>,
+    /// 自定义标签
+    labels: Vec<Label>,
+}
+
+impl From<common::admin::WorkerInfo> for Worker {
+    fn from(worker: common::admin::WorkerInfo) -> Self {
+        let profile = worker.profile.unwrap_or_default();
+        Self {
+            worker_id: worker.worker_id,
+            task_ids: worker.task_ids,
+            last_heartbeat_secs: worker.last_heartbeat_secs.map(|secs| secs as i64),
+            speed: worker.speed,
+            hostname: profile.hostname,
+            region: profile.region,
+            egress: profile.egress,
+            labels: profile
+                .labels
+                .into_iter()
+                .map(|(key, value)| Label { key, value })
+                .collect(),
+        }
+    }
+}
+
+#[ComplexObject]
+impl Worker {
+    /// Worker持有的任务
+    async fn tasks(&self, ctx: &Context<'_>) -> Vec<Task> {
+        admin::collect_tasks(app_state(ctx))
+            .await
+            .into_iter()
+            .filter(|task| self.task_ids.contains(&task.task_id))
+            .map(Task::from)
+            .collect()
+    }
+}
+
+/// Worker的自定义标签
+#[derive(SimpleObject)]
+struct Label {
+    key: String,
+    value: String,
+}
+
+/// 有效ID
+#[derive(SimpleObject)]
+struct ValidResult {
+    id: i64,
+    /// 发现时间
+    found_at: String,
+}
+
+/// 一分钟的扫描历史
+#[derive(SimpleObject)]
+struct StatsPoint {
+    /// 分钟（UTC，形如 2024-01-01 12:34:00）
+    minute: String,
+    /// 提交的任务数
+    tasks: i64,
+    /// 扫描的ID数
+    ids_scanned: i64,
+    /// 发现的有效ID数
+    valid_found: i64,
+    /// Worker上报的上游延迟
+    latency: Option<Latency>,
+}
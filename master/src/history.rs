@@ -0,0 +1,121 @@
+//! 按分钟汇总的扫描历史
+//!
+//! 每次提交结果时把扫描量、有效ID数和Worker上报的上游延迟累加到 stats_history 表中当前分钟的一行，
+//! 供管理接口查询，用于对照命中率下降和上游变慢、封禁等事件。
+//! 延迟分位数按请求数加权累加，读取时得到加权平均值。
+
+use common::admin::StatsPoint;
+use common::latency::LatencyStats;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::time::Duration;
+
+type HistoryRow = (String, i64, i64, i64, i64, i64, i64, i64, i64);
+
+/// 把一次提交累加到当前分钟
+pub async fn record(
+    conn: &mut SqliteConnection,
+    ids_scanned: i64,
+    valid_found: i64,
+    latency: Option<&LatencyStats>,
+) -> Result<(), sqlx::Error> {
+    let latency = latency.copied().unwrap_or_default();
+    let weighted = |ms: u64| i64::try_from(ms.saturating_mul(latency.count)).unwrap_or(i64::MAX);
+    sqlx::query(
+        r#"
+        INSERT INTO stats_history
+            (minute, tasks, ids_scanned, valid_found, latency_requests, p50_ms_sum, p90_ms_sum, p99_ms_sum, max_ms)
+        VALUES (strftime('%Y-%m-%d %H:%M:00', 'now'), 1, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(minute) DO UPDATE SET
+            tasks = tasks + 1,
+            ids_scanned = ids_scanned + excluded.ids_scanned,
+            valid_found = valid_found + excluded.valid_found,
+            latency_requests = latency_requests + excluded.latency_requests,
+            p50_ms_sum = p50_ms_sum + excluded.p50_ms_sum,
+            p90_ms_sum = p90_ms_sum + excluded.p90_ms_sum,
+            p99_ms_sum = p99_ms_sum + excluded.p99_ms_sum,
+            max_ms = MAX(max_ms, excluded.max_ms)
+        "#,
+    )
+    .bind(ids_scanned)
+    .bind(valid_found)
+    .bind(i64::try_from(latency.count).unwrap_or(i64::MAX))
+    .bind(weighted(latency.p50_ms))
+    .bind(weighted(latency.p90_ms))
+    .bind(weighted(latency.p99_ms))
+    .bind(i64::try_from(latency.max_ms).unwrap_or(i64::MAX))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// 最近 `minutes` 分钟的历史，按时间升序；没有提交的分钟不返回
+pub async fn fetch(pool: &SqlitePool, minutes: u32) -> Result<Vec<StatsPoint>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, HistoryRow>(
+        r#"
+        SELECT minute, tasks, ids_scanned, valid_found, latency_requests,
+               p50_ms_sum, p90_ms_sum, p99_ms_sum, max_ms
+        FROM stats_history
+        WHERE minute >= strftime('%Y-%m-%d %H:%M:00', 'now', ?)
+        ORDER BY minute
+        "#,
+    )
+    .bind(format!("-{} minutes", minutes.saturating_sub(1)))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(
+            |(minute, tasks, ids_scanned, valid_found, requests, p50, p90, p99, max)| StatsPoint {
+                minute,
+                tasks,
+                ids_scanned,
+                valid_found,
+                latency: weighted_latency(requests, p50, p90, p99, max),
+            },
+        )
+        .collect())
+}
+
+/// 最近 `minutes` 分钟所有提交的上游延迟汇总
+pub async fn recent_latency(
+    pool: &SqlitePool,
+    minutes: u32,
+) -> Result<Option<LatencyStats>, sqlx::Error> {
+    let (requests, p50, p90, p99, max) = sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+        r#"
+        SELECT COALESCE(SUM(latency_requests), 0), COALESCE(SUM(p50_ms_sum), 0),
+               COALESCE(SUM(p90_ms_sum), 0), COALESCE(SUM(p99_ms_sum), 0), COALESCE(MAX(max_ms), 0)
+        FROM stats_history
+        WHERE minute >= strftime('%Y-%m-%d %H:%M:00', 'now', ?)
+        "#,
+    )
+    .bind(format!("-{} minutes", minutes.saturating_sub(1)))
+    .fetch_one(pool)
+    .await?;
+    Ok(weighted_latency(requests, p50, p90, p99, max))
+}
+
+/// 删除超过保留时长的历史
+pub async fn prune(pool: &SqlitePool, retention: Duration) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM stats_history WHERE minute < datetime('now', ?)")
+        .bind(format!("-{} seconds", retention.as_secs()))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// 由加权累加值还原平均分位数
+fn weighted_latency(requests: i64, p50: i64, p90: i64, p99: i64, max: i64) -> Option<LatencyStats> {
+    if requests <= 0 {
+        return None;
+    }
+    let average = |sum: i64| (sum / requests).max(0) as u64;
+    Some(LatencyStats {
+        count: requests as u64,
+        p50_ms: average(p50),
+        p90_ms: average(p90),
+        p99_ms: average(p99),
+        max_ms: max.max(0) as u64,
+    })
+}
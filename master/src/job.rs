@@ -0,0 +1,39 @@
+//! 整个扫描的设置
+//!
+//! ID类型（[`IdKind`]）决定结果表中的序号如何对应到上游的标识，首次启动时写入 `job_settings` 表，
+//! 之后启动时命令行指定的ID类型必须与之一致，未指定时沿用数据库中的设置。
+
+use common::ids::IdKind;
+use sqlx::SqlitePool;
+
+/// ID类型在 `job_settings` 表中的名称
+const ID_KIND_SETTING: &str = "id_kind";
+
+/// 确定本次扫描的ID类型：首次启动时保存，之后与数据库中保存的比较
+pub async fn load_id_kind(
+    pool: &SqlitePool,
+    configured: Option<IdKind>,
+) -> Result<IdKind, Box<dyn std::error::Error>> {
+    // 主备节点同时启动时只有一个能写入，之后都读取同一个值
+    let initial = configured.clone().unwrap_or_default();
+    sqlx::query("INSERT OR IGNORE INTO job_settings (name, value) VALUES (?, ?)")
+        .bind(ID_KIND_SETTING)
+        .bind(serde_json::to_string(&initial)?)
+        .execute(pool)
+        .await?;
+    let stored: String = sqlx::query_scalar("SELECT value FROM job_settings WHERE name = ?")
+        .bind(ID_KIND_SETTING)
+        .fetch_one(pool)
+        .await?;
+    let stored: IdKind =
+        serde_json::from_str(&stored).map_err(|e| format!("数据库中的ID类型无法解析: {}", e))?;
+
+    match configured {
+        Some(configured) if configured != stored => Err(format!(
+            "数据库中的ID类型为 {}，与 --id-kind 指定的 {} 不一致",
+            stored, configured
+        )
+        .into()),
+        _ => Ok(stored),
+    }
+}
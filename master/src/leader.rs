@@ -0,0 +1,179 @@
+//! 主备高可用
+//!
+//! 同一台机器上的两个 Master 进程共用同一个数据库时，通过 master_lease 表中的租约选出主节点：
+//! - 主节点定期续约，负责任务分发、超时回收和备份
+//! - 备用节点只提供只读接口（统计、管理查询、指标），写请求返回 503
+//! - 主节点停止续约超过租约时长后，备用节点接管，并从数据库重新加载内存视图
+//!
+//! `/readyz` 在备用节点上返回 503，负载均衡据此只把Worker的请求转发给主节点。
+//!
+//! SQLite 的 WAL 模式依赖本机共享内存，数据库文件不能放在网络文件系统上跨机器共享，
+//! 所以主备只能防止单个进程故障，不能防止整台机器故障。
+
+use crate::{admin_auth, api_error, retry_later, AppState};
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use common::ErrorCode;
+use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// 备用节点拒绝写请求时建议客户端等待的时长
+const STANDBY_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// 主节点选举状态
+pub struct Leadership {
+    /// 本节点ID（写入租约的 holder）
+    node_id: String,
+
+    /// 租约时长
+    ttl: Duration,
+
+    /// 本节点当前是否为主节点
+    is_leader: AtomicBool,
+}
+
+impl Leadership {
+    /// 不参与选举的单节点模式，始终为主节点
+    pub fn standalone() -> Self {
+        Self {
+            node_id: String::new(),
+            ttl: Duration::ZERO,
+            is_leader: AtomicBool::new(true),
+        }
+    }
+
+    /// 参与选举的节点，初始为备用节点
+    pub fn elected(node_id: String, ttl: Duration) -> Self {
+        Self {
+            node_id,
+            ttl,
+            is_leader: AtomicBool::new(false),
+        }
+    }
+
+    /// 本节点当前是否为主节点
+    pub fn is_leader(&self) -> bool {
+        self.is_leader.load(Ordering::Relaxed)
+    }
+
+    /// 切换主备状态
+    pub fn set_leader(&self, leader: bool) {
+        self.is_leader.store(leader, Ordering::Relaxed);
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    /// 尝试获取或续约租约，返回本节点是否持有租约
+    ///
+    /// 租约由本节点持有或已过期时才会写入，单条语句保证两个节点不会同时成功。
+    pub async fn try_acquire(&self, pool: &SqlitePool) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO master_lease (id, holder, expires_at)
+            VALUES (1, ?, datetime('now', ?))
+            ON CONFLICT(id) DO UPDATE SET
+                holder = excluded.holder,
+                expires_at = excluded.expires_at,
+                acquired_at = CASE
+                    WHEN master_lease.holder = excluded.holder THEN master_lease.acquired_at
+                    ELSE CURRENT_TIMESTAMP
+                END
+            WHERE master_lease.holder = excluded.holder
+               OR master_lease.expires_at < datetime('now')
+            "#,
+        )
+        .bind(&self.node_id)
+        .bind(format!("+{} seconds", self.ttl.as_secs()))
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// 启动选举任务：每隔租约时长的 1/3 尝试获取或续约一次
+pub fn spawn_election(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let ttl = state.leadership.ttl;
+        let mut ticker = tokio::time::interval(ttl / 3);
+        // 最近一次成功续约的时间，数据库不可用时据此判断租约是否已经过期
+        let mut renewed_at: Option<Instant> = None;
+
+        loop {
+            ticker.tick().await;
+
+            match state.leadership.try_acquire(&state.db_pool).await {
+                Ok(true) => {
+                    renewed_at = Some(Instant::now());
+                    if !state.leadership.is_leader() {
+                        if let Err(e) = promote(&state).await {
+                            error!("切换为主节点失败，稍后重试: {}", e);
+                        }
+                    }
+                }
+                Ok(false) => {
+                    renewed_at = None;
+                    if state.leadership.is_leader() {
+                        state.leadership.set_leader(false);
+                        error!("主节点租约已被其他节点接管，切换为备用节点");
+                    }
+                }
+                Err(e) => {
+                    error!("续约主节点租约失败: {}", e);
+                    let expired = renewed_at.is_none_or(|at| at.elapsed() >= ttl);
+                    if expired && state.leadership.is_leader() {
+                        state.leadership.set_leader(false);
+                        error!("主节点租约已过期，切换为备用节点");
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// 切换为主节点
+///
+/// 备用期间数据库由另一个节点修改，内存中的任务视图、封禁名单和Worker速度都需要重新加载，
+/// 加载完成后才开始接受写请求。
+async fn promote(state: &AppState) -> Result<(), sqlx::Error> {
+    let tasks = state.running.reload_from_db(&state.db_pool).await?;
+    state.bans.reload(&state.db_pool).await?;
+    state.api_keys.reload(&state.db_pool).await?;
+    state.profiles.reload(&state.db_pool).await?;
+    state.speeds.reload(&state.db_pool).await?;
+    state.leadership.set_leader(true);
+    warn!(
+        "节点 {} 已成为主节点，接管 {} 个任务",
+        state.leadership.node_id, tasks
+    );
+    Ok(())
+}
+
+/// 中间件：备用节点拒绝写请求（只读的 GET/HEAD 请求照常处理）
+pub async fn reject_writes_on_standby(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    // GraphQL 接口只读，POST 查询也可以由备用节点处理
+    if state.leadership.is_leader() || admin_auth::is_read_only(req.method(), req.uri().path()) {
+        return next.run(req).await;
+    }
+
+    info!("备用节点拒绝写请求: {} {}", req.method(), req.uri());
+    retry_later(
+        StatusCode::SERVICE_UNAVAILABLE,
+        STANDBY_RETRY_AFTER,
+        api_error::<()>("当前节点为备用节点，请求主节点".to_string())
+            .with_code(ErrorCode::NotLeader),
+    )
+}
@@ -0,0 +1,12 @@
+//! Master节点共享库
+//!
+//! 供 master 服务和 init 等工具二进制共用的代码
+
+pub mod audit;
+pub mod candidates;
+pub mod marked;
+pub mod queue;
+pub mod results;
+pub mod running;
+pub mod schema;
+pub mod shard;
@@ -6,21 +6,139 @@
 //! - 智能任务分发算法（优先重试超时任务）
 //! - 支持Worker主动释放任务
 
-use axum::{extract::State, http::StatusCode, routing::post, Router};
-use chrono::Utc;
-use clap::Parser;
-use common::{
-    AcquireTaskRequest, AcquireTaskResponse, ApiResponse, HeartbeatRequest, ReleaseTaskRequest,
-    SubmitResultRequest,
+mod admin;
+mod admin_auth;
+mod alerts;
+mod anomaly;
+mod api_keys;
+mod backup;
+mod bans;
+mod blob;
+mod compaction;
+mod concurrency;
+mod config_file;
+mod db_pool;
+mod durations;
+mod graphql;
+mod history;
+mod job;
+mod leader;
+mod maintenance;
+mod metrics;
+mod notify;
+mod presence;
+mod profiles;
+mod rate_limit;
+mod reaper;
+mod regions;
+mod request_context;
+mod request_id;
+mod response_archive;
+mod signing;
+mod speed;
+mod spot_check;
+mod standalone;
+mod system_samples;
+mod targets;
+mod ui;
+mod validate;
+mod watchdog;
+
+use alerts::AlertEngine;
+use anomaly::{AnomalyConfig, AnomalyDetector, Submission};
+use api_keys::{ApiKey, ApiKeys, Quota};
+use axum::{
+    extract::{DefaultBodyLimit, State},
+    http::{header::RETRY_AFTER, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Router,
 };
-use sqlx::{
-    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
-    FromRow, SqlitePool,
+use bans::BannedWorkers;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use common::admin::BatchSettings;
+use common::ids::{Checksum, IdKind};
+use common::rolling_file::Rotation;
+use common::routes;
+use common::runtime::RuntimeConfig;
+use common::telemetry::{self, LogFileConfig, LogFormat, TelemetryConfig};
+use common::{
+    AcquireTaskRequest, AcquireTaskResponse, ApiResponse, ArchiveResponsesRequest, ErrorCode,
+    HeartbeatRequest, HeartbeatResponse, PartialResultRequest, ReleaseTaskRequest,
+    SubmitResultRequest, REQUEST_ID_HEADER,
 };
+use concurrency::{ConcurrencyAdvisor, ConcurrencyConfig};
+use config_file::ConfigFile;
+use db_pool::PoolSettings;
+use leader::Leadership;
+use maintenance::Maintenance;
+use master::candidates;
+use master::marked;
+use master::queue;
+use master::results;
+use master::running::{self, Claim, RunningTask, RunningTasks, TaskKinds, TASK_TIMEOUT};
+use master::schema;
+use master::shard::{self, Shard, ShardSpec};
+use metrics::{timed_sql, Metrics};
+use notify::{EmailNotifier, Notifier};
+use presence::WorkerPresence;
+use profiles::WorkerProfiles;
+use rate_limit::{AcquireLimiter, RateSpec, RouteGroup, RouteLimiter, RouteLimits};
+use regions::RegionPolicy;
+use response_archive::ResponseArchive;
+use serde::Serialize;
+use signing::AssignmentSigner;
+use speed::SpeedSmoother;
+use spot_check::{SpotCheck, SpotChecker};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
 use std::str::FromStr;
-use std::{net::SocketAddr, sync::Arc};
-use tower_http::trace::TraceLayer;
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use system_samples::SystemSamples;
+use targets::Targets;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    services::{ServeDir, ServeFile},
+    trace::TraceLayer,
+};
 use tracing::{error, info, warn};
+use validate::ValidJson;
+
+/// 内置接口和页面使用的路径前缀，静态文件不能挂载在这些路径下
+const RESERVED_PATHS: [&str; 7] = [
+    routes::task::PREFIX,
+    routes::admin::PREFIX,
+    "/ui",
+    routes::GRAPHQL,
+    routes::METRICS,
+    routes::HEALTHZ,
+    routes::READYZ,
+];
+
+/// 没有可分配的任务时建议Worker等待的时长
+const NO_TASK_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// 任务分发暂停时建议Worker等待的时长
+const PAUSED_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// 从游标切分新范围时的分配方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum DispatchMode {
+    /// 按Worker的速度估算范围大小
+    Range,
+    /// 限时任务：预留最大批次，Worker扫描 `--batch-target-runtime` 秒后上报扫描到的位置，剩余范围退回
+    Duration,
+}
 
 /// Master节点配置
 #[derive(Parser, Debug)]
@@ -30,6 +148,22 @@ struct Config {
     #[arg(short = 'd', long, default_value = "master.db")]
     database_url: String,
 
+    /// 数据库连接池的最大连接数
+    #[arg(long, default_value = "20", value_parser = clap::value_parser!(u32).range(1..))]
+    db_max_connections: u32,
+
+    /// 统计、导出和面板查询使用的只读连接池的最大连接数，0 表示与任务接口共用同一个连接池
+    #[arg(long, default_value = "4")]
+    db_read_connections: u32,
+
+    /// 等待空闲数据库连接的超时，超时的请求返回 500
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "30s")]
+    db_acquire_timeout: Duration,
+
+    /// 单条SQL语句（事务中为整个事务）的执行超时（如 10s），超时的语句被中断，不设置则不限制；备份不受限制
+    #[arg(long, value_parser = humantime::parse_duration)]
+    db_statement_timeout: Option<Duration>,
+
     /// 监听地址
     #[arg(short = 'H', long, default_value = "0.0.0.0")]
     host: String,
@@ -37,26 +171,486 @@ struct Config {
     /// 监听端口
     #[arg(short = 'p', long, default_value = "3000")]
     port: u16,
+
+    /// 配置文件（TOML，目前包含告警邮件的 [smtp] 设置）
+    #[arg(short = 'c', long = "config", env = "PA_MASTER_CONFIG")]
+    config_file: Option<PathBuf>,
+
+    /// 后台回收超时任务的间隔（秒）
+    #[arg(long, default_value = "15")]
+    reaper_interval: u64,
+
+    /// 回收超时任务时的拆分粒度（ID数，0表示不拆分）
+    #[arg(long, default_value = "10000")]
+    split_size: i64,
+
+    /// 慢请求日志阈值（毫秒，0表示关闭）
+    #[arg(long, default_value = "250")]
+    slow_request_ms: u64,
+
+    /// OTLP 链路追踪导出地址（如 http://localhost:4318/v1/traces），不设置则不导出
+    #[arg(long)]
+    otlp_endpoint: Option<String>,
+
+    /// 日志格式（text 或 json）
+    #[arg(long, default_value = "text")]
+    log_format: LogFormat,
+
+    /// 日志文件路径（在终端输出之外额外写入文件）
+    #[arg(long)]
+    log_file: Option<PathBuf>,
+
+    /// 日志文件轮转策略（daily、hourly、never，或按大小如 100MB）
+    #[arg(long, default_value = "daily")]
+    log_rotation: Rotation,
+
+    /// 保留的历史日志文件数（0表示全部保留）
+    #[arg(long, default_value = "7")]
+    log_max_files: usize,
+
+    /// 启动时暂停任务分发（只提供心跳、提交等接口）
+    #[arg(long)]
+    start_paused: bool,
+
+    /// 任务分配签名密钥，不设置时使用数据库中自动生成的密钥（主备节点共用）
+    #[arg(long, env = "PA_SIGNING_KEY", hide_env_values = true)]
+    signing_key: Option<String>,
+
+    /// 管理接口的用户名（HTTP Basic 认证）
+    #[arg(long, default_value = "admin")]
+    admin_user: String,
+
+    /// 管理接口的密码，设置后 /admin/*、/ui、/graphql 和前端面板要求 HTTP Basic 认证
+    #[arg(long, env = "PA_ADMIN_PASSWORD", hide_env_values = true)]
+    admin_password: Option<String>,
+
+    /// 要求 `/task/*` 请求携带有效的API Key（用 `admin create-key` 创建）
+    #[arg(long)]
+    require_api_key: bool,
+
+    /// 请求体大小上限（KB）
+    #[arg(long, default_value = "4096")]
+    max_body_kb: usize,
+
+    /// 单次提交的有效ID数上限
+    #[arg(long, default_value = "100000")]
+    max_valid_ids: usize,
+
+    /// 同一个Worker两次获取任务之间的最小间隔（0表示不限制）
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "1s")]
+    acquire_interval: Duration,
+
+    /// 一次请求最多领取的任务数（protocol v5 的Worker可以一次领取多个任务）
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..))]
+    max_tasks_per_acquire: u32,
+
+    /// `/task/acquire` 的限流速率（如 20/s、600/m），按API Key或客户端IP计数，不设置则不限流
+    #[arg(long)]
+    rate_limit_acquire: Option<RateSpec>,
+
+    /// `/task/heartbeat` 的限流速率
+    #[arg(long)]
+    rate_limit_heartbeat: Option<RateSpec>,
+
+    /// `/task/submit`、`/task/results`、`/task/responses`、`/task/release` 的限流速率
+    #[arg(long)]
+    rate_limit_submit: Option<RateSpec>,
+
+    /// 管理接口、GraphQL、状态页面和前端面板的限流速率
+    #[arg(long)]
+    rate_limit_admin: Option<RateSpec>,
+
+    /// 全体Worker对上游的总请求速度上限（req/s），按活跃Worker数平分（0表示不限制）
+    #[arg(long, default_value = "0")]
+    global_rps: f64,
+
+    /// 期望每个任务运行的秒数（批次大小 = Worker上报的速度 × 该值）
+    #[arg(long, default_value = "30")]
+    batch_target_runtime: i64,
+
+    /// 新范围的分配方式：range 按速度估算大小；duration 分配限时任务（不支持 protocol v4 的Worker仍按 range 分配）
+    #[arg(long, value_enum, default_value = "range")]
+    dispatch_mode: DispatchMode,
+
+    /// Worker未上报速度时假定的速度（req/s）
+    #[arg(long, default_value = "100")]
+    batch_default_speed: i64,
+
+    /// Worker上报的速度（last_performance）最多为Master测得速度（没有时为默认速度）的多少倍，超过时截断
+    #[arg(long, default_value = "2.0")]
+    reported_speed_tolerance: f64,
+
+    /// Worker速度的平滑系数：最新一次测得的速度的权重（0 到 1，1 表示不平滑）
+    #[arg(long, default_value = "0.3")]
+    speed_smoothing: f64,
+
+    /// 批次大小下限
+    #[arg(long, default_value = "1000")]
+    min_batch_size: i64,
+
+    /// 批次大小上限
+    #[arg(long, default_value = "50000")]
+    max_batch_size: i64,
+
+    /// Master还没有测得速度的Worker先领取的校准批次大小（不受批次大小下限限制，0 表示不校准）
+    #[arg(long, default_value = "200")]
+    calibration_batch_size: i64,
+
+    /// 建议Worker使用的并发数上限
+    #[arg(long, default_value = "64")]
+    max_worker_concurrency: usize,
+
+    /// 全体Worker的目标总速度（req/s），超过时下调建议并发数（0表示不限制）
+    #[arg(long, default_value = "0")]
+    target_throughput: f64,
+
+    /// 可接受的上游错误率，Worker上报的平均错误率超过时减半建议并发数
+    #[arg(long, default_value = "0.2")]
+    max_upstream_error_rate: f64,
+
+    /// 每次提交后由Master抽查的ID数（0表示不抽查）
+    #[arg(long, default_value = "0")]
+    spot_check_sample: usize,
+
+    /// 同时进行的抽查数，抽查繁忙时跳过新的提交
+    #[arg(long, default_value = "4")]
+    spot_check_concurrency: usize,
+
+    /// 可疑提交检测：实际速度超过上报速度的倍数（0表示不检查）
+    #[arg(long, default_value = "5")]
+    anomaly_speed_factor: f64,
+
+    /// 可疑提交检测：命中率与相邻范围相差的倍数（0表示不检查）
+    #[arg(long, default_value = "10")]
+    anomaly_rate_factor: f64,
+
+    /// 可疑提交检测：预期命中数至少为多少时才比较命中率
+    #[arg(long, default_value = "5")]
+    anomaly_min_expected: f64,
+
+    /// 可疑提交检测：向两侧各取多少个ID范围内的已完成任务作为参照
+    #[arg(long, default_value = "1000000")]
+    anomaly_window: i64,
+
+    /// 定期备份数据库的目录，不设置则不备份
+    #[arg(long)]
+    backup_dir: Option<PathBuf>,
+
+    /// 备份间隔（如 30m、6h）
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "1h")]
+    backup_interval: Duration,
+
+    /// 保留的备份数（0表示全部保留）
+    #[arg(long, default_value = "24")]
+    backup_keep: usize,
+
+    /// 备份完成后上传到的远程地址（s3://bucket/prefix 或 WebDAV 的 http(s) 地址）
+    #[arg(long)]
+    backup_upload: Option<String>,
+
+    /// S3 兼容存储的地址（MinIO 等自建服务时修改）
+    #[arg(long, default_value = "https://s3.amazonaws.com")]
+    backup_s3_endpoint: String,
+
+    /// S3 区域
+    #[arg(long, default_value = "us-east-1")]
+    backup_s3_region: String,
+
+    /// 上传失败时的重试次数
+    #[arg(long, default_value = "3")]
+    backup_upload_retries: u32,
+
+    /// 启用主备模式：同一台机器上的多个 Master 进程共用同一个数据库，通过租约选出主节点
+    #[arg(long)]
+    ha: bool,
+
+    /// 主备模式下的节点ID（默认随机生成）
+    #[arg(long)]
+    node_id: Option<String>,
+
+    /// 主节点租约时长，主节点停止续约超过该时长后备用节点接管
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "15s")]
+    lease_ttl: Duration,
+
+    /// 只分配属于该分片的ID（如 2/4），供多个独立的 Master 共同覆盖同一个ID空间
+    #[arg(long)]
+    shard: Option<ShardSpec>,
+
+    /// 分片的块大小：ID空间按该大小切块，各块轮流分给各个分片（所有 Master 必须一致）
+    #[arg(long, default_value = "1000000")]
+    shard_block_size: i64,
+
+    /// 只分配小于该ID的范围，游标到达后不再切分新范围（已分配的任务照常完成），不设置则不限制
+    #[arg(long)]
+    end_id: Option<i64>,
+
+    /// 只把普通任务分给这些地区的Worker（逗号分隔，如 cn），其他Worker只领取复查任务；不设置则不限制
+    #[arg(long, value_delimiter = ',')]
+    normal_regions: Vec<String>,
+
+    /// 只把复查任务分给这些地区的Worker（逗号分隔）；不设置则不限制
+    #[arg(long, value_delimiter = ',')]
+    verify_regions: Vec<String>,
+
+    /// 上游探测目标的 JSON 文件（可多次指定，分配任务时轮流下发），不设置则Worker探测内置的上游
+    #[arg(long = "target", value_name = "FILE")]
+    targets: Vec<PathBuf>,
+
+    /// 保存Worker上传的有效ID原始响应（gzip 压缩）的位置：本地目录或 s3://bucket/prefix，
+    /// 不设置则不接受上传
+    #[arg(long, value_name = "DIR|URL")]
+    response_archive: Option<String>,
+
+    /// 原始响应存档使用的 S3 兼容存储地址
+    #[arg(long, default_value = "https://s3.amazonaws.com")]
+    response_archive_s3_endpoint: String,
+
+    /// 原始响应存档使用的 S3 区域
+    #[arg(long, default_value = "us-east-1")]
+    response_archive_s3_region: String,
+
+    /// ID类型：integer（连续整数）、template（按 --id-template 格式化）或 list（只扫描候选ID）；
+    /// 首次启动时保存在数据库中，不设置则沿用数据库中的设置
+    #[arg(long, value_parser = ["integer", "template", "list"])]
+    id_kind: Option<String>,
+
+    /// template 类型的模板，`{n}` 替换为数字部分（如 C{n}）
+    #[arg(long)]
+    id_template: Option<String>,
+
+    /// template 类型数字部分补零到的宽度（含校验位，0 表示不补零）
+    #[arg(long, default_value = "0")]
+    id_width: usize,
+
+    /// template 类型数字部分末尾的校验位（none 或 luhn）
+    #[arg(long, default_value = "none")]
+    id_checksum: Checksum,
+
+    /// 心跳持续上报零速度超过该时长的任务视为停滞，释放给其他Worker（0s 表示不检测）
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "10m")]
+    stall_timeout: Duration,
+
+    /// 超过该时长没有新的提交（或游标没有前进且没有待分配任务）时发出扫描停滞告警（0s 表示不检测）
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "30m")]
+    scan_stall_timeout: Duration,
+
+    /// 告警通知的 webhook 地址（POST JSON），不设置且配置文件中没有 [smtp] 时只记录日志
+    #[arg(long)]
+    alert_webhook: Option<String>,
+
+    /// 告警规则文件（JSON 数组，见 alerts 模块），触发和恢复时通过告警 webhook 或邮件通知
+    #[arg(long)]
+    alert_rules: Option<PathBuf>,
+
+    /// 计算告警规则的间隔
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "60s")]
+    alert_interval: Duration,
+
+    /// 定期把连续的有效ID压缩为范围的间隔（如 1h，0s 表示不压缩）
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "0s")]
+    compact_interval: Duration,
+
+    /// 压缩时连续ID至少为多少个才按范围保存（与已有范围相邻的不受限制）
+    #[arg(long, default_value = "16")]
+    compact_min_run: i64,
+
+    /// 在 /graphql 提供只读的 GraphQL 查询接口（GET 不带参数时为 GraphiQL 页面）
+    #[arg(long)]
+    graphql: bool,
+
+    /// 前端静态文件目录（如编译好的 SPA 面板），不设置则不提供静态文件
+    #[arg(long)]
+    static_dir: Option<PathBuf>,
+
+    /// 静态文件挂载的URL路径，找不到的文件返回目录下的 index.html，交给前端路由处理
+    #[arg(long, default_value = "/dashboard")]
+    static_path: String,
+
+    /// Tokio 工作线程数，默认取可用的 CPU 核数（容器中可能不准确）
+    #[arg(long, env = "PA_WORKER_THREADS")]
+    worker_threads: Option<NonZeroUsize>,
+
+    /// Tokio 阻塞线程池的线程数上限（默认 512）
+    #[arg(long)]
+    max_blocking_threads: Option<NonZeroUsize>,
+
+    /// 子命令，不指定时作为Master节点运行
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Master的子命令
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 单机模式：在同一个进程中运行Master和一个Worker，扫描完指定范围后导出结果并退出
+    Standalone(standalone::StandaloneArgs),
+}
+
+impl Config {
+    fn runtime(&self) -> RuntimeConfig {
+        RuntimeConfig {
+            worker_threads: self.worker_threads,
+            max_blocking_threads: self.max_blocking_threads,
+        }
+    }
 }
 
 /// 应用状态
-#[derive(Clone)]
 struct AppState {
     /// SQLite数据库连接池
     db_pool: SqlitePool,
+
+    /// 统计、导出和面板查询使用的只读连接池（`--db-read-connections 0` 时与 db_pool 相同）
+    read_pool: SqlitePool,
+
+    /// 运行中任务的内存视图
+    running: RunningTasks,
+
+    /// 运行指标
+    metrics: Metrics,
+
+    /// 被封禁的Worker
+    bans: BannedWorkers,
+
+    /// Worker的API Key
+    api_keys: ApiKeys,
+
+    /// Worker上报的主机信息和标签
+    profiles: WorkerProfiles,
+
+    /// 各Worker最近一次请求的时间
+    presence: WorkerPresence,
+
+    /// 各Worker最近一次随心跳上报的系统状态
+    system_samples: SystemSamples,
+
+    /// 任务分配签名
+    signer: AssignmentSigner,
+
+    /// 可疑提交检测
+    anomaly: AnomalyDetector,
+
+    /// 提交结果的抽查（未启用时为 None）
+    spot_checker: Option<SpotChecker>,
+
+    /// 任务批次大小的计算参数（可通过管理接口修改）
+    batch_settings: std::sync::RwLock<BatchSettings>,
+
+    /// 根据上游错误率和目标总速度给Worker的并发建议
+    concurrency: ConcurrencyAdvisor,
+
+    /// 各Worker测得的速度的移动平均（持久化在数据库中），用于计算批次大小
+    speeds: SpeedSmoother,
+
+    /// 本节点负责的ID分片（未分片时为 None）
+    shard: Option<Shard>,
+
+    /// 按Worker所在地区限制可领取的任务类型
+    regions: RegionPolicy,
+
+    /// 随任务下发的上游探测目标
+    targets: Targets,
+
+    /// ID类型
+    id_kind: IdKind,
+
+    /// 原始响应存档（未启用时为 None）
+    response_archive: Option<ResponseArchive>,
+
+    /// 按Worker限制任务获取频率
+    acquire_limiter: AcquireLimiter,
+
+    /// 按路由组限流
+    route_limits: RouteLimits,
+
+    /// 是否暂停任务分发
+    dispatch_paused: AtomicBool,
+
+    /// 维护模式
+    maintenance: Maintenance,
+
+    /// 告警通知（webhook 和邮件都未配置时为 None）
+    notifier: Option<Notifier>,
+
+    /// 告警规则（未配置时为 None）
+    alerts: Option<AlertEngine>,
+
+    /// 主备状态
+    leadership: Leadership,
+
+    /// 启动配置
+    config: Config,
+}
+
+/// 就绪检查结果
+#[derive(Serialize)]
+struct ReadinessStatus {
+    /// 数据库是否可用
+    database: bool,
+
+    /// 任务分发是否已暂停
+    dispatch_paused: bool,
+
+    /// 是否在维护中（任务接口返回 503）
+    maintenance: bool,
+
+    /// 本节点是否为主节点（备用节点返回 503）
+    leader: bool,
+
+    /// 运行中的任务数
+    running_tasks: usize,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 解析命令行参数，按参数创建运行时
+    let matches = Config::command().get_matches();
+    let mut config = Config::from_arg_matches(&matches)?;
+    let runtime = config.runtime().build()?;
+    match config.command.take() {
+        Some(Command::Standalone(args)) => {
+            // 单机模式没有指定监听地址时只监听本机
+            if matches.value_source("host") == Some(ValueSource::DefaultValue) {
+                config.host = "127.0.0.1".to_string();
+            }
+            runtime.block_on(standalone::run(config, args))
+        }
+        None => runtime.block_on(run(config)),
+    }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // 初始化日志
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let _telemetry = init_telemetry(&config, "pa_master")?;
+    let state = start(config).await?;
+    let listener = listen(&state.config).await?;
+    serve(state, listener).await
+}
 
-    // 解析命令行参数
-    let config = Config::parse();
+/// 初始化日志与链路追踪，返回值需要保持到进程退出
+fn init_telemetry(
+    config: &Config,
+    service_name: &'static str,
+) -> Result<telemetry::TelemetryGuard, Box<dyn std::error::Error>> {
+    telemetry::init_tracing(&TelemetryConfig {
+        service_name,
+        log_format: config.log_format,
+        otlp_endpoint: config.otlp_endpoint.as_deref(),
+        log_file: config.log_file.as_deref().map(|path| LogFileConfig {
+            path,
+            rotation: config.log_rotation,
+            max_files: config.log_max_files,
+        }),
+    })
+}
+
+/// 连接数据库、加载状态并启动后台任务，返回应用状态（尚未开始监听）
+async fn start(config: Config) -> Result<Arc<AppState>, Box<dyn std::error::Error>> {
     info!("启动Master节点，端口: {}", config.port);
     info!("数据库路径: {}", config.database_url);
+    info!(
+        "运行时: {} 个工作线程，阻塞线程上限 {}",
+        config.runtime().worker_threads(),
+        config.runtime().max_blocking_threads()
+    );
 
     // 确保数据库文件的目录存在
     if let Some(parent) = std::path::Path::new(&config.database_url).parent() {
@@ -66,109 +660,510 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // 创建数据库连接池（使用标准文件路径，自动创建文件）
+    // WAL 模式下读事务不阻塞写入，统计查询和在线备份不会拖慢任务接口
     let database_url = format!("sqlite:{}", config.database_url);
-    let connect_options = SqliteConnectOptions::from_str(&database_url)?.create_if_missing(true);
-
-    let pool = SqlitePoolOptions::new()
-        .max_connections(20)
-        .connect_with(connect_options)
-        .await?;
+    let connect_options = SqliteConnectOptions::from_str(&database_url)?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal);
+
+    let pool_settings = PoolSettings {
+        max_connections: config.db_max_connections,
+        acquire_timeout: config.db_acquire_timeout,
+        // 0 与不设置相同
+        statement_timeout: config
+            .db_statement_timeout
+            .filter(|timeout| !timeout.is_zero()),
+    };
+    let metrics = Metrics::default();
+    let pool = db_pool::connect(
+        connect_options.clone(),
+        pool_settings,
+        Arc::clone(&metrics.statements_timed_out),
+    )
+    .await?;
+    info!(
+        "数据库连接池: 最多 {} 个连接，获取连接超时 {:?}，语句超时 {}",
+        pool_settings.max_connections,
+        pool_settings.acquire_timeout,
+        pool_settings
+            .statement_timeout
+            .map_or_else(|| "不限".to_string(), |timeout| format!("{:?}", timeout))
+    );
 
-    // 执行初始化SQL
-    init_database(&pool).await?;
+    // 检查schema版本并执行迁移
+    let version = schema::migrate(&pool).await?;
+    info!("数据库schema版本: {}", version);
 
     // 测试数据库连接
     sqlx::query("SELECT 1").fetch_one(&pool).await?;
     info!("数据库连接成功");
 
+    // 统计查询使用单独的只读连接池，不占用任务接口的连接（迁移完成后再打开）
+    let read_pool = if config.db_read_connections > 0 {
+        let read_pool = db_pool::connect(
+            connect_options.read_only(true),
+            PoolSettings {
+                max_connections: config.db_read_connections,
+                ..pool_settings
+            },
+            Arc::clone(&metrics.statements_timed_out),
+        )
+        .await?;
+        info!("只读连接池: 最多 {} 个连接", config.db_read_connections);
+        read_pool
+    } else {
+        pool.clone()
+    };
+
+    // 从数据库同步运行中的任务
+    let running = RunningTasks::default();
+    running.sync_from_db(&pool).await?;
+    info!("已从数据库加载 {} 个运行中的任务", running.len().await);
+
+    // 加载Worker封禁名单
+    let bans = BannedWorkers::load(&pool).await?;
+    let api_keys = ApiKeys::load(&pool).await?;
+    let profiles = WorkerProfiles::load(&pool).await?;
+    let signer = AssignmentSigner::load(&pool, config.signing_key.as_deref()).await?;
+    let id_kind = job::load_id_kind(&pool, configured_id_kind(&config)?).await?;
+    if config.require_api_key {
+        info!("已启用API Key校验，/task/* 请求必须携带有效的API Key");
+    }
+    match &config.admin_password {
+        Some(password) if password.is_empty() => {
+            return Err("--admin-password 不能为空".into());
+        }
+        Some(_) => info!("已启用管理凭据校验，用户名: {}", config.admin_user),
+        None if api_keys.has_management_keys().await => {
+            info!("没有设置 --admin-password，已有管理令牌，管理接口要求携带令牌")
+        }
+        None => warn!("没有设置 --admin-password，管理接口不需要认证，请勿暴露在公网"),
+    }
+
+    // 主备模式下先尝试获取租约，获取不到则以备用节点启动
+    let leadership = if config.ha {
+        if config.lease_ttl < Duration::from_secs(3) {
+            return Err("--lease-ttl 不能小于3秒".into());
+        }
+        let node_id = config
+            .node_id
+            .clone()
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let leadership = Leadership::elected(node_id, config.lease_ttl);
+        if leadership.try_acquire(&pool).await? {
+            leadership.set_leader(true);
+            info!("主备模式: 节点 {} 以主节点启动", leadership.node_id());
+        } else {
+            info!("主备模式: 节点 {} 以备用节点启动", leadership.node_id());
+        }
+        leadership
+    } else {
+        Leadership::standalone()
+    };
+
+    let batch_settings = BatchSettings {
+        target_runtime_secs: config.batch_target_runtime,
+        default_speed: config.batch_default_speed,
+        min_batch_size: config.min_batch_size,
+        max_batch_size: config.max_batch_size,
+        calibration_batch_size: config.calibration_batch_size,
+    };
+    batch_settings
+        .check()
+        .map_err(|e| format!("批次参数无效: {}", e))?;
+    if !(config.speed_smoothing > 0.0 && config.speed_smoothing <= 1.0) {
+        return Err("--speed-smoothing 必须在 (0, 1] 之间".into());
+    }
+    if config.reported_speed_tolerance.is_nan() || config.reported_speed_tolerance < 1.0 {
+        return Err("--reported-speed-tolerance 不能小于1".into());
+    }
+    let speeds = SpeedSmoother::load(&pool, config.speed_smoothing).await?;
+    if config.compact_min_run < 1 {
+        return Err("--compact-min-run 必须大于0".into());
+    }
+    if config.static_dir.is_some() {
+        let path = config.static_path.trim_end_matches('/');
+        if !path.starts_with('/') {
+            return Err("--static-path 必须以 / 开头，且不能是根路径".into());
+        }
+        if RESERVED_PATHS
+            .iter()
+            .any(|reserved| path == *reserved || path.starts_with(&format!("{}/", reserved)))
+        {
+            return Err(format!("--static-path 不能占用内置路径 {}", path).into());
+        }
+    }
+
+    let shard = match config.shard {
+        Some(_) if config.shard_block_size <= 0 => {
+            return Err("--shard-block-size 必须大于0".into());
+        }
+        Some(spec) => {
+            info!(
+                "分片模式: 只分配第 {} 个分片的ID，块大小 {}",
+                spec, config.shard_block_size
+            );
+            Some(Shard {
+                spec,
+                block_size: config.shard_block_size,
+            })
+        }
+        None => None,
+    };
+    shard::save(&pool, shard).await?;
+    if let Some(end_id) = config.end_id {
+        info!("只分配小于 {} 的ID，游标到达后不再切分新范围", end_id);
+    }
+
+    let regions = RegionPolicy {
+        normal: config.normal_regions.clone(),
+        verification: config.verify_regions.clone(),
+    };
+    if regions.is_restricted() {
+        info!(
+            "按地区分配任务: 普通任务 {:?}，复查任务 {:?}（空表示不限制）",
+            regions.normal, regions.verification
+        );
+    }
+
+    let targets = Targets::load(&config.targets)?;
+    for target in targets.specs() {
+        info!(
+            "上游探测目标: {} ({} {})",
+            target.label(),
+            target.method,
+            target.url
+        );
+    }
+    if !id_kind.is_integer() {
+        // 内置的上游只接受整数ID
+        if targets.is_empty() {
+            return Err(format!("ID类型 {} 需要配合 --target 使用", id_kind).into());
+        }
+        info!("ID类型: {}", id_kind);
+    }
+
+    let response_archive = match &config.response_archive {
+        Some(location) => {
+            let store = blob::open(
+                location,
+                &config.response_archive_s3_endpoint,
+                &config.response_archive_s3_region,
+            )?;
+            info!("原始响应存档位置: {}", store);
+            Some(ResponseArchive::new(store))
+        }
+        None => None,
+    };
+
+    let config_file = match &config.config_file {
+        Some(path) => ConfigFile::load(path)?,
+        None => ConfigFile::default(),
+    };
+    let email = match &config_file.smtp {
+        Some(smtp) => {
+            let email = EmailNotifier::new(smtp, notify::SEND_TIMEOUT)?;
+            info!(
+                "告警邮件: 通过 {} 发送给 {} 个收件人",
+                smtp.host,
+                email.recipients()
+            );
+            Some(email)
+        }
+        None => None,
+    };
+    let notifier = Notifier::new(config.alert_webhook.clone(), email);
+
+    let alerts = match &config.alert_rules {
+        Some(path) => {
+            if config.alert_interval.is_zero() {
+                return Err("--alert-interval 必须大于0".into());
+            }
+            let rules = alerts::load_rules(path)?;
+            for rule in &rules {
+                info!(
+                    "告警规则 {}: {} {} {}，持续 {}",
+                    rule.name,
+                    rule.metric.name(),
+                    rule.op,
+                    rule.threshold,
+                    humantime::format_duration(rule.for_duration)
+                );
+            }
+            Some(AlertEngine::new(rules))
+        }
+        None => None,
+    };
+
     // 创建应用状态
-    let state = Arc::new(AppState { db_pool: pool });
+    let state = Arc::new(AppState {
+        db_pool: pool,
+        read_pool,
+        running,
+        metrics,
+        bans,
+        api_keys,
+        profiles,
+        presence: WorkerPresence::default(),
+        system_samples: SystemSamples::default(),
+        signer,
+        anomaly: AnomalyDetector::new(AnomalyConfig {
+            speed_factor: config.anomaly_speed_factor,
+            rate_factor: config.anomaly_rate_factor,
+            min_expected: config.anomaly_min_expected,
+            window: config.anomaly_window,
+        }),
+        spot_checker: (config.spot_check_sample > 0)
+            .then(|| SpotChecker::new(config.spot_check_sample, config.spot_check_concurrency)),
+        batch_settings: std::sync::RwLock::new(batch_settings),
+        concurrency: ConcurrencyAdvisor::new(ConcurrencyConfig {
+            max_concurrency: config.max_worker_concurrency,
+            target_throughput: config.target_throughput,
+            max_error_rate: config.max_upstream_error_rate,
+        }),
+        speeds,
+        shard,
+        regions,
+        targets,
+        id_kind,
+        response_archive,
+        acquire_limiter: AcquireLimiter::new(config.acquire_interval),
+        route_limits: RouteLimits {
+            acquire: config.rate_limit_acquire.map(RouteLimiter::new),
+            heartbeat: config.rate_limit_heartbeat.map(RouteLimiter::new),
+            submit: config.rate_limit_submit.map(RouteLimiter::new),
+            admin: config.rate_limit_admin.map(RouteLimiter::new),
+        },
+        dispatch_paused: AtomicBool::new(config.start_paused),
+        maintenance: Maintenance::default(),
+        notifier,
+        alerts,
+        leadership,
+        config,
+    });
+
+    // 启动主节点选举
+    if state.config.ha {
+        leader::spawn_election(Arc::clone(&state));
+    }
 
-    // 构建路由
-    let app = Router::new()
-        .route("/task/acquire", post(acquire_task))
-        .route("/task/heartbeat", post(heartbeat))
-        .route("/task/submit", post(submit_result))
-        .route("/task/release", post(release_task))
-        .layer(TraceLayer::new_for_http())
-        .with_state(state);
+    // 启动后台超时任务回收
+    reaper::spawn_reaper(
+        Arc::clone(&state),
+        Duration::from_secs(state.config.reaper_interval),
+        state.config.split_size,
+    );
 
-    // 启动服务器
-    let addr: SocketAddr = format!("{}:{}", config.host, config.port)
-        .parse()
-        .expect("无效的主机:端口组合");
-    info!("Master服务器监听在 http://{}", addr);
+    // 启动有效ID压缩
+    if !state.config.compact_interval.is_zero() {
+        info!(
+            "已启用有效ID压缩: 间隔={}, 最短游程={}",
+            humantime::format_duration(state.config.compact_interval),
+            state.config.compact_min_run
+        );
+        compaction::spawn_compaction(
+            Arc::clone(&state),
+            state.config.compact_interval,
+            state.config.compact_min_run,
+        );
+    }
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // 启动扫描停滞检查
+    if !state.config.scan_stall_timeout.is_zero() {
+        watchdog::spawn_watchdog(Arc::clone(&state), state.config.scan_stall_timeout);
+    }
 
-    Ok(())
-}
+    // 启动告警规则计算
+    if state.alerts.is_some() {
+        alerts::spawn_alerts(Arc::clone(&state), state.config.alert_interval);
+    }
 
-/// 初始化数据库表
-async fn init_database(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    // 创建global_cursor表
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS global_cursor (
-            id INTEGER PRIMARY KEY,
-            next_start_id INTEGER NOT NULL
-        )",
-    )
-    .execute(pool)
-    .await?;
+    // 启动定期备份
+    if let Some(dir) = &state.config.backup_dir {
+        let uploader = match &state.config.backup_upload {
+            Some(url) => {
+                let target = backup::UploadTarget::parse(
+                    url,
+                    &state.config.backup_s3_endpoint,
+                    &state.config.backup_s3_region,
+                )?;
+                info!("备份将上传到 {}", target);
+                Some(backup::Uploader::new(
+                    target,
+                    state.config.backup_upload_retries,
+                ))
+            }
+            None => None,
+        };
+        info!(
+            "已启用定期备份: 目录={}, 间隔={}, 保留={}",
+            dir.display(),
+            humantime::format_duration(state.config.backup_interval),
+            state.config.backup_keep
+        );
+        backup::spawn_backup(
+            Arc::clone(&state),
+            dir.clone(),
+            state.config.backup_interval,
+            state.config.backup_keep,
+            uploader,
+        );
+    } else if state.config.backup_upload.is_some() {
+        warn!("设置了 --backup-upload 但没有设置 --backup-dir，不会执行备份");
+    }
 
-    // 初始化全局游标
-    let result =
-        sqlx::query("INSERT OR IGNORE INTO global_cursor (id, next_start_id) VALUES (1, 0)")
-            .execute(pool)
-            .await;
+    Ok(state)
+}
 
-    match result {
-        Ok(_) => info!("全局游标已初始化"),
-        Err(e) => info!("全局游标已存在或出错: {}", e),
-    }
-
-    // 创建task_queue表
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS task_queue (
-            task_id INTEGER PRIMARY KEY AUTOINCREMENT,
-            start_id INTEGER NOT NULL,
-            end_id INTEGER NOT NULL,
-            worker_id TEXT NOT NULL,
-            status TEXT NOT NULL DEFAULT 'running',
-            last_heartbeat DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )",
-    )
-    .execute(pool)
-    .await?;
+/// 监听 `--host`、`--port`
+async fn listen(config: &Config) -> std::io::Result<tokio::net::TcpListener> {
+    let addr: SocketAddr = format!("{}:{}", config.host, config.port)
+        .parse()
+        .expect("无效的主机:端口组合");
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!("Master服务器监听在 http://{}", listener.local_addr()?);
+    Ok(listener)
+}
 
-    // 创建task_queue的索引
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_task_queue_last_heartbeat ON task_queue(last_heartbeat)",
-    )
-    .execute(pool)
-    .await?;
+/// 构建路由并在 `listener` 上提供服务
+async fn serve(
+    state: Arc<AppState>,
+    listener: tokio::net::TcpListener,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // 管理接口、状态页面和前端面板需要管理凭据
+    let mut admin_routes = admin::router().merge(ui::router());
+    if state.config.graphql {
+        info!("已启用GraphQL查询接口: /graphql");
+        admin_routes = admin_routes.merge(graphql::router(Arc::clone(&state)));
+    }
+    if let Some(dir) = &state.config.static_dir {
+        let path = state.config.static_path.trim_end_matches('/');
+        info!("提供静态文件: {} -> {}", path, dir.display());
+        if !dir.join("index.html").is_file() {
+            warn!("静态文件目录 {} 下没有 index.html", dir.display());
+        }
+        let spa = ServeDir::new(dir).fallback(ServeFile::new(dir.join("index.html")));
+        admin_routes = admin_routes.nest_service(path, spa);
+    }
 
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_task_queue_status ON task_queue(status)")
-        .execute(pool)
-        .await?;
+    // 构建路由
+    let app = Router::new()
+        .route(
+            routes::task::ACQUIRE,
+            post(acquire_task)
+                .layer(middleware::from_fn_with_state(
+                    Arc::clone(&state),
+                    rate_limit::limit_acquire,
+                ))
+                .layer(middleware::from_fn_with_state(
+                    (Arc::clone(&state), RouteGroup::Acquire),
+                    rate_limit::limit_route,
+                )),
+        )
+        .route(
+            routes::task::HEARTBEAT,
+            post(heartbeat).layer(middleware::from_fn_with_state(
+                (Arc::clone(&state), RouteGroup::Heartbeat),
+                rate_limit::limit_route,
+            )),
+        )
+        .route(
+            routes::task::SUBMIT,
+            post(submit_result).layer(middleware::from_fn_with_state(
+                (Arc::clone(&state), RouteGroup::Submit),
+                rate_limit::limit_route,
+            )),
+        )
+        .route(
+            routes::task::RESULTS,
+            post(submit_partial).layer(middleware::from_fn_with_state(
+                (Arc::clone(&state), RouteGroup::Submit),
+                rate_limit::limit_route,
+            )),
+        )
+        .route(
+            routes::task::RESPONSES,
+            post(archive_responses).layer(middleware::from_fn_with_state(
+                (Arc::clone(&state), RouteGroup::Submit),
+                rate_limit::limit_route,
+            )),
+        )
+        .route(
+            routes::task::RELEASE,
+            post(release_task).layer(middleware::from_fn_with_state(
+                (Arc::clone(&state), RouteGroup::Submit),
+                rate_limit::limit_route,
+            )),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            api_keys::authenticate,
+        ))
+        // 维护期间数据库可能不可用，先于API Key校验拒绝
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            maintenance::reject_during_maintenance,
+        ))
+        .route(routes::METRICS, get(metrics_handler))
+        .route(routes::HEALTHZ, get(healthz))
+        .route(routes::READYZ, get(readyz))
+        .merge(
+            admin_routes
+                .route_layer(middleware::from_fn_with_state(
+                    Arc::clone(&state),
+                    admin_auth::require_admin,
+                ))
+                // 先限流再校验凭据，限制暴力猜测密码
+                .route_layer(middleware::from_fn_with_state(
+                    (Arc::clone(&state), RouteGroup::Admin),
+                    rate_limit::limit_route,
+                )),
+        )
+        .fallback(not_found)
+        .method_not_allowed_fallback(method_not_allowed)
+        .layer(DefaultBodyLimit::max(state.config.max_body_kb * 1024))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            leader::reject_writes_on_standby,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            metrics::track_latency,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            request_context::record_context,
+        ))
+        .layer(middleware::from_fn(request_id::scope_request_id))
+        .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER.parse()?))
+        .layer(
+            TraceLayer::new_for_http().make_span_with(|req: &axum::http::Request<_>| {
+                let span = tracing::info_span!(
+                    "request",
+                    method = %req.method(),
+                    uri = %req.uri(),
+                    request_id = request_id::from_request(req).unwrap_or_default(),
+                    worker_id = tracing::field::Empty,
+                    task_id = tracing::field::Empty,
+                );
+                // 接续Worker端传来的链路上下文
+                telemetry::set_parent_from_headers(&span, req.headers());
+                span
+            }),
+        )
+        .layer(SetRequestIdLayer::new(
+            REQUEST_ID_HEADER.parse()?,
+            MakeRequestUuid,
+        ))
+        .with_state(Arc::clone(&state));
 
-    // 创建valid_results表
-    sqlx::query(
-        "CREATE TABLE IF NOT EXISTS valid_results (
-            id INTEGER PRIMARY KEY,
-            found_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-        )",
+    // 启动服务器
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
     )
-    .execute(pool)
     .await?;
 
-    // 创建valid_results的索引
-    sqlx::query("CREATE INDEX IF NOT EXISTS idx_valid_results_found_at ON valid_results(found_at)")
-        .execute(pool)
-        .await?;
-
     Ok(())
 }
 
@@ -176,204 +1171,1240 @@ async fn init_database(pool: &SqlitePool) -> Result<(), sqlx::Error> {
 /// POST /task/acquire
 async fn acquire_task(
     State(state): State<Arc<AppState>>,
-    axum::Json(req): axum::Json<AcquireTaskRequest>,
-) -> (StatusCode, axum::Json<ApiResponse<AcquireTaskResponse>>) {
+    api_key: Option<Extension<ApiKey>>,
+    ValidJson(req): ValidJson<AcquireTaskRequest>,
+) -> Response {
     info!("Worker {} 请求任务", req.worker_id);
 
-    // 计算batch_size（基于last_performance）
-    let batch_size = calculate_batch_size(req.last_performance);
-    info!("计算得到的batch_size: {}", batch_size);
+    if state.bans.is_banned(&req.worker_id).await {
+        warn!("Worker {} 已被封禁，拒绝分配任务", req.worker_id);
+        return banned::<()>().into_response();
+    }
 
-    // 尝试获取任务（优先分配超时任务）
-    match try_acquire_task(&state.db_pool, &req.worker_id, batch_size).await {
-        Ok(Some(task)) => {
-            info!(
-                "任务已分配: task_id={}, 范围=[{}, {}]",
-                task.task_id, task.start_id, task.end_id
+    // 任务携带探测目标时，旧版本Worker会忽略目标而探测内置的上游
+    if !state.targets.is_empty() && req.protocol < 2 {
+        warn!(
+            "Worker {} 的协议版本 {} 不支持探测目标，拒绝分配任务",
+            req.worker_id, req.protocol
+        );
+        return (
+            StatusCode::BAD_REQUEST,
+            axum::Json(
+                api_error::<()>("Master配置了探测目标，请升级Worker".to_string())
+                    .with_code(ErrorCode::InvalidRequest),
+            ),
+        )
+            .into_response();
+    }
+
+    // 记录Worker上报的主机信息（失败不影响分配任务）
+    if let Some(profile) = &req.profile {
+        let recorded = state
+            .profiles
+            .record(&state.db_pool, &req.worker_id, profile);
+        if let Err(e) = timed_sql(recorded).await {
+            error!("记录Worker {} 的主机信息失败: {}", req.worker_id, e);
+        }
+    }
+
+    if state.dispatch_paused.load(Ordering::Relaxed) {
+        warn!("任务分发已暂停，拒绝Worker {} 的请求", req.worker_id);
+        return retry_later(
+            StatusCode::SERVICE_UNAVAILABLE,
+            PAUSED_RETRY_AFTER,
+            api_error::<()>("任务分发已暂停".to_string()).with_code(ErrorCode::DispatchPaused),
+        );
+    }
+
+    // 计算batch_size（基于Master测得的速度的移动平均，没有历史时使用Worker上报的last_performance）
+    let history = state.speeds.get(&req.worker_id);
+    let settings = *state.batch_settings.read().expect("批次参数锁中毒");
+    // Worker上报的速度不可信：按测得的速度截断后才用于批次大小、并发建议和异常检测
+    let reported = req
+        .last_performance
+        .filter(|speed| *speed > 0)
+        .map(|speed| {
+            let reference =
+                history.unwrap_or(u32::try_from(settings.default_speed).unwrap_or(u32::MAX));
+            let cap = speed::reported_speed_cap(reference, state.config.reported_speed_tolerance);
+            if speed <= cap {
+                return speed;
+            }
+            warn!(
+                "Worker {} 上报的速度 {} req/s 超过参考速度 {} req/s 的 {} 倍，按 {} 计算",
+                req.worker_id, speed, reference, state.config.reported_speed_tolerance, cap
             );
-            (StatusCode::OK, axum::Json(ApiResponse::success(task)))
+            Metrics::incr(&state.metrics.reported_speed_clamped);
+            cap
+        });
+    state.anomaly.record_speed(&req.worker_id, reported);
+    // 限时任务预留最大批次，Worker扫描到的位置之后的范围在提交时退回
+    let timed = state.config.dispatch_mode == DispatchMode::Duration
+        && req.protocol >= 4
+        && state.id_kind.is_sequential();
+    // 还没有测得速度的Worker先领取一个校准批次，提交后按测得的速度放大（限时任务不需要校准）
+    let mut batch_size = if history.is_none() && settings.calibration_batch_size > 0 && !timed {
+        let size = settings.calibration_batch_size.min(settings.max_batch_size);
+        info!(
+            "Worker {} 还没有速度历史，分配 {} 个ID的校准批次",
+            req.worker_id, size
+        );
+        size
+    } else {
+        calculate_batch_size(&settings, history.or(reported))
+    };
+    let mut reserve = settings.max_batch_size;
+    // Worker要求的范围上限优先于按速度计算的结果
+    if let Some(max) = req.max_batch_size {
+        batch_size = batch_size.min(max);
+        reserve = reserve.min(max);
+    }
+    info!("计算得到的batch_size: {}", batch_size);
+
+    let api_key = api_key.map(|Extension(key)| key);
+    let key_id = api_key.as_ref().map(|key| key.key_id);
+
+    // 按Worker所在地区决定可领取的任务类型（请求中未带主机信息时使用之前上报的）
+    let mut kinds = if state.regions.is_restricted() {
+        let region = match req.profile.as_ref() {
+            Some(profile) => profile.region.clone(),
+            None => state
+                .profiles
+                .get(&req.worker_id)
+                .await
+                .and_then(|profile| profile.region),
+        };
+        state.regions.kinds(region.as_deref())
+    } else {
+        TaskKinds::ALL
+    };
+    // 旧版本Worker会忽略ID列表而扫描整个范围
+    kinds.list = req.protocol >= 3;
+
+    // 一次领取多个任务（protocol v5），不超过 --max-tasks-per-acquire
+    let wanted = match req.max_tasks {
+        Some(max) if req.protocol >= 5 => max.clamp(1, state.config.max_tasks_per_acquire),
+        _ => 1,
+    };
+
+    // 逐个获取任务（优先分配超时任务），第一个任务就失败时返回错误，之后失败则只返回已获取的任务
+    let mut tasks = Vec::new();
+    let mut failure = None;
+    while tasks.len() < wanted as usize {
+        let (mut batch_size, mut reserve, mut max_len) = (batch_size, reserve, req.max_batch_size);
+
+        // 每个任务都先检查API Key的配额，无论它来自超时任务还是新范围：
+        // 同时持有的任务数已满时停止获取，每日配额不足一个批次时缩小批次，
+        // 也不认领超过剩余配额的不可拆分超时任务。
+        // 检查、领取和记录用量都在持有该Key的配额锁时进行，共用一个Key的Worker不会同时通过检查
+        let _quota_guard = match &api_key {
+            Some(key) => Some(state.api_keys.lock_quota(key.key_id).await),
+            None => None,
+        };
+        if let Some(key) = &api_key {
+            let wanted_ids = if timed { reserve } else { batch_size };
+            match timed_sql(api_keys::check_quota(&state.db_pool, key, wanted_ids)).await {
+                Ok(Quota::Allowed(allowed)) => {
+                    batch_size = batch_size.min(allowed);
+                    reserve = reserve.min(allowed);
+                    if key.max_ids_per_day.is_some() {
+                        max_len = Some(max_len.map_or(allowed, |max| max.min(allowed)));
+                    }
+                }
+                Ok(Quota::Exceeded(reason, wait)) => {
+                    warn!("API Key {} ({}) 配额不足: {}", key.key_id, key.name, reason);
+                    failure = Some(retry_later(
+                        StatusCode::TOO_MANY_REQUESTS,
+                        wait,
+                        api_error::<()>(reason).with_code(ErrorCode::QuotaExceeded),
+                    ));
+                    break;
+                }
+                Err(e) => {
+                    error!("检查API Key配额失败: {}", e);
+                    failure = Some(database_error(e));
+                    break;
+                }
+            }
         }
-        Ok(None) => {
-            warn!("没有可用的任务");
-            (
-                StatusCode::OK,
-                axum::Json(ApiResponse::error("没有可用的任务".to_string())),
-            )
+
+        let acquired = try_acquire_task(
+            &state,
+            &req.worker_id,
+            key_id,
+            batch_size,
+            max_len,
+            kinds,
+            timed.then_some((reserve, settings.target_runtime_secs as u64)),
+        );
+        match timed_sql(acquired).await {
+            Ok(Some(task)) => {
+                if let Some(key_id) = key_id {
+                    let ids = task.id_count();
+                    let recorded =
+                        timed_sql(api_keys::record_usage(&state.db_pool, key_id, ids)).await;
+                    if let Err(e) = recorded {
+                        error!("记录API Key {} 的用量失败: {}", key_id, e);
+                    }
+                }
+                tasks.push(task);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                error!("获取任务失败: {}", e);
+                failure = Some(database_error(e));
+                break;
+            }
         }
-        Err(e) => {
-            error!("获取任务失败: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                axum::Json(ApiResponse::error(format!("数据库错误: {}", e))),
+    }
+    if tasks.is_empty() {
+        return failure.unwrap_or_else(|| {
+            warn!("没有可用的任务");
+            retry_later(
+                StatusCode::SERVICE_UNAVAILABLE,
+                NO_TASK_RETRY_AFTER,
+                api_error::<()>("没有可用的任务".to_string()).with_code(ErrorCode::NoTaskAvailable),
             )
+        });
+    }
+
+    let suggested = state
+        .concurrency
+        .suggest(&req.worker_id, reported, req.last_error_rate);
+    let rate_limit = rate_share(&state).await;
+    for task in &mut tasks {
+        task.suggested_concurrency = Some(suggested);
+        task.rate_limit = rate_limit;
+        task.target = state.targets.next();
+        task.id_kind = state.id_kind.clone();
+        info!(
+            "任务已分配: task_id={}, 范围=[{}, {}], 建议并发数={}, 探测目标={}",
+            task.task_id,
+            task.start_id,
+            task.end_id,
+            suggested,
+            task.target.as_ref().map_or("内置", |target| target.label())
+        );
+        if let Some(secs) = task.scan_for_secs {
+            info!("任务 {} 为限时任务，扫描 {} 秒", task.task_id, secs);
         }
     }
+    if tasks.len() > 1 {
+        info!(
+            "Worker {} 本次领取了 {} 个任务（请求 {} 个）",
+            req.worker_id,
+            tasks.len(),
+            wanted
+        );
+    }
+
+    // 第一个任务作为响应本身，其余放在 extra_tasks 中
+    let mut tasks = tasks.into_iter();
+    let mut task = tasks.next().expect("至少有一个任务");
+    task.extra_tasks = tasks.collect();
+    (StatusCode::OK, axum::Json(ApiResponse::success(task))).into_response()
+}
+
+/// 数据库错误的响应
+fn database_error(e: sqlx::Error) -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        axum::Json(api_error::<()>(format!("数据库错误: {}", e))),
+    )
+        .into_response()
 }
 
 /// 任务保活
 /// POST /task/heartbeat
 async fn heartbeat(
     State(state): State<Arc<AppState>>,
-    axum::Json(req): axum::Json<HeartbeatRequest>,
-) -> StatusCode {
+    ValidJson(req): ValidJson<HeartbeatRequest>,
+) -> (StatusCode, axum::Json<ApiResponse<HeartbeatResponse>>) {
     info!(
         "收到来自worker {} 的任务 {} 的心跳",
         req.worker_id, req.task_id
     );
 
-    // 先查询任务当前状态，用于调试
-    let task_info: Option<(String, String)> = sqlx::query_as(
-        "SELECT worker_id, last_heartbeat FROM task_queue WHERE task_id = ?"
-    )
-    .bind(req.task_id)
-    .fetch_optional(&state.db_pool)
-    .await
-    .ok()
-    .flatten();
+    // 被封禁的Worker不再续约，任务由回收任务重新分配
+    if state.bans.is_banned(&req.worker_id).await {
+        warn!("Worker {} 已被封禁，拒绝心跳", req.worker_id);
+        return banned();
+    }
 
-    if let Some((current_worker_id, last_heartbeat)) = &task_info {
-        info!(
-            "任务 {} 当前状态: worker_id={}, last_heartbeat={}",
-            req.task_id, current_worker_id, last_heartbeat
-        );
-        if current_worker_id != &req.worker_id {
+    // 在内存中检查任务归属，任务已不属于该Worker时通知其停止扫描
+    match state.running.owner(req.task_id).await {
+        Some(owner) if owner == req.worker_id => {}
+        Some(owner) => {
             warn!(
-                "Worker ID不匹配! 请求的worker_id={}, 数据库中的worker_id={}",
-                req.worker_id, current_worker_id
+                "Worker ID不匹配! 请求的worker_id={}, 任务当前的worker_id={}",
+                req.worker_id, owner
             );
+            return abort_heartbeat(&state, "任务已重新分配给其他Worker");
+        }
+        None => {
+            warn!("任务 {} 不存在", req.task_id);
+            return abort_heartbeat(&state, "任务不存在或已完成");
         }
-    } else {
-        warn!("任务 {} 在数据库中不存在", req.task_id);
     }
 
     // 更新心跳时间
-    let result = sqlx::query(
-        "UPDATE task_queue SET last_heartbeat = datetime('now') WHERE task_id = ? AND worker_id = ?"
+    let result = timed_sql(
+        sqlx::query(
+            "UPDATE task_queue SET last_heartbeat = datetime('now'), lease_expires_at = ? WHERE task_id = ? AND worker_id = ?",
+        )
+        .bind(running::lease_expiry_millis())
+        .bind(req.task_id)
+        .bind(&req.worker_id)
+        .execute(&state.db_pool),
     )
-    .bind(req.task_id)
-    .bind(&req.worker_id)
-    .execute(&state.db_pool)
     .await;
 
     match result {
         Ok(res) => {
             if res.rows_affected() > 0 {
+                let stalled = state
+                    .running
+                    .touch(req.task_id, &req.worker_id, req.speed)
+                    .await;
+                if let Some(speed) = req.speed {
+                    state.concurrency.record_live_speed(&req.worker_id, speed);
+                }
+                if let Some(sample) = req.system {
+                    state.system_samples.record(&req.worker_id, sample);
+                }
+                let stall_timeout = state.config.stall_timeout;
+                if !stall_timeout.is_zero() && stalled.is_some_and(|d| d > stall_timeout) {
+                    return release_stalled(&state, req.task_id, &req.worker_id).await;
+                }
                 info!("任务 {} 的心跳已更新", req.task_id);
-                StatusCode::OK
+                let response = HeartbeatResponse {
+                    rate_limit: rate_share(&state).await,
+                    ..Default::default()
+                };
+                (StatusCode::OK, axum::Json(ApiResponse::success(response)))
             } else {
-                warn!("任务 {} 不存在或Worker不匹配 (rows_affected=0)", req.task_id);
-                StatusCode::NOT_FOUND
+                warn!(
+                    "任务 {} 不存在或Worker不匹配 (rows_affected=0)",
+                    req.task_id
+                );
+                abort_heartbeat(&state, "任务不存在或已重新分配")
             }
         }
         Err(e) => {
             error!("更新心跳失败: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(api_error(format!("数据库错误: {}", e))),
+            )
         }
     }
-}
+}
+
+/// 释放停滞的任务，使其立即可被其他Worker获取，并通知原Worker停止扫描
+async fn release_stalled(
+    state: &AppState,
+    task_id: i32,
+    worker_id: &str,
+) -> (StatusCode, axum::Json<ApiResponse<HeartbeatResponse>>) {
+    warn!(
+        "任务 {} 已超过 {} 没有进展，释放给其他Worker",
+        task_id,
+        humantime::format_duration(state.config.stall_timeout)
+    );
+    let result = timed_sql(
+        sqlx::query(
+            "UPDATE task_queue SET lease_expires_at = 0 WHERE task_id = ? AND worker_id = ?",
+        )
+        .bind(task_id)
+        .bind(worker_id)
+        .execute(&state.db_pool),
+    )
+    .await;
+    if let Err(e) = result {
+        // 释放失败时任务仍会在心跳停止后超时回收
+        error!("释放停滞的任务 {} 失败: {}", task_id, e);
+    }
+    state.running.expire(task_id, worker_id).await;
+    Metrics::incr(&state.metrics.tasks_stalled);
+    abort_heartbeat(state, "任务长时间没有进展，已释放给其他Worker")
+}
+
+/// 通知Worker任务已不属于它，应立即停止扫描
+fn abort_heartbeat(
+    state: &AppState,
+    reason: &str,
+) -> (StatusCode, axum::Json<ApiResponse<HeartbeatResponse>>) {
+    Metrics::incr(&state.metrics.heartbeat_aborts);
+    (
+        StatusCode::OK,
+        axum::Json(ApiResponse::success(HeartbeatResponse {
+            abort: true,
+            reason: Some(reason.to_string()),
+            rate_limit: None,
+        })),
+    )
+}
+
+/// 分给每个活跃Worker的请求速度上限：总速度上限按持有任务的Worker数平分
+async fn rate_share(state: &AppState) -> Option<f64> {
+    if state.config.global_rps <= 0.0 {
+        return None;
+    }
+    let workers = state.running.active_workers().await.max(1);
+    Some(state.config.global_rps / workers as f64)
+}
+
+/// 提交结果
+/// POST /task/submit
+async fn submit_result(
+    State(state): State<Arc<AppState>>,
+    ValidJson(req): ValidJson<SubmitResultRequest>,
+) -> (StatusCode, axum::Json<ApiResponse<String>>) {
+    info!(
+        "Worker提交任务 {} 的结果，发现有效ID数: {}",
+        req.task_id,
+        req.valid_ids.len()
+    );
+    if !req.marked.is_empty() {
+        info!(
+            "任务 {} 中有 {} 个ID命中响应标记",
+            req.task_id,
+            req.marked.len()
+        );
+    }
+    if let Some(latency) = &req.latency {
+        info!(
+            "任务 {} 的探测延迟: {} 次请求, p50={}ms, p90={}ms, p99={}ms, max={}ms",
+            req.task_id,
+            latency.count,
+            latency.p50_ms,
+            latency.p90_ms,
+            latency.p99_ms,
+            latency.max_ms
+        );
+    }
+
+    // 使用事务：写入结果 + 归档并删除任务
+    let mut tx = match timed_sql(state.db_pool.begin()).await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("启动事务失败: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(api_error(format!("事务错误: {}", e))),
+            );
+        }
+    };
+
+    // 1. 带幂等键的重试提交：首次提交已经成功时直接返回原结果
+    if let Some(key) = &req.idempotency_key {
+        match timed_sql(find_submit_outcome(&mut tx, key)).await {
+            Ok(Some((task_id, message))) if task_id == req.task_id => {
+                info!(
+                    "任务 {} 的提交已处理过（幂等键 {}），返回原结果",
+                    task_id, key
+                );
+                let _ = tx.rollback().await;
+                return (StatusCode::OK, axum::Json(ApiResponse::success(message)));
+            }
+            Ok(Some((task_id, _))) => {
+                warn!(
+                    "幂等键 {} 已用于任务 {}，拒绝任务 {} 的提交",
+                    key, task_id, req.task_id
+                );
+                let _ = tx.rollback().await;
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    axum::Json(
+                        api_error(format!("幂等键已用于任务 {}", task_id))
+                            .with_code(ErrorCode::InvalidRequest),
+                    ),
+                );
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("查询幂等键 {} 失败: {}", key, e);
+                let _ = tx.rollback().await;
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    axum::Json(api_error(format!("数据库错误: {}", e))),
+                );
+            }
+        }
+    }
+
+    // 2. 确认任务仍属于该Worker，再按数据库中的任务范围校验分配签名
+    let assignment = check_assignment(
+        &state,
+        &mut tx,
+        req.task_id,
+        &req.worker_id,
+        req.lease,
+        &req.signature,
+        req.valid_ids
+            .iter()
+            .copied()
+            .chain(req.marked.iter().map(|marked| marked.id)),
+    )
+    .await;
+    let mut assignment = match assignment {
+        Ok(assignment) => assignment,
+        Err(response) => {
+            let _ = tx.rollback().await;
+            return response;
+        }
+    };
+
+    // 限时任务截断到Worker扫描到的位置，没有扫描到的范围退回
+    let mut unscanned = None;
+    if let Some(scanned_to) = req.scanned_to {
+        if let Err(message) = check_scanned_to(&assignment, scanned_to, &req) {
+            warn!("拒绝任务 {} 的提交: {}", req.task_id, message);
+            let _ = tx.rollback().await;
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                axum::Json(api_error(message).with_code(ErrorCode::InvalidRequest)),
+            );
+        }
+        if scanned_to < assignment.end_id {
+            let trimmed = timed_sql(trim_timed_task(
+                &mut tx,
+                req.task_id,
+                scanned_to,
+                assignment.end_id,
+            ))
+            .await;
+            match trimmed {
+                Ok(trimmed) => {
+                    info!(
+                        "限时任务 {} 扫描到 {}，范围 [{}, {}] 已退回",
+                        req.task_id,
+                        scanned_to,
+                        scanned_to + 1,
+                        assignment.end_id
+                    );
+                    unscanned = Some((assignment.end_id - scanned_to, trimmed));
+                    assignment.end_id = scanned_to;
+                }
+                Err(e) => {
+                    error!("截断限时任务 {} 失败: {}", req.task_id, e);
+                    let _ = tx.rollback().await;
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        axum::Json(api_error(format!("数据库错误: {}", e))),
+                    );
+                }
+            }
+        }
+    }
+
+    // 3. 批量写入valid_ids
+    if !req.valid_ids.is_empty() {
+        for id in &req.valid_ids {
+            // 使用INSERT OR IGNORE避免重复
+            let result = timed_sql(results::insert(&mut tx, *id, None)).await;
+
+            if let Err(e) = result {
+                error!("插入有效ID {} 失败: {}", id, e);
+                let _ = tx.rollback().await;
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    axum::Json(api_error(format!("插入错误: {}", e))),
+                );
+            }
+        }
+    }
+
+    // 记录命中响应标记的无效ID
+    for entry in &req.marked {
+        let result = timed_sql(marked::record(
+            &mut tx,
+            entry.id,
+            &entry.label,
+            req.task_id,
+            &req.worker_id,
+        ))
+        .await;
+
+        if let Err(e) = result {
+            error!("记录ID {} 的响应标记失败: {}", entry.id, e);
+            let _ = tx.rollback().await;
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(api_error(format!("插入错误: {}", e))),
+            );
+        }
+    }
+
+    // 4. 将任务归档，记录该范围已扫描完成和从分配到提交的耗时（没有带回租约时不记录）
+    let elapsed_ms = chrono::Utc::now().timestamp_millis() - req.lease;
+    let result = timed_sql(
+        sqlx::query(
+            r#"
+            INSERT INTO task_archive (task_id, start_id, end_id, worker_id, valid_count, created_at, list_task, unknown_count, blocked_count, duration_ms)
+            SELECT task_id, start_id, end_id, worker_id, ?, created_at, list_task, ?, ?, ?
+            FROM task_queue WHERE task_id = ?
+            "#,
+        )
+        .bind(req.valid_ids.len() as i64)
+        .bind(req.coverage.map_or(0, |coverage| coverage.unknown as i64))
+        .bind(req.coverage.map_or(0, |coverage| coverage.blocked as i64))
+        .bind((req.lease > 0 && elapsed_ms >= 0).then_some(elapsed_ms))
+        .bind(req.task_id)
+        .execute(&mut *tx),
+    )
+    .await;
+
+    if let Err(e) = result {
+        error!("归档任务 {} 失败: {}", req.task_id, e);
+        let _ = tx.rollback().await;
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(api_error(format!("归档错误: {}", e))),
+        );
+    }
+
+    // 5. 复查任务写回复查结果；显式ID列表任务记录候选ID已完成（不是连续的范围，不做可疑检查）；
+    //    普通任务检查是否可疑，可疑时为该范围创建复查任务
+    let submission = Submission {
+        task_id: req.task_id,
+        start_id: assignment.start_id,
+        end_id: assignment.end_id,
+        worker_id: &req.worker_id,
+        valid_count: req.valid_ids.len() as i64,
+        elapsed_ms,
+        coverage: req.coverage,
+    };
+    let result = match assignment.verification_id {
+        Some(verification_id) => timed_sql(anomaly::complete(
+            &mut tx,
+            verification_id,
+            submission.valid_count,
+        ))
+        .await
+        .map(|_| None),
+        None if assignment.is_list() => timed_sql(candidates::complete(&mut tx, req.task_id))
+            .await
+            .map(|_| None),
+        None => flag_if_suspicious(&state, &mut tx, &submission).await,
+    };
+
+    let verify_task = match result {
+        Ok(task) => task,
+        Err(e) => {
+            error!("检查任务 {} 的提交失败: {}", req.task_id, e);
+            let _ = tx.rollback().await;
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(api_error(format!("数据库错误: {}", e))),
+            );
+        }
+    };
+
+    // 6. 从task_queue删除任务
+    let result = timed_sql(
+        sqlx::query("DELETE FROM task_queue WHERE task_id = ?")
+            .bind(req.task_id)
+            .execute(&mut *tx),
+    )
+    .await;
+
+    if let Err(e) = result {
+        error!("删除任务 {} 失败: {}", req.task_id, e);
+        let _ = tx.rollback().await;
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(api_error(format!("删除错误: {}", e))),
+        );
+    }
+
+    // 7. 记录带幂等键的提交结果，供Worker重试时返回
+    let message = "任务提交成功".to_string();
+    if let Some(key) = &req.idempotency_key {
+        let result = timed_sql(
+            sqlx::query(
+                "INSERT INTO submit_outcomes (idempotency_key, task_id, message) VALUES (?, ?, ?)",
+            )
+            .bind(key)
+            .bind(req.task_id)
+            .bind(&message)
+            .execute(&mut *tx),
+        )
+        .await;
+
+        if let Err(e) = result {
+            error!("记录任务 {} 的提交结果失败: {}", req.task_id, e);
+            let _ = tx.rollback().await;
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(api_error(format!("数据库错误: {}", e))),
+            );
+        }
+    }
+
+    // 8. 累加到本分钟的扫描历史
+    let result = timed_sql(history::record(
+        &mut tx,
+        assignment.id_count(),
+        req.valid_ids.len() as i64,
+        req.latency.as_ref(),
+    ))
+    .await;
+
+    if let Err(e) = result {
+        error!("记录任务 {} 的扫描历史失败: {}", req.task_id, e);
+        let _ = tx.rollback().await;
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(api_error(format!("数据库错误: {}", e))),
+        );
+    }
+
+    // 提交事务
+    if let Err(e) = timed_sql(tx.commit()).await {
+        error!("提交事务失败: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(api_error(format!("提交错误: {}", e))),
+        );
+    }
+
+    state.running.remove(req.task_id).await;
+    // 按任务的ID数和耗时记录Worker的速度（显式ID列表任务的耗时主要取决于候选ID的分布，不计入）。
+    // 没有历史时的第一个样本（校准批次）即使很快完成也计入，否则快的Worker会一直领到校准批次
+    let sample = elapsed_ms >= speed::MIN_SAMPLE_MS
+        || (elapsed_ms > 0 && state.speeds.get(&req.worker_id).is_none());
+    if !assignment.is_list() && req.lease > 0 && sample {
+        let measured = assignment.id_count() as f64 * 1000.0 / elapsed_ms as f64;
+        let recorded = state
+            .speeds
+            .record(&state.db_pool, &req.worker_id, measured);
+        if let Err(e) = timed_sql(recorded).await {
+            error!("记录Worker {} 的速度失败: {}", req.worker_id, e);
+        }
+    }
+    if let Some((returned, (key_id, pending))) = unscanned {
+        if let Some(pending) = pending {
+            state.running.insert(pending).await;
+        }
+        // 分配时按预留的范围计入了API Key的用量，退回没有扫描的部分
+        if let Some(key_id) = key_id {
+            let refunded =
+                timed_sql(api_keys::record_usage(&state.db_pool, key_id, -returned)).await;
+            if let Err(e) = refunded {
+                error!("退回API Key {} 的用量失败: {}", key_id, e);
+            }
+        }
+    }
+    if let Some(latency) = req.latency {
+        state.metrics.record_upstream_latency(latency);
+    }
+    if let Some(task) = verify_task {
+        state.running.insert(task).await;
+    }
+    if state.spot_checker.is_some() {
+        spot_check::spawn_spot_check(
+            Arc::clone(&state),
+            SpotCheck {
+                task_id: req.task_id,
+                worker_id: req.worker_id.clone(),
+                start_id: assignment.start_id,
+                end_id: assignment.end_id,
+                ids: assignment.ids,
+                valid_ids: req.valid_ids.clone(),
+            },
+        );
+    }
+
+    info!(
+        "任务 {} 提交成功，发现 {} 个有效ID",
+        req.task_id,
+        req.valid_ids.len()
+    );
+    (StatusCode::OK, axum::Json(ApiResponse::success(message)))
+}
+
+/// 提前上报已发现的有效ID（任务仍在执行）
+/// POST /task/results
+///
+/// Worker中途崩溃时已发现的结果不会丢失；任务完成时的提交会再次写入全部结果（重复的ID被忽略）。
+async fn submit_partial(
+    State(state): State<Arc<AppState>>,
+    ValidJson(req): ValidJson<PartialResultRequest>,
+) -> (StatusCode, axum::Json<ApiResponse<String>>) {
+    let mut tx = match timed_sql(state.db_pool.begin()).await {
+        Ok(t) => t,
+        Err(e) => {
+            error!("启动事务失败: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(api_error(format!("事务错误: {}", e))),
+            );
+        }
+    };
+
+    // 1. 与完整提交相同的归属和签名检查
+    let assignment = check_assignment(
+        &state,
+        &mut tx,
+        req.task_id,
+        &req.worker_id,
+        req.lease,
+        &req.signature,
+        req.valid_ids.iter().copied(),
+    )
+    .await;
+    if let Err(response) = assignment {
+        let _ = tx.rollback().await;
+        return response;
+    }
+
+    // 2. 写入有效ID
+    for id in &req.valid_ids {
+        let result = timed_sql(results::insert(&mut tx, *id, None)).await;
+
+        if let Err(e) = result {
+            error!("插入有效ID {} 失败: {}", id, e);
+            let _ = tx.rollback().await;
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(api_error(format!("插入错误: {}", e))),
+            );
+        }
+    }
+
+    if let Err(e) = timed_sql(tx.commit()).await {
+        error!("提交事务失败: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(api_error(format!("提交错误: {}", e))),
+        );
+    }
 
-/// 提交结果
-/// POST /task/submit
-async fn submit_result(
-    State(state): State<Arc<AppState>>,
-    axum::Json(req): axum::Json<SubmitResultRequest>,
-) -> (StatusCode, axum::Json<ApiResponse<String>>) {
     info!(
-        "Worker提交任务 {} 的结果，发现有效ID数: {}",
+        "Worker提前上报任务 {} 的 {} 个有效ID",
         req.task_id,
         req.valid_ids.len()
     );
+    (
+        StatusCode::OK,
+        axum::Json(ApiResponse::success(format!(
+            "已接收 {} 个有效ID",
+            req.valid_ids.len()
+        ))),
+    )
+}
+
+/// 保存有效ID的原始响应（原始响应存档模式，任务仍在执行）
+///
+/// 与提前上报相同，先确认任务仍属于该Worker并校验签名，上传的ID必须在任务范围内。
+async fn archive_responses(
+    State(state): State<Arc<AppState>>,
+    ValidJson(req): ValidJson<ArchiveResponsesRequest>,
+) -> (StatusCode, axum::Json<ApiResponse<String>>) {
+    let Some(archive) = &state.response_archive else {
+        return (
+            StatusCode::NOT_FOUND,
+            axum::Json(
+                api_error("Master没有启用原始响应存档".to_string()).with_code(ErrorCode::NotFound),
+            ),
+        );
+    };
+
+    let mut decoded = Vec::with_capacity(req.responses.len());
+    for response in &req.responses {
+        match response_archive::decode(&response.body) {
+            Ok(decoded_response) => decoded.push((response.id, decoded_response)),
+            Err(e) => {
+                return (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    axum::Json(
+                        api_error(format!("ID {} 的{}", response.id, e))
+                            .with_code(ErrorCode::InvalidRequest),
+                    ),
+                )
+            }
+        }
+    }
 
-    // 使用事务：写入结果 + 删除任务
-    let mut tx = match state.db_pool.begin().await {
+    let mut tx = match timed_sql(state.db_pool.begin()).await {
         Ok(t) => t,
         Err(e) => {
             error!("启动事务失败: {}", e);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                axum::Json(ApiResponse::error(format!("事务错误: {}", e))),
+                axum::Json(api_error(format!("事务错误: {}", e))),
             );
         }
     };
 
-    // 1. 批量写入valid_ids
-    if !req.valid_ids.is_empty() {
-        for id in &req.valid_ids {
-            // 使用INSERT OR IGNORE避免重复
-            let result = sqlx::query("INSERT OR IGNORE INTO valid_results (id) VALUES (?)")
-                .bind(id)
-                .execute(&mut *tx)
-                .await;
+    // 1. 与完整提交相同的归属和签名检查
+    let assignment = check_assignment(
+        &state,
+        &mut tx,
+        req.task_id,
+        &req.worker_id,
+        req.lease,
+        &req.signature,
+        decoded.iter().map(|(id, _)| *id),
+    )
+    .await;
+    if let Err(response) = assignment {
+        let _ = tx.rollback().await;
+        return response;
+    }
 
-            if let Err(e) = result {
-                error!("插入有效ID {} 失败: {}", id, e);
+    // 2. 写入存档（内容相同的响应只写一次）并记录位置
+    let mut written = 0;
+    for (id, response) in &decoded {
+        let key = match archive.put(&mut tx, response).await {
+            Ok((key, stored)) => {
+                if stored {
+                    written += 1;
+                }
+                key
+            }
+            Err(e) => {
+                error!("写入ID {} 的原始响应失败: {}", id, e);
                 let _ = tx.rollback().await;
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    axum::Json(ApiResponse::error(format!("插入错误: {}", e))),
+                    axum::Json(api_error(format!("存档错误: {}", e))),
                 );
             }
-        }
-    }
-
-    // 2. 从task_queue删除任务
-    let result = sqlx::query("DELETE FROM task_queue WHERE task_id = ?")
-        .bind(req.task_id)
-        .execute(&mut *tx)
+        };
+        let result = timed_sql(response_archive::record(
+            &mut tx,
+            *id,
+            &key,
+            response,
+            req.task_id,
+            &req.worker_id,
+        ))
         .await;
 
-    if let Err(e) = result {
-        error!("删除任务 {} 失败: {}", req.task_id, e);
-        let _ = tx.rollback().await;
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            axum::Json(ApiResponse::error(format!("删除错误: {}", e))),
-        );
+        if let Err(e) = result {
+            error!("记录ID {} 的原始响应失败: {}", id, e);
+            let _ = tx.rollback().await;
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(api_error(format!("插入错误: {}", e))),
+            );
+        }
     }
 
-    // 提交事务
-    if let Err(e) = tx.commit().await {
+    if let Err(e) = timed_sql(tx.commit()).await {
         error!("提交事务失败: {}", e);
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
-            axum::Json(ApiResponse::error(format!("提交错误: {}", e))),
+            axum::Json(api_error(format!("提交错误: {}", e))),
         );
     }
 
     info!(
-        "任务 {} 提交成功，发现 {} 个有效ID",
+        "已存档任务 {} 的 {} 个原始响应（新写入 {} 个）",
         req.task_id,
-        req.valid_ids.len()
+        decoded.len(),
+        written
     );
     (
         StatusCode::OK,
-        axum::Json(ApiResponse::success("任务提交成功".to_string())),
+        axum::Json(ApiResponse::success(format!(
+            "已存档 {} 个原始响应，其中 {} 个与已有内容相同",
+            decoded.len(),
+            decoded.len() - written
+        ))),
+    )
+}
+
+/// 数据库中记录的任务分配
+struct Assignment {
+    start_id: i64,
+    end_id: i64,
+    verification_id: Option<i64>,
+    /// 是否为限时任务（end_id 只是预留的上限）
+    timed: bool,
+    /// 显式ID列表任务分配到的候选ID（普通任务为空）
+    ids: Vec<i64>,
+}
+
+impl Assignment {
+    fn is_list(&self) -> bool {
+        !self.ids.is_empty()
+    }
+
+    /// 任务要扫描的ID数
+    fn id_count(&self) -> i64 {
+        if self.is_list() {
+            self.ids.len() as i64
+        } else {
+            self.end_id - self.start_id + 1
+        }
+    }
+}
+
+/// 确认任务仍属于该Worker，并按数据库中的任务范围校验分配签名
+///
+/// 上报的ID（有效ID和命中标记的ID）必须在任务范围内，显式ID列表任务还要求都在分配给它的候选ID中。
+/// 失败时返回对应的错误响应，由调用方回滚事务。
+async fn check_assignment(
+    state: &AppState,
+    conn: &mut SqliteConnection,
+    task_id: i32,
+    worker_id: &str,
+    lease: i64,
+    signature: &str,
+    reported_ids: impl IntoIterator<Item = i64>,
+) -> Result<Assignment, (StatusCode, axum::Json<ApiResponse<String>>)> {
+    let range = timed_sql(
+        sqlx::query_as::<_, (i64, i64, Option<i64>, bool, bool, String)>(
+            "SELECT start_id, end_id, verification_id, list_task != 0, scan_for_secs IS NOT NULL, worker_id FROM task_queue WHERE task_id = ?",
+        )
+        .bind(task_id)
+        .fetch_optional(&mut *conn),
+    )
+    .await;
+
+    let (start_id, end_id, verification_id, list, timed) = match range {
+        Ok(Some((start_id, end_id, verification_id, list, timed, owner))) if owner == worker_id => {
+            (start_id, end_id, verification_id, list, timed)
+        }
+        Ok(Some(_)) => {
+            warn!(
+                "任务 {} 已被重新分配，拒绝Worker {} 的提交",
+                task_id, worker_id
+            );
+            return Err(task_conflict(format!(
+                "任务 {} 已被重新分配给其他Worker",
+                task_id
+            )));
+        }
+        Ok(None) => {
+            let completed_by = timed_sql(
+                sqlx::query_scalar::<_, String>(
+                    "SELECT worker_id FROM task_archive WHERE task_id = ?",
+                )
+                .bind(task_id)
+                .fetch_optional(&mut *conn),
+            )
+            .await;
+
+            return Err(match completed_by {
+                Ok(Some(completed_by)) => {
+                    warn!(
+                        "任务 {} 已由Worker {} 完成，拒绝Worker {} 的提交",
+                        task_id, completed_by, worker_id
+                    );
+                    task_conflict(format!("任务 {} 已完成", task_id))
+                }
+                Ok(None) => {
+                    warn!("任务 {} 不存在，拒绝提交", task_id);
+                    (
+                        StatusCode::NOT_FOUND,
+                        axum::Json(
+                            api_error(format!("任务 {} 不存在", task_id))
+                                .with_code(ErrorCode::NotFound),
+                        ),
+                    )
+                }
+                Err(e) => {
+                    error!("查询任务 {} 的归档失败: {}", task_id, e);
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        axum::Json(api_error(format!("数据库错误: {}", e))),
+                    )
+                }
+            });
+        }
+        Err(e) => {
+            error!("查询任务 {} 失败: {}", task_id, e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(api_error(format!("数据库错误: {}", e))),
+            ));
+        }
+    };
+
+    if !state
+        .signer
+        .verify(task_id, start_id, end_id, worker_id, lease, signature)
+    {
+        warn!(
+            "任务 {} 的分配签名无效，拒绝Worker {} 的提交",
+            task_id, worker_id
+        );
+        return Err((
+            StatusCode::FORBIDDEN,
+            axum::Json(
+                api_error("任务分配签名无效".to_string()).with_code(ErrorCode::InvalidSignature),
+            ),
+        ));
+    }
+
+    let ids = if list {
+        match timed_sql(candidates::of_task(&mut *conn, task_id)).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                error!("查询任务 {} 的候选ID失败: {}", task_id, e);
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    axum::Json(api_error(format!("数据库错误: {}", e))),
+                ));
+            }
+        }
+    } else {
+        Vec::new()
+    };
+    if list {
+        let assigned: HashSet<i64> = ids.iter().copied().collect();
+        if let Some(id) = reported_ids.into_iter().find(|id| !assigned.contains(id)) {
+            warn!(
+                "Worker {} 上报的ID {} 不在任务 {} 的ID列表中",
+                worker_id, id, task_id
+            );
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                axum::Json(
+                    api_error(format!("上报的ID {} 不在任务的ID列表中", id))
+                        .with_code(ErrorCode::InvalidRequest),
+                ),
+            ));
+        }
+    } else if let Some(id) = reported_ids
+        .into_iter()
+        .find(|id| *id < start_id || *id > end_id)
+    {
+        warn!(
+            "Worker {} 上报的ID {} 不在任务 {} 的范围 [{}, {}] 内",
+            worker_id, id, task_id, start_id, end_id
+        );
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            axum::Json(
+                api_error(format!("上报的ID {} 不在任务范围内", id))
+                    .with_code(ErrorCode::InvalidRequest),
+            ),
+        ));
+    }
+
+    Ok(Assignment {
+        start_id,
+        end_id,
+        verification_id,
+        timed,
+        ids,
+    })
+}
+
+/// 检查限时任务上报的扫描位置：必须在任务范围内，上报的ID不能超过该位置
+fn check_scanned_to(
+    assignment: &Assignment,
+    scanned_to: i64,
+    req: &SubmitResultRequest,
+) -> Result<(), String> {
+    if !assignment.timed {
+        return Err(format!(
+            "任务 {} 不是限时任务，不能上报扫描位置",
+            req.task_id
+        ));
+    }
+    if scanned_to < assignment.start_id || scanned_to > assignment.end_id {
+        return Err(format!(
+            "扫描位置 {} 不在任务范围 [{}, {}] 内",
+            scanned_to, assignment.start_id, assignment.end_id
+        ));
+    }
+    let reported = req
+        .valid_ids
+        .iter()
+        .copied()
+        .chain(req.marked.iter().map(|marked| marked.id));
+    match reported.filter(|id| *id > scanned_to).max() {
+        Some(id) => Err(format!("上报的ID {} 超过了扫描位置 {}", id, scanned_to)),
+        None => Ok(()),
+    }
+}
+
+/// 把限时任务截断为 [start_id, scanned_to]，退回 [scanned_to + 1, end_id]
+///
+/// 返回任务的API Key和退回时新建的待分配任务（回退了游标时为 None）。
+async fn trim_timed_task(
+    conn: &mut SqliteConnection,
+    task_id: i32,
+    scanned_to: i64,
+    end_id: i64,
+) -> Result<(Option<i64>, Option<RunningTask>), sqlx::Error> {
+    let key_id: Option<i64> =
+        sqlx::query_scalar("UPDATE task_queue SET end_id = ? WHERE task_id = ? RETURNING key_id")
+            .bind(scanned_to)
+            .bind(task_id)
+            .fetch_one(&mut *conn)
+            .await?;
+    let pending = queue::return_unscanned(conn, scanned_to + 1, end_id).await?;
+    Ok((key_id, pending))
+}
+
+/// 提交的任务已不属于该Worker时的 409 响应
+fn task_conflict(message: String) -> (StatusCode, axum::Json<ApiResponse<String>>) {
+    (
+        StatusCode::CONFLICT,
+        axum::Json(api_error(message).with_code(ErrorCode::TaskConflict)),
     )
 }
 
+/// 按幂等键查找已处理过的提交，返回其任务ID和结果
+async fn find_submit_outcome(
+    conn: &mut SqliteConnection,
+    key: &str,
+) -> Result<Option<(i32, String)>, sqlx::Error> {
+    sqlx::query_as("SELECT task_id, message FROM submit_outcomes WHERE idempotency_key = ?")
+        .bind(key)
+        .fetch_optional(conn)
+        .await
+}
+
+/// 检查提交是否可疑，可疑时记入复查队列，返回新建的复查任务
+async fn flag_if_suspicious(
+    state: &AppState,
+    conn: &mut SqliteConnection,
+    submission: &Submission<'_>,
+) -> Result<Option<RunningTask>, sqlx::Error> {
+    let Some(reason) = timed_sql(state.anomaly.check(&mut *conn, submission)).await? else {
+        return Ok(None);
+    };
+
+    let task = timed_sql(anomaly::enqueue(&mut *conn, submission, &reason)).await?;
+    Metrics::incr(&state.metrics.submissions_flagged);
+    warn!(
+        "任务 {} (Worker {}) 的提交可疑: {}，已创建复查任务 {}",
+        submission.task_id, submission.worker_id, reason, task.task_id
+    );
+    Ok(Some(task))
+}
+
 /// 释放任务（Worker主动放弃任务）
 /// POST /task/release
 async fn release_task(
     State(state): State<Arc<AppState>>,
-    axum::Json(req): axum::Json<ReleaseTaskRequest>,
+    ValidJson(req): ValidJson<ReleaseTaskRequest>,
 ) -> (StatusCode, axum::Json<ApiResponse<String>>) {
-    info!(
-        "Worker {} 请求释放任务 {}",
-        req.worker_id, req.task_id
-    );
+    info!("Worker {} 请求释放任务 {}", req.worker_id, req.task_id);
 
-    // 将任务的 last_heartbeat 设置为很早的时间，使其立即可被其他 worker 获取
-    let result = sqlx::query(
-        "UPDATE task_queue SET last_heartbeat = datetime('now', '-120 seconds') WHERE task_id = ? AND worker_id = ?"
+    // 使租约立即失效，任务可以马上被其他 worker 获取
+    let result = timed_sql(
+        sqlx::query(
+            "UPDATE task_queue SET lease_expires_at = 0 WHERE task_id = ? AND worker_id = ?",
+        )
+        .bind(req.task_id)
+        .bind(&req.worker_id)
+        .execute(&state.db_pool),
     )
-    .bind(req.task_id)
-    .bind(&req.worker_id)
-    .execute(&state.db_pool)
     .await;
 
     match result {
         Ok(res) => {
             if res.rows_affected() > 0 {
+                state.running.expire(req.task_id, &req.worker_id).await;
                 info!("任务 {} 已释放，可被其他Worker获取", req.task_id);
                 (
                     StatusCode::OK,
@@ -383,7 +2414,10 @@ async fn release_task(
                 warn!("任务 {} 不存在或Worker不匹配", req.task_id);
                 (
                     StatusCode::NOT_FOUND,
-                    axum::Json(ApiResponse::error("任务不存在或Worker不匹配".to_string())),
+                    axum::Json(
+                        api_error("任务不存在或Worker不匹配".to_string())
+                            .with_code(ErrorCode::NotFound),
+                    ),
                 )
             }
         }
@@ -391,93 +2425,345 @@ async fn release_task(
             error!("释放任务失败: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                axum::Json(ApiResponse::error(format!("数据库错误: {}", e))),
+                axum::Json(api_error(format!("数据库错误: {}", e))),
             )
         }
     }
 }
 
-/// 计算batch_size（基于last_performance）
-/// 公式: size = last_performance * 30 (期望运行30秒)
-/// 约束: 1000 <= size <= 50000
-fn calculate_batch_size(last_performance: Option<u32>) -> i64 {
-    const MIN_SIZE: i64 = 1000;
-    const MAX_SIZE: i64 = 50000;
-    const EXPECTED_RUNTIME_SECS: i64 = 30;
+/// 构造失败响应，附带当前请求的关联ID
+fn api_error<T>(msg: String) -> ApiResponse<T> {
+    ApiResponse::error(msg).with_request_id(request_id::current())
+}
+
+/// 构造需要稍后重试的失败响应（429/503），`Retry-After` 按整秒向上取整
+fn retry_later<T: Serialize>(status: StatusCode, wait: Duration, body: ApiResponse<T>) -> Response {
+    let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    (
+        status,
+        [(RETRY_AFTER, retry_after.to_string())],
+        axum::Json(body),
+    )
+        .into_response()
+}
+
+/// 被封禁的Worker的 403 响应
+fn banned<T>() -> (StatusCode, axum::Json<ApiResponse<T>>) {
+    (
+        StatusCode::FORBIDDEN,
+        axum::Json(api_error("Worker已被封禁".to_string()).with_code(ErrorCode::Banned)),
+    )
+}
+
+/// 路由存在但不支持该请求方法
+async fn method_not_allowed() -> (StatusCode, axum::Json<ApiResponse<()>>) {
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        axum::Json(api_error("不支持的请求方法".to_string()).with_code(ErrorCode::InvalidRequest)),
+    )
+}
+
+/// 未匹配任何路由的请求
+async fn not_found() -> (StatusCode, axum::Json<ApiResponse<()>>) {
+    (
+        StatusCode::NOT_FOUND,
+        axum::Json(api_error("接口不存在".to_string()).with_code(ErrorCode::NotFound)),
+    )
+}
+
+/// 存活检查（进程在运行即返回200）
+/// GET /healthz
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// 就绪检查（检查数据库连接和主备状态，并报告任务分发是否暂停）
+/// GET /readyz
+async fn readyz(
+    State(state): State<Arc<AppState>>,
+) -> (StatusCode, axum::Json<ApiResponse<ReadinessStatus>>) {
+    let database = match sqlx::query("SELECT 1").execute(&state.db_pool).await {
+        Ok(_) => true,
+        Err(e) => {
+            error!("就绪检查: 数据库不可用: {}", e);
+            false
+        }
+    };
+
+    let status = ReadinessStatus {
+        database,
+        dispatch_paused: state.dispatch_paused.load(Ordering::Relaxed),
+        maintenance: state.maintenance.is_active(),
+        leader: state.leadership.is_leader(),
+        running_tasks: state.running.len().await,
+    };
+
+    let code = if database && status.leader {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, axum::Json(ApiResponse::success(status)))
+}
+
+/// 运行指标
+/// GET /metrics
+async fn metrics_handler(State(state): State<Arc<AppState>>) -> String {
+    let read_pool = (state.config.db_read_connections > 0).then_some(&state.read_pool);
+    state.metrics.render(&state.db_pool, read_pool)
+}
 
-    let base_speed = last_performance.unwrap_or(100) as i64; // 默认100 req/s
-    let size = base_speed * EXPECTED_RUNTIME_SECS;
+/// 计算batch_size（基于Worker的速度）
+/// 公式: size = speed * target_runtime_secs
+/// 约束: min_batch_size <= size <= max_batch_size
+fn calculate_batch_size(settings: &BatchSettings, speed: Option<u32>) -> i64 {
+    let base_speed = speed.map(i64::from).unwrap_or(settings.default_speed);
+    let size = base_speed.saturating_mul(settings.target_runtime_secs);
 
-    size.clamp(MIN_SIZE, MAX_SIZE)
+    size.clamp(settings.min_batch_size, settings.max_batch_size)
 }
 
 /// 尝试获取任务
-/// 1. 优先查找超时任务（last_heartbeat > 60秒）
+/// 1. 优先认领超时任务（在内存视图中查找租约已到期的任务）
 /// 2. 如果没有超时任务，从global_cursor切分新范围
+///
+/// `timed` 为限时任务的 (预留ID数, 扫描秒数)，只用于从游标切分的新范围；None 时按 batch_size 切分。
 async fn try_acquire_task(
+    state: &AppState,
+    worker_id: &str,
+    key_id: Option<i64>,
+    batch_size: i64,
+    max_batch_size: Option<i64>,
+    kinds: TaskKinds,
+    timed: Option<(i64, u64)>,
+) -> Result<Option<AcquireTaskResponse>, sqlx::Error> {
+    // 如果找到超时任务（类型允许，过大时按Worker的批次大小拆分），分配给当前Worker
+    let claimed = state
+        .running
+        .claim_timed_out(worker_id, batch_size, max_batch_size, kinds)
+        .await;
+    if let Some(claim) = claimed {
+        let task = &claim.previous;
+        warn!(
+            "发现超时任务 {}: 原worker={}, 现在重新分配给worker {}",
+            task.task_id, task.worker_id, worker_id
+        );
+
+        match reassign_task(&state.db_pool, worker_id, key_id, &claim, batch_size).await {
+            Ok(Some(pending)) => {
+                Metrics::incr(&state.metrics.tasks_reassigned);
+                if claim.is_split() {
+                    info!(
+                        "超时任务 {} 按批次大小 {} 拆分: 分配 [{}, {}]，其余 [{}, {}] 拆为 {} 个待分配任务",
+                        task.task_id,
+                        batch_size,
+                        task.start_id,
+                        claim.end_id,
+                        claim.end_id + 1,
+                        task.end_id,
+                        pending.len()
+                    );
+                }
+                for pending in pending {
+                    state.running.insert(pending).await;
+                    Metrics::incr(&state.metrics.tasks_split);
+                }
+                let mut assignment = sign_assignment(
+                    &state.signer,
+                    worker_id,
+                    task.task_id,
+                    task.start_id,
+                    claim.end_id,
+                );
+                if task.list {
+                    assignment.ids = candidates::of_task(&state.db_pool, task.task_id).await?;
+                }
+                return Ok(Some(assignment));
+            }
+            Ok(None) => {
+                // 数据库中任务已不存在或已被修改，内存视图过期，重新同步
+                warn!("任务 {} 在数据库中已变更，重新同步内存视图", task.task_id);
+                state.running.remove(task.task_id).await;
+                state.running.sync_from_db(&state.db_pool).await?;
+            }
+            Err(e) => {
+                state.running.restore(claim.previous).await;
+                return Err(e);
+            }
+        }
+    }
+
+    if !kinds.normal {
+        return Ok(None);
+    }
+
+    // 有尚未分配的候选ID时先分配显式ID列表任务
+    if kinds.list {
+        let task =
+            acquire_candidate_task(&state.db_pool, &state.signer, worker_id, key_id, batch_size)
+                .await?;
+        if let Some(task) = task {
+            state
+                .running
+                .insert(RunningTask {
+                    task_id: task.task_id,
+                    start_id: task.start_id,
+                    end_id: task.end_id,
+                    worker_id: worker_id.to_string(),
+                    lease_expires: Some(Instant::now() + TASK_TIMEOUT),
+                    verification: false,
+                    list: true,
+                    speed: None,
+                    stalled_since: None,
+                })
+                .await;
+            return Ok(Some(task));
+        }
+    }
+
+    // 从global_cursor切分新范围（list 类型的扫描只扫描候选ID，不分配新范围）
+    if !state.id_kind.is_sequential() {
+        return Ok(None);
+    }
+    let (batch_size, scan_for_secs) = match timed {
+        Some((reserve, secs)) => (reserve, Some(secs)),
+        None => (batch_size, None),
+    };
+    let task = acquire_new_task(state, worker_id, key_id, batch_size, scan_for_secs).await?;
+    if let Some(task) = &task {
+        state
+            .running
+            .insert(RunningTask {
+                task_id: task.task_id,
+                start_id: task.start_id,
+                end_id: task.end_id,
+                worker_id: worker_id.to_string(),
+                lease_expires: Some(Instant::now() + TASK_TIMEOUT),
+                verification: false,
+                list: false,
+                speed: None,
+                stalled_since: None,
+            })
+            .await;
+    }
+
+    Ok(task)
+}
+
+/// 把认领的超时任务写回数据库：更新归属，拆分时截断范围并把其余部分按 batch_size 插入为待分配任务
+///
+/// 以原worker_id为条件，防止覆盖并发修改；任务已变更时返回 None。
+/// 重新分配的限时任务按普通任务扫描整个范围。
+async fn reassign_task(
     pool: &SqlitePool,
     worker_id: &str,
+    key_id: Option<i64>,
+    claim: &Claim,
+    batch_size: i64,
+) -> Result<Option<Vec<RunningTask>>, sqlx::Error> {
+    let task = &claim.previous;
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        "UPDATE task_queue SET worker_id = ?, key_id = ?, status = 'running', last_heartbeat = datetime('now'), lease_expires_at = ?, scan_for_secs = NULL, end_id = ? WHERE task_id = ? AND worker_id = ?"
+    )
+    .bind(worker_id)
+    .bind(key_id)
+    .bind(running::lease_expiry_millis())
+    .bind(claim.end_id)
+    .bind(task.task_id)
+    .bind(&task.worker_id)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        tx.rollback().await?;
+        return Ok(None);
+    }
+
+    let mut pending = Vec::new();
+    if claim.is_split() {
+        for (start_id, end_id) in queue::split_range(claim.end_id + 1, task.end_id, batch_size) {
+            let task_id = queue::insert_pending(&mut tx, start_id, end_id).await?;
+            pending.push(RunningTask {
+                task_id,
+                start_id,
+                end_id,
+                worker_id: String::new(),
+                lease_expires: None,
+                verification: false,
+                list: false,
+                speed: None,
+                stalled_since: None,
+            });
+        }
+    }
+
+    tx.commit().await?;
+    Ok(Some(pending))
+}
+
+/// 取出至多 batch_size 个尚未分配的候选ID，创建显式ID列表任务
+async fn acquire_candidate_task(
+    pool: &SqlitePool,
+    signer: &AssignmentSigner,
+    worker_id: &str,
+    key_id: Option<i64>,
     batch_size: i64,
 ) -> Result<Option<AcquireTaskResponse>, sqlx::Error> {
-    // 开启事务，确保 FOR UPDATE SKIP LOCKED 能正常工作
     let mut tx = pool.begin().await?;
 
-    // 查找超时任务（60秒未更新心跳）
-    // 使用SQLite内置函数datetime计算超时时间，确保时间格式一致
-    // CURRENT_TIMESTAMP和datetime都使用SQLite的UTC时间
-    let timeout_task = sqlx::query_as::<_, TaskRecord>(
+    let ids = candidates::pending_batch(&mut tx, batch_size).await?;
+    let (Some(&start_id), Some(&end_id)) = (ids.first(), ids.last()) else {
+        return Ok(None);
+    };
+
+    let task_id: i32 = sqlx::query_scalar(
         r#"
-        SELECT task_id, start_id, end_id, worker_id, status, last_heartbeat, created_at
-        FROM task_queue
-        WHERE last_heartbeat < datetime('now', '-60 seconds')
-        ORDER BY last_heartbeat ASC
-        LIMIT 1
+        INSERT INTO task_queue
+            (start_id, end_id, worker_id, key_id, status, last_heartbeat, lease_expires_at, list_task)
+        VALUES (?, ?, ?, ?, 'running', datetime('now'), ?, 1)
+        RETURNING task_id
         "#,
     )
-    .fetch_optional(&mut *tx)
+    .bind(start_id)
+    .bind(end_id)
+    .bind(worker_id)
+    .bind(key_id)
+    .bind(running::lease_expiry_millis())
+    .fetch_one(&mut *tx)
     .await?;
 
-    // 如果找到超时任务，分配给当前Worker
-    if let Some(task) = timeout_task {
-        warn!(
-            "发现超时任务 {}: 原worker={}, last_heartbeat={}, 现在重新分配给worker {}",
-            task.task_id, task.worker_id, task.last_heartbeat, worker_id
-        );
-
-        // 更新任务的worker_id和heartbeat
-        sqlx::query(
-            "UPDATE task_queue SET worker_id = ?, last_heartbeat = datetime('now') WHERE task_id = ?"
-        )
-        .bind(worker_id)
-        .bind(task.task_id)
-        .execute(&mut *tx)
-        .await?;
-
-        // 提交事务
-        tx.commit().await?;
+    // 候选ID按顺序取出，范围内尚未分配的正好是这一批
+    candidates::assign(&mut tx, task_id, start_id, end_id).await?;
 
-        return Ok(Some(AcquireTaskResponse {
-            task_id: task.task_id,
-            start_id: task.start_id,
-            end_id: task.end_id,
-        }));
-    }
+    tx.commit().await?;
 
-    // 没有超时任务，回滚事务（实际上没有任何修改，但需要结束事务）
-    tx.rollback().await?;
+    info!(
+        "创建显式ID列表任务: task_id={}, {} 个候选ID，范围=[{}, {}]",
+        task_id,
+        ids.len(),
+        start_id,
+        end_id
+    );
 
-    // 从global_cursor切分新范围
-    acquire_new_task(pool, worker_id, batch_size).await
+    let mut task = sign_assignment(signer, worker_id, task_id, start_id, end_id);
+    task.ids = ids;
+    Ok(Some(task))
 }
 
-/// 从global_cursor切分新任务
+/// 从global_cursor切分新任务，分片模式下跳过不属于本分片的块，且任务不跨块
+///
+/// `scan_for_secs` 不为 None 时创建限时任务，batch_size 为预留的ID数。
+/// 设置了 `--end-id` 时任务不超过该ID，游标到达后返回 None。
 async fn acquire_new_task(
-    pool: &SqlitePool,
+    state: &AppState,
     worker_id: &str,
+    key_id: Option<i64>,
     batch_size: i64,
+    scan_for_secs: Option<u64>,
 ) -> Result<Option<AcquireTaskResponse>, sqlx::Error> {
     // 开启事务
-    let mut tx = pool.begin().await?;
+    let mut tx = state.db_pool.begin().await?;
 
     // 锁定global_cursor行
     let cursor_row = sqlx::query_as::<_, CursorRecord>(
@@ -486,8 +2772,18 @@ async fn acquire_new_task(
     .fetch_one(&mut *tx)
     .await?;
 
-    let start_id = cursor_row.next_start_id;
-    let end_id = start_id + batch_size - 1; // 包含end_id
+    let mut start_id = cursor_row.next_start_id;
+    let mut end_id = start_id + batch_size - 1; // 包含end_id
+    if let Some(shard) = &state.shard {
+        start_id = shard.align(start_id);
+        end_id = (start_id + batch_size - 1).min(shard.block_end(start_id));
+    }
+    if let Some(limit) = state.config.end_id {
+        if start_id >= limit {
+            return Ok(None);
+        }
+        end_id = end_id.min(limit - 1);
+    }
 
     // 更新global_cursor
     sqlx::query("UPDATE global_cursor SET next_start_id = ? WHERE id = 1")
@@ -498,14 +2794,18 @@ async fn acquire_new_task(
     // 插入新任务到task_queue
     let task_id: i32 = sqlx::query_scalar(
         r#"
-        INSERT INTO task_queue (start_id, end_id, worker_id, status, last_heartbeat)
-        VALUES (?, ?, ?, 'running', datetime('now'))
+        INSERT INTO task_queue
+            (start_id, end_id, worker_id, key_id, status, last_heartbeat, lease_expires_at, scan_for_secs)
+        VALUES (?, ?, ?, ?, 'running', datetime('now'), ?, ?)
         RETURNING task_id
         "#,
     )
     .bind(start_id)
     .bind(end_id)
     .bind(worker_id)
+    .bind(key_id)
+    .bind(running::lease_expiry_millis())
+    .bind(scan_for_secs.map(|secs| secs as i64))
     .fetch_one(&mut *tx)
     .await?;
 
@@ -517,11 +2817,55 @@ async fn acquire_new_task(
         task_id, start_id, end_id
     );
 
-    Ok(Some(AcquireTaskResponse {
+    let mut task = sign_assignment(&state.signer, worker_id, task_id, start_id, end_id);
+    task.scan_for_secs = scan_for_secs;
+    Ok(Some(task))
+}
+
+/// 命令行指定的ID类型，未指定 `--id-kind` 时为 None
+fn configured_id_kind(config: &Config) -> Result<Option<IdKind>, String> {
+    let id_kind = match config.id_kind.as_deref() {
+        None => return Ok(None),
+        Some("template") => {
+            let Some(template) = config.id_template.clone() else {
+                return Err("--id-kind template 需要 --id-template".to_string());
+            };
+            IdKind::Template {
+                template,
+                width: config.id_width,
+                checksum: config.id_checksum,
+            }
+        }
+        Some("list") => IdKind::List,
+        Some(_) => IdKind::Integer,
+    };
+    id_kind.validate()?;
+    Ok(Some(id_kind))
+}
+
+/// 生成带签名的任务分配，租约标识取当前的Unix毫秒时间戳
+fn sign_assignment(
+    signer: &AssignmentSigner,
+    worker_id: &str,
+    task_id: i32,
+    start_id: i64,
+    end_id: i64,
+) -> AcquireTaskResponse {
+    let lease = chrono::Utc::now().timestamp_millis();
+    AcquireTaskResponse {
         task_id,
         start_id,
         end_id,
-    }))
+        lease,
+        signature: signer.sign(task_id, start_id, end_id, worker_id, lease),
+        suggested_concurrency: None,
+        rate_limit: None,
+        target: None,
+        id_kind: IdKind::Integer,
+        ids: Vec::new(),
+        scan_for_secs: None,
+        extra_tasks: Vec::new(),
+    }
 }
 
 /// 游标记录
@@ -531,16 +2875,3 @@ struct CursorRecord {
     id: i32,
     next_start_id: i64,
 }
-
-/// 任务记录
-#[derive(FromRow)]
-#[allow(dead_code)]
-struct TaskRecord {
-    task_id: i32,
-    start_id: i64,
-    end_id: i64,
-    worker_id: String,
-    status: String,
-    last_heartbeat: chrono::DateTime<Utc>,
-    created_at: chrono::DateTime<Utc>,
-}
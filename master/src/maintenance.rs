@@ -0,0 +1,117 @@
+//! 维护模式
+//!
+//! 迁移数据库、手动备份等操作前通过 `POST /admin/maintenance` 开启。维护期间 `/task/*` 全部返回 503、
+//! 错误码 `maintenance` 和 `Retry-After`，Worker按建议的间隔安静地等待；管理接口和只读接口照常可用。
+//!
+//! 维护期间收不到心跳，超时回收暂停；结束时把所有租约顺延维护的时长，Worker可以继续心跳和提交原来的任务。
+//! 维护状态只保存在当前节点的内存中，重启后恢复正常。
+
+use crate::{api_error, retry_later, AppState};
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use common::admin::MaintenanceStatus;
+use common::ErrorCode;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 未指定时建议Worker等待的时长
+pub const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// 一次维护
+struct Window {
+    reason: Option<String>,
+    started: Instant,
+    retry_after: Duration,
+}
+
+/// 维护模式的状态
+#[derive(Default)]
+pub struct Maintenance {
+    window: Mutex<Option<Window>>,
+}
+
+impl Maintenance {
+    /// 开启维护，已在维护中时只更新原因和建议的等待时长；返回之前是否已在维护中
+    pub fn begin(&self, reason: Option<String>, retry_after: Duration) -> bool {
+        let mut window = self.window.lock().expect("维护状态锁中毒");
+        match window.as_mut() {
+            Some(current) => {
+                current.reason = reason;
+                current.retry_after = retry_after;
+                true
+            }
+            None => {
+                *window = Some(Window {
+                    reason,
+                    started: Instant::now(),
+                    retry_after,
+                });
+                false
+            }
+        }
+    }
+
+    /// 结束维护，返回维护持续的时长（不在维护中时为 None）
+    pub fn end(&self) -> Option<Duration> {
+        self.window
+            .lock()
+            .expect("维护状态锁中毒")
+            .take()
+            .map(|window| window.started.elapsed())
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.window.lock().expect("维护状态锁中毒").is_some()
+    }
+
+    pub fn status(&self) -> MaintenanceStatus {
+        match &*self.window.lock().expect("维护状态锁中毒") {
+            Some(window) => MaintenanceStatus {
+                enabled: true,
+                reason: window.reason.clone(),
+                since_secs: Some(window.started.elapsed().as_secs()),
+                retry_after_secs: Some(window.retry_after.as_secs()),
+            },
+            None => MaintenanceStatus {
+                enabled: false,
+                reason: None,
+                since_secs: None,
+                retry_after_secs: None,
+            },
+        }
+    }
+
+    /// 维护中时返回 (原因, 建议的等待时长)
+    fn current(&self) -> Option<(Option<String>, Duration)> {
+        self.window
+            .lock()
+            .expect("维护状态锁中毒")
+            .as_ref()
+            .map(|window| (window.reason.clone(), window.retry_after))
+    }
+}
+
+/// 中间件：维护期间拒绝 `/task/*` 请求
+pub async fn reject_during_maintenance(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some((reason, retry_after)) = state.maintenance.current() else {
+        return next.run(req).await;
+    };
+
+    let message = match reason {
+        Some(reason) => format!("Master 维护中: {}", reason),
+        None => "Master 维护中".to_string(),
+    };
+    retry_later(
+        StatusCode::SERVICE_UNAVAILABLE,
+        retry_after,
+        api_error::<()>(message).with_code(ErrorCode::Maintenance),
+    )
+}
@@ -0,0 +1,87 @@
+//! 命中响应标记的无效ID
+//!
+//! 探测目标的判定规则可以列出响应标记（如已下架、地区限制），命中的ID不是有效ID，
+//! 但比单纯不存在的ID更值得关注。Worker提交结果时一并上报，保存在 marked_results 表中，
+//! 每个ID只保留最新的标记。
+
+use common::admin::{MarkedCount, MarkedResult};
+use sqlx::{SqliteConnection, SqliteExecutor};
+
+/// 记录一个命中标记的ID
+///
+/// 已有记录时更新标记、所属任务和最后出现时间；标记变化时首次出现时间重新计算。
+pub async fn record(
+    conn: &mut SqliteConnection,
+    id: i64,
+    label: &str,
+    task_id: i32,
+    worker_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO marked_results (id, label, task_id, worker_id) VALUES (?, ?, ?, ?)
+        ON CONFLICT (id) DO UPDATE SET
+            first_seen = CASE WHEN label = excluded.label THEN first_seen ELSE CURRENT_TIMESTAMP END,
+            label = excluded.label,
+            task_id = excluded.task_id,
+            worker_id = excluded.worker_id,
+            last_seen = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(id)
+    .bind(label)
+    .bind(task_id)
+    .bind(worker_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// 每种标记的ID数，按数量倒序
+pub async fn counts<'e>(
+    executor: impl SqliteExecutor<'e>,
+) -> Result<Vec<MarkedCount>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (String, i64)>(
+        "SELECT label, COUNT(*) FROM marked_results GROUP BY label ORDER BY COUNT(*) DESC, label",
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(label, count)| MarkedCount { label, count })
+        .collect())
+}
+
+/// 最近出现的命中标记的ID（新的在前），可按标记过滤
+pub async fn recent<'e>(
+    executor: impl SqliteExecutor<'e>,
+    limit: u32,
+    label: Option<&str>,
+) -> Result<Vec<MarkedResult>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, String, String, String, String)>(
+        r#"
+        SELECT id, label, worker_id, first_seen, last_seen
+        FROM marked_results
+        WHERE ? IS NULL OR label = ?
+        ORDER BY last_seen DESC, id DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(label)
+    .bind(label)
+    .bind(limit)
+    .fetch_all(executor)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, label, worker_id, first_seen, last_seen)| MarkedResult {
+                id,
+                label,
+                worker_id,
+                first_seen,
+                last_seen,
+            },
+        )
+        .collect())
+}
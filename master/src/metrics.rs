@@ -0,0 +1,399 @@
+//! Master运行指标
+//!
+//! 以 Prometheus 文本格式通过 `GET /metrics` 暴露
+
+use crate::AppState;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use common::latency::LatencyStats;
+use sqlx::SqlitePool;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// 延迟直方图的桶上界（秒）
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+tokio::task_local! {
+    /// 当前请求中累计的SQL耗时
+    static SQL_TIME: Cell<Duration>;
+}
+
+/// 运行指标
+#[derive(Default)]
+pub struct Metrics {
+    /// 被后台回收的超时任务数
+    pub tasks_reaped: AtomicU64,
+
+    /// 回收或重新分配时因范围过大而被拆分出的子任务数
+    pub tasks_split: AtomicU64,
+
+    /// 超时任务被重新分配给其他Worker的次数
+    pub tasks_reassigned: AtomicU64,
+
+    /// 因请求过于频繁被拒绝的 acquire 次数
+    pub acquire_rate_limited: AtomicU64,
+
+    /// Worker上报的速度远超Master测得的速度、被截断的次数
+    pub reported_speed_clamped: AtomicU64,
+
+    /// 超过路由组限流被拒绝的请求数
+    pub requests_rate_limited: AtomicU64,
+
+    /// 通过心跳通知Worker放弃已失去的任务的次数
+    pub heartbeat_aborts: AtomicU64,
+
+    /// 因长时间没有进展被释放的任务数
+    pub tasks_stalled: AtomicU64,
+
+    /// 检测到扫描停滞的次数
+    pub scan_stalls: AtomicU64,
+
+    /// 被判定为可疑、送去复查的提交数
+    pub submissions_flagged: AtomicU64,
+
+    /// 抽查的ID数
+    pub spot_checks: AtomicU64,
+
+    /// 抽查结论与Worker不一致的ID数
+    pub spot_check_mismatches: AtomicU64,
+
+    /// 成功完成的数据库备份数
+    pub backups_completed: AtomicU64,
+
+    /// 失败的数据库备份数
+    pub backups_failed: AtomicU64,
+
+    /// 成功上传到远程存储的备份数
+    pub backup_uploads_completed: AtomicU64,
+
+    /// 上传失败（重试耗尽）的备份数
+    pub backup_uploads_failed: AtomicU64,
+
+    /// 告警规则触发的次数
+    pub alerts_fired: AtomicU64,
+
+    /// 超过 `--db-statement-timeout` 被中断的SQL语句数（由连接池的 progress handler 累加）
+    pub statements_timed_out: Arc<AtomicU64>,
+
+    /// 按路由统计的请求延迟
+    request_latency: Mutex<BTreeMap<String, Arc<Histogram>>>,
+
+    /// 最近一次提交中上报的上游延迟
+    upstream_latency: Mutex<Option<LatencyStats>>,
+}
+
+/// 延迟直方图
+#[derive(Default)]
+pub struct Histogram {
+    /// 每个桶的计数（非累积，最后一个为 +Inf）
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    /// 总耗时（微秒）
+    sum_micros: AtomicU64,
+    /// 总次数
+    count: AtomicU64,
+}
+
+impl Histogram {
+    /// 记录一次耗时
+    pub fn observe(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let index = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| secs <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[index].fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 以 Prometheus 直方图格式写入，labels 形如 `route="/task/acquire"`
+    fn write(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+            cumulative += self.buckets[i].load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                name, labels, bound, cumulative
+            );
+        }
+        cumulative += self.buckets[LATENCY_BUCKETS.len()].load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{},le=\"+Inf\"}} {}",
+            name, labels, cumulative
+        );
+        let _ = writeln!(
+            out,
+            "{}_sum{{{}}} {}",
+            name,
+            labels,
+            self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        );
+        let _ = writeln!(
+            out,
+            "{}_count{{{}}} {}",
+            name,
+            labels,
+            self.count.load(Ordering::Relaxed)
+        );
+    }
+}
+
+impl Metrics {
+    /// 计数器加一
+    pub fn incr(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 获取某个路由的延迟直方图
+    pub fn route_latency(&self, route: &str) -> Arc<Histogram> {
+        let mut map = self.request_latency.lock().expect("指标锁中毒");
+        Arc::clone(map.entry(route.to_string()).or_default())
+    }
+
+    /// 记录最近一次提交中上报的上游延迟
+    pub fn record_upstream_latency(&self, latency: LatencyStats) {
+        *self.upstream_latency.lock().expect("指标锁中毒") = Some(latency);
+    }
+
+    /// 渲染为 Prometheus 文本格式，连接池的使用情况从 `pool` 读取
+    pub fn render(&self, pool: &SqlitePool, read_pool: Option<&SqlitePool>) -> String {
+        let mut out = String::new();
+
+        write_counter(
+            &mut out,
+            "pa_master_tasks_reaped_total",
+            "被后台回收的超时任务数",
+            &self.tasks_reaped,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_tasks_split_total",
+            "回收或重新分配时拆分出的子任务数",
+            &self.tasks_split,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_tasks_reassigned_total",
+            "超时任务被重新分配的次数",
+            &self.tasks_reassigned,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_acquire_rate_limited_total",
+            "因请求过于频繁被拒绝的 acquire 次数",
+            &self.acquire_rate_limited,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_reported_speed_clamped_total",
+            "Worker上报的速度超过参考速度的容忍倍数、被截断的次数",
+            &self.reported_speed_clamped,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_requests_rate_limited_total",
+            "超过路由组限流被拒绝的请求数",
+            &self.requests_rate_limited,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_heartbeat_aborts_total",
+            "通过心跳通知Worker放弃任务的次数",
+            &self.heartbeat_aborts,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_tasks_stalled_total",
+            "因长时间没有进展被释放的任务数",
+            &self.tasks_stalled,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_scan_stalls_total",
+            "检测到扫描停滞的次数",
+            &self.scan_stalls,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_submissions_flagged_total",
+            "被判定为可疑、送去复查的提交数",
+            &self.submissions_flagged,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_spot_checks_total",
+            "抽查的ID数",
+            &self.spot_checks,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_spot_check_mismatches_total",
+            "抽查结论与Worker不一致的ID数",
+            &self.spot_check_mismatches,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_backups_total",
+            "成功完成的数据库备份数",
+            &self.backups_completed,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_backup_failures_total",
+            "失败的数据库备份数",
+            &self.backups_failed,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_backup_uploads_total",
+            "成功上传到远程存储的备份数",
+            &self.backup_uploads_completed,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_backup_upload_failures_total",
+            "上传失败的备份数",
+            &self.backup_uploads_failed,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_alerts_fired_total",
+            "告警规则触发的次数",
+            &self.alerts_fired,
+        );
+        write_counter(
+            &mut out,
+            "pa_master_db_statements_timed_out_total",
+            "超过语句超时被中断的SQL语句数",
+            &self.statements_timed_out,
+        );
+
+        // 连接池：in_use 接近 max 说明连接数不够，请求在排队等待连接
+        write_pool(&mut out, "pa_master_db_pool", "连接池", pool);
+        if let Some(read_pool) = read_pool {
+            write_pool(&mut out, "pa_master_db_read_pool", "只读连接池", read_pool);
+        }
+
+        let name = "pa_master_upstream_latency_ms";
+        let _ = writeln!(out, "# HELP {} 最近一次提交中上报的上游延迟（毫秒）", name);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        if let Some(latency) = *self.upstream_latency.lock().expect("指标锁中毒") {
+            for (quantile, value) in [
+                ("0.5", latency.p50_ms),
+                ("0.9", latency.p90_ms),
+                ("0.99", latency.p99_ms),
+                ("1", latency.max_ms),
+            ] {
+                let _ = writeln!(out, "{}{{quantile=\"{}\"}} {}", name, quantile, value);
+            }
+        }
+
+        let name = "pa_master_request_duration_seconds";
+        let _ = writeln!(out, "# HELP {} 按路由统计的请求延迟", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let routes = self.request_latency.lock().expect("指标锁中毒").clone();
+        for (route, histogram) in routes {
+            histogram.write(&mut out, name, &format!("route=\"{}\"", route));
+        }
+
+        out
+    }
+}
+
+/// 写入连接池的最大连接数和按状态统计的连接数
+fn write_pool(out: &mut String, prefix: &str, label: &str, pool: &SqlitePool) {
+    let idle = pool.num_idle() as u32;
+    let size = pool.size();
+    write_gauge(
+        out,
+        &format!("{}_max_connections", prefix),
+        &format!("{}的最大连接数", label),
+        pool.options().get_max_connections(),
+    );
+    let name = format!("{}_connections", prefix);
+    let _ = writeln!(out, "# HELP {} {}中的连接数", name, label);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "{}{{state=\"idle\"}} {}", name, idle);
+    let _ = writeln!(
+        out,
+        "{}{{state=\"in_use\"}} {}",
+        name,
+        size.saturating_sub(idle)
+    );
+}
+
+/// 统计一段数据库操作的耗时，累计到当前请求的SQL耗时中
+pub async fn timed_sql<F: Future>(fut: F) -> F::Output {
+    let start = Instant::now();
+    let output = fut.await;
+    let _ = SQL_TIME.try_with(|total| total.set(total.get() + start.elapsed()));
+    output
+}
+
+/// 请求计时中间件：记录每个路由的延迟直方图，并输出慢请求日志
+pub async fn track_latency(
+    State(state): State<Arc<AppState>>,
+    matched_path: Option<MatchedPath>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let route = matched_path
+        .as_ref()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = req.method().clone();
+    let request_id = crate::request_id::from_request(&req);
+
+    let start = Instant::now();
+    let (response, sql_time) = SQL_TIME
+        .scope(Cell::new(Duration::ZERO), async {
+            let response = next.run(req).await;
+            (response, SQL_TIME.with(|total| total.get()))
+        })
+        .await;
+    let elapsed = start.elapsed();
+
+    state.metrics.route_latency(&route).observe(elapsed);
+
+    let threshold = state.config.slow_request_ms;
+    if threshold > 0 && elapsed >= Duration::from_millis(threshold) {
+        warn!(
+            "慢请求: {} {} 耗时 {:.1}ms (SQL {:.1}ms), 状态码={}, request_id={}",
+            method,
+            route,
+            elapsed.as_secs_f64() * 1000.0,
+            sql_time.as_secs_f64() * 1000.0,
+            response.status(),
+            request_id.unwrap_or_default()
+        );
+    }
+
+    response
+}
+
+/// 写入一个计数器
+fn write_gauge(out: &mut String, name: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+fn write_counter(out: &mut String, name: &str, help: &str, counter: &AtomicU64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+}
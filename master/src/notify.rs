@@ -0,0 +1,78 @@
+//! 告警通知
+//!
+//! 配置了 `--alert-webhook` 时，Master把告警以 JSON POST 到该地址：
+//!
+//! ```json
+//! {"source": "pa_master", "kind": "scan_stalled", "text": "...", "details": {...}, "at": "2024-01-01T00:00:00Z"}
+//! ```
+//!
+//! `text` 是可以直接展示的一句话说明，兼容 Slack 等只读取 `text` 字段的 incoming webhook。
+//! 配置文件中有 `[smtp]` 一节时，同样的告警还会以邮件发送（见 [`email`]）。
+//! 发送失败只记录日志，不重试。
+
+mod email;
+
+pub use email::{EmailNotifier, SmtpConfig};
+
+use serde_json::json;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// 单次发送的超时
+pub const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 告警通知，发送到所有配置了的渠道
+pub struct Notifier {
+    client: reqwest::Client,
+    webhook: Option<String>,
+    email: Option<EmailNotifier>,
+}
+
+impl Notifier {
+    /// 没有配置任何渠道时返回 None
+    pub fn new(webhook: Option<String>, email: Option<EmailNotifier>) -> Option<Self> {
+        if webhook.is_none() && email.is_none() {
+            return None;
+        }
+        Some(Self {
+            client: reqwest::Client::new(),
+            webhook,
+            email,
+        })
+    }
+
+    /// 发送一条告警，`kind` 为告警类型（如 `scan_stalled`），`details` 为附带的结构化信息
+    pub async fn send(&self, kind: &str, text: &str, details: serde_json::Value) {
+        let at = chrono::Utc::now().to_rfc3339();
+
+        if let Some(email) = &self.email {
+            match email.send(kind, text, &details, &at).await {
+                Ok(()) => info!("已通过邮件发送告警 {}: {}", kind, text),
+                Err(e) => warn!("通过邮件发送告警 {} 失败: {}", kind, e),
+            }
+        }
+
+        let Some(url) = &self.webhook else {
+            return;
+        };
+        let payload = json!({
+            "source": "pa_master",
+            "kind": kind,
+            "text": text,
+            "details": details,
+            "at": at,
+        });
+        let result = self
+            .client
+            .post(url)
+            .timeout(SEND_TIMEOUT)
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => info!("已发送告警 {}: {}", kind, text),
+            Err(e) => warn!("发送告警 {} 到 {} 失败: {}", kind, url, e),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! 邮件通知
+//!
+//! 在配置文件的 `[smtp]` 一节中设置：
+//!
+//! ```toml
+//! [smtp]
+//! host = "smtp.example.com"
+//! security = "starttls"        # starttls（默认）、tls 或 none
+//! username = "alerts@example.com"
+//! password = "..."             # 也可以用环境变量 PA_SMTP_PASSWORD
+//! from = "pa_market <alerts@example.com>"
+//! to = ["ops@example.com"]
+//! ```
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use std::time::Duration;
+
+/// 未在配置文件中设置密码时读取的环境变量
+const PASSWORD_ENV: &str = "PA_SMTP_PASSWORD";
+
+/// SMTP 连接方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// 明文连接后升级为 TLS（默认端口 587）
+    #[default]
+    Starttls,
+    /// 直接使用 TLS 连接（默认端口 465）
+    Tls,
+    /// 不加密（默认端口 25），只应用于本机或内网的中继
+    None,
+}
+
+/// `[smtp]` 配置
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmtpConfig {
+    /// SMTP 服务器地址
+    pub host: String,
+    /// 端口，不设置时按连接方式使用默认端口
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    /// 登录用户名，不设置则不认证
+    pub username: Option<String>,
+    /// 登录密码，不设置时读取环境变量 `PA_SMTP_PASSWORD`
+    pub password: Option<String>,
+    /// 发件人（如 `pa_market <alerts@example.com>`）
+    pub from: String,
+    /// 收件人
+    pub to: Vec<String>,
+    /// 邮件标题前缀
+    #[serde(default = "default_subject_prefix")]
+    pub subject_prefix: String,
+}
+
+fn default_subject_prefix() -> String {
+    "[pa_market]".to_string()
+}
+
+/// 通过 SMTP 发送告警邮件
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    /// 服务器地址，用于日志
+    host: String,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    subject_prefix: String,
+}
+
+impl EmailNotifier {
+    pub fn new(config: &SmtpConfig, timeout: Duration) -> Result<Self, String> {
+        let from: Mailbox = config
+            .from
+            .parse()
+            .map_err(|e| format!("SMTP 发件人 {:?} 无效: {}", config.from, e))?;
+        if config.to.is_empty() {
+            return Err("SMTP 配置中没有收件人".into());
+        }
+        let to = config
+            .to
+            .iter()
+            .map(|address| {
+                address
+                    .parse()
+                    .map_err(|e| format!("SMTP 收件人 {:?} 无效: {}", address, e))
+            })
+            .collect::<Result<Vec<Mailbox>, String>>()?;
+
+        let mut builder = match config.security {
+            SmtpSecurity::Starttls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host),
+            SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &config.host,
+            )),
+        }
+        .map_err(|e| format!("SMTP 服务器 {} 无效: {}", config.host, e))?
+        .timeout(Some(timeout));
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let Some(username) = &config.username {
+            let password = match &config.password {
+                Some(password) => password.clone(),
+                None => std::env::var(PASSWORD_ENV).map_err(|_| {
+                    format!(
+                        "设置了 SMTP 用户名但没有密码（配置文件中的 password 或环境变量 {}）",
+                        PASSWORD_ENV
+                    )
+                })?,
+            };
+            builder = builder.credentials(Credentials::new(username.clone(), password));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            host: config.host.clone(),
+            from,
+            to,
+            subject_prefix: config.subject_prefix.clone(),
+        })
+    }
+
+    /// 收件人数量
+    pub fn recipients(&self) -> usize {
+        self.to.len()
+    }
+
+    /// 发送一封告警邮件：标题为告警说明，正文附带结构化信息
+    pub async fn send(
+        &self,
+        kind: &str,
+        text: &str,
+        details: &serde_json::Value,
+        at: &str,
+    ) -> Result<(), String> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(format!("{} {}", self.subject_prefix, text).trim_start())
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let body = format!(
+            "{}\n\nkind: {}\nat: {}\n\n{}\n",
+            text,
+            kind,
+            at,
+            serde_json::to_string_pretty(details).unwrap_or_default()
+        );
+        let message = builder.body(body).map_err(|e| e.to_string())?;
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("{}: {}", self.host, e))
+    }
+}
@@ -0,0 +1,40 @@
+//! Worker的在线状态
+//!
+//! 记录每个Worker最近一次成功调用 `/task/*` 的时间，集群概览据此判断哪些Worker仍然在线。
+//! 只保存在内存中，Master重启后由Worker的下一次请求重新填充。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 超过该时长没有请求的Worker不再保留
+const RETENTION: Duration = Duration::from_secs(24 * 3600);
+
+/// 各Worker最近一次请求的时间
+#[derive(Default)]
+pub struct WorkerPresence {
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl WorkerPresence {
+    /// 记录Worker的一次请求
+    pub fn touch(&self, worker_id: &str) {
+        let mut seen = self.seen.lock().expect("在线状态锁中毒");
+        match seen.get_mut(worker_id) {
+            Some(at) => *at = Instant::now(),
+            None => {
+                seen.insert(worker_id.to_string(), Instant::now());
+            }
+        }
+    }
+
+    /// 最近 `within` 内有过请求的Worker及距最近一次请求的时长
+    pub fn seen_within(&self, within: Duration) -> Vec<(String, Duration)> {
+        let mut seen = self.seen.lock().expect("在线状态锁中毒");
+        seen.retain(|_, at| at.elapsed() <= RETENTION);
+        seen.iter()
+            .map(|(worker_id, at)| (worker_id.clone(), at.elapsed()))
+            .filter(|(_, age)| *age <= within)
+            .collect()
+    }
+}
@@ -0,0 +1,106 @@
+//! Worker的主机信息和标签
+//!
+//! Worker在获取任务时上报，持久化在 worker_profiles 表中，内存中保留一份供管理接口展示。
+//! 内容与上次相同时不写数据库。
+
+use common::WorkerProfile;
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::RwLock;
+
+type ProfileRow = (
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+    Option<String>,
+);
+
+/// 各Worker最近一次上报的主机信息
+#[derive(Default)]
+pub struct WorkerProfiles {
+    profiles: RwLock<HashMap<String, WorkerProfile>>,
+}
+
+impl WorkerProfiles {
+    /// 从数据库加载
+    pub async fn load(pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            profiles: RwLock::new(fetch_all(pool).await?),
+        })
+    }
+
+    /// 从数据库重新加载（其他节点可能修改过）
+    pub async fn reload(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        *self.profiles.write().await = fetch_all(pool).await?;
+        Ok(())
+    }
+
+    /// Worker最近一次上报的主机信息
+    pub async fn get(&self, worker_id: &str) -> Option<WorkerProfile> {
+        self.profiles.read().await.get(worker_id).cloned()
+    }
+
+    /// 记录Worker上报的主机信息，内容有变化时写入数据库
+    pub async fn record(
+        &self,
+        pool: &SqlitePool,
+        worker_id: &str,
+        profile: &WorkerProfile,
+    ) -> Result<(), sqlx::Error> {
+        if self.profiles.read().await.get(worker_id) == Some(profile) {
+            return Ok(());
+        }
+
+        let labels = serde_json::to_string(&profile.labels).expect("标签序列化失败");
+        sqlx::query(
+            "INSERT INTO worker_profiles (worker_id, hostname, region, egress, labels, version, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+             ON CONFLICT(worker_id) DO UPDATE SET
+                 hostname = excluded.hostname,
+                 region = excluded.region,
+                 egress = excluded.egress,
+                 labels = excluded.labels,
+                 version = excluded.version,
+                 updated_at = excluded.updated_at",
+        )
+        .bind(worker_id)
+        .bind(&profile.hostname)
+        .bind(&profile.region)
+        .bind(&profile.egress)
+        .bind(labels)
+        .bind(&profile.version)
+        .execute(pool)
+        .await?;
+        self.profiles
+            .write()
+            .await
+            .insert(worker_id.to_string(), profile.clone());
+        Ok(())
+    }
+}
+
+async fn fetch_all(pool: &SqlitePool) -> Result<HashMap<String, WorkerProfile>, sqlx::Error> {
+    let rows: Vec<ProfileRow> = sqlx::query_as(
+        "SELECT worker_id, hostname, region, egress, labels, version FROM worker_profiles",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(worker_id, hostname, region, egress, labels, version)| {
+            // 标签只由本模块写入，解析失败时按没有标签处理
+            let labels: BTreeMap<String, String> =
+                serde_json::from_str(&labels).unwrap_or_default();
+            let profile = WorkerProfile {
+                hostname,
+                region,
+                egress,
+                labels,
+                version,
+            };
+            (worker_id, profile)
+        })
+        .collect())
+}
@@ -0,0 +1,268 @@
+//! 任务队列的公共操作
+//!
+//! 供 master 的回收任务和 init 工具共用，保证重新排队的任务格式一致。
+
+use crate::running::RunningTask;
+use sqlx::{SqliteConnection, SqlitePool};
+
+/// 按 chunk_size 拆分闭区间 [start_id, end_id]，chunk_size <= 0 时不拆分
+pub fn split_range(start_id: i64, end_id: i64, chunk_size: i64) -> Vec<(i64, i64)> {
+    if chunk_size <= 0 {
+        return vec![(start_id, end_id)];
+    }
+
+    let mut chunks = Vec::new();
+    let mut start = start_id;
+    while start <= end_id {
+        let end = (start + chunk_size - 1).min(end_id);
+        chunks.push((start, end));
+        start = end + 1;
+    }
+    chunks
+}
+
+/// 插入一个待分配的任务，返回任务ID
+///
+/// 心跳时间设为过去，使其立即被视为超时、可被下一次 acquire 认领。
+pub async fn insert_pending(
+    conn: &mut SqliteConnection,
+    start_id: i64,
+    end_id: i64,
+) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO task_queue (start_id, end_id, worker_id, status, last_heartbeat)
+        VALUES (?, ?, '', 'pending', datetime('now', '-120 seconds'))
+        RETURNING task_id
+        "#,
+    )
+    .bind(start_id)
+    .bind(end_id)
+    .fetch_one(conn)
+    .await
+}
+
+/// 退回限时任务没有扫描到的闭区间 [start_id, end_id]
+///
+/// 范围紧挨着全局游标（之后还没有分配过新范围）时直接回退游标，否则作为待分配任务插入。
+/// 返回新建的待分配任务，回退游标时为 None。
+pub async fn return_unscanned(
+    conn: &mut SqliteConnection,
+    start_id: i64,
+    end_id: i64,
+) -> Result<Option<RunningTask>, sqlx::Error> {
+    let rewound = sqlx::query(
+        "UPDATE global_cursor SET next_start_id = ? WHERE id = 1 AND next_start_id = ?",
+    )
+    .bind(start_id)
+    .bind(end_id + 1)
+    .execute(&mut *conn)
+    .await?;
+    if rewound.rows_affected() > 0 {
+        return Ok(None);
+    }
+
+    let task_id = insert_pending(conn, start_id, end_id).await?;
+    Ok(Some(RunningTask {
+        task_id,
+        start_id,
+        end_id,
+        worker_id: String::new(),
+        lease_expires: None,
+        verification: false,
+        list: false,
+        speed: None,
+        stalled_since: None,
+    }))
+}
+
+/// 重新排队失败的原因
+#[derive(Debug)]
+pub enum RequeueError {
+    /// 起始ID大于结束ID，或拆分粒度不大于0
+    InvalidRange,
+
+    /// 范围超出了全局游标（尚未分配的范围无需重新排队）
+    BeyondCursor { cursor: i64 },
+
+    /// 数据库错误
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for RequeueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequeueError::InvalidRange => {
+                write!(f, "无效的范围：起始ID不能大于结束ID，拆分粒度必须大于0")
+            }
+            RequeueError::BeyondCursor { cursor } => write!(
+                f,
+                "范围超出了全局游标 {}，尚未分配的范围无需重新排队",
+                cursor
+            ),
+            RequeueError::Database(e) => write!(f, "数据库错误: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RequeueError {}
+
+impl From<sqlx::Error> for RequeueError {
+    fn from(e: sqlx::Error) -> Self {
+        RequeueError::Database(e)
+    }
+}
+
+/// 将已分配过的闭区间 [start_id, end_id] 按 chunk 拆分后重新排队
+///
+/// 范围必须位于全局游标之前，否则会与正常分配重复。返回新建的待分配任务。
+pub async fn requeue_range(
+    pool: &SqlitePool,
+    start_id: i64,
+    end_id: i64,
+    chunk: i64,
+) -> Result<Vec<RunningTask>, RequeueError> {
+    if start_id > end_id || chunk <= 0 {
+        return Err(RequeueError::InvalidRange);
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let cursor: (i64,) = sqlx::query_as("SELECT next_start_id FROM global_cursor WHERE id = 1")
+        .fetch_one(&mut *tx)
+        .await?;
+    if end_id >= cursor.0 {
+        return Err(RequeueError::BeyondCursor { cursor: cursor.0 });
+    }
+
+    let mut tasks = Vec::new();
+    for (chunk_start, chunk_end) in split_range(start_id, end_id, chunk) {
+        let task_id = insert_pending(&mut tx, chunk_start, chunk_end).await?;
+        tasks.push(RunningTask {
+            task_id,
+            start_id: chunk_start,
+            end_id: chunk_end,
+            worker_id: String::new(),
+            lease_expires: None,
+            verification: false,
+            list: false,
+            speed: None,
+            stalled_since: None,
+        });
+    }
+
+    tx.commit().await?;
+    Ok(tasks)
+}
+
+/// 没有得到结论的ID重新排队的结果
+#[derive(Debug, Default)]
+pub struct UncheckedRequeue {
+    /// 重新排队的归档范围数
+    pub ranges: usize,
+
+    /// 新建的待分配任务（普通范围按 chunk 拆分）
+    pub tasks: Vec<RunningTask>,
+
+    /// 重新等待分配的候选ID数（显式ID列表任务）
+    pub candidates: u64,
+}
+
+/// 将提交时上报了未确认或被拒绝ID的归档范围重新排队
+///
+/// Worker只上报了数量，没有上报具体的ID，因此整个范围重新检查：普通范围按 chunk 拆分为待分配任务，
+/// 显式ID列表任务重新打开其中的候选ID。处理过的归档记录标记 requeued_at，不会重复排队。
+pub async fn requeue_unchecked(
+    pool: &SqlitePool,
+    chunk: i64,
+) -> Result<UncheckedRequeue, RequeueError> {
+    if chunk <= 0 {
+        return Err(RequeueError::InvalidRange);
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let ranges: Vec<(i32, i64, i64, bool)> = sqlx::query_as(
+        r#"
+        SELECT task_id, start_id, end_id, list_task != 0
+        FROM task_archive
+        WHERE unknown_count + blocked_count > 0 AND requeued_at IS NULL
+        ORDER BY start_id
+        "#,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut requeue = UncheckedRequeue {
+        ranges: ranges.len(),
+        ..Default::default()
+    };
+    for (archived_id, start_id, end_id, list) in ranges {
+        if list {
+            requeue.candidates += crate::candidates::reopen(&mut tx, archived_id).await?;
+        } else {
+            for (chunk_start, chunk_end) in split_range(start_id, end_id, chunk) {
+                let task_id = insert_pending(&mut tx, chunk_start, chunk_end).await?;
+                requeue.tasks.push(RunningTask {
+                    task_id,
+                    start_id: chunk_start,
+                    end_id: chunk_end,
+                    worker_id: String::new(),
+                    lease_expires: None,
+                    verification: false,
+                    list: false,
+                    speed: None,
+                    stalled_since: None,
+                });
+            }
+        }
+        sqlx::query("UPDATE task_archive SET requeued_at = CURRENT_TIMESTAMP WHERE task_id = ?")
+            .bind(archived_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(requeue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_into_chunks() {
+        assert_eq!(split_range(0, 9, 4), [(0, 3), (4, 7), (8, 9)]);
+        assert_eq!(split_range(5, 5, 1), [(5, 5)]);
+        assert_eq!(split_range(1, 3, 1), [(1, 1), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn chunk_not_smaller_than_range() {
+        // chunk_size 等于范围大小时恰好一块，大于时不拆分
+        assert_eq!(split_range(100, 199, 100), [(100, 199)]);
+        assert_eq!(split_range(100, 199, 1000), [(100, 199)]);
+        assert_eq!(split_range(100, 199, i64::MAX / 2), [(100, 199)]);
+    }
+
+    #[test]
+    fn no_chunk_size_keeps_range() {
+        assert_eq!(split_range(3, 8, 0), [(3, 8)]);
+        assert_eq!(split_range(3, 8, -1), [(3, 8)]);
+    }
+
+    #[test]
+    fn chunks_are_contiguous() {
+        for chunk_size in 1..=12 {
+            let chunks = split_range(10, 40, chunk_size);
+            assert_eq!(chunks.first().unwrap().0, 10);
+            assert_eq!(chunks.last().unwrap().1, 40);
+            for window in chunks.windows(2) {
+                assert_eq!(window[0].1 + 1, window[1].0);
+            }
+            assert!(chunks
+                .iter()
+                .all(|(start, end)| start <= end && end - start < chunk_size));
+        }
+    }
+}
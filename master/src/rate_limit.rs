@@ -0,0 +1,291 @@
+//! 请求限流
+//!
+//! 崩溃重启循环中的Worker会不停地 acquire，每次都切出新范围或认领超时任务，
+//! 很快把游标推远、把任务队列搅乱。[`AcquireLimiter`] 限制同一个 worker_id 两次 acquire 之间的最小间隔。
+//!
+//! 此外 [`RouteLimiter`] 按路由组（acquire、heartbeat、submit、admin）分别做令牌桶限流，
+//! 带API Key的请求按API Key计数，其余按客户端IP计数，防止意外的重试风暴压垮Master。
+//!
+//! 超出时都返回 429 和 [`ErrorCode::RateLimited`]。
+
+use crate::api_keys::ApiKey;
+use crate::metrics::Metrics;
+use crate::request_context::RequestContext;
+use crate::{api_error, retry_later, AppState};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use common::ErrorCode;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// 记录的Worker数超过该值时清理过期记录
+const CLEANUP_THRESHOLD: usize = 1024;
+
+/// 每个Worker最近一次被放行的 acquire 时间
+pub struct AcquireLimiter {
+    interval: Duration,
+    last_acquire: Mutex<HashMap<String, Instant>>,
+}
+
+impl AcquireLimiter {
+    /// `interval` 为同一个Worker两次 acquire 之间的最小间隔，0 表示不限制
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_acquire: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 检查并登记一次 acquire，被限流时返回还需等待的时间
+    pub fn check(&self, worker_id: &str) -> Result<(), Duration> {
+        if self.interval.is_zero() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut last_acquire = self.last_acquire.lock().expect("限流锁中毒");
+
+        if let Some(last) = last_acquire.get(worker_id) {
+            let elapsed = now.duration_since(*last);
+            if elapsed < self.interval {
+                return Err(self.interval - elapsed);
+            }
+        }
+
+        if last_acquire.len() >= CLEANUP_THRESHOLD {
+            let interval = self.interval;
+            last_acquire.retain(|_, last| now.duration_since(*last) < interval);
+        }
+        last_acquire.insert(worker_id.to_string(), now);
+        Ok(())
+    }
+}
+
+/// 中间件：限制 `/task/acquire` 的调用频率
+///
+/// worker_id 由 [`crate::request_context::record_context`] 从请求体中取出；
+/// 请求体无法解析时不做限制，由处理函数返回错误。
+pub async fn limit_acquire(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let worker_id = req
+        .extensions()
+        .get::<RequestContext>()
+        .and_then(|context| context.worker_id.as_deref());
+    if let Some(worker_id) = worker_id {
+        if let Err(wait) = state.acquire_limiter.check(worker_id) {
+            Metrics::incr(&state.metrics.acquire_rate_limited);
+            warn!(
+                "Worker {} 请求任务过于频繁，需等待 {}ms",
+                worker_id,
+                wait.as_millis()
+            );
+            return retry_later(
+                StatusCode::TOO_MANY_REQUESTS,
+                wait,
+                api_error::<()>("请求任务过于频繁，请稍后重试".to_string())
+                    .with_code(ErrorCode::RateLimited),
+            );
+        }
+    }
+
+    next.run(req).await
+}
+
+/// 限流速率，形如 `20/s`、`600/m`、`1000/h`：每个时间单位最多的请求数，同时也是允许的突发量
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateSpec {
+    /// 每个时间单位的请求数
+    pub count: u32,
+    /// 时间单位
+    pub per: Duration,
+}
+
+impl RateSpec {
+    /// 每秒补充的令牌数
+    fn refill_per_sec(&self) -> f64 {
+        f64::from(self.count) / self.per.as_secs_f64()
+    }
+}
+
+impl FromStr for RateSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (count, unit) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| format!("限流速率格式应为 次数/单位（如 20/s、600/m）: {}", s))?;
+        let count: u32 = count
+            .trim()
+            .parse()
+            .map_err(|_| format!("无效的请求次数: {}", count))?;
+        if count == 0 {
+            return Err(format!("请求次数必须大于0: {}", s));
+        }
+        let per = match unit.trim() {
+            "s" => Duration::from_secs(1),
+            "m" => Duration::from_secs(60),
+            "h" => Duration::from_secs(3600),
+            other => return Err(format!("未知的时间单位: {}（可选 s、m、h）", other)),
+        };
+        Ok(Self { count, per })
+    }
+}
+
+impl fmt::Display for RateSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self.per.as_secs() {
+            1 => "s",
+            60 => "m",
+            _ => "h",
+        };
+        write!(f, "{}/{}", self.count, unit)
+    }
+}
+
+/// 限流的路由组
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteGroup {
+    /// `/task/acquire`
+    Acquire,
+    /// `/task/heartbeat`
+    Heartbeat,
+    /// `/task/submit`、`/task/results`、`/task/responses`、`/task/release`
+    Submit,
+    /// 管理接口、GraphQL、状态页面和前端面板
+    Admin,
+}
+
+impl RouteGroup {
+    fn name(self) -> &'static str {
+        match self {
+            RouteGroup::Acquire => "acquire",
+            RouteGroup::Heartbeat => "heartbeat",
+            RouteGroup::Submit => "submit",
+            RouteGroup::Admin => "admin",
+        }
+    }
+}
+
+/// 令牌桶
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// 一个路由组的令牌桶限流，每个调用方一个桶
+pub struct RouteLimiter {
+    spec: RateSpec,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RouteLimiter {
+    pub fn new(spec: RateSpec) -> Self {
+        Self {
+            spec,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 取走调用方的一个令牌，被限流时返回还需等待的时间
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        let now = Instant::now();
+        let capacity = f64::from(self.spec.count);
+        let refill = self.spec.refill_per_sec();
+        let mut buckets = self.buckets.lock().expect("限流锁中毒");
+
+        // 已经补满的桶与新建的桶等价，可以丢弃
+        if buckets.len() >= CLEANUP_THRESHOLD {
+            let per = self.spec.per;
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated) < per);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / refill))
+        }
+    }
+}
+
+/// 各路由组的限流器，未配置的组不限流
+#[derive(Default)]
+pub struct RouteLimits {
+    pub acquire: Option<RouteLimiter>,
+    pub heartbeat: Option<RouteLimiter>,
+    pub submit: Option<RouteLimiter>,
+    pub admin: Option<RouteLimiter>,
+}
+
+impl RouteLimits {
+    fn get(&self, group: RouteGroup) -> Option<&RouteLimiter> {
+        match group {
+            RouteGroup::Acquire => self.acquire.as_ref(),
+            RouteGroup::Heartbeat => self.heartbeat.as_ref(),
+            RouteGroup::Submit => self.submit.as_ref(),
+            RouteGroup::Admin => self.admin.as_ref(),
+        }
+    }
+}
+
+/// 中间件：按路由组限流
+///
+/// 带有效API Key的请求（由 [`crate::api_keys::authenticate`] 识别）按API Key计数，其余按客户端IP计数。
+pub async fn limit_route(
+    State((state, group)): State<(Arc<AppState>, RouteGroup)>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(limiter) = state.route_limits.get(group) else {
+        return next.run(req).await;
+    };
+
+    let key = match (
+        req.extensions().get::<ApiKey>(),
+        req.extensions().get::<ConnectInfo<SocketAddr>>(),
+    ) {
+        (Some(api_key), _) => format!("key:{}", api_key.key_id),
+        (None, Some(ConnectInfo(addr))) => format!("ip:{}", addr.ip()),
+        (None, None) => "unknown".to_string(),
+    };
+
+    if let Err(wait) = limiter.check(&key) {
+        Metrics::incr(&state.metrics.requests_rate_limited);
+        warn!(
+            "{} 的 {} 请求超过限流 {}，需等待 {}ms",
+            key,
+            group.name(),
+            limiter.spec,
+            wait.as_millis()
+        );
+        return retry_later(
+            StatusCode::TOO_MANY_REQUESTS,
+            wait,
+            api_error::<()>("请求过于频繁，请稍后重试".to_string())
+                .with_code(ErrorCode::RateLimited),
+        );
+    }
+
+    next.run(req).await
+}
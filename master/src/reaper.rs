@@ -0,0 +1,183 @@
+//! 超时任务回收
+//!
+//! 原先超时检测只在 `try_acquire_task` 中惰性进行，没有Worker请求任务时
+//! 过期任务会一直挂在队列里。回收任务定期扫描过期租约，把它们重新排队
+//! （范围过大时拆分为多个子任务），等待下一次 acquire 认领。
+
+use crate::history;
+use crate::metrics::Metrics;
+use crate::AppState;
+use master::queue;
+use master::running::RunningTask;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// 带幂等键的提交结果保留时长
+const SUBMIT_OUTCOME_RETENTION: Duration = Duration::from_secs(24 * 3600);
+
+/// 扫描历史的保留时长
+const HISTORY_RETENTION: Duration = Duration::from_secs(30 * 24 * 3600);
+
+/// 启动后台回收任务
+pub fn spawn_reaper(state: Arc<AppState>, interval: Duration, split_size: i64) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = reap_once(&state, split_size).await {
+                error!("回收超时任务失败: {}", e);
+            }
+        }
+    });
+}
+
+/// 执行一轮回收
+async fn reap_once(state: &AppState, split_size: i64) -> Result<(), sqlx::Error> {
+    // 先与数据库对齐，吸收 init 工具等外部修改
+    let (added, removed) = state.running.sync_from_db(&state.db_pool).await?;
+    if added > 0 || removed > 0 {
+        info!("内存任务视图已同步: 新增 {}，移除 {}", added, removed);
+    }
+
+    // 备用节点只同步视图供只读接口使用，回收由主节点负责
+    if !state.leadership.is_leader() {
+        return Ok(());
+    }
+
+    // 维护期间Worker无法发送心跳，结束维护时会顺延租约
+    if state.maintenance.is_active() {
+        return Ok(());
+    }
+
+    for task in state.running.expired().await {
+        requeue_task(state, &task, split_size).await?;
+    }
+
+    // 过了重试窗口的幂等键不会再被用到
+    sqlx::query("DELETE FROM submit_outcomes WHERE created_at < datetime('now', ?)")
+        .bind(format!("-{} seconds", SUBMIT_OUTCOME_RETENTION.as_secs()))
+        .execute(&state.db_pool)
+        .await?;
+    history::prune(&state.db_pool, HISTORY_RETENTION).await?;
+
+    Ok(())
+}
+
+/// 过期任务重新排队后的子范围，第一个子范围留给原任务
+///
+/// 显式ID列表任务的ID不连续，复查任务需要完整地重新扫描，都原样重新排队。
+fn requeue_chunks(task: &RunningTask, split_size: i64) -> Vec<(i64, i64)> {
+    let split_size = if task.splittable() { split_size } else { 0 };
+    queue::split_range(task.start_id, task.end_id, split_size)
+}
+
+/// 将一个过期任务重新排队，范围超过 split_size 时拆分
+async fn requeue_task(
+    state: &AppState,
+    task: &RunningTask,
+    split_size: i64,
+) -> Result<(), sqlx::Error> {
+    let chunks = requeue_chunks(task, split_size);
+    let (_, first_end) = chunks[0];
+
+    let mut tx = state.db_pool.begin().await?;
+
+    // 原任务截断为第一个子范围并清空归属（以原worker_id为条件，防止覆盖并发认领），限时任务改为普通任务
+    let result = sqlx::query(
+        "UPDATE task_queue SET worker_id = '', status = 'pending', lease_expires_at = 0, end_id = ?, scan_for_secs = NULL WHERE task_id = ? AND worker_id = ?",
+    )
+    .bind(first_end)
+    .bind(task.task_id)
+    .bind(&task.worker_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        // 任务已被认领或删除，交给下一轮同步处理
+        tx.rollback().await?;
+        return Ok(());
+    }
+
+    // 其余子范围作为新的待分配任务插入
+    let mut new_tasks = Vec::with_capacity(chunks.len() - 1);
+    for &(start_id, end_id) in &chunks[1..] {
+        let task_id = queue::insert_pending(&mut tx, start_id, end_id).await?;
+
+        new_tasks.push(RunningTask {
+            task_id,
+            start_id,
+            end_id,
+            worker_id: String::new(),
+            lease_expires: None,
+            verification: false,
+            list: false,
+            speed: None,
+            stalled_since: None,
+        });
+    }
+
+    tx.commit().await?;
+
+    if !state
+        .running
+        .requeue(task.task_id, &task.worker_id, first_end)
+        .await
+    {
+        warn!("任务 {} 在回收期间被修改，等待下一轮同步", task.task_id);
+    }
+    for new_task in new_tasks {
+        state.running.insert(new_task).await;
+        Metrics::incr(&state.metrics.tasks_split);
+    }
+    Metrics::incr(&state.metrics.tasks_reaped);
+
+    info!(
+        "已回收超时任务 {}: 原worker={}, 范围=[{}, {}]，拆分为 {} 个子任务",
+        task.task_id,
+        task.worker_id,
+        task.start_id,
+        task.end_id,
+        chunks.len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expired_task(verification: bool, list: bool) -> RunningTask {
+        RunningTask {
+            task_id: 1,
+            start_id: 0,
+            end_id: 49_999,
+            worker_id: "worker-1".to_string(),
+            lease_expires: None,
+            verification,
+            list,
+            speed: None,
+            stalled_since: None,
+        }
+    }
+
+    #[test]
+    fn range_task_is_split() {
+        let chunks = requeue_chunks(&expired_task(false, false), 10_000);
+        assert_eq!(chunks.len(), 5);
+        assert_eq!(chunks[0], (0, 9_999));
+    }
+
+    #[test]
+    fn verification_task_keeps_its_whole_range() {
+        let chunks = requeue_chunks(&expired_task(true, false), 10_000);
+        assert_eq!(chunks, vec![(0, 49_999)]);
+    }
+
+    #[test]
+    fn list_task_keeps_its_whole_range() {
+        let chunks = requeue_chunks(&expired_task(false, true), 10_000);
+        assert_eq!(chunks, vec![(0, 49_999)]);
+    }
+}
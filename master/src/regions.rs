@@ -0,0 +1,56 @@
+//! 按地区分配任务类型
+//!
+//! Worker在获取任务时上报所在地区（`--region`），Master按以下规则决定它能领取哪些任务：
+//! - `--normal-regions`：只有这些地区的Worker领取普通任务（新范围和重新分配的范围）
+//! - `--verify-regions`：只有这些地区的Worker领取复查任务
+//!
+//! 例如 `--normal-regions cn` 时，国内出口的Worker照常扫描，海外Worker只领取复查任务。
+//! 规则中的 `cn` 同时匹配 `cn` 和 `cn-sh` 这类带后缀的地区（不区分大小写）；
+//! 未设置的规则不做限制，设置了规则时未上报地区的Worker不属于任何地区。
+
+use master::running::TaskKinds;
+
+/// 各类任务允许的地区
+#[derive(Debug, Clone, Default)]
+pub struct RegionPolicy {
+    /// 可以领取普通任务的地区（空表示不限制）
+    pub normal: Vec<String>,
+    /// 可以领取复查任务的地区（空表示不限制）
+    pub verification: Vec<String>,
+}
+
+impl RegionPolicy {
+    /// 是否设置了任何规则
+    pub fn is_restricted(&self) -> bool {
+        !self.normal.is_empty() || !self.verification.is_empty()
+    }
+
+    /// 该地区的Worker可以领取的任务类型
+    pub fn kinds(&self, region: Option<&str>) -> TaskKinds {
+        TaskKinds {
+            normal: allowed(&self.normal, region),
+            verification: allowed(&self.verification, region),
+            list: true,
+        }
+    }
+}
+
+fn allowed(regions: &[String], region: Option<&str>) -> bool {
+    if regions.is_empty() {
+        return true;
+    }
+    let Some(region) = region else {
+        return false;
+    };
+    regions.iter().any(|rule| matches_region(rule, region))
+}
+
+/// `rule` 与地区相同，或是地区以 `-` 分隔的前缀
+fn matches_region(rule: &str, region: &str) -> bool {
+    let region = region.to_ascii_lowercase();
+    let rule = rule.to_ascii_lowercase();
+    region == rule
+        || region
+            .strip_prefix(rule.as_str())
+            .is_some_and(|rest| rest.starts_with('-'))
+}
@@ -0,0 +1,117 @@
+//! 请求上下文
+//!
+//! 在进入处理函数之前取出请求所属的 worker_id / task_id，记录到当前请求的 span 上，
+//! 同一个请求的所有日志都带上这两个结构化字段，处理函数不需要再各自记录。
+//!
+//! 优先读取请求头 `x-pa-worker-id` / `x-pa-task-id`；没有时从 `/task/*` 的 JSON 请求体中读取，
+//! 读取后把请求体原样放回交给后续的处理函数。取出的上下文同时作为 [`RequestContext`] 扩展放入请求，
+//! 供限流等中间件使用。请求成功时记录Worker的在线状态（见 [`crate::presence`]）。
+
+use crate::{api_error, AppState};
+use axum::{
+    body::{self, Body},
+    extract::{Request, State},
+    http::{HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::{routes, ErrorCode, TASK_ID_HEADER, WORKER_ID_HEADER};
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// 请求所属的Worker和任务
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RequestContext {
+    #[serde(default)]
+    pub worker_id: Option<String>,
+    #[serde(default)]
+    pub task_id: Option<i32>,
+}
+
+impl RequestContext {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            worker_id: header(WORKER_ID_HEADER),
+            task_id: header(TASK_ID_HEADER).and_then(|value| value.parse().ok()),
+        }
+    }
+
+    /// 在当前 span 上记录已知的字段
+    fn record(&self) {
+        let span = tracing::Span::current();
+        if let Some(worker_id) = &self.worker_id {
+            span.record("worker_id", worker_id.as_str());
+        }
+        if let Some(task_id) = self.task_id {
+            span.record("task_id", task_id);
+        }
+    }
+}
+
+/// 中间件：取出请求上下文并记录到 span 上
+///
+/// 请求体无法解析时不记录，由处理函数返回错误。
+pub async fn record_context(
+    State(state): State<Arc<AppState>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let mut context = RequestContext::from_headers(req.headers());
+    let needs_body = context.worker_id.is_none()
+        && req.method() == Method::POST
+        && req
+            .uri()
+            .path()
+            .strip_prefix(routes::task::PREFIX)
+            .is_some_and(|rest| rest.starts_with('/'));
+    if !needs_body {
+        context.record();
+        let mut req = req;
+        req.extensions_mut().insert(context.clone());
+        let response = next.run(req).await;
+        touch_worker(&state, &context, &response);
+        return response;
+    }
+
+    let (mut parts, body) = req.into_parts();
+    let bytes = match body::to_bytes(body, state.config.max_body_kb * 1024).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(
+                    api_error::<()>("请求体过大".to_string()).with_code(ErrorCode::InvalidRequest),
+                ),
+            )
+                .into_response()
+        }
+    };
+    if let Ok(from_body) = serde_json::from_slice::<RequestContext>(&bytes) {
+        context.worker_id = from_body.worker_id;
+        context.task_id = context.task_id.or(from_body.task_id);
+    }
+
+    context.record();
+    parts.extensions.insert(context.clone());
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+    touch_worker(&state, &context, &response);
+    response
+}
+
+/// Worker的请求成功时记录在线状态（失败的请求可能来自伪造或已被封禁的 worker_id）
+fn touch_worker(state: &AppState, context: &RequestContext, response: &Response) {
+    if let Some(worker_id) = &context.worker_id {
+        if response.status().is_success() {
+            state.presence.touch(worker_id);
+        }
+    }
+}
@@ -0,0 +1,31 @@
+//! 请求关联ID
+//!
+//! 由 `SetRequestIdLayer` 生成（或沿用Worker传入的 `x-request-id`），
+//! 在请求处理期间放入 task-local，供日志和错误响应使用。
+
+use axum::{extract::Request, middleware::Next, response::Response};
+use common::REQUEST_ID_HEADER;
+
+tokio::task_local! {
+    /// 当前请求的关联ID
+    static REQUEST_ID: Option<String>;
+}
+
+/// 从请求头中读取关联ID
+pub fn from_request<B>(req: &axum::http::Request<B>) -> Option<String> {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+/// 获取当前请求的关联ID（不在请求上下文中时返回 None）
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok().flatten()
+}
+
+/// 中间件：将关联ID放入 task-local
+pub async fn scope_request_id(req: Request, next: Next) -> Response {
+    let request_id = from_request(&req);
+    REQUEST_ID.scope(request_id, next.run(req)).await
+}
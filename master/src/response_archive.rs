@@ -0,0 +1,164 @@
+//! 原始响应存档
+//!
+//! 设置 `--response-archive` 后，Master 接受 Worker（`--archive-responses`）通过
+//! `POST /task/responses` 上传的有效ID原始响应，响应体保持 gzip 压缩写入对象存储（本地目录或 S3）。
+//!
+//! 对象按解压后内容的 SHA-256 命名（`responses/sha256/<前两位>/<哈希>.json.gz`），
+//! 内容相同的响应只保存一份；raw_responses 表记录每个ID最新一份响应对应的对象和内容哈希。
+
+use crate::blob::{BlobError, BlobStore};
+use base64::Engine;
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+use sqlx::SqliteConnection;
+use std::io::Read;
+
+/// gzip 数据的文件头
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// 单个响应解压后的最大字节数，超过时拒绝（防止压缩炸弹）
+const MAX_RESPONSE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Worker上传的一份响应
+pub struct DecodedResponse {
+    /// gzip 压缩后的响应体，原样写入存储
+    pub compressed: Vec<u8>,
+    /// 解压后内容的 SHA-256（十六进制）
+    pub content_hash: String,
+}
+
+/// 原始响应存档
+pub struct ResponseArchive {
+    store: Box<dyn BlobStore>,
+}
+
+impl ResponseArchive {
+    pub fn new(store: Box<dyn BlobStore>) -> Self {
+        Self { store }
+    }
+
+    /// 写入一份响应，返回对象名和是否实际写入（内容相同的对象已存在时跳过）
+    ///
+    /// 先查 raw_responses 表中是否已有相同的内容哈希，没有记录时再向存储确认，
+    /// 数据库被清空后也不会重复上传。
+    pub async fn put(
+        &self,
+        conn: &mut SqliteConnection,
+        response: &DecodedResponse,
+    ) -> Result<(String, bool), ArchiveError> {
+        let key = blob_key(&response.content_hash);
+        let known: Option<i64> =
+            sqlx::query_scalar("SELECT 1 FROM raw_responses WHERE content_hash = ? LIMIT 1")
+                .bind(&response.content_hash)
+                .fetch_optional(conn)
+                .await?;
+        if known.is_some() || self.store.exists(&key).await? {
+            return Ok((key, false));
+        }
+        self.store.put(&key, &response.compressed).await?;
+        Ok((key, true))
+    }
+}
+
+/// 内容哈希对应的对象名
+fn blob_key(content_hash: &str) -> String {
+    format!(
+        "responses/sha256/{}/{}.json.gz",
+        &content_hash[..2],
+        content_hash
+    )
+}
+
+/// 写入存档失败
+#[derive(Debug)]
+pub enum ArchiveError {
+    Database(sqlx::Error),
+    Blob(BlobError),
+}
+
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::Database(e) => write!(f, "查询失败: {}", e),
+            ArchiveError::Blob(e) => write!(f, "存储失败: {}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ArchiveError {
+    fn from(e: sqlx::Error) -> Self {
+        ArchiveError::Database(e)
+    }
+}
+
+impl From<BlobError> for ArchiveError {
+    fn from(e: BlobError) -> Self {
+        ArchiveError::Blob(e)
+    }
+}
+
+/// 解码Worker上传的响应体（base64 编码的 gzip 数据），解压一遍校验数据并计算内容哈希
+pub fn decode(body: &str) -> Result<DecodedResponse, String> {
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(body)
+        .map_err(|e| format!("响应体不是有效的 base64: {}", e))?;
+    if !compressed.starts_with(&GZIP_MAGIC) {
+        return Err("响应体不是 gzip 数据".to_string());
+    }
+
+    let mut decoder = GzDecoder::new(compressed.as_slice()).take(MAX_RESPONSE_BYTES + 1);
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let read = decoder
+            .read(&mut buffer)
+            .map_err(|e| format!("响应体解压失败: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        size += read as u64;
+        if size > MAX_RESPONSE_BYTES {
+            return Err(format!("响应体解压后超过 {} 字节", MAX_RESPONSE_BYTES));
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(DecodedResponse {
+        compressed,
+        content_hash: hex::encode(hasher.finalize()),
+    })
+}
+
+/// 记录一个ID最新一份存档的位置
+pub async fn record(
+    conn: &mut SqliteConnection,
+    id: i64,
+    key: &str,
+    response: &DecodedResponse,
+    task_id: i32,
+    worker_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO raw_responses (id, blob_key, size, content_hash, task_id, worker_id)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT (id) DO UPDATE SET
+            blob_key = excluded.blob_key,
+            size = excluded.size,
+            content_hash = excluded.content_hash,
+            task_id = excluded.task_id,
+            worker_id = excluded.worker_id,
+            stored_at = CURRENT_TIMESTAMP
+        "#,
+    )
+    .bind(id)
+    .bind(key)
+    .bind(response.compressed.len() as i64)
+    .bind(&response.content_hash)
+    .bind(task_id)
+    .bind(worker_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
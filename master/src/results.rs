@@ -0,0 +1,255 @@
+//! 有效ID的存储
+//!
+//! 有效ID逐个写入 valid_results 表。有效ID往往成片出现，压缩任务会把连续的一段ID（游程）
+//! 移到 valid_ranges 表中按范围保存，大幅减小表的体积。
+//! 读取结果时需要同时统计两张表；写入时跳过已被范围覆盖的ID，保证同一个ID只出现一次。
+
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
+use std::time::Duration;
+
+/// 压缩时每个事务处理的ID数
+const COMPACT_PAGE_SIZE: i64 = 10000;
+
+/// 写入一个有效ID，`found_at` 为 None 时取当前时间，返回是否新增
+///
+/// 已存在或已被范围覆盖的ID保持不变。
+pub async fn insert(
+    conn: &mut SqliteConnection,
+    id: i64,
+    found_at: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    // 范围互不重叠，只需检查起点不大于该ID的最后一个范围
+    let result = sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO valid_results (id, found_at)
+        SELECT ?1, COALESCE(?2, CURRENT_TIMESTAMP)
+        WHERE NOT EXISTS (
+            SELECT 1 FROM (
+                SELECT end_id FROM valid_ranges WHERE start_id <= ?1 ORDER BY start_id DESC LIMIT 1
+            ) WHERE end_id >= ?1
+        )
+        "#,
+    )
+    .bind(id)
+    .bind(found_at)
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// 有效ID总数
+pub async fn count<'e>(executor: impl SqliteExecutor<'e>) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT (SELECT COUNT(*) FROM valid_results)
+             + (SELECT COALESCE(SUM(end_id - start_id + 1), 0) FROM valid_ranges)
+        "#,
+    )
+    .fetch_one(executor)
+    .await
+}
+
+/// 闭区间 [start_id, end_id] 内的有效ID数
+pub async fn count_between<'e>(
+    executor: impl SqliteExecutor<'e>,
+    start_id: i64,
+    end_id: i64,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT (SELECT COUNT(*) FROM valid_results WHERE id BETWEEN ?1 AND ?2)
+             + (SELECT COALESCE(SUM(MIN(end_id, ?2) - MAX(start_id, ?1) + 1), 0)
+                FROM valid_ranges WHERE start_id <= ?2 AND end_id >= ?1)
+        "#,
+    )
+    .bind(start_id)
+    .bind(end_id)
+    .fetch_one(executor)
+    .await
+}
+
+/// 最大的有效ID
+pub async fn max_id<'e>(executor: impl SqliteExecutor<'e>) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT MAX(high) FROM (SELECT MAX(id) AS high FROM valid_results UNION ALL SELECT MAX(end_id) FROM valid_ranges)",
+    )
+    .fetch_one(executor)
+    .await
+}
+
+/// 一轮压缩的结果
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CompactStats {
+    /// 移入范围表的游程数
+    pub runs: u64,
+    /// 移入范围表的ID数
+    pub ids: u64,
+}
+
+/// 把连续的有效ID压缩为范围
+///
+/// 只处理发现时间早于 `min_age` 的ID，最近的结果仍可按时间查询。
+/// 长度达到 `min_run` 的游程，以及与已有范围相邻（或重复）的游程会被移入 valid_ranges，
+/// 与相邻的范围合并。按ID分页，每页一个事务，不会长时间阻塞提交。
+pub async fn compact(
+    pool: &SqlitePool,
+    min_run: i64,
+    min_age: Duration,
+) -> Result<CompactStats, sqlx::Error> {
+    let mut stats = CompactStats::default();
+    let mut after = i64::MIN;
+    loop {
+        let mut tx = pool.begin().await?;
+        let rows: Vec<(i64, String)> = sqlx::query_as(
+            r#"
+            SELECT id, found_at FROM valid_results
+            WHERE id > ? AND found_at <= datetime('now', ?)
+            ORDER BY id
+            LIMIT ?
+            "#,
+        )
+        .bind(after)
+        .bind(format!("-{} seconds", min_age.as_secs()))
+        .bind(COMPACT_PAGE_SIZE)
+        .fetch_all(&mut *tx)
+        .await?;
+        let full = rows.len() as i64 == COMPACT_PAGE_SIZE;
+
+        // 1. 切分游程；页满时最后一个游程可能延续到下一页，留到下一页处理
+        let mut runs = split_runs(&rows);
+        if full && runs.len() > 1 {
+            runs.pop();
+        }
+        if let Some(&(_, end_id, _)) = runs.last() {
+            after = end_id;
+        }
+
+        // 2. 逐个游程与相邻的范围合并
+        for (start_id, end_id, found_at) in runs {
+            if merge_run(&mut tx, start_id, end_id, found_at, min_run).await? {
+                stats.runs += 1;
+                stats.ids += (end_id - start_id + 1) as u64;
+            }
+        }
+        tx.commit().await?;
+
+        if !full {
+            return Ok(stats);
+        }
+    }
+}
+
+/// 按ID升序的结果切分为游程：(起始ID, 结束ID, 最晚的发现时间)
+fn split_runs(rows: &[(i64, String)]) -> Vec<(i64, i64, &str)> {
+    let mut runs: Vec<(i64, i64, &str)> = Vec::new();
+    for (id, found_at) in rows {
+        match runs.last_mut() {
+            Some(run) if run.1 + 1 == *id => {
+                run.1 = *id;
+                if found_at.as_str() > run.2 {
+                    run.2 = found_at;
+                }
+            }
+            _ => runs.push((*id, *id, found_at)),
+        }
+    }
+    runs
+}
+
+/// 把一个游程并入范围表，返回是否合并
+async fn merge_run(
+    conn: &mut SqliteConnection,
+    start_id: i64,
+    end_id: i64,
+    found_at: &str,
+    min_run: i64,
+) -> Result<bool, sqlx::Error> {
+    let prev: Option<(i64, i64, String)> = sqlx::query_as(
+        "SELECT start_id, end_id, found_at FROM valid_ranges WHERE start_id <= ? ORDER BY start_id DESC LIMIT 1",
+    )
+    .bind(start_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let prev = prev.filter(|&(_, prev_end, _)| prev_end >= start_id - 1);
+
+    let next: Option<(i64, i64, String)> = sqlx::query_as(
+        "SELECT start_id, end_id, found_at FROM valid_ranges WHERE start_id > ? ORDER BY start_id LIMIT 1",
+    )
+    .bind(start_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let next = next.filter(|&(next_start, _, _)| next_start <= end_id + 1);
+
+    if end_id - start_id + 1 < min_run && prev.is_none() && next.is_none() {
+        return Ok(false);
+    }
+
+    let mut merged = (start_id, end_id, found_at.to_string());
+    for (range_start, range_end, range_found_at) in prev.into_iter().chain(next) {
+        sqlx::query("DELETE FROM valid_ranges WHERE start_id = ?")
+            .bind(range_start)
+            .execute(&mut *conn)
+            .await?;
+        merged.0 = merged.0.min(range_start);
+        merged.1 = merged.1.max(range_end);
+        if range_found_at > merged.2 {
+            merged.2 = range_found_at;
+        }
+    }
+
+    sqlx::query("INSERT INTO valid_ranges (start_id, end_id, found_at) VALUES (?, ?, ?)")
+        .bind(merged.0)
+        .bind(merged.1)
+        .bind(&merged.2)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM valid_results WHERE id BETWEEN ? AND ?")
+        .bind(start_id)
+        .bind(end_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(true)
+}
+
+/// 按ID升序列出 (after, end_id] 内的有效ID及发现时间，最多 `limit` 个
+///
+/// 压缩为范围的ID逐个展开，发现时间取范围的 found_at。
+pub async fn list(
+    pool: &SqlitePool,
+    after: i64,
+    end_id: i64,
+    limit: i64,
+) -> Result<Vec<(i64, String)>, sqlx::Error> {
+    let mut rows: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT id, found_at FROM valid_results WHERE id > ? AND id <= ? ORDER BY id LIMIT ?",
+    )
+    .bind(after)
+    .bind(end_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    // 每个范围至少贡献一个ID，取前 limit 个范围就足够
+    let ranges: Vec<(i64, i64, String)> = sqlx::query_as(
+        r#"
+        SELECT start_id, end_id, found_at FROM valid_ranges
+        WHERE end_id > ? AND start_id <= ?
+        ORDER BY start_id
+        LIMIT ?
+        "#,
+    )
+    .bind(after)
+    .bind(end_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    for (range_start, range_end, found_at) in ranges {
+        let start = range_start.max(after + 1);
+        let end = range_end.min(end_id).min(start + limit - 1);
+        rows.extend((start..=end).map(|id| (id, found_at.clone())));
+    }
+
+    rows.sort_unstable_by_key(|(id, _)| *id);
+    rows.truncate(limit.max(0) as usize);
+    Ok(rows)
+}
@@ -0,0 +1,463 @@
+//! 运行中任务的内存视图
+//!
+//! 数据库仍然是持久化的唯一可信来源，这里只是它的一份镜像：
+//! - 启动时从 task_queue 同步
+//! - 每次写数据库的同时更新内存
+//!
+//! 这样热路径上的超时扫描和归属检查不再需要执行 SELECT。
+//!
+//! 租约到期时间在内存中是单调时钟上的 [`Instant`]，不受系统时间跳变影响；
+//! 数据库中的 `lease_expires_at` 是Master按自己的时钟写入的 Unix 毫秒数，只在重启或接管时读取，
+//! 读取时剩余时长不超过一个 [`TASK_TIMEOUT`]。不比较Worker上报的时间，也不比较 SQLite 中的时间字符串。
+
+use sqlx::{FromRow, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex as AsyncMutex, RwLock};
+
+/// 任务租约超时时间（心跳超过该时间未更新即视为超时）
+pub const TASK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 内存中的运行中任务
+#[derive(Debug, Clone)]
+pub struct RunningTask {
+    pub task_id: i32,
+    pub start_id: i64,
+    pub end_id: i64,
+    pub worker_id: String,
+    /// 租约到期时间（None 表示租约已失效，可立即重新分配）
+    pub lease_expires: Option<Instant>,
+    /// 是否为复查任务（重新扫描可疑提交的范围）
+    pub verification: bool,
+    /// 是否为显式ID列表任务（只扫描分配给它的候选ID）
+    pub list: bool,
+    /// 最近一次心跳上报的速度（ID/s）
+    pub speed: Option<f64>,
+    /// 从何时起心跳持续上报零速度（None 表示有进展或未上报）
+    pub stalled_since: Option<Instant>,
+}
+
+impl RunningTask {
+    /// 租约是否已经超时
+    pub fn is_expired(&self) -> bool {
+        self.lease_expires
+            .is_none_or(|deadline| Instant::now() >= deadline)
+    }
+
+    /// 从现在起续约一个 [`TASK_TIMEOUT`]
+    pub fn renew(&mut self) {
+        self.lease_expires = Some(Instant::now() + TASK_TIMEOUT);
+    }
+
+    /// 是否可以拆分（只有普通范围任务可以；显式ID列表任务的ID不连续，复查任务需要完整地重新扫描）
+    pub fn splittable(&self) -> bool {
+        !self.list && !self.verification
+    }
+
+    /// 距最后一次续约（分配或心跳）的时长，按租约到期时间推算（租约已失效时为 None）
+    pub fn heartbeat_age(&self) -> Option<Duration> {
+        let deadline = self.lease_expires?;
+        let now = Instant::now();
+        Some(if deadline >= now {
+            TASK_TIMEOUT.saturating_sub(deadline - now)
+        } else {
+            TASK_TIMEOUT + (now - deadline)
+        })
+    }
+}
+
+/// 当前时间（Unix 毫秒），用于写入数据库中的租约到期时间
+pub fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// 从现在起续约一个 [`TASK_TIMEOUT`] 后的到期时间（Unix 毫秒）
+pub fn lease_expiry_millis() -> i64 {
+    now_millis() + TASK_TIMEOUT.as_millis() as i64
+}
+
+/// 认领超时任务的结果
+#[derive(Debug)]
+pub struct Claim {
+    /// 认领前的任务快照
+    pub previous: RunningTask,
+    /// 分配给新Worker的结束ID；小于原结束ID时，其余部分需要由调用方作为待分配任务插入
+    pub end_id: i64,
+}
+
+impl Claim {
+    /// 是否拆分了原任务
+    pub fn is_split(&self) -> bool {
+        self.end_id < self.previous.end_id
+    }
+}
+
+/// Worker可以领取的任务类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskKinds {
+    /// 普通任务
+    pub normal: bool,
+    /// 复查任务
+    pub verification: bool,
+    /// 显式ID列表任务（需要支持 protocol v3 的Worker）
+    pub list: bool,
+}
+
+impl TaskKinds {
+    /// 不限制任务类型
+    pub const ALL: TaskKinds = TaskKinds {
+        normal: true,
+        verification: true,
+        list: true,
+    };
+
+    /// 是否可以领取该任务
+    pub fn allows(&self, task: &RunningTask) -> bool {
+        if task.verification {
+            self.verification
+        } else if task.list {
+            self.normal && self.list
+        } else {
+            self.normal
+        }
+    }
+}
+
+/// 从数据库加载任务时使用的行结构
+#[derive(FromRow)]
+struct TaskRow {
+    task_id: i32,
+    start_id: i64,
+    end_id: i64,
+    worker_id: String,
+    lease_expires_at: i64,
+    verification: bool,
+    list: bool,
+}
+
+/// 运行中任务表
+#[derive(Default)]
+pub struct RunningTasks {
+    tasks: RwLock<HashMap<i32, RunningTask>>,
+
+    /// 任务的登记和移除记录，与数据库同步时据此跳过查询期间变化过的任务
+    changes: Mutex<Changes>,
+
+    /// 同一时间只进行一次同步
+    syncing: AsyncMutex<()>,
+}
+
+/// 内存中任务的登记和移除记录（修改时需要同时持有任务表的写锁）
+#[derive(Default)]
+struct Changes {
+    /// 每次登记或移除任务时递增
+    epoch: u64,
+
+    /// task_id -> 最近一次登记或移除时的 epoch，每次同步后清理
+    touched: HashMap<i32, u64>,
+}
+
+impl Changes {
+    fn touch(&mut self, task_id: i32) {
+        self.epoch += 1;
+        self.touched.insert(task_id, self.epoch);
+    }
+
+    /// 任务在 `since` 之后是否登记或移除过
+    fn touched_after(&self, task_id: i32, since: u64) -> bool {
+        self.touched
+            .get(&task_id)
+            .is_some_and(|&epoch| epoch > since)
+    }
+}
+
+impl RunningTasks {
+    /// 与数据库中的 task_queue 同步
+    ///
+    /// 只补充内存中缺失的任务、移除数据库中已不存在的任务，
+    /// 已在内存中的任务保持不变（内存中的心跳时间更精确）。
+    /// 查询时不持有锁，不阻塞分配、心跳和提交；查询期间登记或移除过的任务以内存为准，
+    /// 不会被过时的查询结果误删或恢复。
+    /// 返回 (新增数, 移除数)
+    pub async fn sync_from_db(&self, pool: &SqlitePool) -> Result<(usize, usize), sqlx::Error> {
+        let _syncing = self.syncing.lock().await;
+        let since = self.changes.lock().expect("运行中任务记录锁中毒").epoch;
+        let rows = sqlx::query_as::<_, TaskRow>(
+            r#"
+            SELECT task_id, start_id, end_id, worker_id, lease_expires_at,
+                   verification_id IS NOT NULL AS verification,
+                   list_task != 0 AS list
+            FROM task_queue
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut tasks = self.tasks.write().await;
+        let mut changes = self.changes.lock().expect("运行中任务记录锁中毒");
+        let now = Instant::now();
+        let now_millis = now_millis();
+
+        let before = tasks.len();
+        let queued: HashSet<i32> = rows.iter().map(|row| row.task_id).collect();
+        tasks.retain(|&task_id, _| {
+            queued.contains(&task_id) || changes.touched_after(task_id, since)
+        });
+        let removed = before - tasks.len();
+
+        let mut added = 0;
+        for row in rows {
+            if changes.touched_after(row.task_id, since) {
+                continue;
+            }
+            tasks.entry(row.task_id).or_insert_with(|| {
+                added += 1;
+                // 0 表示已释放或尚未分配；系统时间回拨时剩余时长也不超过一个超时周期
+                let remaining =
+                    (row.lease_expires_at - now_millis).clamp(0, TASK_TIMEOUT.as_millis() as i64);
+                let lease_expires = (row.lease_expires_at > 0 && !row.worker_id.is_empty())
+                    .then(|| now + Duration::from_millis(remaining as u64));
+                RunningTask {
+                    task_id: row.task_id,
+                    start_id: row.start_id,
+                    end_id: row.end_id,
+                    worker_id: row.worker_id,
+                    lease_expires,
+                    verification: row.verification,
+                    list: row.list,
+                    speed: None,
+                    stalled_since: None,
+                }
+            });
+        }
+
+        // 之后的同步都从更新的 epoch 开始，更早的记录不再需要
+        changes.touched.retain(|_, &mut epoch| epoch > since);
+        Ok((added, removed))
+    }
+
+    /// 丢弃内存视图，从数据库完整地重新加载，返回加载的任务数
+    ///
+    /// 用于备用节点接管：数据库中的任务和心跳由另一个节点维护，内存中的都已过时。
+    /// 另一个节点写入的到期时间来自它自己的时钟，两台机器的时间可能不一致，
+    /// 因此所有未释放的租约从接管时起重新计算一个完整的超时周期，而不是按写入的时间提前判定超时。
+    pub async fn reload_from_db(&self, pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+        self.tasks.write().await.clear();
+        let (added, _) = self.sync_from_db(pool).await?;
+        for task in self.tasks.write().await.values_mut() {
+            if task.lease_expires.is_some() {
+                task.renew();
+            }
+        }
+        Ok(added)
+    }
+
+    /// 登记一个新分配的任务
+    pub async fn insert(&self, task: RunningTask) {
+        let mut tasks = self.tasks.write().await;
+        self.changes
+            .lock()
+            .expect("运行中任务记录锁中毒")
+            .touch(task.task_id);
+        tasks.insert(task.task_id, task);
+    }
+
+    /// 移除任务（任务已完成）
+    pub async fn remove(&self, task_id: i32) -> Option<RunningTask> {
+        let mut tasks = self.tasks.write().await;
+        self.changes
+            .lock()
+            .expect("运行中任务记录锁中毒")
+            .touch(task_id);
+        tasks.remove(&task_id)
+    }
+
+    /// 获取任务当前的归属Worker
+    pub async fn owner(&self, task_id: i32) -> Option<String> {
+        self.tasks
+            .read()
+            .await
+            .get(&task_id)
+            .map(|task| task.worker_id.clone())
+    }
+
+    /// 刷新心跳并记录上报的速度，任务不存在或Worker不匹配时返回 None
+    ///
+    /// 返回任务已经持续停滞（上报零速度）的时长，有进展时为 `Duration::ZERO`。
+    pub async fn touch(
+        &self,
+        task_id: i32,
+        worker_id: &str,
+        speed: Option<f64>,
+    ) -> Option<Duration> {
+        let mut tasks = self.tasks.write().await;
+        match tasks.get_mut(&task_id) {
+            Some(task) if task.worker_id == worker_id => {
+                let now = Instant::now();
+                task.renew();
+                if speed.is_some() {
+                    task.speed = speed;
+                }
+                // 未上报速度的Worker无法判断是否停滞
+                task.stalled_since = match speed {
+                    Some(speed) if speed <= 0.0 => Some(task.stalled_since.unwrap_or(now)),
+                    _ => None,
+                };
+                Some(
+                    task.stalled_since
+                        .map_or(Duration::ZERO, |since| now.duration_since(since)),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// 把所有有效租约的到期时间顺延 `by`（不超过从现在起一个超时周期），返回顺延的任务数
+    ///
+    /// 维护期间Worker无法发送心跳，结束维护时调用，避免任务因此被判定超时。
+    pub async fn extend_leases(&self, by: Duration) -> usize {
+        let now = Instant::now();
+        let mut tasks = self.tasks.write().await;
+        let mut extended = 0;
+        for task in tasks.values_mut() {
+            let Some(deadline) = task.lease_expires else {
+                continue;
+            };
+            if task.worker_id.is_empty() {
+                continue;
+            }
+            task.lease_expires = Some((deadline + by).min(now + TASK_TIMEOUT));
+            task.stalled_since = task.stalled_since.map(|since| (since + by).min(now));
+            extended += 1;
+        }
+        extended
+    }
+
+    /// 使任务租约立即失效（Worker主动释放），任务不存在或Worker不匹配时返回 false
+    pub async fn expire(&self, task_id: i32, worker_id: &str) -> bool {
+        let mut tasks = self.tasks.write().await;
+        match tasks.get_mut(&task_id) {
+            Some(task) if task.worker_id == worker_id => {
+                task.lease_expires = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 使某个Worker持有的所有任务的租约失效，返回受影响的任务ID
+    pub async fn expire_worker(&self, worker_id: &str) -> Vec<i32> {
+        let mut tasks = self.tasks.write().await;
+        tasks
+            .values_mut()
+            .filter(|task| task.worker_id == worker_id)
+            .map(|task| {
+                task.lease_expires = None;
+                task.task_id
+            })
+            .collect()
+    }
+
+    /// 认领租约最早到期的超时任务，将其归属改为新的Worker
+    ///
+    /// 可拆分的任务超过 `batch_size` 时只认领开头的 `batch_size` 个ID，其余部分由调用方拆成同样大小的
+    /// 待分配任务，一个停滞的大范围可以由多个Worker并行地重新扫描。
+    /// 不可拆分的任务受 `max_len` 限制（ID数，None 表示不限制）；只认领 `kinds` 允许的任务类型。
+    /// 调用方需要把变更写回数据库，写入失败时用 [`RunningTasks::restore`] 回滚。
+    pub async fn claim_timed_out(
+        &self,
+        worker_id: &str,
+        batch_size: i64,
+        max_len: Option<i64>,
+        kinds: TaskKinds,
+    ) -> Option<Claim> {
+        let mut tasks = self.tasks.write().await;
+
+        let task = tasks
+            .values_mut()
+            .filter(|task| task.is_expired())
+            .filter(|task| {
+                task.splittable() || max_len.is_none_or(|max| task.end_id - task.start_id < max)
+            })
+            .filter(|task| kinds.allows(task))
+            .min_by_key(|task| (task.lease_expires, task.start_id))?;
+
+        let previous = task.clone();
+        let batch_size = batch_size.max(1);
+        if task.splittable() && task.end_id - task.start_id >= batch_size {
+            task.end_id = task.start_id + batch_size - 1;
+        }
+        task.worker_id = worker_id.to_string();
+        task.renew();
+        task.speed = None;
+        task.stalled_since = None;
+        Some(Claim {
+            end_id: task.end_id,
+            previous,
+        })
+    }
+
+    /// 列出租约已超时、且仍归属于某个Worker的任务（尚未被重新排队）
+    pub async fn expired(&self) -> Vec<RunningTask> {
+        self.tasks
+            .read()
+            .await
+            .values()
+            .filter(|task| !task.worker_id.is_empty() && task.is_expired())
+            .cloned()
+            .collect()
+    }
+
+    /// 将任务标记为待重新分配（清空归属并截断范围），归属已变化时返回 false
+    pub async fn requeue(&self, task_id: i32, expected_worker: &str, end_id: i64) -> bool {
+        let mut tasks = self.tasks.write().await;
+        match tasks.get_mut(&task_id) {
+            Some(task) if task.worker_id == expected_worker => {
+                task.worker_id.clear();
+                task.end_id = end_id;
+                task.lease_expires = None;
+                task.speed = None;
+                task.stalled_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 用快照覆盖内存中的任务（用于数据库写入失败时回滚）
+    pub async fn restore(&self, task: RunningTask) {
+        let mut tasks = self.tasks.write().await;
+        if let Some(current) = tasks.get_mut(&task.task_id) {
+            *current = task;
+        }
+    }
+
+    /// 所有任务的快照（按任务ID排序）
+    pub async fn snapshot(&self) -> Vec<RunningTask> {
+        let mut tasks: Vec<RunningTask> = self.tasks.read().await.values().cloned().collect();
+        tasks.sort_by_key(|task| task.task_id);
+        tasks
+    }
+
+    /// 持有未超时任务的Worker数
+    pub async fn active_workers(&self) -> usize {
+        let tasks = self.tasks.read().await;
+        let workers: HashSet<&str> = tasks
+            .values()
+            .filter(|task| !task.worker_id.is_empty() && !task.is_expired())
+            .map(|task| task.worker_id.as_str())
+            .collect();
+        workers.len()
+    }
+
+    /// 当前运行中任务数
+    pub async fn len(&self) -> usize {
+        self.tasks.read().await.len()
+    }
+
+    /// 是否没有运行中的任务
+    pub async fn is_empty(&self) -> bool {
+        self.tasks.read().await.is_empty()
+    }
+}
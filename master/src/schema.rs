@@ -0,0 +1,96 @@
+//! 数据库schema管理
+//!
+//! 表结构由 `migrations/` 目录下的 sqlx 迁移文件定义，编译时嵌入二进制。
+//! 新增列或表时添加新的迁移文件即可，启动时自动应用。
+
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::SqlitePool;
+
+/// 嵌入的迁移
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// schema 相关错误
+#[derive(Debug)]
+pub enum SchemaError {
+    /// 数据库的schema版本高于当前程序支持的版本
+    TooNew { database: i64, supported: i64 },
+    /// 执行迁移失败
+    Migrate(MigrateError),
+    /// 数据库错误
+    Database(sqlx::Error),
+}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::TooNew {
+                database,
+                supported,
+            } => write!(
+                f,
+                "数据库schema版本 {} 高于当前程序支持的版本 {}，请升级程序",
+                database, supported
+            ),
+            SchemaError::Migrate(e) => write!(f, "数据库迁移失败: {}", e),
+            SchemaError::Database(e) => write!(f, "数据库错误: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<MigrateError> for SchemaError {
+    fn from(e: MigrateError) -> Self {
+        SchemaError::Migrate(e)
+    }
+}
+
+impl From<sqlx::Error> for SchemaError {
+    fn from(e: sqlx::Error) -> Self {
+        SchemaError::Database(e)
+    }
+}
+
+/// 当前程序支持的最新schema版本
+pub fn latest_version() -> i64 {
+    MIGRATOR
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0)
+}
+
+/// 数据库当前的schema版本（未执行过迁移时为0）
+pub async fn schema_version(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    let table_exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !table_exists {
+        return Ok(0);
+    }
+
+    let version: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+            .fetch_one(pool)
+            .await?;
+
+    Ok(version.unwrap_or(0))
+}
+
+/// 检查schema版本并应用未执行的迁移，返回迁移后的版本
+pub async fn migrate(pool: &SqlitePool) -> Result<i64, SchemaError> {
+    let database = schema_version(pool).await?;
+    let supported = latest_version();
+    if database > supported {
+        return Err(SchemaError::TooNew {
+            database,
+            supported,
+        });
+    }
+
+    MIGRATOR.run(pool).await?;
+    Ok(schema_version(pool).await?)
+}
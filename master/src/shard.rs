@@ -0,0 +1,250 @@
+//! ID空间分片
+//!
+//! 多个互不相干的 Master（各自使用独立的数据库）可以共同覆盖同一个巨大的ID空间：
+//! ID空间按 `--shard-block-size` 切成连续的块，第 i 块（从0开始）属于第 `i % count + 1` 个分片。
+//! 配置了 `--shard 2/4` 的 Master 只从属于第2个分片的块中切分新任务，游标遇到其他分片的块时直接跳过。
+//!
+//! 所有 Master 必须使用相同的分片数和块大小，否则各分片的范围会重叠或遗漏。
+//!
+//! 启动时把分片配置写入 `job_settings` 表，`init check` 据此跳过其他分片的块，不把它们当作覆盖空档。
+
+use sqlx::SqlitePool;
+use std::str::FromStr;
+
+/// 分片配置在 `job_settings` 表中的名称
+const SHARD_SETTING: &str = "shard";
+
+/// 块大小在 `job_settings` 表中的名称
+const SHARD_BLOCK_SIZE_SETTING: &str = "shard_block_size";
+
+/// 分片配置，形如 `2/4`（共4个分片中的第2个，从1开始编号）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardSpec {
+    /// 分片序号（从1开始）
+    pub index: i64,
+    /// 分片总数
+    pub count: i64,
+}
+
+impl FromStr for ShardSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, count) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| format!("分片格式应为 序号/总数（如 2/4）: {}", s))?;
+        let index: i64 = index
+            .trim()
+            .parse()
+            .map_err(|_| format!("无效的分片序号: {}", index))?;
+        let count: i64 = count
+            .trim()
+            .parse()
+            .map_err(|_| format!("无效的分片总数: {}", count))?;
+        if count < 1 || index < 1 || index > count {
+            return Err(format!("分片序号必须在 1 到 {} 之间: {}", count.max(1), s));
+        }
+        Ok(Self { index, count })
+    }
+}
+
+impl std::fmt::Display for ShardSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// 本 Master 负责的分片
+#[derive(Debug, Clone, Copy)]
+pub struct Shard {
+    pub spec: ShardSpec,
+    /// 每个块的ID数
+    pub block_size: i64,
+}
+
+impl Shard {
+    /// `id` 所在的块是否属于本分片
+    pub fn contains(&self, id: i64) -> bool {
+        id.div_euclid(self.block_size).rem_euclid(self.spec.count) == self.spec.index - 1
+    }
+
+    /// 不小于 `id` 的第一个属于本分片的ID
+    pub fn align(&self, id: i64) -> i64 {
+        if self.contains(id) {
+            return id;
+        }
+        let block = id.div_euclid(self.block_size);
+        let offset = (self.spec.index - 1 - block).rem_euclid(self.spec.count);
+        (block + offset) * self.block_size
+    }
+
+    /// `id` 所在块的最后一个ID
+    pub fn block_end(&self, id: i64) -> i64 {
+        (id.div_euclid(self.block_size) + 1) * self.block_size - 1
+    }
+
+    /// 闭区间 [start_id, end_id] 中属于本分片的部分
+    pub fn owned_parts(&self, start_id: i64, end_id: i64) -> Vec<(i64, i64)> {
+        let mut parts = Vec::new();
+        let mut id = self.align(start_id);
+        while id <= end_id {
+            let end = self.block_end(id).min(end_id);
+            parts.push((id, end));
+            if end == end_id {
+                break;
+            }
+            id = self.align(end + 1);
+        }
+        parts
+    }
+}
+
+/// 记录本次启动的分片配置（不分片时删除），供 `init check` 等离线工具读取
+pub async fn save(pool: &SqlitePool, shard: Option<Shard>) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    match shard {
+        Some(shard) => {
+            for (name, value) in [
+                (SHARD_SETTING, shard.spec.to_string()),
+                (SHARD_BLOCK_SIZE_SETTING, shard.block_size.to_string()),
+            ] {
+                sqlx::query(
+                    "INSERT INTO job_settings (name, value) VALUES (?, ?)
+                     ON CONFLICT(name) DO UPDATE SET value = excluded.value",
+                )
+                .bind(name)
+                .bind(value)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        None => {
+            sqlx::query("DELETE FROM job_settings WHERE name IN (?, ?)")
+                .bind(SHARD_SETTING)
+                .bind(SHARD_BLOCK_SIZE_SETTING)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+    tx.commit().await
+}
+
+/// 读取 Master 最近一次启动时的分片配置，不分片或无法解析时为 None
+pub async fn load(pool: &SqlitePool) -> Result<Option<Shard>, sqlx::Error> {
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT name, value FROM job_settings WHERE name IN (?, ?)")
+            .bind(SHARD_SETTING)
+            .bind(SHARD_BLOCK_SIZE_SETTING)
+            .fetch_all(pool)
+            .await?;
+    let value = |name: &str| {
+        rows.iter()
+            .find(|(row_name, _)| row_name == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let spec = value(SHARD_SETTING).and_then(|spec| spec.parse::<ShardSpec>().ok());
+    let block_size = value(SHARD_BLOCK_SIZE_SETTING)
+        .and_then(|size| size.parse::<i64>().ok())
+        .filter(|size| *size > 0);
+    Ok(spec
+        .zip(block_size)
+        .map(|(spec, block_size)| Shard { spec, block_size }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shard(index: i64, count: i64, block_size: i64) -> Shard {
+        Shard {
+            spec: ShardSpec { index, count },
+            block_size,
+        }
+    }
+
+    #[test]
+    fn parse_spec() {
+        assert_eq!(
+            "2/4".parse::<ShardSpec>(),
+            Ok(ShardSpec { index: 2, count: 4 })
+        );
+        assert_eq!(" 1 / 1 ".parse::<ShardSpec>().unwrap().to_string(), "1/1");
+        for invalid in ["0/4", "5/4", "1/0", "2", "a/4", "-1/4"] {
+            assert!(invalid.parse::<ShardSpec>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn align_across_block_boundaries() {
+        // 块大小10，第2个分片（共3个）负责块 1、4、7……即 [10, 19]、[40, 49]……
+        let shard = shard(2, 3, 10);
+        assert_eq!(shard.align(0), 10);
+        assert_eq!(shard.align(9), 10);
+        assert_eq!(shard.align(10), 10);
+        assert_eq!(shard.align(19), 19);
+        assert_eq!(shard.align(20), 40);
+        assert_eq!(shard.align(39), 40);
+        assert_eq!(shard.block_end(10), 19);
+        assert_eq!(shard.block_end(19), 19);
+        assert_eq!(shard.block_end(40), 49);
+    }
+
+    #[test]
+    fn last_shard() {
+        let shard = shard(3, 3, 10);
+        assert!(!shard.contains(19));
+        assert!(shard.contains(20));
+        assert!(shard.contains(29));
+        assert!(!shard.contains(30));
+        assert_eq!(shard.align(0), 20);
+        assert_eq!(shard.align(25), 25);
+        assert_eq!(shard.align(30), 50);
+    }
+
+    #[test]
+    fn block_size_one() {
+        let shard = shard(1, 2, 1);
+        assert!(shard.contains(0));
+        assert!(!shard.contains(1));
+        assert_eq!(shard.align(1), 2);
+        assert_eq!(shard.align(2), 2);
+        assert_eq!(shard.block_end(5), 5);
+    }
+
+    #[test]
+    fn shards_cover_every_id_once() {
+        for (count, block_size) in [(1, 7), (3, 10), (4, 1), (5, 3)] {
+            let mut owners = vec![0; 200];
+            for index in 1..=count {
+                let shard = shard(index, count, block_size);
+                // 按游标的方式逐块前进：对齐到本分片，取到块末尾
+                let mut cursor = 0;
+                while cursor < owners.len() as i64 {
+                    let start = shard.align(cursor);
+                    let end = shard.block_end(start);
+                    for id in start..=end.min(owners.len() as i64 - 1) {
+                        assert!(shard.contains(id));
+                        owners[id as usize] += 1;
+                    }
+                    cursor = end + 1;
+                }
+            }
+            assert!(
+                owners.iter().all(|owners| *owners == 1),
+                "{} 个分片、块大小 {} 时有遗漏或重叠",
+                count,
+                block_size
+            );
+        }
+    }
+
+    #[test]
+    fn owned_parts_skip_other_shards() {
+        // 块大小10，第2个分片（共3个）负责 [10, 19]、[40, 49]……
+        let shard = shard(2, 3, 10);
+        assert_eq!(shard.owned_parts(0, 45), vec![(10, 19), (40, 45)]);
+        assert_eq!(shard.owned_parts(15, 17), vec![(15, 17)]);
+        assert!(shard.owned_parts(20, 39).is_empty());
+    }
+}
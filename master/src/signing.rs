@@ -0,0 +1,100 @@
+//! 任务分配签名
+//!
+//! Master分配任务时用服务端密钥对 `task_id|start_id-end_id|worker_id|lease` 计算 HMAC-SHA256，
+//! Worker提交结果时必须带回 lease 和签名。这样即使猜到了任务ID，
+//! 也无法为从未分配给自己的任务伪造结果。
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::SqlitePool;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 签名密钥在 `server_secrets` 表中的名称
+const SECRET_NAME: &str = "assignment_signing_key";
+
+/// 任务分配签名器
+pub struct AssignmentSigner {
+    key: Vec<u8>,
+}
+
+impl AssignmentSigner {
+    /// 使用命令行指定的密钥，未指定时从数据库加载（首次启动时生成并保存）
+    pub async fn load(pool: &SqlitePool, configured: Option<&str>) -> Result<Self, sqlx::Error> {
+        if let Some(key) = configured {
+            return Ok(Self {
+                key: key.as_bytes().to_vec(),
+            });
+        }
+
+        // 主备节点同时启动时只有一个能写入，之后都读取同一个值
+        let generated = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        sqlx::query("INSERT OR IGNORE INTO server_secrets (name, value) VALUES (?, ?)")
+            .bind(SECRET_NAME)
+            .bind(&generated)
+            .execute(pool)
+            .await?;
+        let key: String = sqlx::query_scalar("SELECT value FROM server_secrets WHERE name = ?")
+            .bind(SECRET_NAME)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(Self {
+            key: key.into_bytes(),
+        })
+    }
+
+    /// 计算任务分配的签名（十六进制）
+    pub fn sign(
+        &self,
+        task_id: i32,
+        start_id: i64,
+        end_id: i64,
+        worker_id: &str,
+        lease: i64,
+    ) -> String {
+        let mac = self.mac(task_id, start_id, end_id, worker_id, lease);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// 校验签名（常数时间比较）
+    pub fn verify(
+        &self,
+        task_id: i32,
+        start_id: i64,
+        end_id: i64,
+        worker_id: &str,
+        lease: i64,
+        signature: &str,
+    ) -> bool {
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        self.mac(task_id, start_id, end_id, worker_id, lease)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+
+    fn mac(
+        &self,
+        task_id: i32,
+        start_id: i64,
+        end_id: i64,
+        worker_id: &str,
+        lease: i64,
+    ) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC 接受任意长度的密钥");
+        mac.update(
+            format!(
+                "{}|{}-{}|{}|{}",
+                task_id, start_id, end_id, worker_id, lease
+            )
+            .as_bytes(),
+        );
+        mac
+    }
+}
@@ -0,0 +1,185 @@
+//! Worker速度的历史
+//!
+//! 单个任务的速度可能因上游抖动、任务被中途收回等原因偏离很大。Master在每次提交时按任务的ID数
+//! 和从分配到提交的耗时测得Worker的速度，按Worker维护指数加权移动平均（EWMA），用平滑后的速度
+//! 计算批次大小，避免一次异常的任务导致批次大小剧烈波动。
+//!
+//! 平均值持久化在 worker_speeds 表中：Master重启或切换主备后从数据库恢复，Worker重启后不再上报
+//! `last_performance`，第一次获取任务时也能按历史速度分配。没有历史时才使用Worker上报的速度。
+
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 内存中最多记录的Worker数，超过时淘汰最久没有更新的一个（加载时也只取最近更新的这么多个）
+const MAX_TRACKED_WORKERS: usize = 4096;
+
+/// 耗时短于该值的任务不计入速度（固定开销占比太大）
+pub const MIN_SAMPLE_MS: i64 = 1000;
+
+/// Worker上报的速度允许的上限：参考速度的 `tolerance` 倍
+///
+/// 参考速度取Master测得的速度，没有时取默认速度。测得的速度包含请求和提交的开销，
+/// Worker也可能刚提高了并发数，因此上报值略高于测得值是正常的，只截断明显不合理的值。
+pub fn reported_speed_cap(reference: u32, tolerance: f64) -> u32 {
+    (f64::from(reference.max(1)) * tolerance).min(f64::from(u32::MAX)) as u32
+}
+
+/// 按Worker平滑测得的速度
+pub struct SpeedSmoother {
+    /// 最新一次测得的速度的权重（0 到 1，1 表示不平滑）
+    alpha: f64,
+    averages: Mutex<Averages>,
+}
+
+/// 各Worker的平均速度及其最近一次更新的先后
+#[derive(Default)]
+struct Averages {
+    /// worker_id -> (平均速度, 最近一次更新的序号)
+    entries: HashMap<String, (f64, u64)>,
+
+    /// 下一次更新的序号
+    clock: u64,
+}
+
+impl Averages {
+    fn from_rows(rows: Vec<(String, f64)>) -> Self {
+        // 行按更新时间倒序，越新的序号越大
+        let clock = rows.len() as u64;
+        let entries = rows
+            .into_iter()
+            .enumerate()
+            .map(|(index, (worker_id, speed))| (worker_id, (speed, clock - index as u64)))
+            .collect();
+        Self {
+            entries,
+            clock: clock + 1,
+        }
+    }
+
+    /// 把新的速度计入平均值；`history` 是内存中没有该Worker时从数据库读到的平均值
+    fn update(&mut self, worker_id: &str, speed: f64, alpha: f64, history: Option<f64>) -> f64 {
+        let seen = self.clock;
+        self.clock += 1;
+        if let Some((average, last_seen)) = self.entries.get_mut(worker_id) {
+            *average += alpha * (speed - *average);
+            *last_seen = seen;
+            return *average;
+        }
+
+        if self.entries.len() >= MAX_TRACKED_WORKERS {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_seen))| *last_seen)
+                .map(|(worker_id, _)| worker_id.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        let average = history.map_or(speed, |average| average + alpha * (speed - average));
+        self.entries.insert(worker_id.to_string(), (average, seen));
+        average
+    }
+}
+
+impl SpeedSmoother {
+    /// 从数据库加载各Worker的历史速度
+    pub async fn load(pool: &SqlitePool, alpha: f64) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            alpha,
+            averages: Mutex::new(Averages::from_rows(fetch_all(pool).await?)),
+        })
+    }
+
+    /// 从数据库重新加载（其他节点可能修改过）
+    pub async fn reload(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        let averages = Averages::from_rows(fetch_all(pool).await?);
+        *self.averages.lock().expect("速度平滑锁中毒") = averages;
+        Ok(())
+    }
+
+    /// Worker平滑后的速度，没有历史时为 None
+    pub fn get(&self, worker_id: &str) -> Option<u32> {
+        self.averages
+            .lock()
+            .expect("速度平滑锁中毒")
+            .entries
+            .get(worker_id)
+            .map(|(average, _)| average.round() as u32)
+    }
+
+    /// 记录一次测得的速度并写入数据库，返回平滑后的速度
+    pub async fn record(
+        &self,
+        pool: &SqlitePool,
+        worker_id: &str,
+        speed: f64,
+    ) -> Result<u32, sqlx::Error> {
+        let tracked = self
+            .averages
+            .lock()
+            .expect("速度平滑锁中毒")
+            .entries
+            .contains_key(worker_id);
+        // 被淘汰出内存的Worker从数据库取回平均值，不会被一次测得的速度覆盖
+        let history = if tracked {
+            None
+        } else {
+            sqlx::query_scalar("SELECT speed FROM worker_speeds WHERE worker_id = ?")
+                .bind(worker_id)
+                .fetch_optional(pool)
+                .await?
+        };
+        let average = self
+            .averages
+            .lock()
+            .expect("速度平滑锁中毒")
+            .update(worker_id, speed, self.alpha, history);
+
+        sqlx::query(
+            "INSERT INTO worker_speeds (worker_id, speed, last_speed, samples, updated_at)
+             VALUES (?, ?, ?, 1, CURRENT_TIMESTAMP)
+             ON CONFLICT(worker_id) DO UPDATE SET
+                 speed = excluded.speed,
+                 last_speed = excluded.last_speed,
+                 samples = samples + 1,
+                 updated_at = excluded.updated_at",
+        )
+        .bind(worker_id)
+        .bind(average)
+        .bind(speed)
+        .execute(pool)
+        .await?;
+        Ok(average.round() as u32)
+    }
+}
+
+async fn fetch_all(pool: &SqlitePool) -> Result<Vec<(String, f64)>, sqlx::Error> {
+    sqlx::query_as("SELECT worker_id, speed FROM worker_speeds ORDER BY updated_at DESC LIMIT ?")
+        .bind(MAX_TRACKED_WORKERS as i64)
+        .fetch_all(pool)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow_evicts_only_the_stalest_worker() {
+        let rows = (0..MAX_TRACKED_WORKERS)
+            .map(|index| (format!("worker-{}", index), 100.0))
+            .collect();
+        // 行按更新时间倒序，最后一行最久没有更新
+        let mut averages = Averages::from_rows(rows);
+        let stalest = format!("worker-{}", MAX_TRACKED_WORKERS - 1);
+        averages.update("worker-0", 200.0, 0.5, None);
+
+        assert_eq!(averages.update("new", 50.0, 0.5, Some(150.0)), 100.0);
+        assert_eq!(averages.entries.len(), MAX_TRACKED_WORKERS);
+        assert!(!averages.entries.contains_key(&stalest));
+        assert_eq!(averages.entries["worker-0"].0, 150.0);
+        assert_eq!(averages.entries["worker-1"].0, 100.0);
+    }
+}
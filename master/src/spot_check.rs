@@ -0,0 +1,197 @@
+//! 抽查Worker提交的结果
+//!
+//! 启用 `--spot-check-sample` 后，每次提交成功时Master用与Worker相同的探测逻辑
+//! （[`common::probe`]）在后台重新检查该范围中随机抽取的少量ID：
+//! 一半取自Worker上报的有效ID，其余取自范围内的其他ID。
+//! 结论与Worker一致的比例按Worker累计在 `spot_checks` 表中，不一致的明细记入 `spot_check_mismatches`。
+//! Worker漏报的有效ID会直接补进结果表。
+
+use crate::metrics::Metrics;
+use crate::AppState;
+use common::ids::IdKind;
+use common::probe::{self, Outcome, TargetSpec};
+use master::results;
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info, warn};
+
+/// 单个ID探测结果不确定（appId 不匹配或超时）时的最大重试次数
+const MAX_PROBE_RETRIES: u32 = 3;
+
+/// 抽查器
+pub struct SpotChecker {
+    client: reqwest::Client,
+    sample: usize,
+    permits: Arc<Semaphore>,
+}
+
+/// 一次抽查的对象
+pub struct SpotCheck {
+    pub task_id: i32,
+    pub worker_id: String,
+    pub start_id: i64,
+    pub end_id: i64,
+    /// 显式ID列表任务的ID列表（为空时抽查整个范围）
+    pub ids: Vec<i64>,
+    pub valid_ids: Vec<i64>,
+}
+
+impl SpotChecker {
+    /// `sample` 为每个范围抽查的ID数，`concurrency` 为同时进行的抽查数
+    pub fn new(sample: usize, concurrency: usize) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            sample,
+            permits: Arc::new(Semaphore::new(concurrency.max(1))),
+        }
+    }
+
+    /// 从范围（或ID列表）中抽取要检查的ID，返回 (ID, Worker是否上报为有效)
+    fn pick(&self, check: &SpotCheck) -> Vec<(i64, bool)> {
+        let mut rng = rand::rng();
+        let claimed = check.valid_ids.choose_multiple(&mut rng, self.sample / 2);
+        let valid: HashSet<i64> = check.valid_ids.iter().copied().collect();
+
+        let mut picked: Vec<(i64, bool)> = claimed.map(|id| (*id, true)).collect();
+        let mut seen: HashSet<i64> = picked.iter().map(|(id, _)| *id).collect();
+        let len = if check.ids.is_empty() {
+            (check.end_id - check.start_id + 1) as usize
+        } else {
+            check.ids.len()
+        };
+        let wanted = self.sample.min(len);
+        let mut attempts = 0;
+        while picked.len() < wanted && attempts < wanted * 4 {
+            attempts += 1;
+            let id = match check.ids.choose(&mut rng) {
+                Some(id) => *id,
+                None => rng.random_range(check.start_id..=check.end_id),
+            };
+            if seen.insert(id) {
+                picked.push((id, valid.contains(&id)));
+            }
+        }
+        picked
+    }
+}
+
+/// 在后台抽查一次提交；抽查繁忙时跳过
+pub fn spawn_spot_check(state: Arc<AppState>, check: SpotCheck) {
+    let Some(checker) = &state.spot_checker else {
+        return;
+    };
+    let Ok(permit) = Arc::clone(&checker.permits).try_acquire_owned() else {
+        debug!("抽查繁忙，跳过任务 {}", check.task_id);
+        return;
+    };
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        if let Err(e) = run_spot_check(&state, &check).await {
+            error!("抽查任务 {} 失败: {}", check.task_id, e);
+        }
+    });
+}
+
+async fn run_spot_check(state: &AppState, check: &SpotCheck) -> Result<(), sqlx::Error> {
+    let Some(checker) = &state.spot_checker else {
+        return Ok(());
+    };
+
+    // 1. 逐个探测抽取的ID，结果不确定的跳过；配置了探测目标时使用其中一个
+    let target = state.targets.next();
+    let id_kind = &state.id_kind;
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+    for (id, claimed_valid) in checker.pick(check) {
+        let probe = probe_with_retries(&checker.client, target.as_ref(), id_kind, id);
+        let Some(actual) = probe.await else {
+            continue;
+        };
+        checked += 1;
+        Metrics::incr(&state.metrics.spot_checks);
+        if actual != claimed_valid {
+            Metrics::incr(&state.metrics.spot_check_mismatches);
+            mismatches.push((id, claimed_valid));
+        }
+    }
+    if checked == 0 {
+        return Ok(());
+    }
+
+    // 2. 记录统计和不一致的明细，补上Worker漏报的有效ID
+    let mut tx = state.db_pool.begin().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO spot_checks (worker_id, checked, agreed, last_checked_at)
+        VALUES (?, ?, ?, CURRENT_TIMESTAMP)
+        ON CONFLICT(worker_id) DO UPDATE SET
+            checked = checked + excluded.checked,
+            agreed = agreed + excluded.agreed,
+            last_checked_at = excluded.last_checked_at
+        "#,
+    )
+    .bind(&check.worker_id)
+    .bind(checked as i64)
+    .bind((checked - mismatches.len()) as i64)
+    .execute(&mut *tx)
+    .await?;
+
+    for (id, claimed_valid) in &mismatches {
+        warn!(
+            "抽查不一致: 任务 {} (Worker {}) 的ID {} 上报为{}，抽查结果为{}",
+            check.task_id,
+            check.worker_id,
+            id,
+            if *claimed_valid { "有效" } else { "无效" },
+            if *claimed_valid { "无效" } else { "有效" }
+        );
+        sqlx::query(
+            "INSERT INTO spot_check_mismatches (worker_id, task_id, item_id, claimed_valid) VALUES (?, ?, ?, ?)",
+        )
+        .bind(&check.worker_id)
+        .bind(check.task_id)
+        .bind(id)
+        .bind(claimed_valid)
+        .execute(&mut *tx)
+        .await?;
+
+        if !claimed_valid {
+            results::insert(&mut tx, *id, None).await?;
+        }
+    }
+    tx.commit().await?;
+
+    info!(
+        "抽查任务 {} (Worker {}): 检查 {} 个ID，{} 个不一致",
+        check.task_id,
+        check.worker_id,
+        checked,
+        mismatches.len()
+    );
+    Ok(())
+}
+
+/// 探测一个ID，appId 不匹配或超时时重试，仍不确定时返回 None
+async fn probe_with_retries(
+    client: &reqwest::Client,
+    target: Option<&TargetSpec>,
+    id_kind: &IdKind,
+    id: i64,
+) -> Option<bool> {
+    for _ in 0..=MAX_PROBE_RETRIES {
+        let result = match target {
+            Some(target) => probe::check_target(client, target, &id_kind.format(id), id).await,
+            None => probe::check_id(client, id).await,
+        };
+        match result {
+            Outcome::Valid(_) => return Some(true),
+            Outcome::Invalid | Outcome::Marked(_) => return Some(false),
+            Outcome::Retry | Outcome::Blocked => {}
+        }
+    }
+    None
+}
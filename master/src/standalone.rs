@@ -0,0 +1,229 @@
+//! 单机模式
+//!
+//! 个人的小规模扫描不需要分别部署Master和Worker：`master standalone --range 0..10000000 --out results.csv`
+//! 在同一个进程中启动Master（数据库照常由 `-d` 指定）和一个Worker，游标到达范围终点且所有任务完成后，
+//! 把范围内的有效ID导出为 CSV 并退出。
+//!
+//! Master的其他参数（如 `--target`、`--id-kind`）写在 `standalone` 之前，内嵌Worker的参数写在 `--` 之后。
+//! 中途按 ctrl+c 时Worker完成当前任务后退出，已发现的结果同样导出；用同一个数据库再次运行会从中断处继续。
+
+use crate::{init_telemetry, listen, serve, start, Config};
+use clap::{Args, Parser};
+use master::results;
+use sqlx::SqlitePool;
+use std::io::{BufWriter, Write};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+/// 检查扫描是否完成的间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 输出扫描进度的间隔
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 导出时每次从数据库读取的结果数
+const EXPORT_PAGE_SIZE: i64 = 10000;
+
+/// 内嵌Worker的ID（固定，重新运行时沿用Master测得的速度）
+const WORKER_ID: &str = "standalone";
+
+/// 单机模式的参数
+#[derive(Args, Debug)]
+pub struct StandaloneArgs {
+    /// 扫描的ID范围（左闭右开，如 0..10000000）
+    #[arg(long)]
+    range: ScanRange,
+
+    /// 有效ID导出到的 CSV 文件（id,found_at），已存在时覆盖
+    #[arg(long)]
+    out: PathBuf,
+
+    /// 传给内嵌Worker的参数（写在 `--` 之后，如 `-- -c 20`）
+    #[arg(last = true)]
+    worker_args: Vec<String>,
+}
+
+/// 左闭右开的ID范围
+#[derive(Debug, Clone, Copy)]
+struct ScanRange {
+    start: i64,
+    end: i64,
+}
+
+impl FromStr for ScanRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once("..")
+            .ok_or_else(|| format!("范围的格式应为 START..END: {}", s))?;
+        let start: i64 = start
+            .trim()
+            .parse()
+            .map_err(|e| format!("无效的起始ID {}: {}", start, e))?;
+        let end: i64 = end
+            .trim()
+            .parse()
+            .map_err(|e| format!("无效的结束ID {}: {}", end, e))?;
+        if start < 0 || start >= end {
+            return Err(format!(
+                "无效的范围 {}：起始ID不能为负数，且必须小于结束ID",
+                s
+            ));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+/// 运行单机模式，扫描完成或Worker退出后导出结果
+pub async fn run(
+    mut config: Config,
+    args: StandaloneArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _telemetry = init_telemetry(&config, "pa_standalone")?;
+    let range = args.range;
+    if config.end_id.is_some() {
+        return Err("单机模式用 --range 指定终点，不能同时设置 --end-id".into());
+    }
+    config.end_id = Some(range.end);
+    info!(
+        "单机模式: 扫描 [{}, {})，结果导出到 {}",
+        range.start,
+        range.end,
+        args.out.display()
+    );
+
+    let state = start(config).await?;
+    if !state.id_kind.is_sequential() {
+        return Err(format!("单机模式按范围扫描，不支持 {} 类型的ID", state.id_kind).into());
+    }
+
+    // 新数据库的游标从范围起点开始；用同一个数据库再次运行时从上次的位置继续
+    sqlx::query("UPDATE global_cursor SET next_start_id = ? WHERE id = 1 AND next_start_id < ?")
+        .bind(range.start)
+        .bind(range.start)
+        .execute(&state.db_pool)
+        .await?;
+    let cursor: i64 = sqlx::query_scalar("SELECT next_start_id FROM global_cursor WHERE id = 1")
+        .fetch_one(&state.db_pool)
+        .await?;
+    if cursor > range.start {
+        info!("继续之前的扫描，游标位于 {}", cursor);
+    }
+
+    // 内嵌Worker通过本机地址访问Master
+    let listener = listen(&state.config).await?;
+    let mut addr = listener.local_addr()?;
+    if addr.ip().is_unspecified() {
+        addr.set_ip(Ipv4Addr::LOCALHOST.into());
+    }
+    let mut worker_argv = vec![
+        "worker".to_string(),
+        "--master-url".to_string(),
+        format!("http://{}", addr),
+        "--worker-id".to_string(),
+        WORKER_ID.to_string(),
+    ];
+    worker_argv.extend(args.worker_args);
+    let worker_config = worker::Config::try_parse_from(worker_argv)?;
+
+    tokio::select! {
+        result = serve(Arc::clone(&state), listener) => {
+            result?;
+            return Err("Master服务意外停止".into());
+        }
+        result = worker::run(worker_config) => {
+            result?;
+            warn!("Worker已退出，扫描尚未完成，用同一个数据库再次运行可以继续");
+        }
+        result = wait_until_done(&state.read_pool, range) => {
+            result?;
+            info!("范围 [{}, {}) 已扫描完成", range.start, range.end);
+        }
+    }
+
+    let unresolved: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(SUM(unknown_count + blocked_count), 0) FROM task_archive
+        WHERE requeued_at IS NULL AND start_id >= ? AND end_id < ?
+        "#,
+    )
+    .bind(range.start)
+    .bind(range.end)
+    .fetch_one(&state.read_pool)
+    .await?;
+    if unresolved > 0 {
+        warn!(
+            "有 {} 个ID没有得到结论（上游出错或拒绝访问），不在导出结果中",
+            unresolved
+        );
+    }
+
+    let exported = export(&state.read_pool, range, &args.out).await?;
+    info!("✓ 已导出 {} 个有效ID到 {}", exported, args.out.display());
+    Ok(())
+}
+
+/// 等待游标到达范围终点且队列中没有任务，期间定期输出进度
+async fn wait_until_done(pool: &SqlitePool, range: ScanRange) -> Result<(), sqlx::Error> {
+    let mut last_progress = Instant::now();
+    loop {
+        let (cursor, tasks): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT (SELECT next_start_id FROM global_cursor WHERE id = 1),
+                   (SELECT COUNT(*) FROM task_queue)
+            "#,
+        )
+        .fetch_one(pool)
+        .await?;
+        if cursor >= range.end && tasks == 0 {
+            return Ok(());
+        }
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            let allocated = cursor.clamp(range.start, range.end) - range.start;
+            let found = results::count_between(pool, range.start, range.end - 1).await?;
+            info!(
+                "扫描进度: 已分配 {}/{} 个ID（{:.1}%），未完成任务 {} 个，发现 {} 个有效ID",
+                allocated,
+                range.end - range.start,
+                allocated as f64 * 100.0 / (range.end - range.start) as f64,
+                tasks,
+                found
+            );
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// 把范围内的有效ID按ID顺序写入 CSV，返回写入的条数
+async fn export(
+    pool: &SqlitePool,
+    range: ScanRange,
+    out: &Path,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut writer = BufWriter::new(std::fs::File::create(out)?);
+    writeln!(writer, "id,found_at")?;
+
+    let mut after = range.start - 1;
+    let mut total = 0;
+    loop {
+        let page = results::list(pool, after, range.end - 1, EXPORT_PAGE_SIZE).await?;
+        for (id, found_at) in &page {
+            writeln!(writer, "{},{}", id, found_at)?;
+        }
+        total += page.len();
+        match page.last() {
+            Some((id, _)) if page.len() as i64 == EXPORT_PAGE_SIZE => after = *id,
+            _ => break,
+        }
+    }
+    writer.flush()?;
+    Ok(total)
+}
@@ -0,0 +1,61 @@
+//! Worker主机的系统状态
+//!
+//! Worker启用 `--report-system` 后随心跳上报负载、内存、交换空间和网卡错误计数。
+//! 这里只在内存中保留每个Worker最近一次的采样，以及与上一次采样相比网卡错误和丢包的增长速度，
+//! 供集群概览展示。
+
+use common::admin::WorkerSystemStatus;
+use common::SystemSample;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 超过该时长没有新采样的Worker不再保留
+const RETENTION: Duration = Duration::from_secs(24 * 3600);
+
+struct Latest {
+    sample: SystemSample,
+    at: Instant,
+    net_errors_per_sec: Option<f64>,
+}
+
+/// 各Worker最近一次上报的系统状态
+#[derive(Default)]
+pub struct SystemSamples {
+    latest: Mutex<HashMap<String, Latest>>,
+}
+
+impl SystemSamples {
+    /// 记录一次采样
+    pub fn record(&self, worker_id: &str, sample: SystemSample) {
+        let now = Instant::now();
+        let mut latest = self.latest.lock().expect("系统状态锁中毒");
+        latest.retain(|_, entry| now.duration_since(entry.at) <= RETENTION);
+
+        // 计数器在主机重启后归零，这时不计算增长速度
+        let net_errors_per_sec = latest.get(worker_id).and_then(|previous| {
+            let elapsed = now.duration_since(previous.at).as_secs_f64();
+            let before = previous.sample.net_errors + previous.sample.net_drops;
+            let after = sample.net_errors + sample.net_drops;
+            (elapsed > 0.0 && after >= before).then(|| (after - before) as f64 / elapsed)
+        });
+        latest.insert(
+            worker_id.to_string(),
+            Latest {
+                sample,
+                at: now,
+                net_errors_per_sec,
+            },
+        );
+    }
+
+    /// Worker最近一次上报的系统状态
+    pub fn get(&self, worker_id: &str) -> Option<WorkerSystemStatus> {
+        let latest = self.latest.lock().expect("系统状态锁中毒");
+        latest.get(worker_id).map(|entry| WorkerSystemStatus {
+            sample: entry.sample,
+            age_secs: entry.at.elapsed().as_secs(),
+            net_errors_per_sec: entry.net_errors_per_sec,
+        })
+    }
+}
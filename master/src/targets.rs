@@ -0,0 +1,57 @@
+//! 上游探测目标
+//!
+//! `--target FILE`（可多次指定）从 JSON 文件读取 [`TargetSpec`]，分配任务时轮流下发给Worker，
+//! 同一个上游的多个入口可以分摊请求；更换入口只需修改文件后重启Master，不需要重新部署Worker。
+//! 没有配置时任务不携带目标，Worker探测内置的上游。
+//!
+//! 目标只能下发给支持 protocol v2 的Worker，配置了目标时旧版本Worker的获取请求会被拒绝。
+
+use common::probe::TargetSpec;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 轮流下发的探测目标
+#[derive(Debug, Default)]
+pub struct Targets {
+    specs: Vec<TargetSpec>,
+    next: AtomicUsize,
+}
+
+impl Targets {
+    /// 读取并检查各个目标文件
+    pub fn load(paths: &[PathBuf]) -> Result<Self, String> {
+        let mut specs = Vec::with_capacity(paths.len());
+        for path in paths {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("读取探测目标 {} 失败: {}", path.display(), e))?;
+            let spec: TargetSpec = serde_json::from_str(&text)
+                .map_err(|e| format!("解析探测目标 {} 失败: {}", path.display(), e))?;
+            spec.validate()
+                .map_err(|e| format!("探测目标 {} 无效: {}", path.display(), e))?;
+            specs.push(spec);
+        }
+        Ok(Self {
+            specs,
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// 是否配置了目标
+    pub fn is_empty(&self) -> bool {
+        self.specs.is_empty()
+    }
+
+    /// 所有目标
+    pub fn specs(&self) -> &[TargetSpec] {
+        &self.specs
+    }
+
+    /// 下一个要下发的目标，没有配置时为 None
+    pub fn next(&self) -> Option<TargetSpec> {
+        if self.specs.is_empty() {
+            return None;
+        }
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % self.specs.len();
+        Some(self.specs[index].clone())
+    }
+}
@@ -0,0 +1,232 @@
+//! 服务端渲染的状态页面
+//!
+//! `/ui` 下的几个只读页面，不需要前端也能在浏览器里查看Master的状态。
+//! 数据与管理接口使用同样的查询，页面定时自动刷新。
+
+use crate::metrics::timed_sql;
+use crate::{admin, history, AppState};
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use common::admin::{StatsResponse, TaskInfo, WorkerInfo};
+use common::latency::LatencyStats;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::error;
+
+/// 页面自动刷新的间隔（秒）
+const REFRESH_SECS: u32 = 10;
+
+/// 概览页显示最近多少分钟的扫描历史
+const HISTORY_MINUTES: u32 = 15;
+
+/// 有效ID页最多显示的条数
+const MAX_RESULTS: u32 = 1000;
+
+/// 状态页面路由
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/ui", get(index))
+        .route("/ui/workers", get(workers))
+        .route("/ui/results", get(results))
+}
+
+/// 渲染模板，失败时返回500
+fn render(template: impl Template) -> Response {
+    match template.render() {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => {
+            error!("渲染页面失败: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "渲染页面失败").into_response()
+        }
+    }
+}
+
+fn internal_error(e: sqlx::Error) -> Response {
+    error!("状态页面查询失败: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        format!("数据库错误: {}", e),
+    )
+        .into_response()
+}
+
+fn format_latency(latency: Option<LatencyStats>) -> String {
+    match latency {
+        Some(latency) => format!(
+            "p50 {}ms / p90 {}ms / p99 {}ms",
+            latency.p50_ms, latency.p90_ms, latency.p99_ms
+        ),
+        None => "-".to_string(),
+    }
+}
+
+fn format_speed(speed: Option<f64>) -> String {
+    speed.map_or_else(|| "-".to_string(), |speed| format!("{:.1}", speed))
+}
+
+fn format_age(secs: Option<u64>) -> String {
+    secs.map_or_else(|| "-".to_string(), |secs| format!("{}秒前", secs))
+}
+
+#[derive(Template)]
+#[template(path = "index.html")]
+struct IndexPage {
+    refresh_secs: u32,
+    stats: StatsResponse,
+    upstream_latency: String,
+    history_minutes: u32,
+    history: Vec<HistoryRow>,
+}
+
+struct HistoryRow {
+    minute: String,
+    tasks: i64,
+    ids_scanned: i64,
+    valid_found: i64,
+    latency: String,
+}
+
+/// 概览：全局统计和最近的扫描历史
+/// GET /ui
+async fn index(State(state): State<Arc<AppState>>) -> Response {
+    let stats = match admin::collect_stats(&state).await {
+        Ok(stats) => stats,
+        Err(e) => return internal_error(e),
+    };
+    let points = match timed_sql(history::fetch(&state.read_pool, HISTORY_MINUTES)).await {
+        Ok(points) => points,
+        Err(e) => return internal_error(e),
+    };
+
+    // 最新的一分钟排在最前
+    let history = points
+        .into_iter()
+        .rev()
+        .map(|point| HistoryRow {
+            minute: point.minute,
+            tasks: point.tasks,
+            ids_scanned: point.ids_scanned,
+            valid_found: point.valid_found,
+            latency: format_latency(point.latency),
+        })
+        .collect();
+    render(IndexPage {
+        refresh_secs: REFRESH_SECS,
+        upstream_latency: format_latency(stats.upstream_latency),
+        stats,
+        history_minutes: HISTORY_MINUTES,
+        history,
+    })
+}
+
+#[derive(Template)]
+#[template(path = "workers.html")]
+struct WorkersPage {
+    refresh_secs: u32,
+    workers: Vec<WorkerRow>,
+    tasks: Vec<TaskRow>,
+}
+
+struct WorkerRow {
+    worker_id: String,
+    host: String,
+    task_ids: String,
+    last_heartbeat: String,
+    speed: String,
+}
+
+impl From<WorkerInfo> for WorkerRow {
+    fn from(worker: WorkerInfo) -> Self {
+        Self {
+            host: worker
+                .profile
+                .map(|profile| profile.summary())
+                .unwrap_or_default(),
+            task_ids: worker
+                .task_ids
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            last_heartbeat: format_age(worker.last_heartbeat_secs),
+            speed: format_speed(worker.speed),
+            worker_id: worker.worker_id,
+        }
+    }
+}
+
+struct TaskRow {
+    task_id: i32,
+    start_id: i64,
+    end_id: i64,
+    worker_id: String,
+    heartbeat: String,
+    speed: String,
+}
+
+impl From<TaskInfo> for TaskRow {
+    fn from(task: TaskInfo) -> Self {
+        Self {
+            task_id: task.task_id,
+            start_id: task.start_id,
+            end_id: task.end_id,
+            worker_id: if task.worker_id.is_empty() {
+                "（待分配）".to_string()
+            } else {
+                task.worker_id
+            },
+            heartbeat: format_age(task.heartbeat_age_secs),
+            speed: format_speed(task.speed),
+        }
+    }
+}
+
+/// 持有任务的Worker和任务队列
+/// GET /ui/workers
+async fn workers(State(state): State<Arc<AppState>>) -> Response {
+    let workers = admin::collect_workers(&state).await;
+    let tasks = admin::collect_tasks(&state).await;
+    render(WorkersPage {
+        refresh_secs: REFRESH_SECS,
+        workers: workers.into_iter().map(WorkerRow::from).collect(),
+        tasks: tasks.into_iter().map(TaskRow::from).collect(),
+    })
+}
+
+#[derive(Template)]
+#[template(path = "results.html")]
+struct ResultsPage {
+    refresh_secs: u32,
+    /// “显示更多”链接的条数，已达上限时为 None
+    more: Option<u32>,
+    results: Vec<common::admin::RecentResult>,
+}
+
+#[derive(Deserialize)]
+struct ResultsQuery {
+    /// 显示条数（默认100）
+    limit: Option<u32>,
+}
+
+/// 最近发现的有效ID
+/// GET /ui/results?limit=100
+async fn results(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ResultsQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(100).clamp(1, MAX_RESULTS);
+    match admin::fetch_recent_results(&state, limit).await {
+        Ok(results) => render(ResultsPage {
+            refresh_secs: REFRESH_SECS,
+            more: (limit < MAX_RESULTS).then(|| (limit * 5).min(MAX_RESULTS)),
+            results,
+        }),
+        Err(e) => internal_error(e),
+    }
+}
@@ -0,0 +1,304 @@
+//! 请求体校验
+//!
+//! [`ValidJson`] 替代 `axum::Json` 作为处理函数的参数：反序列化后按 [`Validate`]
+//! 做基本检查（worker_id 非空、范围合法、valid_ids 数量上限等），
+//! 不合格的请求直接返回 422 和 [`ErrorCode::InvalidRequest`]，不会进入数据库。
+//! Worker请求体与客户端共用 [`common::request`] 中的检查，这里只补充由Master配置决定的上限。
+//! 请求体的大小上限由路由上的 `DefaultBodyLimit` 控制。
+
+use crate::{api_error, AppState};
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::admin::{
+    BanRequest, CreateApiKeyRequest, ImportCandidatesRequest, MaintenanceRequest, RequeueRequest,
+    RequeueUncheckedRequest, UpdateBatchSettings,
+};
+use common::probe::MAX_MARKER_LABEL_LEN;
+use common::request::check_worker_id;
+use common::{
+    AcquireTaskRequest, ArchiveResponsesRequest, ErrorCode, HeartbeatRequest, PartialResultRequest,
+    ReleaseTaskRequest, SubmitResultRequest, WorkerProfile,
+};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use tracing::warn;
+
+/// 单次上传的最多原始响应数
+const MAX_ARCHIVE_RESPONSES: usize = 1000;
+
+/// 幂等键的最大长度
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 128;
+
+/// Worker主机信息中每个字段（含标签的键和值）的最大长度
+const MAX_PROFILE_FIELD_LEN: usize = 128;
+
+/// Worker标签的最大个数
+const MAX_PROFILE_LABELS: usize = 32;
+
+/// 单次导入的候选ID数上限
+const MAX_IMPORT_CANDIDATES: usize = 100_000;
+
+/// 维护原因的最大长度
+const MAX_MAINTENANCE_REASON_LEN: usize = 256;
+
+/// 维护期间建议Worker等待的最长秒数
+const MAX_MAINTENANCE_RETRY_AFTER_SECS: u64 = 3600;
+
+/// 校验时用到的限制
+pub struct Limits {
+    /// 单次提交的有效ID数上限
+    pub max_valid_ids: usize,
+}
+
+/// 请求体的基本检查
+pub trait Validate {
+    fn validate(&self, limits: &Limits) -> Result<(), String>;
+}
+
+/// 经过校验的 JSON 请求体
+pub struct ValidJson<T>(pub T);
+
+impl<T> FromRequest<Arc<AppState>> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(json_rejection)?;
+
+        let limits = Limits {
+            max_valid_ids: state.config.max_valid_ids,
+        };
+        if let Err(message) = value.validate(&limits) {
+            warn!("请求校验失败: {}", message);
+            return Err(invalid(StatusCode::UNPROCESSABLE_ENTITY, message));
+        }
+
+        Ok(ValidJson(value))
+    }
+}
+
+/// JSON 解析失败（格式错误、缺少字段、超过大小上限等）转为统一格式的错误响应
+fn json_rejection(rejection: JsonRejection) -> Response {
+    let status = match rejection.status() {
+        // 语法错误和字段类型错误都按 422 返回
+        StatusCode::BAD_REQUEST => StatusCode::UNPROCESSABLE_ENTITY,
+        status => status,
+    };
+    warn!("请求体解析失败: {}", rejection.body_text());
+    invalid(status, format!("请求体无效: {}", rejection.body_text()))
+}
+
+fn invalid(status: StatusCode, message: String) -> Response {
+    (
+        status,
+        Json(api_error::<()>(message).with_code(ErrorCode::InvalidRequest)),
+    )
+        .into_response()
+}
+
+impl Validate for AcquireTaskRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        self.check()?;
+        if let Some(profile) = &self.profile {
+            check_profile(profile)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_profile(profile: &WorkerProfile) -> Result<(), String> {
+    if profile.labels.len() > MAX_PROFILE_LABELS {
+        return Err(format!("标签个数不能超过 {}", MAX_PROFILE_LABELS));
+    }
+    let fields = [&profile.hostname, &profile.region, &profile.egress];
+    let values = fields
+        .into_iter()
+        .flatten()
+        .chain(profile.labels.iter().flat_map(|(key, value)| [key, value]));
+    for value in values {
+        if value.len() > MAX_PROFILE_FIELD_LEN {
+            return Err(format!(
+                "主机信息和标签的长度不能超过 {}",
+                MAX_PROFILE_FIELD_LEN
+            ));
+        }
+    }
+    if profile.labels.keys().any(|key| key.trim().is_empty()) {
+        return Err("标签名不能为空".to_string());
+    }
+    Ok(())
+}
+
+impl Validate for HeartbeatRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        self.check()
+    }
+}
+
+impl Validate for SubmitResultRequest {
+    fn validate(&self, limits: &Limits) -> Result<(), String> {
+        self.check()?;
+        if self.valid_ids.len() > limits.max_valid_ids {
+            return Err(format!(
+                "单次提交的有效ID数 {} 超过上限 {}",
+                self.valid_ids.len(),
+                limits.max_valid_ids
+            ));
+        }
+        if self.marked.len() > limits.max_valid_ids {
+            return Err(format!(
+                "单次提交的标记ID数 {} 超过上限 {}",
+                self.marked.len(),
+                limits.max_valid_ids
+            ));
+        }
+        for marked in &self.marked {
+            if marked.id < 0 {
+                return Err(format!("无效的标记ID: {}", marked.id));
+            }
+            if marked.label.trim().is_empty() || marked.label.len() > MAX_MARKER_LABEL_LEN {
+                return Err(format!(
+                    "ID {} 的标记名称不能为空且不能超过 {} 个字节",
+                    marked.id, MAX_MARKER_LABEL_LEN
+                ));
+            }
+        }
+        if matches!(&self.idempotency_key, Some(key) if key.len() > MAX_IDEMPOTENCY_KEY_LEN) {
+            return Err(format!("幂等键长度不能超过 {}", MAX_IDEMPOTENCY_KEY_LEN));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for ArchiveResponsesRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        self.check()?;
+        if self.responses.len() > MAX_ARCHIVE_RESPONSES {
+            return Err(format!(
+                "单次上传的响应数 {} 超过上限 {}",
+                self.responses.len(),
+                MAX_ARCHIVE_RESPONSES
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for PartialResultRequest {
+    fn validate(&self, limits: &Limits) -> Result<(), String> {
+        self.check()?;
+        if self.valid_ids.len() > limits.max_valid_ids {
+            return Err(format!(
+                "单次上报的有效ID数 {} 超过上限 {}",
+                self.valid_ids.len(),
+                limits.max_valid_ids
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for ReleaseTaskRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        self.check()
+    }
+}
+
+impl Validate for BanRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        check_worker_id(&self.worker_id)
+    }
+}
+
+impl Validate for RequeueRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        if self.start_id < 0 {
+            return Err(format!("起始ID不能为负数: {}", self.start_id));
+        }
+        if self.start_id > self.end_id {
+            return Err(format!(
+                "起始ID {} 不能大于结束ID {}",
+                self.start_id, self.end_id
+            ));
+        }
+        if matches!(self.chunk, Some(chunk) if chunk < 0) {
+            return Err("拆分粒度不能为负数".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validate for RequeueUncheckedRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        if matches!(self.chunk, Some(chunk) if chunk < 0) {
+            return Err("拆分粒度不能为负数".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validate for ImportCandidatesRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        if self.ids.is_empty() {
+            return Err("候选ID不能为空".to_string());
+        }
+        if self.ids.len() > MAX_IMPORT_CANDIDATES {
+            return Err(format!(
+                "单次最多导入 {} 个候选ID，请分批导入",
+                MAX_IMPORT_CANDIDATES
+            ));
+        }
+        if let Some(id) = self.ids.iter().find(|id| **id < 0) {
+            return Err(format!("候选ID不能为负数: {}", id));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for CreateApiKeyRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("API Key名称不能为空".to_string());
+        }
+        if matches!(self.max_concurrent_tasks, Some(max) if max < 0) {
+            return Err("同时持有的任务数上限不能为负数".to_string());
+        }
+        if matches!(self.max_ids_per_day, Some(max) if max < 0) {
+            return Err("每天分配的ID数上限不能为负数".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Validate for UpdateBatchSettings {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        // 需要与当前参数合并后才能检查，由处理函数负责
+        Ok(())
+    }
+}
+
+impl Validate for MaintenanceRequest {
+    fn validate(&self, _limits: &Limits) -> Result<(), String> {
+        if matches!(&self.reason, Some(reason) if reason.len() > MAX_MAINTENANCE_REASON_LEN) {
+            return Err(format!(
+                "维护原因过长（最多 {} 字节）",
+                MAX_MAINTENANCE_REASON_LEN
+            ));
+        }
+        match self.retry_after_secs {
+            Some(secs) if secs == 0 || secs > MAX_MAINTENANCE_RETRY_AFTER_SECS => Err(format!(
+                "建议等待时长必须在 1 到 {} 秒之间: {}",
+                MAX_MAINTENANCE_RETRY_AFTER_SECS, secs
+            )),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! 扫描停滞告警
+//!
+//! 定期检查扫描是否还在推进：超过 `--scan-stall-timeout` 没有收到任何提交，
+//! 或者游标没有前进且队列中也没有待分配的任务时，判定为扫描停滞，
+//! 记录警告日志并通过告警 webhook 通知，附带推测的原因。
+//! 每次停滞只告警一次，扫描恢复后发送恢复通知。
+
+use crate::metrics::Metrics;
+use crate::AppState;
+use serde_json::json;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// 检查间隔的上限
+const MAX_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 扫描停滞的推测原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StallCause {
+    /// Master正在维护
+    Maintenance,
+    /// 任务分发已暂停
+    DispatchPaused,
+    /// 没有Worker持有任务
+    NoWorkers,
+    /// 任务被领取后都超时了
+    TasksTimingOut,
+    /// Worker在运行但一直没有完成任务
+    NoProgress,
+}
+
+impl StallCause {
+    /// 告警中使用的原因代码
+    fn code(self) -> &'static str {
+        match self {
+            Self::Maintenance => "maintenance",
+            Self::DispatchPaused => "dispatch_paused",
+            Self::NoWorkers => "no_workers",
+            Self::TasksTimingOut => "tasks_timing_out",
+            Self::NoProgress => "no_progress",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Maintenance => "Master正在维护",
+            Self::DispatchPaused => "任务分发已暂停",
+            Self::NoWorkers => "没有Worker在运行任务",
+            Self::TasksTimingOut => "任务都在超时（Worker可能卡住或无法连接Master）",
+            Self::NoProgress => "Worker在运行但没有完成任务（上游可能变慢或被封禁）",
+        }
+    }
+}
+
+/// 上一轮检查时记录的进度
+struct Progress {
+    /// 游标位置及其最近一次变化的时间
+    cursor: i64,
+    cursor_since: Instant,
+    /// 最近一分钟的扫描历史（分钟, 提交数），变化说明有新的提交
+    last_submit: Option<(String, i64)>,
+    submit_since: Instant,
+    /// 最近一次有进展时的回收计数，用于判断此后是否有任务超时
+    reaped: u64,
+    /// 是否已经发出停滞告警
+    alerted: bool,
+}
+
+/// 启动后台停滞检查
+pub fn spawn_watchdog(state: Arc<AppState>, timeout: Duration) {
+    tokio::spawn(async move {
+        let mut progress: Option<Progress> = None;
+        let mut ticker = tokio::time::interval(timeout.min(MAX_CHECK_INTERVAL));
+        loop {
+            ticker.tick().await;
+            // 主备模式下只由主节点检查
+            if !state.leadership.is_leader() {
+                progress = None;
+                continue;
+            }
+            if let Err(e) = check_once(&state, timeout, &mut progress).await {
+                error!("检查扫描进度失败: {}", e);
+            }
+        }
+    });
+}
+
+/// 执行一轮检查
+async fn check_once(
+    state: &AppState,
+    timeout: Duration,
+    progress: &mut Option<Progress>,
+) -> Result<(), sqlx::Error> {
+    // 1. 读取游标和最近一分钟的扫描历史，与上一轮比较
+    let cursor =
+        sqlx::query_scalar::<_, i64>("SELECT next_start_id FROM global_cursor WHERE id = 1")
+            .fetch_one(&state.db_pool)
+            .await?;
+    let last_submit = sqlx::query_as::<_, (String, i64)>(
+        "SELECT minute, tasks FROM stats_history ORDER BY minute DESC LIMIT 1",
+    )
+    .fetch_optional(&state.db_pool)
+    .await?;
+
+    let now = Instant::now();
+    let reaped = state.metrics.tasks_reaped.load(Ordering::Relaxed);
+    let current = progress.get_or_insert(Progress {
+        cursor,
+        cursor_since: now,
+        last_submit: last_submit.clone(),
+        submit_since: now,
+        reaped,
+        alerted: false,
+    });
+    if current.cursor != cursor {
+        current.cursor = cursor;
+        current.cursor_since = now;
+        current.reaped = reaped;
+    }
+    if current.last_submit != last_submit {
+        current.last_submit = last_submit;
+        current.submit_since = now;
+        current.reaped = reaped;
+    }
+
+    // 2. 判断是否停滞：从第一轮检查开始计时，避免刚启动时立即告警
+    let since_submit = current.submit_since.elapsed();
+    let since_cursor = current.cursor_since.elapsed();
+    let tasks = state.running.snapshot().await;
+    let pending = tasks
+        .iter()
+        .filter(|task| task.worker_id.is_empty())
+        .count();
+    // 设置了 --end-id 时，游标到达终点且没有任务后扫描已经结束，不算停滞
+    let finished = state.config.end_id.is_some_and(|end_id| cursor >= end_id) && tasks.is_empty();
+    let stalled =
+        !finished && (since_submit >= timeout || (since_cursor >= timeout && pending == 0));
+
+    if !stalled {
+        if current.alerted {
+            info!("扫描已恢复");
+            if let Some(notifier) = &state.notifier {
+                notifier
+                    .send("scan_resumed", "扫描已恢复", json!({ "cursor": cursor }))
+                    .await;
+            }
+        }
+        current.alerted = false;
+        return Ok(());
+    }
+    if current.alerted {
+        return Ok(());
+    }
+
+    // 3. 推测原因并告警
+    let assigned: Vec<_> = tasks
+        .iter()
+        .filter(|task| !task.worker_id.is_empty())
+        .collect();
+    let cause = if state.maintenance.is_active() {
+        StallCause::Maintenance
+    } else if state.dispatch_paused.load(Ordering::Relaxed) {
+        StallCause::DispatchPaused
+    } else if reaped > current.reaped
+        || (!assigned.is_empty() && assigned.iter().all(|task| task.is_expired()))
+    {
+        StallCause::TasksTimingOut
+    } else if assigned.is_empty() {
+        StallCause::NoWorkers
+    } else {
+        StallCause::NoProgress
+    };
+
+    let text = format!(
+        "扫描已停滞: {} 没有新的提交，游标 {} 已 {} 没有前进；推测原因: {}",
+        humantime::format_duration(Duration::from_secs(since_submit.as_secs())),
+        cursor,
+        humantime::format_duration(Duration::from_secs(since_cursor.as_secs())),
+        cause.describe()
+    );
+    warn!("{}", text);
+    if let Some(notifier) = &state.notifier {
+        notifier
+            .send(
+                "scan_stalled",
+                &text,
+                json!({
+                    "cause": cause.code(),
+                    "cursor": cursor,
+                    "secs_since_submit": since_submit.as_secs(),
+                    "secs_since_cursor_advanced": since_cursor.as_secs(),
+                    "running_tasks": assigned.len(),
+                    "pending_tasks": pending,
+                    "tasks_reaped": reaped - current.reaped,
+                }),
+            )
+            .await;
+    }
+    Metrics::incr(&state.metrics.scan_stalls);
+    current.alerted = true;
+    Ok(())
+}
@@ -0,0 +1,904 @@
+//! admin - 通过 Master 的管理接口远程运维
+//!
+//! 与 init 不同，admin 不直接访问数据库，所有操作都经过 Master 的 HTTP 接口，
+//! 因此可以在 Master 运行时使用，也可以在其他机器上使用。
+
+use clap::{Parser, Subcommand};
+use common::admin::{
+    local_actor, parse_candidate_line, AlertStatus, ApiKeyInfo, ApiKeyRole, AuditEntry,
+    BatchSettings, ClusterOverview, CoverageBucket, CreateApiKeyRequest, MaintenanceRequest,
+    MaintenanceStatus, MarkedStats, SpotCheckStats, StatsPoint, TaskDurations, TaskInfo,
+    UpdateBatchSettings, VerificationEntry, WorkerInfo, WorkerSystemStatus,
+};
+use common::client::MasterClient;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(name = "admin", about = "通过 Master 管理接口执行运维操作")]
+struct Cli {
+    /// Master 地址
+    #[arg(short, long, default_value = "http://localhost:3000")]
+    master_url: String,
+
+    /// 操作人（写入 Master 的审计日志），默认为当前用户
+    #[arg(long, default_value_t = local_actor())]
+    actor: String,
+
+    /// 管理接口的用户名（Master 的 --admin-user）
+    #[arg(long, default_value = "admin")]
+    admin_user: String,
+
+    /// 管理接口的密码（Master 的 --admin-password）
+    #[arg(long, env = "PA_ADMIN_PASSWORD", hide_env_values = true)]
+    admin_password: Option<String>,
+
+    /// 管理令牌（read_only 或 admin 角色的API Key），优先于用户名和密码
+    #[arg(long, env = "PA_ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: Option<String>,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// 显示全局统计
+    Stats {
+        /// 以 JSON 格式输出
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// 按分钟查看扫描量、命中率和上游延迟
+    History {
+        /// 显示最近多少分钟
+        #[arg(short, long, default_value = "60")]
+        minutes: u32,
+    },
+
+    /// 列出持有任务的Worker
+    Workers,
+
+    /// 集群概览：在线的Worker及其任务、速度和版本
+    Cluster {
+        /// 最近多少秒内有过请求或心跳的Worker视为在线
+        #[arg(long, default_value = "60")]
+        active_secs: u64,
+    },
+
+    /// 各告警规则的当前状态
+    Alerts,
+
+    /// 列出任务队列中的任务
+    Tasks,
+
+    /// 已完成任务的耗时分布（p50/p95），按任务大小分档
+    Durations {
+        /// 统计最近多少小时完成的任务
+        #[arg(long, default_value = "24")]
+        hours: u32,
+    },
+
+    /// 暂停任务分发（已分配的任务继续执行）
+    Pause,
+
+    /// 恢复任务分发
+    Resume,
+
+    /// 查看维护模式状态
+    Maintenance,
+
+    /// 开启维护模式：任务接口返回 503，Worker按建议的间隔等待，管理接口照常可用
+    MaintenanceOn {
+        /// 维护原因，Worker的日志中可见
+        #[arg(long)]
+        reason: Option<String>,
+
+        /// 建议Worker等待多少秒后重试
+        #[arg(long)]
+        retry_after: Option<u64>,
+    },
+
+    /// 结束维护模式，顺延维护期间的任务租约
+    MaintenanceOff,
+
+    /// 查看任务批次大小的计算参数
+    Batch,
+
+    /// 修改任务批次大小的计算参数（只在内存中生效，Master重启后恢复为启动参数）
+    SetBatch {
+        /// 期望每个任务运行的秒数
+        #[arg(long)]
+        target_runtime: Option<i64>,
+
+        /// Worker未上报速度时假定的速度（req/s）
+        #[arg(long)]
+        default_speed: Option<i64>,
+
+        /// 批次大小下限
+        #[arg(long)]
+        min: Option<i64>,
+
+        /// 批次大小上限
+        #[arg(long)]
+        max: Option<i64>,
+
+        /// 没有速度历史的Worker先领取的校准批次大小（0 表示不校准）
+        #[arg(long)]
+        calibration: Option<i64>,
+    },
+
+    /// 列出被封禁的Worker
+    Bans,
+
+    /// 封禁Worker，其持有的任务会被回收并重新分配
+    Ban {
+        /// Worker ID
+        worker_id: String,
+
+        /// 封禁原因
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+
+    /// 解除Worker的封禁
+    Unban {
+        /// Worker ID
+        worker_id: String,
+    },
+
+    /// 将已分配过的范围重新排队扫描（闭区间）
+    Requeue {
+        /// 起始ID
+        #[arg(long)]
+        start: i64,
+
+        /// 结束ID（包含）
+        #[arg(long)]
+        end: i64,
+
+        /// 拆分粒度，默认使用 Master 的 split_size
+        #[arg(long)]
+        chunk: Option<i64>,
+    },
+
+    /// 将上报了没有结论（重试耗尽或被上游拒绝）的ID的已完成范围重新排队
+    RequeueUnchecked {
+        /// 拆分粒度，默认使用 Master 的 split_size
+        #[arg(long)]
+        chunk: Option<i64>,
+    },
+
+    /// 查看候选ID的扫描进度
+    Candidates,
+
+    /// 导入候选ID，调度时先于全局游标以显式ID列表任务扫描
+    ImportCandidates {
+        /// 输入文件：每行一个ID，也接受 init export 导出的 CSV / JSONL
+        #[arg(long = "in", value_name = "FILE")]
+        input: PathBuf,
+
+        /// 每次请求导入的ID数
+        #[arg(long, default_value_t = 10000)]
+        batch: usize,
+    },
+
+    /// 查看管理操作的审计日志
+    Audit {
+        /// 显示条数
+        #[arg(short, long, default_value = "50")]
+        limit: u32,
+
+        /// 只显示该类型的操作（如 set_cursor、ban_worker）
+        #[arg(short, long)]
+        action: Option<String>,
+    },
+
+    /// 查看命中响应标记（如已下架、地区限制）的ID
+    Marked {
+        /// 显示的最近ID条数
+        #[arg(short, long, default_value = "20")]
+        limit: u32,
+
+        /// 只显示该标记的ID
+        #[arg(long)]
+        label: Option<String>,
+    },
+
+    /// 查看被判定为可疑、送去复查的提交
+    Verification {
+        /// 显示条数
+        #[arg(short, long, default_value = "50")]
+        limit: u32,
+
+        /// 只显示该状态的记录（pending 或 done）
+        #[arg(short, long)]
+        status: Option<String>,
+    },
+
+    /// 查看Master抽查各Worker结果的一致率
+    SpotChecks,
+
+    /// 按桶查看ID空间的覆盖情况
+    Coverage {
+        /// 每个桶的ID数
+        #[arg(short, long, default_value = "1000000")]
+        bucket: i64,
+
+        /// 起始ID，默认0
+        #[arg(long)]
+        start: Option<i64>,
+
+        /// 结束ID（包含），默认为游标的前一个ID
+        #[arg(long)]
+        end: Option<i64>,
+    },
+
+    /// 列出Worker的API Key及今天的用量
+    Keys,
+
+    /// 创建API Key（密钥只显示一次）
+    CreateKey {
+        /// 名称（如贡献者的名字）
+        #[arg(long)]
+        name: String,
+
+        /// 角色：worker（Worker使用）、read_only（只读管理令牌）、admin（管理令牌）
+        #[arg(long, default_value_t = ApiKeyRole::Worker)]
+        role: ApiKeyRole,
+
+        /// 同时持有的任务数上限
+        #[arg(long)]
+        max_concurrent: Option<i64>,
+
+        /// 每天（UTC）分配的ID数上限
+        #[arg(long)]
+        max_ids_per_day: Option<i64>,
+    },
+
+    /// 吊销API Key
+    RevokeKey {
+        /// API Key ID
+        key_id: i64,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let mut client = MasterClient::new(cli.master_url).with_actor(cli.actor);
+    if let Some(token) = cli.admin_token {
+        client = client.with_token(token);
+    } else if let Some(password) = cli.admin_password {
+        client = client.with_basic_auth(cli.admin_user, password);
+    }
+
+    if let Err(e) = run(&client, cli.command).await {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(client: &MasterClient, command: Commands) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Commands::Stats { json } => {
+            let stats = client.stats().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                println!("全局游标:   {}", stats.cursor);
+                println!("运行中任务: {}", stats.running_tasks);
+                println!("待分配任务: {}", stats.pending_tasks);
+                println!("已完成任务: {}", stats.completed_tasks);
+                println!("有效ID:     {}", stats.valid_results);
+                println!("未确认ID:   {}", stats.unchecked_ids);
+                println!(
+                    "任务分发:   {}",
+                    if stats.dispatch_paused {
+                        "已暂停"
+                    } else {
+                        "正常"
+                    }
+                );
+                if let Some(latency) = stats.upstream_latency {
+                    println!(
+                        "上游延迟:   p50={}ms p90={}ms p99={}ms max={}ms（最近5分钟 {} 次请求）",
+                        latency.p50_ms,
+                        latency.p90_ms,
+                        latency.p99_ms,
+                        latency.max_ms,
+                        latency.count
+                    );
+                }
+            }
+        }
+        Commands::History { minutes } => print_history(&client.stats_history(minutes).await?),
+        Commands::Workers => print_workers(&client.workers().await?),
+        Commands::Cluster { active_secs } => print_cluster(&client.cluster(active_secs).await?),
+        Commands::Alerts => print_alerts(&client.alerts().await?),
+        Commands::Tasks => print_tasks(&client.tasks().await?),
+        Commands::Durations { hours } => print_durations(&client.task_durations(hours).await?),
+        Commands::Pause => {
+            client.pause_dispatch().await?;
+            println!("✓ 任务分发已暂停");
+        }
+        Commands::Resume => {
+            client.resume_dispatch().await?;
+            println!("✓ 任务分发已恢复");
+        }
+        Commands::Maintenance => print_maintenance(&client.maintenance().await?),
+        Commands::MaintenanceOn {
+            reason,
+            retry_after,
+        } => {
+            let status = client
+                .set_maintenance(&MaintenanceRequest {
+                    enabled: true,
+                    reason,
+                    retry_after_secs: retry_after,
+                })
+                .await?;
+            println!("✓ 已进入维护模式");
+            print_maintenance(&status);
+        }
+        Commands::MaintenanceOff => {
+            client
+                .set_maintenance(&MaintenanceRequest {
+                    enabled: false,
+                    reason: None,
+                    retry_after_secs: None,
+                })
+                .await?;
+            println!("✓ 已退出维护模式");
+        }
+        Commands::Batch => print_batch_settings(&client.batch_settings().await?),
+        Commands::SetBatch {
+            target_runtime,
+            default_speed,
+            min,
+            max,
+            calibration,
+        } => {
+            let settings = client
+                .update_batch_settings(&UpdateBatchSettings {
+                    target_runtime_secs: target_runtime,
+                    default_speed,
+                    min_batch_size: min,
+                    max_batch_size: max,
+                    calibration_batch_size: calibration,
+                })
+                .await?;
+            println!("✓ 批次参数已修改");
+            print_batch_settings(&settings);
+        }
+        Commands::Bans => {
+            let bans = client.bans().await?;
+            if bans.is_empty() {
+                println!("没有被封禁的Worker");
+            }
+            for ban in bans {
+                println!("{:<40} {:<20} {}", ban.worker_id, ban.banned_at, ban.reason);
+            }
+        }
+        Commands::Ban { worker_id, reason } => {
+            let message = client.ban(&worker_id, reason.as_deref()).await?;
+            println!("✓ {}: {}", worker_id, message);
+        }
+        Commands::Unban { worker_id } => {
+            let message = client.unban(&worker_id).await?;
+            println!("✓ {}: {}", worker_id, message);
+        }
+        Commands::Requeue { start, end, chunk } => {
+            let response = client.requeue(start, end, chunk).await?;
+            println!(
+                "✓ 范围 [{}, {}] 已重新排队，共 {} 个任务",
+                start,
+                end,
+                response.task_ids.len()
+            );
+        }
+        Commands::RequeueUnchecked { chunk } => {
+            let response = client.requeue_unchecked(chunk).await?;
+            println!(
+                "✓ {} 个范围已重新排队：新建 {} 个任务，重新打开 {} 个候选ID",
+                response.ranges,
+                response.task_ids.len(),
+                response.candidates
+            );
+        }
+        Commands::Candidates => {
+            let stats = client.candidate_stats().await?;
+            println!("等待分配: {}", stats.pending);
+            println!("扫描中:   {}", stats.assigned);
+            println!("已完成:   {}", stats.checked);
+        }
+        Commands::ImportCandidates { input, batch } => {
+            import_candidates(client, &input, batch.max(1)).await?
+        }
+        Commands::Audit { limit, action } => {
+            print_audit(&client.audit(limit, action.as_deref()).await?)
+        }
+        Commands::Marked { limit, label } => {
+            print_marked(&client.marked(limit, label.as_deref()).await?)
+        }
+        Commands::Verification { limit, status } => {
+            print_verification(&client.verification(limit, status.as_deref()).await?)
+        }
+        Commands::SpotChecks => print_spot_checks(&client.spot_checks().await?),
+        Commands::Coverage { bucket, start, end } => {
+            print_coverage(&client.coverage(bucket, start, end).await?)
+        }
+        Commands::Keys => print_api_keys(&client.api_keys().await?),
+        Commands::CreateKey {
+            name,
+            role,
+            max_concurrent,
+            max_ids_per_day,
+        } => {
+            let created = client
+                .create_api_key(&CreateApiKeyRequest {
+                    name,
+                    role,
+                    max_concurrent_tasks: max_concurrent,
+                    max_ids_per_day,
+                })
+                .await?;
+            println!("✓ 已创建API Key {}", created.key_id);
+            println!("密钥（只显示这一次，请妥善保存）: {}", created.key);
+        }
+        Commands::RevokeKey { key_id } => {
+            let message = client.revoke_api_key(key_id).await?;
+            println!("✓ API Key {}: {}", key_id, message);
+        }
+    }
+
+    Ok(())
+}
+
+/// 读取候选ID文件，按批导入
+async fn import_candidates(
+    client: &MasterClient,
+    input: &Path,
+    batch: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string(input)?;
+    let mut ids = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if let Some(id) =
+            parse_candidate_line(line).map_err(|e| format!("第 {} 行格式错误: {}", index + 1, e))?
+        {
+            ids.push(id);
+        }
+    }
+
+    let (mut queued, mut skipped) = (0, 0);
+    for chunk in ids.chunks(batch) {
+        let response = client.import_candidates(chunk.to_vec()).await?;
+        queued += response.queued;
+        skipped += response.skipped;
+    }
+    println!(
+        "✓ 已导入 {} 个候选ID（{} 个已在等待或扫描中）",
+        queued, skipped
+    );
+    Ok(())
+}
+
+fn print_batch_settings(settings: &BatchSettings) {
+    println!("期望运行时长: {}s", settings.target_runtime_secs);
+    println!("默认速度:     {} req/s", settings.default_speed);
+    println!(
+        "批次大小:     {} - {}",
+        settings.min_batch_size, settings.max_batch_size
+    );
+    if settings.calibration_batch_size > 0 {
+        println!("校准批次:     {}", settings.calibration_batch_size);
+    } else {
+        println!("校准批次:     不校准");
+    }
+}
+
+fn print_workers(workers: &[WorkerInfo]) {
+    if workers.is_empty() {
+        println!("没有持有任务的Worker");
+        return;
+    }
+
+    println!("{:<40} {:>8} {:>10}  HOST", "WORKER", "TASKS", "HEARTBEAT");
+    for worker in workers {
+        let host = worker
+            .profile
+            .as_ref()
+            .map(|profile| profile.summary())
+            .unwrap_or_default();
+        println!(
+            "{:<40} {:>8} {:>10}  {}",
+            worker.worker_id,
+            worker.task_ids.len(),
+            format_age(worker.last_heartbeat_secs),
+            host
+        );
+        if let Some(profile) = worker.profile.as_ref().filter(|p| !p.labels.is_empty()) {
+            let labels: Vec<String> = profile
+                .labels
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            println!("{:<40} {}", "", labels.join(" "));
+        }
+    }
+}
+
+fn print_cluster(cluster: &ClusterOverview) {
+    if cluster.workers.is_empty() {
+        println!("最近 {} 秒内没有在线的Worker", cluster.active_secs);
+        return;
+    }
+
+    println!(
+        "{:<40} {:>8} {:>10} {:>10} {:>12}  HOST",
+        "WORKER", "TASKS", "SEEN", "SPEED", "VERSION"
+    );
+    for worker in &cluster.workers {
+        let profile = worker.profile.as_ref();
+        println!(
+            "{:<40} {:>8} {:>10} {:>10} {:>12}  {}",
+            worker.worker_id,
+            worker.tasks.len(),
+            format_age(Some(worker.last_seen_secs)),
+            worker
+                .speed
+                .map(|speed| format!("{:.0}/s", speed))
+                .unwrap_or_else(|| "-".to_string()),
+            profile
+                .and_then(|profile| profile.version.as_deref())
+                .unwrap_or("-"),
+            profile.map(|profile| profile.summary()).unwrap_or_default()
+        );
+        if let Some(profile) = profile.filter(|p| !p.labels.is_empty()) {
+            let labels: Vec<String> = profile
+                .labels
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            println!("{:<40} {}", "", labels.join(" "));
+        }
+        if let Some(system) = &worker.system {
+            println!("{:<40} {}", "", format_system(system));
+        }
+    }
+
+    let versions: Vec<String> = cluster
+        .versions
+        .iter()
+        .map(|(version, count)| format!("{}×{}", version, count))
+        .collect();
+    println!();
+    println!(
+        "在线Worker: {}  总速度: {:.0}/s  版本: {}",
+        cluster.workers.len(),
+        cluster.total_speed,
+        versions.join(" ")
+    );
+}
+
+/// 系统状态的一行摘要：负载、内存、交换空间和网卡错误
+fn format_system(system: &WorkerSystemStatus) -> String {
+    let sample = &system.sample;
+    let mem_used = sample.mem_total_kb.saturating_sub(sample.mem_available_kb);
+    let mem_percent = if sample.mem_total_kb > 0 {
+        mem_used as f64 / sample.mem_total_kb as f64 * 100.0
+    } else {
+        0.0
+    };
+    let net_rate = system
+        .net_errors_per_sec
+        .map(|rate| format!(" ({:.1}/s)", rate))
+        .unwrap_or_default();
+    format!(
+        "负载 {:.2}/{}核  内存 {:.0}%  交换 {}MB  网卡错误 {} 丢包 {}{}  [{}]",
+        sample.load1,
+        sample.cpus,
+        mem_percent,
+        sample.swap_used_kb / 1024,
+        sample.net_errors,
+        sample.net_drops,
+        net_rate,
+        format_age(Some(system.age_secs))
+    )
+}
+
+fn print_maintenance(status: &MaintenanceStatus) {
+    if !status.enabled {
+        println!("未在维护中");
+        return;
+    }
+    println!(
+        "维护中: {}  已持续: {}  建议Worker等待: {}s",
+        status.reason.as_deref().unwrap_or("未说明原因"),
+        format_age(status.since_secs),
+        status.retry_after_secs.unwrap_or_default()
+    );
+}
+
+fn print_alerts(alerts: &[AlertStatus]) {
+    if alerts.is_empty() {
+        println!("没有配置告警规则");
+        return;
+    }
+
+    println!(
+        "{:<24} {:<8} {:>10}  {:<32} {:>12}",
+        "RULE", "STATE", "SINCE", "CONDITION", "VALUE"
+    );
+    for alert in alerts {
+        let mut condition = format!("{} {} {}", alert.metric, alert.op, alert.threshold);
+        if alert.for_secs > 0 {
+            condition.push_str(&format!(" for {}s", alert.for_secs));
+        }
+        println!(
+            "{:<24} {:<8} {:>10}  {:<32} {:>12}",
+            alert.name,
+            alert.state,
+            format_age(alert.since_secs),
+            condition,
+            alert
+                .value
+                .map(|value| format!("{:.4}", value))
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+}
+
+fn print_tasks(tasks: &[TaskInfo]) {
+    if tasks.is_empty() {
+        println!("任务队列为空");
+        return;
+    }
+
+    println!(
+        "{:>8} {:>20} {:>20} {:<40} {:>10} {:>10}",
+        "TASK", "START", "END", "WORKER", "HEARTBEAT", "SPEED"
+    );
+    for task in tasks {
+        let worker = if task.worker_id.is_empty() {
+            "(pending)"
+        } else {
+            task.worker_id.as_str()
+        };
+        println!(
+            "{:>8} {:>20} {:>20} {:<40} {:>10} {:>10}",
+            task.task_id,
+            task.start_id,
+            task.end_id,
+            worker,
+            format_age(task.heartbeat_age_secs),
+            format_speed(task.speed)
+        );
+    }
+}
+
+fn print_audit(entries: &[AuditEntry]) {
+    if entries.is_empty() {
+        println!("没有审计日志");
+        return;
+    }
+
+    println!("{:<20} {:<24} {:<16} DETAILS", "TIME", "ACTOR", "ACTION");
+    for entry in entries {
+        println!(
+            "{:<20} {:<24} {:<16} {}",
+            entry.created_at, entry.actor, entry.action, entry.details
+        );
+    }
+}
+
+fn print_marked(stats: &MarkedStats) {
+    if stats.labels.is_empty() {
+        println!("没有命中响应标记的ID");
+        return;
+    }
+
+    for label in &stats.labels {
+        println!("{:<24} {}", label.label, label.count);
+    }
+    println!();
+    println!(
+        "{:>20} {:<24} {:<20} {:<20} WORKER",
+        "ID", "LABEL", "FIRST SEEN", "LAST SEEN"
+    );
+    for entry in &stats.recent {
+        println!(
+            "{:>20} {:<24} {:<20} {:<20} {}",
+            entry.id, entry.label, entry.first_seen, entry.last_seen, entry.worker_id
+        );
+    }
+}
+
+fn print_verification(entries: &[VerificationEntry]) {
+    if entries.is_empty() {
+        println!("复查队列为空");
+        return;
+    }
+
+    println!(
+        "{:>6} {:>20} {:>20} {:<36} {:>7} {:>8} {:<8} REASON",
+        "ID", "START", "END", "WORKER", "FOUND", "VERIFIED", "STATUS"
+    );
+    for entry in entries {
+        let verified = entry
+            .verified_count
+            .map(|count| count.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:>6} {:>20} {:>20} {:<36} {:>7} {:>8} {:<8} {}",
+            entry.id,
+            entry.start_id,
+            entry.end_id,
+            entry.worker_id,
+            entry.valid_count,
+            verified,
+            entry.status,
+            entry.reason
+        );
+    }
+}
+
+fn print_spot_checks(stats: &[SpotCheckStats]) {
+    if stats.is_empty() {
+        println!("还没有抽查记录（Master 需要以 --spot-check-sample 启动）");
+        return;
+    }
+
+    println!(
+        "{:<40} {:>8} {:>8} {:>8} {:<20}",
+        "WORKER", "CHECKED", "AGREED", "RATE", "LAST CHECKED"
+    );
+    for worker in stats {
+        println!(
+            "{:<40} {:>8} {:>8} {:>7.1}% {:<20}",
+            worker.worker_id,
+            worker.checked,
+            worker.agreed,
+            worker.agreement_rate * 100.0,
+            worker.last_checked_at.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+fn print_history(points: &[StatsPoint]) {
+    if points.is_empty() {
+        println!("这段时间内没有提交");
+        return;
+    }
+
+    println!(
+        "{:<20} {:>6} {:>12} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "MINUTE", "TASKS", "SCANNED", "VALID", "HIT", "P50", "P90", "P99", "MAX"
+    );
+    for point in points {
+        let hit_rate = if point.ids_scanned > 0 {
+            point.valid_found as f64 / point.ids_scanned as f64 * 100.0
+        } else {
+            0.0
+        };
+        let ms = |value: Option<u64>| value.map_or("-".to_string(), |v| format!("{}ms", v));
+        println!(
+            "{:<20} {:>6} {:>12} {:>8} {:>7.2}% {:>8} {:>8} {:>8} {:>8}",
+            point.minute,
+            point.tasks,
+            point.ids_scanned,
+            point.valid_found,
+            hit_rate,
+            ms(point.latency.map(|l| l.p50_ms)),
+            ms(point.latency.map(|l| l.p90_ms)),
+            ms(point.latency.map(|l| l.p99_ms)),
+            ms(point.latency.map(|l| l.max_ms)),
+        );
+    }
+}
+
+fn print_durations(durations: &TaskDurations) {
+    if durations.tasks == 0 {
+        println!("最近 {} 小时内没有记录了耗时的任务", durations.hours);
+        return;
+    }
+
+    println!(
+        "最近 {} 小时完成 {} 个任务: p50 {:.1}s, p95 {:.1}s, 最长 {:.1}s",
+        durations.hours,
+        durations.tasks,
+        durations.p50_ms as f64 / 1000.0,
+        durations.p95_ms as f64 / 1000.0,
+        durations.max_ms as f64 / 1000.0
+    );
+    println!();
+    println!(
+        "{:>23} {:>8} {:>9} {:>9} {:>10}",
+        "SIZE", "TASKS", "P50", "P95", "IDS/S"
+    );
+    for bucket in &durations.by_batch_size {
+        println!(
+            "{:>23} {:>8} {:>8.1}s {:>8.1}s {:>10.1}",
+            format!("{}-{}", bucket.min_size, bucket.max_size),
+            bucket.tasks,
+            bucket.p50_ms as f64 / 1000.0,
+            bucket.p95_ms as f64 / 1000.0,
+            bucket.ids_per_sec
+        );
+    }
+}
+
+fn print_coverage(buckets: &[CoverageBucket]) {
+    if buckets.is_empty() {
+        println!("范围内还没有分配过任务");
+        return;
+    }
+
+    println!(
+        "{:>20} {:>20} {:>9} {:>9} {:>9}",
+        "START", "END", "SCANNED", "RUNNING", "UNSCANNED"
+    );
+    for bucket in buckets {
+        println!(
+            "{:>20} {:>20} {:>8.1}% {:>8.1}% {:>8.1}%",
+            bucket.start_id,
+            bucket.end_id,
+            bucket.scanned * 100.0,
+            bucket.running * 100.0,
+            bucket.unscanned * 100.0
+        );
+    }
+}
+
+fn print_api_keys(keys: &[ApiKeyInfo]) {
+    if keys.is_empty() {
+        println!("没有API Key");
+        return;
+    }
+
+    println!(
+        "{:>6} {:<24} {:<10} {:>12} {:>24} {:<8} {:<20}",
+        "ID", "NAME", "ROLE", "TASKS", "IDS TODAY", "STATUS", "CREATED"
+    );
+    for key in keys {
+        let tasks = format_quota(key.running_tasks, key.max_concurrent_tasks);
+        let ids = format_quota(key.ids_today, key.max_ids_per_day);
+        let status = if key.revoked { "revoked" } else { "active" };
+        println!(
+            "{:>6} {:<24} {:<10} {:>12} {:>24} {:<8} {:<20}",
+            key.key_id,
+            key.name,
+            key.role.as_str(),
+            tasks,
+            ids,
+            status,
+            key.created_at
+        );
+    }
+}
+
+/// 将用量和上限格式化为 `3/10`，没有上限时只显示用量
+fn format_quota(used: i64, max: Option<i64>) -> String {
+    match max {
+        Some(max) => format!("{}/{}", used, max),
+        None => used.to_string(),
+    }
+}
+
+/// 将心跳距今的秒数格式化为 `12s`、`3m05s` 等形式
+fn format_speed(speed: Option<f64>) -> String {
+    match speed {
+        None => "-".to_string(),
+        Some(speed) => format!("{:.1}/s", speed),
+    }
+}
+
+fn format_age(secs: Option<u64>) -> String {
+    match secs {
+        None => "-".to_string(),
+        Some(secs) if secs < 60 => format!("{}s", secs),
+        Some(secs) if secs < 3600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        Some(secs) => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
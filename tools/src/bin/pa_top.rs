@@ -0,0 +1,395 @@
+//! pa_top - Master 节点的终端监控面板
+//!
+//! 通过 Master 的管理接口（`/admin/*`）读取状态，显示：
+//! - 全局统计和扫描/发现速度曲线
+//! - 活跃的Worker和运行中的任务
+//! - 最近发现的有效ID
+
+use clap::Parser;
+use common::admin::{ClusterOverview, RecentResult, StatsResponse, TaskInfo};
+use common::client::{ClientError, MasterClient};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Sparkline, Table},
+    DefaultTerminal, Frame,
+};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// 速度曲线保留的采样点数
+const HISTORY_LEN: usize = 120;
+
+/// 最近结果显示的条数
+const RECENT_RESULTS: u32 = 50;
+
+/// 心跳超过该秒数的任务标红（与 Master 的任务超时一致）
+const STALE_SECS: u64 = 60;
+
+#[derive(Parser)]
+#[command(name = "pa_top", about = "Master 节点终端监控面板")]
+struct Cli {
+    /// Master 地址
+    #[arg(short, long, default_value = "http://localhost:3000")]
+    master_url: String,
+
+    /// 刷新间隔（如 1s、2s、500ms）
+    #[arg(short, long, value_parser = humantime::parse_duration, default_value = "2s")]
+    interval: Duration,
+
+    /// 管理接口的用户名（Master 的 --admin-user）
+    #[arg(long, default_value = "admin")]
+    admin_user: String,
+
+    /// 管理接口的密码（Master 的 --admin-password）
+    #[arg(long, env = "PA_ADMIN_PASSWORD", hide_env_values = true)]
+    admin_password: Option<String>,
+
+    /// 管理令牌（read_only 或 admin 角色的API Key），优先于用户名和密码
+    #[arg(long, env = "PA_ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: Option<String>,
+}
+
+/// 一次刷新拉取到的数据
+struct Snapshot {
+    stats: StatsResponse,
+    cluster: ClusterOverview,
+    tasks: Vec<TaskInfo>,
+    recent: Vec<RecentResult>,
+}
+
+/// 面板状态
+struct App {
+    client: MasterClient,
+    snapshot: Option<Snapshot>,
+    /// 上一次的统计和采样时间，用于计算速度
+    previous: Option<(StatsResponse, Instant)>,
+    /// 扫描速度（ID/s）
+    scan_rate: VecDeque<u64>,
+    /// 发现速度（个/分钟）
+    hit_rate: VecDeque<u64>,
+    /// 最近一次拉取失败的原因
+    last_error: Option<String>,
+}
+
+impl App {
+    fn new(client: MasterClient) -> Self {
+        Self {
+            client,
+            snapshot: None,
+            previous: None,
+            scan_rate: VecDeque::with_capacity(HISTORY_LEN),
+            hit_rate: VecDeque::with_capacity(HISTORY_LEN),
+            last_error: None,
+        }
+    }
+
+    /// 从 Master 拉取最新状态
+    async fn refresh(&mut self) {
+        match self.fetch().await {
+            Ok(snapshot) => {
+                let now = Instant::now();
+                if let Some((last, at)) = &self.previous {
+                    let secs = now.duration_since(*at).as_secs_f64().max(0.001);
+                    let scanned = (snapshot.stats.cursor - last.cursor).max(0) as f64;
+                    let hits = (snapshot.stats.valid_results - last.valid_results).max(0) as f64;
+                    push_sample(&mut self.scan_rate, (scanned / secs) as u64);
+                    push_sample(&mut self.hit_rate, (hits / secs * 60.0) as u64);
+                }
+                self.previous = Some((snapshot.stats.clone(), now));
+                self.snapshot = Some(snapshot);
+                self.last_error = None;
+            }
+            Err(e) => self.last_error = Some(e.to_string()),
+        }
+    }
+
+    async fn fetch(&self) -> Result<Snapshot, ClientError> {
+        Ok(Snapshot {
+            stats: self.client.stats().await?,
+            cluster: self.client.cluster(STALE_SECS).await?,
+            tasks: self.client.tasks().await?,
+            recent: self.client.recent_results(RECENT_RESULTS).await?,
+        })
+    }
+}
+
+fn push_sample(history: &mut VecDeque<u64>, value: u64) {
+    if history.len() == HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let mut client = MasterClient::new(cli.master_url);
+    if let Some(token) = cli.admin_token {
+        client = client.with_token(token);
+    } else if let Some(password) = cli.admin_password {
+        client = client.with_basic_auth(cli.admin_user, password);
+    }
+    let mut app = App::new(client);
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app, cli.interval).await;
+    ratatui::restore();
+    result
+}
+
+/// 主循环：定时刷新，按 q / Esc 退出，按 r 立即刷新
+async fn run(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    interval: Duration,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut next_refresh = Instant::now();
+    loop {
+        if Instant::now() >= next_refresh {
+            app.refresh().await;
+            next_refresh = Instant::now() + interval;
+        }
+        terminal.draw(|frame| draw(frame, app))?;
+
+        // 等待按键，最多等到下一次刷新
+        let timeout = next_refresh.saturating_duration_since(Instant::now());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('r') => next_refresh = Instant::now(),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(7),
+            Constraint::Min(8),
+            Constraint::Length(1),
+        ])
+        .split(frame.area());
+
+    draw_header(frame, rows[0], app);
+    draw_sparklines(frame, rows[1], app);
+
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(30),
+            Constraint::Percentage(45),
+            Constraint::Percentage(25),
+        ])
+        .split(rows[2]);
+    draw_workers(frame, body[0], app);
+    draw_tasks(frame, body[1], app);
+    draw_recent(frame, body[2], app);
+
+    let footer = match &app.last_error {
+        Some(e) => Line::from(Span::styled(
+            format!(" 刷新失败: {}", e),
+            Style::default().fg(Color::Red),
+        )),
+        None => Line::from(format!(" {}  |  q 退出  r 刷新", app.client.base_url())),
+    };
+    frame.render_widget(Paragraph::new(footer), rows[3]);
+}
+
+fn draw_header(frame: &mut Frame, area: Rect, app: &App) {
+    let line = match &app.snapshot {
+        Some(snapshot) => {
+            let stats = &snapshot.stats;
+            let dispatch = if stats.dispatch_paused {
+                Span::styled("已暂停", Style::default().fg(Color::Yellow))
+            } else {
+                Span::styled("运行中", Style::default().fg(Color::Green))
+            };
+            Line::from(vec![
+                Span::raw(" 分发: "),
+                dispatch,
+                Span::raw(format!(
+                    "   游标: {}   运行中: {}   待分配: {}   已完成: {}   有效ID: {}",
+                    stats.cursor,
+                    stats.running_tasks,
+                    stats.pending_tasks,
+                    stats.completed_tasks,
+                    stats.valid_results
+                )),
+            ])
+        }
+        None => Line::from(" 正在连接 Master..."),
+    };
+    frame.render_widget(
+        Paragraph::new(line).block(Block::default().borders(Borders::ALL).title(" pa_top ")),
+        area,
+    );
+}
+
+fn draw_sparklines(frame: &mut Frame, area: Rect, app: &App) {
+    let halves = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+
+    let charts = [
+        (&app.scan_rate, "扫描速度", "ID/s", Color::Cyan),
+        (&app.hit_rate, "发现速度", "个/分钟", Color::Magenta),
+    ];
+    for ((history, title, unit, color), area) in charts.into_iter().zip(halves.iter()) {
+        // 只显示能放下的最近若干个点
+        let width = area.width.saturating_sub(2) as usize;
+        let data: Vec<u64> = history
+            .iter()
+            .skip(history.len().saturating_sub(width))
+            .copied()
+            .collect();
+        let title = format!(
+            " {}: {} {} ",
+            title,
+            history.back().copied().unwrap_or(0),
+            unit
+        );
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .data(&data)
+                .style(Style::default().fg(color)),
+            *area,
+        );
+    }
+}
+
+fn draw_workers(frame: &mut Frame, area: Rect, app: &App) {
+    let cluster = app.snapshot.as_ref().map(|s| &s.cluster);
+    let workers = cluster.map(|c| c.workers.as_slice()).unwrap_or_default();
+    let rows = workers.iter().map(|worker| {
+        let host = worker
+            .profile
+            .as_ref()
+            .map(|profile| match &profile.version {
+                Some(version) if !profile.summary().is_empty() => {
+                    format!("{} (v{})", profile.summary(), version)
+                }
+                Some(version) => format!("v{}", version),
+                None => profile.summary(),
+            })
+            .unwrap_or_default();
+        Row::new(vec![
+            Cell::from(worker.worker_id.clone()),
+            Cell::from(host),
+            Cell::from(worker.tasks.len().to_string()),
+            heartbeat_cell(Some(worker.last_seen_secs)),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(12),
+            Constraint::Min(8),
+            Constraint::Length(4),
+            Constraint::Length(6),
+        ],
+    )
+    .header(header_row(["Worker", "主机", "任务", "最近"]))
+    .block(Block::default().borders(Borders::ALL).title(format!(
+        " Worker ({}, {:.0}/s) ",
+        workers.len(),
+        cluster.map(|c| c.total_speed).unwrap_or_default()
+    )));
+    frame.render_widget(table, area);
+}
+
+fn draw_tasks(frame: &mut Frame, area: Rect, app: &App) {
+    let tasks = app
+        .snapshot
+        .as_ref()
+        .map(|s| s.tasks.as_slice())
+        .unwrap_or_default();
+    let rows = tasks.iter().map(|task| {
+        let worker = if task.worker_id.is_empty() {
+            "（待分配）".to_string()
+        } else {
+            task.worker_id.clone()
+        };
+        Row::new(vec![
+            Cell::from(task.task_id.to_string()),
+            Cell::from(format!("{}-{}", task.start_id, task.end_id)),
+            Cell::from(worker),
+            Cell::from(
+                task.speed
+                    .map(|speed| format!("{:.0}/s", speed))
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            heartbeat_cell(task.heartbeat_age_secs),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(7),
+            Constraint::Min(16),
+            Constraint::Min(12),
+            Constraint::Length(7),
+            Constraint::Length(6),
+        ],
+    )
+    .header(header_row(["任务", "范围", "Worker", "速度", "心跳"]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" 任务 ({}) ", tasks.len())),
+    );
+    frame.render_widget(table, area);
+}
+
+fn draw_recent(frame: &mut Frame, area: Rect, app: &App) {
+    let recent = app
+        .snapshot
+        .as_ref()
+        .map(|s| s.recent.as_slice())
+        .unwrap_or_default();
+    let items: Vec<ListItem> = recent
+        .iter()
+        .map(|result| {
+            // 只显示时间部分，日期在窄终端里太占地方
+            let time = result
+                .found_at
+                .split(' ')
+                .nth(1)
+                .unwrap_or(&result.found_at);
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", time), Style::default().fg(Color::DarkGray)),
+                Span::raw(result.id.to_string()),
+            ]))
+        })
+        .collect();
+    frame.render_widget(
+        List::new(items).block(Block::default().borders(Borders::ALL).title(" 最近发现 ")),
+        area,
+    );
+}
+
+fn header_row<const N: usize>(titles: [&'static str; N]) -> Row<'static> {
+    Row::new(titles).style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+/// 心跳时间单元格，超时的标红
+fn heartbeat_cell(age: Option<u64>) -> Cell<'static> {
+    match age {
+        Some(secs) if secs <= STALE_SECS => Cell::from(format!("{}s", secs)),
+        Some(secs) => Cell::from(format!("{}s", secs)).style(Style::default().fg(Color::Red)),
+        None => Cell::from("失效").style(Style::default().fg(Color::Red)),
+    }
+}
@@ -0,0 +1,49 @@
+//! 原始响应存档
+//!
+//! 启用 `--archive-responses` 后，扫描中每个有效ID的原始响应体经 gzip 压缩、base64 编码后暂存，
+//! 任务结束、提交结果之前按批通过 `POST /task/responses` 上传给Master保存，
+//! 以后增加新的元数据字段时可以重新解析，不需要重新扫描。上传失败只记录日志，不影响结果提交。
+
+use base64::Engine;
+use common::RawResponse;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+/// 每次上传的最多响应数
+pub const UPLOAD_BATCH: usize = 200;
+
+/// 每次上传的响应体总大小上限（编码后，字节），避免超过Master的请求体上限
+pub const UPLOAD_MAX_BYTES: usize = 2 * 1024 * 1024;
+
+/// 压缩并编码一个有效ID的原始响应
+pub fn encode(id: i64, raw: &[u8]) -> std::io::Result<RawResponse> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(raw.len() / 2), Compression::default());
+    encoder.write_all(raw)?;
+    let compressed = encoder.finish()?;
+    Ok(RawResponse {
+        id,
+        body: base64::engine::general_purpose::STANDARD.encode(compressed),
+    })
+}
+
+/// 按条数和总大小把响应分成若干批
+pub fn batches(responses: Vec<RawResponse>) -> Vec<Vec<RawResponse>> {
+    let mut batches = Vec::new();
+    let mut current = Vec::new();
+    let mut bytes = 0;
+    for response in responses {
+        if !current.is_empty()
+            && (current.len() >= UPLOAD_BATCH || bytes + response.body.len() > UPLOAD_MAX_BYTES)
+        {
+            batches.push(std::mem::take(&mut current));
+            bytes = 0;
+        }
+        bytes += response.body.len();
+        current.push(response);
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
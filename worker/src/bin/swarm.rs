@@ -0,0 +1,256 @@
+//! swarm - 在一台机器上启动并看管多个Worker实例
+//!
+//! - 所有实例共用 `--` 之后的Worker参数
+//! - 每个实例使用固定的ID `<前缀>-<序号>`，重启后仍是同一个Worker
+//! - 实例崩溃后自动重启，连续快速退出时逐步延长等待；正常退出（如达到 `--max-tasks`）的实例不再重启
+//! - 各实例的输出加上 `[序号]` 前缀后汇总到 swarm 的标准输出/标准错误
+//!
+//! ```bash
+//! swarm -n 8 -- -m http://master:3000 -c 10
+//! ```
+
+use clap::Parser;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+
+/// 实例运行超过该时长后退出，不算作快速失败，重启等待恢复为初始值
+const STABLE_RUNTIME: Duration = Duration::from_secs(60);
+
+/// 重启等待的最大倍数（2 的幂）
+const MAX_BACKOFF_SHIFT: u32 = 5;
+
+#[derive(Parser, Debug)]
+#[command(name = "swarm", about = "在一台机器上启动并看管多个Worker实例")]
+struct Cli {
+    /// 实例数
+    #[arg(short = 'n', long, default_value = "4")]
+    instances: usize,
+
+    /// Worker可执行文件，默认使用与 swarm 同目录的 worker
+    #[arg(long)]
+    worker_bin: Option<PathBuf>,
+
+    /// 实例ID的前缀，默认取主机名
+    #[arg(long)]
+    id_prefix: Option<String>,
+
+    /// 实例退出后重启前的等待时间（秒），连续快速退出时加倍
+    #[arg(long, default_value = "5")]
+    restart_delay: u64,
+
+    /// 传给每个Worker实例的参数（写在 `--` 之后）
+    #[arg(last = true)]
+    worker_args: Vec<String>,
+}
+
+/// 所有实例共用的启动参数
+struct Launch {
+    worker_bin: PathBuf,
+    id_prefix: String,
+    worker_args: Vec<String>,
+    restart_delay: Duration,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if cli.instances == 0 {
+        eprintln!("❌ 实例数必须大于0");
+        std::process::exit(1);
+    }
+
+    let worker_bin = match cli.worker_bin.map(Ok).unwrap_or_else(default_worker_bin) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("❌ 找不到Worker可执行文件: {}，请用 --worker-bin 指定", e);
+            std::process::exit(1);
+        }
+    };
+    let launch = Arc::new(Launch {
+        worker_bin,
+        id_prefix: cli.id_prefix.unwrap_or_else(default_prefix),
+        worker_args: cli.worker_args,
+        restart_delay: Duration::from_secs(cli.restart_delay),
+    });
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (interrupts_tx, interrupts) = watch::channel(0u32);
+
+    println!(
+        "[swarm] 启动 {} 个Worker实例: {} {}",
+        cli.instances,
+        launch.worker_bin.display(),
+        launch.worker_args.join(" ")
+    );
+
+    // ctrl+c 转发给所有实例：第一次优雅退出，第二次强制退出（释放任务）
+    {
+        let shutdown = Arc::clone(&shutdown);
+        tokio::spawn(async move {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                if !shutdown.swap(true, Ordering::SeqCst) {
+                    println!("[swarm] 收到 ctrl+c，通知所有实例退出（不再重启）");
+                }
+                interrupts_tx.send_modify(|count| *count += 1);
+            }
+        });
+    }
+
+    let supervisors: Vec<_> = (0..cli.instances)
+        .map(|index| {
+            let launch = Arc::clone(&launch);
+            let shutdown = Arc::clone(&shutdown);
+            let interrupts = interrupts.clone();
+            tokio::spawn(async move { supervise(index, &launch, &shutdown, interrupts).await })
+        })
+        .collect();
+    for supervisor in supervisors {
+        let _ = supervisor.await;
+    }
+
+    println!("[swarm] 所有实例已退出");
+}
+
+/// 看管一个实例：异常退出后按退避策略重启，直到实例正常退出或收到 ctrl+c
+async fn supervise(
+    index: usize,
+    launch: &Launch,
+    shutdown: &AtomicBool,
+    mut interrupts: watch::Receiver<u32>,
+) {
+    let worker_id = format!("{}-{}", launch.id_prefix, index);
+    let mut failures: u32 = 0;
+
+    loop {
+        let started = Instant::now();
+        let status = run_instance(index, &worker_id, launch, &mut interrupts).await;
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+
+        match &status {
+            Ok(status) if status.success() => {
+                println!("[swarm] 实例 {} ({}) 正常退出，不再重启", index, worker_id);
+                break;
+            }
+            Ok(status) => println!("[swarm] 实例 {} ({}) 已退出: {}", index, worker_id, status),
+            Err(e) => eprintln!("[swarm] 实例 {} ({}) 启动失败: {}", index, worker_id, e),
+        }
+        if started.elapsed() >= STABLE_RUNTIME {
+            failures = 0;
+        }
+        let delay = launch.restart_delay * 2u32.pow(failures.min(MAX_BACKOFF_SHIFT));
+        failures += 1;
+
+        println!(
+            "[swarm] {} 秒后重启实例 {}（第 {} 次连续重启）",
+            delay.as_secs(),
+            index,
+            failures
+        );
+        tokio::time::sleep(delay).await;
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+    }
+}
+
+/// 启动一个实例并转发它的输出和 ctrl+c，返回退出状态
+async fn run_instance(
+    index: usize,
+    worker_id: &str,
+    launch: &Launch,
+    interrupts: &mut watch::Receiver<u32>,
+) -> std::io::Result<ExitStatus> {
+    let mut command = Command::new(&launch.worker_bin);
+    command
+        .args(&launch.worker_args)
+        .env("PA_WORKER_ID", worker_id)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // 实例放在独立的进程组中，终端的 ctrl+c 只发给 swarm，由 swarm 转发，避免实例收到两次
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command.spawn()?;
+
+    let prefix = format!("[{}]", index);
+    let stdout = child
+        .stdout
+        .take()
+        .map(|out| tokio::spawn(forward(out, prefix.clone(), false)));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|err| tokio::spawn(forward(err, prefix, true)));
+
+    interrupts.mark_unchanged();
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status,
+            changed = interrupts.changed() => match changed {
+                Ok(()) => interrupt(&child),
+                Err(_) => break child.wait().await,
+            },
+        }
+    };
+    // 等输出转发完，避免实例最后几行日志丢失
+    for handle in [stdout, stderr].into_iter().flatten() {
+        let _ = handle.await;
+    }
+    status
+}
+
+/// 给每一行加上实例前缀后输出
+async fn forward<R: AsyncRead + Unpin>(reader: R, prefix: String, to_stderr: bool) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if to_stderr {
+            eprintln!("{} {}", prefix, line);
+        } else {
+            println!("{} {}", prefix, line);
+        }
+    }
+}
+
+/// 向实例发送 SIGINT（相当于在它的终端按 ctrl+c）
+#[cfg(unix)]
+fn interrupt(child: &Child) {
+    if let Some(pid) = child.id() {
+        // SAFETY: 只向自己启动、尚未回收的子进程发送信号
+        unsafe {
+            libc::kill(pid as libc::pid_t, libc::SIGINT);
+        }
+    }
+}
+
+/// Windows 控制台的 ctrl+c 会直接发给所有实例，不需要转发
+#[cfg(not(unix))]
+fn interrupt(_child: &Child) {}
+
+/// 与 swarm 同目录的 worker 可执行文件
+fn default_worker_bin() -> std::io::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let path = exe.with_file_name(format!("worker{}", std::env::consts::EXE_SUFFIX));
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            path.display().to_string(),
+        ))
+    }
+}
+
+/// 默认的实例ID前缀：主机名，取不到时为 swarm
+fn default_prefix() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "swarm".to_string())
+}
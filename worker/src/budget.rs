@@ -0,0 +1,78 @@
+//! 每日上游请求预算
+//!
+//! 设置 `--max-requests-per-day` 后统计当天（本地时间）发出的探测请求数（含重试），
+//! 用完后不再获取新任务，直到第二天零点。获取任务时请求的范围不超过剩余预算，
+//! 实际请求数只会因重试略超预算。计数只保存在内存中，重启后从零开始。
+
+use chrono::{Local, NaiveDate};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// 每日请求预算
+pub struct DailyBudget {
+    limit: u64,
+    state: Mutex<BudgetState>,
+}
+
+struct BudgetState {
+    /// 计数所属的日期
+    day: NaiveDate,
+    /// 当天已发出的请求数
+    used: u64,
+}
+
+impl DailyBudget {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(BudgetState {
+                day: today(),
+                used: 0,
+            }),
+        }
+    }
+
+    /// 记录一次请求
+    pub fn record(&self) {
+        let mut state = self.state.lock().expect("请求预算锁中毒");
+        state.roll_over();
+        state.used += 1;
+    }
+
+    /// 当天剩余的请求数
+    pub fn remaining(&self) -> u64 {
+        let mut state = self.state.lock().expect("请求预算锁中毒");
+        state.roll_over();
+        self.limit.saturating_sub(state.used)
+    }
+
+    /// 距预算重置（明天零点）的时长
+    pub fn until_reset(&self) -> Duration {
+        let now = Local::now();
+        let midnight = now
+            .date_naive()
+            .succ_opt()
+            .and_then(|day| day.and_hms_opt(0, 0, 0))
+            .and_then(|at| at.and_local_timezone(Local).earliest());
+        match midnight {
+            Some(midnight) => (midnight - now).to_std().unwrap_or_default(),
+            // 时区切换等导致零点不存在时，一小时后再检查
+            None => Duration::from_secs(3600),
+        }
+    }
+}
+
+impl BudgetState {
+    /// 日期变化时清零
+    fn roll_over(&mut self) {
+        let today = today();
+        if self.day != today {
+            self.day = today;
+            self.used = 0;
+        }
+    }
+}
+
+fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
@@ -0,0 +1,431 @@
+//! 自定义DNS解析
+//!
+//! 部分地区的DNS被污染或不稳定，解析失败的探测会被当成无效ID。这里提供两种绕过方式：
+//! - `--resolve host=ip`：把主机名固定解析到指定IP，不再查询DNS
+//! - `--dns-server ip[:port]` 或 `--doh url`：向指定的DNS服务器（UDP）或 DNS over HTTPS（RFC 8484）查询
+//!
+//! 查询结果按记录的TTL缓存；查询暂时失败时沿用过期的缓存，避免DNS抖动时整批探测失败。
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tracing::warn;
+
+/// 单个DNS服务器的查询超时
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 缓存时间的下限和上限（按记录的TTL，限制在该范围内）
+const MIN_CACHE_TTL: Duration = Duration::from_secs(10);
+const MAX_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// 记录类型
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+
+/// 固定解析，形如 `example.com=1.2.3.4`
+#[derive(Debug, Clone)]
+pub struct HostPin {
+    pub host: String,
+    pub addr: IpAddr,
+}
+
+impl FromStr for HostPin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, addr) = s
+            .split_once('=')
+            .ok_or_else(|| format!("固定解析的格式应为 主机名=IP: {}", s))?;
+        let host = host.trim();
+        if host.is_empty() {
+            return Err(format!("固定解析缺少主机名: {}", s));
+        }
+        let addr = addr
+            .trim()
+            .parse()
+            .map_err(|_| format!("无效的IP地址: {}", addr))?;
+        Ok(Self {
+            host: host.to_ascii_lowercase(),
+            addr,
+        })
+    }
+}
+
+/// 解析DNS服务器地址，未给出端口时使用53
+pub fn parse_dns_server(s: &str) -> Result<SocketAddr, String> {
+    if let Ok(addr) = s.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+    s.parse::<IpAddr>()
+        .map(|ip| SocketAddr::new(ip, 53))
+        .map_err(|_| format!("无效的DNS服务器地址: {}", s))
+}
+
+/// 查询方式
+enum Upstream {
+    /// 依次尝试的UDP DNS服务器
+    Udp(Vec<SocketAddr>),
+    /// DNS over HTTPS 地址及使用的客户端
+    Doh {
+        url: String,
+        client: reqwest::Client,
+    },
+}
+
+/// 缓存的解析结果
+struct CacheEntry {
+    addrs: Vec<IpAddr>,
+    expires_at: Instant,
+}
+
+/// 向指定服务器查询的解析器，交给 reqwest 代替系统DNS
+#[derive(Clone)]
+pub struct CustomResolver {
+    upstream: Arc<Upstream>,
+    cache: Arc<Mutex<HashMap<String, CacheEntry>>>,
+}
+
+impl CustomResolver {
+    /// 通过UDP向 `servers` 依次查询
+    pub fn udp(servers: Vec<SocketAddr>) -> Self {
+        Self::new(Upstream::Udp(servers))
+    }
+
+    /// 通过 DNS over HTTPS 查询，`client` 用于访问DoH服务（不能再使用本解析器）
+    pub fn doh(url: String, client: reqwest::Client) -> Self {
+        Self::new(Upstream::Doh { url, client })
+    }
+
+    fn new(upstream: Upstream) -> Self {
+        Self {
+            upstream: Arc::new(upstream),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 解析主机名，先查缓存，查询失败时沿用过期的缓存
+    async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>, String> {
+        let stale = {
+            let cache = self.cache.lock().expect("DNS缓存锁中毒");
+            match cache.get(host) {
+                Some(entry) if entry.expires_at > Instant::now() => {
+                    return Ok(entry.addrs.clone());
+                }
+                Some(entry) => Some(entry.addrs.clone()),
+                None => None,
+            }
+        };
+
+        let result = match self.query(host, TYPE_A).await {
+            Ok((addrs, _)) if addrs.is_empty() => self.query(host, TYPE_AAAA).await,
+            result => result,
+        };
+        match result {
+            Ok((addrs, ttl)) if !addrs.is_empty() => {
+                let ttl = ttl.clamp(MIN_CACHE_TTL, MAX_CACHE_TTL);
+                self.cache.lock().expect("DNS缓存锁中毒").insert(
+                    host.to_string(),
+                    CacheEntry {
+                        addrs: addrs.clone(),
+                        expires_at: Instant::now() + ttl,
+                    },
+                );
+                Ok(addrs)
+            }
+            Ok(_) => stale.ok_or_else(|| format!("{} 没有解析记录", host)),
+            Err(e) => match stale {
+                Some(addrs) => {
+                    warn!("解析 {} 失败: {}，沿用过期的缓存", host, e);
+                    Ok(addrs)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// 查询一种记录，返回地址和最小TTL
+    async fn query(&self, host: &str, qtype: u16) -> Result<(Vec<IpAddr>, Duration), String> {
+        match &*self.upstream {
+            Upstream::Udp(servers) => {
+                let mut last_error = "没有配置DNS服务器".to_string();
+                for server in servers {
+                    match query_udp(*server, host, qtype).await {
+                        Ok(result) => return Ok(result),
+                        Err(e) => last_error = format!("{}: {}", server, e),
+                    }
+                }
+                Err(last_error)
+            }
+            Upstream::Doh { url, client } => query_doh(client, url, host, qtype).await,
+        }
+    }
+}
+
+impl Resolve for CustomResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addrs = resolver.lookup(&name.as_str().to_ascii_lowercase()).await?;
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+/// 通过UDP查询
+async fn query_udp(
+    server: SocketAddr,
+    host: &str,
+    qtype: u16,
+) -> Result<(Vec<IpAddr>, Duration), String> {
+    let bind: SocketAddr = if server.is_ipv4() {
+        "0.0.0.0:0".parse().expect("固定的地址")
+    } else {
+        "[::]:0".parse().expect("固定的地址")
+    };
+    let socket = UdpSocket::bind(bind).await.map_err(|e| e.to_string())?;
+    let id = rand::random::<u16>();
+    let query = encode_query(id, host, qtype)?;
+
+    let exchange = async {
+        socket.send_to(&query, server).await?;
+        let mut buf = [0u8; 1500];
+        loop {
+            let (len, from) = socket.recv_from(&mut buf).await?;
+            // 忽略其他来源或ID不符的报文（防止伪造的响应）
+            if from == server && len >= 2 && u16::from_be_bytes([buf[0], buf[1]]) == id {
+                return Ok::<_, std::io::Error>(buf[..len].to_vec());
+            }
+        }
+    };
+    let response = tokio::time::timeout(QUERY_TIMEOUT, exchange)
+        .await
+        .map_err(|_| "查询超时".to_string())?
+        .map_err(|e| e.to_string())?;
+    decode_response(&response, id)
+}
+
+/// 通过 DNS over HTTPS（RFC 8484，POST application/dns-message）查询
+async fn query_doh(
+    client: &reqwest::Client,
+    url: &str,
+    host: &str,
+    qtype: u16,
+) -> Result<(Vec<IpAddr>, Duration), String> {
+    // RFC 8484 建议 DoH 请求的ID为0，便于HTTP缓存
+    let query = encode_query(0, host, qtype)?;
+    let response = client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/dns-message")
+        .header(reqwest::header::ACCEPT, "application/dns-message")
+        .timeout(QUERY_TIMEOUT)
+        .body(query)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("DoH 服务返回 {}", response.status()));
+    }
+    let body = response.bytes().await.map_err(|e| e.to_string())?;
+    decode_response(&body, 0)
+}
+
+/// 构造只有一个问题、要求递归查询的请求报文
+fn encode_query(id: u16, host: &str, qtype: u16) -> Result<Vec<u8>, String> {
+    let mut packet = Vec::with_capacity(32 + host.len());
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&0x0100u16.to_be_bytes()); // RD
+    packet.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    packet.extend_from_slice(&[0; 6]); // ANCOUNT、NSCOUNT、ARCOUNT
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("无效的主机名: {}", host));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes()); // IN
+    Ok(packet)
+}
+
+/// 解析响应报文中的 A/AAAA 记录，返回地址和其中最小的TTL
+fn decode_response(packet: &[u8], id: u16) -> Result<(Vec<IpAddr>, Duration), String> {
+    let malformed = || "DNS响应格式错误".to_string();
+    let read_u16 = |pos: usize| -> Result<u16, String> {
+        packet
+            .get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(malformed)
+    };
+
+    if read_u16(0)? != id {
+        return Err("DNS响应ID不匹配".to_string());
+    }
+    let flags = read_u16(2)?;
+    match flags & 0x000f {
+        0 => {}
+        3 => return Ok((Vec::new(), MIN_CACHE_TTL)), // NXDOMAIN
+        rcode => return Err(format!("DNS服务器返回错误码 {}", rcode)),
+    }
+    let questions = read_u16(4)?;
+    let answers = read_u16(6)?;
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(packet, pos).ok_or_else(malformed)? + 4;
+    }
+
+    let mut addrs = Vec::new();
+    let mut ttl = MAX_CACHE_TTL;
+    for _ in 0..answers {
+        pos = skip_name(packet, pos).ok_or_else(malformed)?;
+        let rtype = read_u16(pos)?;
+        let record_ttl = packet
+            .get(pos + 4..pos + 8)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(malformed)?;
+        let len = read_u16(pos + 8)? as usize;
+        let data = packet.get(pos + 10..pos + 10 + len).ok_or_else(malformed)?;
+        pos += 10 + len;
+
+        let addr = match (rtype, data.len()) {
+            (TYPE_A, 4) => IpAddr::from(<[u8; 4]>::try_from(data).expect("长度已检查")),
+            (TYPE_AAAA, 16) => IpAddr::from(<[u8; 16]>::try_from(data).expect("长度已检查")),
+            // CNAME 等其他记录：递归服务器已经给出了最终的地址记录
+            _ => continue,
+        };
+        addrs.push(addr);
+        ttl = ttl.min(Duration::from_secs(u64::from(record_ttl)));
+    }
+    Ok((addrs, ttl))
+}
+
+/// 跳过报文中的一个域名（支持压缩指针），返回其后的位置
+fn skip_name(packet: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(pos)?;
+        match len {
+            0 => return Some(pos + 1),
+            len if len & 0xc0 == 0xc0 => return Some(pos + 2),
+            len => pos += 1 + len as usize,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    const TYPE_CNAME: u16 = 5;
+
+    /// 在请求报文的基础上构造响应：设置标志位和回答数，追加回答记录
+    fn response(id: u16, rcode: u16, answers: &[Vec<u8>]) -> Vec<u8> {
+        let mut packet = encode_query(id, "www.example.com", TYPE_A).unwrap();
+        packet[2..4].copy_from_slice(&(0x8180 | rcode).to_be_bytes());
+        packet[6..8].copy_from_slice(&(answers.len() as u16).to_be_bytes());
+        for answer in answers {
+            packet.extend_from_slice(answer);
+        }
+        packet
+    }
+
+    /// 回答记录：`name` 为已编码的域名（可以是压缩指针）
+    fn record(name: &[u8], rtype: u16, ttl: u32, data: &[u8]) -> Vec<u8> {
+        let mut record = name.to_vec();
+        record.extend_from_slice(&rtype.to_be_bytes());
+        record.extend_from_slice(&1u16.to_be_bytes());
+        record.extend_from_slice(&ttl.to_be_bytes());
+        record.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        record.extend_from_slice(data);
+        record
+    }
+
+    /// 指向问题中域名（偏移12）的压缩指针
+    const QUESTION_NAME: [u8; 2] = [0xc0, 0x0c];
+
+    #[test]
+    fn query_layout() {
+        let query = encode_query(0x1234, "www.example.com.", TYPE_AAAA).unwrap();
+        assert_eq!(&query[..4], &[0x12, 0x34, 0x01, 0x00]);
+        assert_eq!(&query[4..12], &[0, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&query[12..29], b"\x03www\x07example\x03com\x00");
+        assert_eq!(&query[29..], &[0, 28, 0, 1]);
+
+        assert!(encode_query(1, "a..b", TYPE_A).is_err());
+        assert!(encode_query(1, &format!("{}.com", "a".repeat(64)), TYPE_A).is_err());
+    }
+
+    #[test]
+    fn compressed_name_answer() {
+        let packet = response(
+            7,
+            0,
+            &[
+                record(&QUESTION_NAME, TYPE_A, 60, &[1, 2, 3, 4]),
+                record(&QUESTION_NAME, TYPE_AAAA, 30, &Ipv6Addr::LOCALHOST.octets()),
+            ],
+        );
+        let (addrs, ttl) = decode_response(&packet, 7).unwrap();
+        assert_eq!(
+            addrs,
+            [
+                IpAddr::from(Ipv4Addr::new(1, 2, 3, 4)),
+                IpAddr::from(Ipv6Addr::LOCALHOST)
+            ]
+        );
+        assert_eq!(ttl, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn cname_then_address() {
+        let query_len = response(7, 0, &[]).len();
+        // CNAME 指向 cdn.example.com（"example.com" 压缩为指向问题中偏移16的指针）
+        let cname = record(&QUESTION_NAME, TYPE_CNAME, 600, b"\x03cdn\xc0\x10");
+        // A 记录的名字指向 CNAME 数据（记录头12字节之后）
+        let target = (0xc000 | (query_len + 12) as u16).to_be_bytes();
+        let a = record(&target, TYPE_A, 120, &[10, 0, 0, 1]);
+        let packet = response(7, 0, &[cname, a]);
+
+        let (addrs, ttl) = decode_response(&packet, 7).unwrap();
+        assert_eq!(addrs, [IpAddr::from(Ipv4Addr::new(10, 0, 0, 1))]);
+        // CNAME 的TTL不影响地址的缓存时间
+        assert_eq!(ttl, Duration::from_secs(120));
+    }
+
+    #[test]
+    fn nxdomain_has_no_addresses() {
+        let (addrs, ttl) = decode_response(&response(7, 3, &[]), 7).unwrap();
+        assert!(addrs.is_empty());
+        assert_eq!(ttl, MIN_CACHE_TTL);
+
+        // 其他错误码（如 SERVFAIL）返回错误
+        assert!(decode_response(&response(7, 2, &[]), 7).is_err());
+    }
+
+    #[test]
+    fn truncated_packets_are_rejected() {
+        let packet = response(7, 0, &[record(&QUESTION_NAME, TYPE_A, 60, &[1, 2, 3, 4])]);
+        assert!(decode_response(&packet, 7).is_ok());
+        for len in 0..packet.len() {
+            assert!(
+                decode_response(&packet[..len], 7).is_err(),
+                "截断到 {} 字节的报文应解析失败",
+                len
+            );
+        }
+        assert!(decode_response(&[0], 0).is_err());
+    }
+
+    #[test]
+    fn mismatched_id_is_rejected() {
+        let packet = response(7, 0, &[record(&QUESTION_NAME, TYPE_A, 60, &[1, 2, 3, 4])]);
+        assert_eq!(decode_response(&packet, 8).unwrap_err(), "DNS响应ID不匹配");
+    }
+}
@@ -0,0 +1,30 @@
+//! 有效ID的提前上报
+//!
+//! 启用 `--stream-interval` 后，扫描中发现的有效ID先放进缓冲区，后台每隔一段时间
+//! 通过 `POST /task/results` 上报给Master，Worker在任务快结束时崩溃也不会丢失已发现的结果。
+//! 任务完成时的提交仍然带上全部有效ID，Master会忽略已经写入的ID。
+
+use std::sync::Mutex;
+
+/// 尚未上报的有效ID
+#[derive(Default)]
+pub struct HitBuffer {
+    pending: Mutex<Vec<i64>>,
+}
+
+impl HitBuffer {
+    /// 记录一个新发现的有效ID
+    pub fn push(&self, id: i64) {
+        self.pending.lock().expect("上报缓冲锁中毒").push(id);
+    }
+
+    /// 取出所有待上报的ID
+    pub fn take(&self) -> Vec<i64> {
+        std::mem::take(&mut *self.pending.lock().expect("上报缓冲锁中毒"))
+    }
+
+    /// 上报失败时放回缓冲区，下次一起上报
+    pub fn restore(&self, ids: Vec<i64>) {
+        self.pending.lock().expect("上报缓冲锁中毒").extend(ids);
+    }
+}
@@ -0,0 +1,1398 @@
+//! Worker节点 - 分布式ID扫描系统的边缘节点
+//!
+//! 功能：
+//! - 持续循环获取任务
+//! - 后台心跳保活
+//! - HTTP探测（并发控制）
+//! - 提交结果
+//! - 优雅退出（ctrl+c）
+//!
+//! `worker` 可执行文件解析命令行、初始化日志后调用 [`run`]；Master的单机模式
+//! （`master standalone`）在同一个进程中以同样的方式运行一个Worker。
+
+mod archive;
+mod budget;
+mod dns;
+mod hits;
+mod pacer;
+mod probe_slots;
+mod progress;
+mod system;
+
+use budget::DailyBudget;
+use clap::Parser;
+use common::client::{CallOptions, ClientError, MasterClient, RetryPolicy};
+use common::coverage::{Coverage, IdStatus};
+use common::latency::{LatencyRecorder, LatencyStats};
+use common::probe::{self, Outcome};
+use common::routes;
+use common::runtime::RuntimeConfig;
+use common::telemetry::LogFormat;
+use common::{
+    AcquireTaskRequest, AcquireTaskResponse, ArchiveResponsesRequest, ErrorCode, HeartbeatRequest,
+    HeartbeatResponse, MarkedId, PartialResultRequest, RawResponse, ReleaseTaskRequest,
+    SubmitResultRequest, WorkerProfile,
+};
+use dns::{CustomResolver, HostPin};
+use hits::HitBuffer;
+use pacer::Pacer;
+use probe_slots::ProbeSlots;
+use progress::Progress;
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::task::{JoinError, JoinSet};
+use tokio::time::sleep;
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+/// 提交结果的最多尝试次数（带幂等键，重试不会重复提交）
+const SUBMIT_ATTEMPTS: u32 = 3;
+
+/// 提交结果重试时单次等待的上限
+const SUBMIT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Master维护时没有给出 `Retry-After` 时的等待时长
+const MAINTENANCE_RETRY: Duration = Duration::from_secs(30);
+
+/// 扫描流水线中每个通道按并发数计的缓冲倍数
+const PIPELINE_DEPTH: usize = 2;
+
+/// 探测被上游拒绝（403、429）后，重试同一个ID之前的等待时间
+const BLOCKED_BACKOFF: Duration = Duration::from_secs(1);
+
+/// 请求预算用完后检查是否已到第二天的间隔
+const BUDGET_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Worker配置
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about = "分布式ID扫描系统 - Worker节点", long_about = None)]
+pub struct Config {
+    /// Master节点地址
+    #[arg(short = 'm', long, default_value = "http://localhost:3000")]
+    pub master_url: String,
+
+    /// Worker ID，不设置则每次启动随机生成（swarm 为每个实例指定固定的ID）
+    #[arg(long, env = "PA_WORKER_ID")]
+    pub worker_id: Option<String>,
+
+    /// 初始处理速度（req/s）
+    #[arg(short = 's', long, default_value = "20")]
+    pub initial_speed: u32,
+
+    /// HTTP并发数（同时执行多个任务时为所有任务共用的总数）
+    #[arg(short = 'c', long, default_value = "5")]
+    pub concurrency: usize,
+
+    /// 同时执行的任务数，各任务分别发送心跳和提交结果（上游延迟高、单个任务跑不满并发数时调大）
+    #[arg(long, default_value = "1")]
+    pub concurrent_tasks: NonZeroUsize,
+
+    /// 每个任务最多接受的ID数（内存受限或按流量计费时设置），不设置则由Master按速度决定
+    #[arg(long)]
+    pub max_batch_size: Option<i64>,
+
+    /// 单个HTTP请求的超时时间（秒），超时的探测会重试
+    #[arg(long, default_value = "30")]
+    pub request_timeout: u64,
+
+    /// 建立连接的超时时间（秒）
+    #[arg(long, default_value = "10")]
+    pub connect_timeout: u64,
+
+    /// 连接池中每个主机最多保留的空闲连接数，不设置则不限制
+    #[arg(long)]
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// 空闲连接的保留时间（秒），0表示不回收
+    #[arg(long, default_value = "90")]
+    pub pool_idle_timeout: u64,
+
+    /// TCP keepalive 间隔（秒），不设置则不启用
+    #[arg(long)]
+    pub tcp_keepalive: Option<u64>,
+
+    /// 直接使用 HTTP/2 连接上游（不经过 ALPN 协商），上游必须支持 HTTP/2
+    #[arg(long, conflicts_with = "http1_only")]
+    pub http2: bool,
+
+    /// 只使用 HTTP/1.1，不协商 HTTP/2
+    #[arg(long)]
+    pub http1_only: bool,
+
+    /// 把主机名固定解析到指定IP（如 web-drcn.hispace.dbankcloud.com=1.2.3.4），可多次指定
+    #[arg(long = "resolve", value_name = "HOST=IP")]
+    pub resolve: Vec<HostPin>,
+
+    /// 用指定的DNS服务器（IP 或 IP:端口）代替系统DNS，可多次指定，按顺序尝试
+    #[arg(long = "dns-server", value_name = "ADDR", value_parser = dns::parse_dns_server)]
+    pub dns_servers: Vec<SocketAddr>,
+
+    /// 用 DNS over HTTPS 代替系统DNS（如 https://1.1.1.1/dns-query）
+    #[arg(long, value_name = "URL", conflicts_with = "dns_servers")]
+    pub doh: Option<String>,
+
+    /// 上报给Master的主机名，默认取本机主机名
+    #[arg(long)]
+    pub hostname: Option<String>,
+
+    /// 上报给Master的地区（如 cn-sh、us-west）
+    #[arg(long)]
+    pub region: Option<String>,
+
+    /// 上报给Master的出口信息（如代理名称或出口IP）
+    #[arg(long)]
+    pub egress: Option<String>,
+
+    /// 上报给Master的自定义标签（如 owner=alice），可多次指定
+    #[arg(long = "label", value_name = "KEY=VALUE", value_parser = parse_label)]
+    pub labels: Vec<(String, String)>,
+
+    /// 心跳间隔（秒）
+    #[arg(short = 'b', long, default_value = "10")]
+    pub heartbeat_interval: u64,
+
+    /// 失败重试间隔（秒）
+    #[arg(short = 'r', long, default_value = "5")]
+    pub retry_interval: u64,
+
+    /// 单个ID最多重试的次数，用完后记为未确认（由Master之后重新排队检查），0表示一直重试
+    #[arg(long, default_value = "10")]
+    pub max_id_retries: u32,
+
+    /// 调用Master接口的最多尝试次数，网络抖动时在客户端内按指数退避重试（非幂等的请求只在连接失败时重试）
+    #[arg(long, default_value = "3")]
+    pub master_attempts: u32,
+
+    /// 完成该数量的任务后退出（适合按量贡献），不设置则不限制
+    #[arg(long)]
+    pub max_tasks: Option<u64>,
+
+    /// 运行超过该时长后不再获取新任务，完成当前任务后退出（如 4h，适合竞价实例和定时任务）
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub max_runtime: Option<Duration>,
+
+    /// 每天（本地时间）最多发出的探测请求数（含重试），用完后暂停获取任务直到第二天，不设置则不限制
+    #[arg(long)]
+    pub max_requests_per_day: Option<u64>,
+
+    /// 扫描中输出进度的间隔（秒），0表示不输出
+    #[arg(long, default_value = "10")]
+    pub progress_interval: u64,
+
+    /// 标准错误是终端时用进度条代替进度日志
+    #[arg(long)]
+    pub progress_bar: bool,
+
+    /// 提前上报已发现的有效ID的间隔（秒），0表示只在任务完成时提交
+    #[arg(long, default_value = "0")]
+    pub stream_interval: u64,
+
+    /// 把每个有效ID的原始响应（gzip 压缩）上传给Master存档，Master需要启用 --response-archive
+    #[arg(long)]
+    pub archive_responses: bool,
+
+    /// 在心跳中上报主机的负载、内存、交换空间和网卡错误计数（从 /proc 读取，仅 Linux）
+    #[arg(long)]
+    pub report_system: bool,
+
+    /// 访问Master的API Key（Master启用 --require-api-key 时必填）
+    #[arg(long, env = "PA_API_KEY", hide_env_values = true)]
+    pub api_key: Option<String>,
+
+    /// OTLP 链路追踪导出地址（如 http://localhost:4318/v1/traces），不设置则不导出
+    #[arg(long)]
+    pub otlp_endpoint: Option<String>,
+
+    /// 日志格式（text 或 json）
+    #[arg(long, default_value = "text")]
+    pub log_format: LogFormat,
+
+    /// Tokio 工作线程数，默认取可用的 CPU 核数（容器中可能不准确，小型 VPS 上 1~2 个即可）
+    #[arg(long, env = "PA_WORKER_THREADS")]
+    pub worker_threads: Option<NonZeroUsize>,
+
+    /// Tokio 阻塞线程池的线程数上限（默认 512）
+    #[arg(long)]
+    pub max_blocking_threads: Option<NonZeroUsize>,
+}
+
+impl Config {
+    /// 按 `--worker-threads`、`--max-blocking-threads` 生成的运行时参数
+    pub fn runtime(&self) -> RuntimeConfig {
+        RuntimeConfig {
+            worker_threads: self.worker_threads,
+            max_blocking_threads: self.max_blocking_threads,
+        }
+    }
+}
+
+/// 一个任务的扫描结果
+struct ScanOutput {
+    /// 有效ID
+    valid_ids: Vec<i64>,
+
+    /// 命中响应标记的无效ID
+    marked: Vec<MarkedId>,
+
+    /// 有效ID的原始响应（启用 `--archive-responses` 时）
+    responses: Vec<RawResponse>,
+
+    /// 探测覆盖信息
+    coverage: Coverage,
+
+    /// 探测延迟分布（没有探测时为 None）
+    latency: Option<LatencyStats>,
+
+    /// 限时任务扫描到的最大ID（普通任务为 None）
+    scanned_to: Option<i64>,
+}
+
+/// 单个ID的探测结论
+struct Probed {
+    /// 最终的状态
+    status: IdStatus,
+
+    /// 命中的响应标记
+    marker: Option<String>,
+
+    /// 有效ID的原始响应体
+    response: Option<Vec<u8>>,
+}
+
+impl Probed {
+    fn new(status: IdStatus) -> Self {
+        Self {
+            status,
+            marker: None,
+            response: None,
+        }
+    }
+}
+
+/// Worker状态
+#[derive(Clone)]
+struct WorkerState {
+    /// Worker唯一标识符
+    pub worker_id: String,
+
+    /// 当前处理速度
+    pub current_speed: Arc<RwLock<u32>>,
+
+    /// 上一个任务中上游请求需要重试的比例
+    pub last_error_rate: Arc<RwLock<Option<f64>>>,
+
+    /// 获取任务时上报的主机信息和标签
+    pub profile: WorkerProfile,
+
+    /// 探测上游的HTTP客户端
+    pub client: reqwest::Client,
+
+    /// Master客户端（带API Key和重试）
+    pub master: MasterClient,
+
+    /// 按Master分配的份额限制上游请求速度
+    pub pacer: Arc<Pacer>,
+
+    /// 每日请求预算（未设置时为 None）
+    pub budget: Option<Arc<DailyBudget>>,
+
+    /// 是否收到退出信号（第一次 ctrl+c）
+    pub shutdown_requested: Arc<AtomicBool>,
+
+    /// 是否需要强制退出（第二次 ctrl+c）
+    pub force_shutdown: Arc<AtomicBool>,
+
+    /// 正在执行的任务ID（强制退出时释放）
+    pub running_tasks: Arc<std::sync::Mutex<BTreeSet<i32>>>,
+
+    /// 所有任务共用的探测并发数（`-c`，不超过Master的建议值）
+    pub probe_slots: Arc<ProbeSlots>,
+}
+
+/// 任务协程的结果：是否提交了任务结果（任务被收回时为 false）
+type TaskResult = Result<bool, String>;
+
+/// 运行Worker直到收到退出信号或达到 `--max-tasks`、`--max-runtime` 上限
+///
+/// 调用方负责初始化日志（进程中只能初始化一次）。
+pub async fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    // 生成Worker ID
+    let worker_id = config
+        .worker_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    // Master会拒绝不合格的ID，启动时就报错而不是每次获取任务都失败
+    common::request::check_worker_id(&worker_id)?;
+    info!("启动Worker节点，ID: {}", worker_id);
+    info!("Master地址: {}", config.master_url);
+    info!("初始速度: {} req/s", config.initial_speed);
+    info!("并发数: {}", config.concurrency);
+    if config.concurrent_tasks.get() > 1 {
+        info!("同时执行 {} 个任务", config.concurrent_tasks);
+    }
+    info!(
+        "运行时: {} 个工作线程，阻塞线程上限 {}",
+        config.runtime().worker_threads(),
+        config.runtime().max_blocking_threads()
+    );
+
+    // 创建Worker状态
+    let client = build_http_client(&config)?;
+    // 访问Master不使用探测上游的超时、连接池和DNS设置
+    let mut master = MasterClient::new(config.master_url.clone()).with_retry(RetryPolicy {
+        max_attempts: config.master_attempts.max(1),
+        ..RetryPolicy::default()
+    });
+    if let Some(key) = &config.api_key {
+        master = master.with_token(key);
+    }
+    let state = Arc::new(WorkerState {
+        worker_id: worker_id.clone(),
+        current_speed: Arc::new(RwLock::new(config.initial_speed)),
+        last_error_rate: Arc::new(RwLock::new(None)),
+        profile: worker_profile(&config),
+        client,
+        master,
+        pacer: Arc::new(Pacer::default()),
+        budget: config
+            .max_requests_per_day
+            .map(|limit| Arc::new(DailyBudget::new(limit))),
+        shutdown_requested: Arc::new(AtomicBool::new(false)),
+        force_shutdown: Arc::new(AtomicBool::new(false)),
+        running_tasks: Arc::new(std::sync::Mutex::new(BTreeSet::new())),
+        probe_slots: Arc::new(ProbeSlots::new(config.concurrency)),
+    });
+
+    // 设置 ctrl+c 信号处理
+    let state_for_signal = Arc::clone(&state);
+    tokio::spawn(async move {
+        setup_signal_handler(&state_for_signal).await;
+    });
+
+    // 启动主循环：每个任务在单独的协程中执行，最多同时执行 --concurrent-tasks 个
+    let started = Instant::now();
+    let deadline = config.max_runtime.map(|max| started + max);
+    let slots = config.concurrent_tasks.get();
+    let mut running: JoinSet<TaskResult> = JoinSet::new();
+    let mut completed_tasks: u64 = 0;
+    let mut in_maintenance = false;
+    let mut budget_exhausted = false;
+    loop {
+        // 等待空闲的位置，同时记录已经结束的任务
+        while running.len() >= slots {
+            if let Some(joined) = running.join_next().await {
+                completed_tasks += task_finished(joined);
+            }
+        }
+        while let Some(joined) = running.try_join_next() {
+            completed_tasks += task_finished(joined);
+        }
+
+        // 检查是否收到退出信号
+        if state.shutdown_requested.load(Ordering::SeqCst) {
+            info!("收到退出信号，停止获取新任务");
+            break;
+        }
+
+        // 检查任务数和运行时长是否已达上限（正在执行的任务完成后就达到上限时不再获取）
+        if let Some(max) = config.max_tasks {
+            if completed_tasks >= max {
+                info!(
+                    "已完成 {} 个任务，达到 --max-tasks 上限，退出",
+                    completed_tasks
+                );
+                break;
+            }
+            if completed_tasks + running.len() as u64 >= max {
+                if let Some(joined) = running.join_next().await {
+                    completed_tasks += task_finished(joined);
+                }
+                continue;
+            }
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            info!(
+                "已运行 {}，达到 --max-runtime 上限，退出",
+                humantime::format_duration(Duration::from_secs(started.elapsed().as_secs()))
+            );
+            break;
+        }
+
+        // 当天的请求预算用完后不再获取任务，等到第二天
+        if let Some(budget) = &state.budget {
+            if budget.remaining() == 0 {
+                let wait = budget.until_reset();
+                if !budget_exhausted {
+                    budget_exhausted = true;
+                    info!(
+                        "今日请求预算已用完，{} 后恢复获取任务",
+                        humantime::format_duration(Duration::from_secs(wait.as_secs()))
+                    );
+                }
+                sleep(capped_wait(wait.min(BUDGET_CHECK_INTERVAL), deadline)).await;
+                continue;
+            }
+            if budget_exhausted {
+                budget_exhausted = false;
+                info!("请求预算已重置，恢复获取任务");
+            }
+        }
+
+        // 一次领取所有空闲位置的任务（不超过 --max-tasks 剩余的数量）
+        let free = (slots - running.len()) as u64;
+        let wanted = config.max_tasks.map_or(free, |max| {
+            free.min(max - completed_tasks - running.len() as u64)
+        });
+        let result = acquire_task(&config, &state, wanted as u32)
+            .instrument(info_span!("acquire", worker_id = %state.worker_id))
+            .await;
+        if in_maintenance && result.is_ok() {
+            in_maintenance = false;
+            info!("Master维护已结束");
+        }
+        match result {
+            Ok(tasks) => {
+                for task in tasks {
+                    spawn_task(&mut running, &config, &state, task);
+                }
+                // 与Master的获取间隔（--acquire-interval）保持一致
+                sleep(Duration::from_secs(1)).await;
+            }
+            Err(e)
+                if e.downcast_ref::<ClientError>()
+                    .is_some_and(ClientError::is_maintenance) =>
+            {
+                // 维护期间只在开始时记录一次，按建议的间隔安静地等待
+                let wait = e
+                    .downcast_ref::<ClientError>()
+                    .and_then(ClientError::retry_after)
+                    .unwrap_or(MAINTENANCE_RETRY);
+                if in_maintenance {
+                    debug!("Master仍在维护，{} 秒后重试", wait.as_secs());
+                } else {
+                    in_maintenance = true;
+                    info!("{}，每 {} 秒重试一次", e, wait.as_secs());
+                }
+                sleep(capped_wait(wait, deadline)).await;
+                continue;
+            }
+            Err(e) => {
+                // Master给出了 Retry-After（暂无任务、分发暂停、限流等）时按其建议等待
+                let retry_after = e
+                    .downcast_ref::<ClientError>()
+                    .and_then(ClientError::retry_after);
+                match retry_after {
+                    Some(wait) => {
+                        info!("暂时无法获取任务: {}，在 {} 秒后重试...", e, wait.as_secs());
+                        sleep(capped_wait(wait, deadline)).await;
+                    }
+                    None => {
+                        error!(
+                            "获取任务失败: {}，在 {} 秒后重试...",
+                            e, config.retry_interval
+                        );
+                        let wait = Duration::from_secs(config.retry_interval);
+                        sleep(capped_wait(wait, deadline)).await;
+                    }
+                }
+            }
+        }
+    }
+
+    // 完成正在执行的任务后再退出
+    if !running.is_empty() {
+        info!("等待 {} 个正在执行的任务完成...", running.len());
+    }
+    while let Some(joined) = running.join_next().await {
+        task_finished(joined);
+    }
+
+    info!("Worker已优雅退出");
+    Ok(())
+}
+
+/// 在单独的协程中执行任务
+fn spawn_task(
+    running: &mut JoinSet<TaskResult>,
+    config: &Config,
+    state: &Arc<WorkerState>,
+    task: AcquireTaskResponse,
+) {
+    let config = config.clone();
+    let state = Arc::clone(state);
+    let task_id = task.task_id;
+    // 在协程启动前登记，强制退出时总能释放；协程结束（包括 panic）时移除
+    let guard = RunningGuard::new(Arc::clone(&state), task_id);
+    let span = info_span!("task_cycle", worker_id = %state.worker_id, task_id);
+    running.spawn(
+        async move {
+            let _guard = guard;
+            run_task(&config, &state, task)
+                .await
+                .map_err(|e| e.to_string())
+        }
+        .instrument(span),
+    );
+}
+
+/// 正在执行的任务的登记，释放时从 [`WorkerState::running_tasks`] 中移除
+struct RunningGuard {
+    state: Arc<WorkerState>,
+    task_id: i32,
+}
+
+impl RunningGuard {
+    fn new(state: Arc<WorkerState>, task_id: i32) -> Self {
+        state
+            .running_tasks
+            .lock()
+            .expect("任务列表锁中毒")
+            .insert(task_id);
+        Self { state, task_id }
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.state
+            .running_tasks
+            .lock()
+            .expect("任务列表锁中毒")
+            .remove(&self.task_id);
+    }
+}
+
+/// 记录结束的任务，返回完成的任务数（0 或 1）
+fn task_finished(joined: Result<TaskResult, JoinError>) -> u64 {
+    match joined {
+        Ok(Ok(true)) => {
+            info!("任务完成，等待下一个任务...");
+            1
+        }
+        Ok(Ok(false)) => 0,
+        Ok(Err(e)) => {
+            error!("任务执行失败: {}", e);
+            0
+        }
+        Err(e) => {
+            error!("任务协程异常退出: {}", e);
+            0
+        }
+    }
+}
+
+/// 等待时间不超过 `--max-runtime` 剩余的时长
+fn capped_wait(wait: Duration, deadline: Option<Instant>) -> Duration {
+    deadline.map_or(wait, |deadline| {
+        wait.min(deadline.saturating_duration_since(Instant::now()))
+    })
+}
+
+/// 解析 `KEY=VALUE` 形式的标签
+fn parse_label(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("标签的格式应为 KEY=VALUE: {}", s))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("标签缺少名称: {}", s));
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+/// 按配置生成上报给Master的主机信息
+fn worker_profile(config: &Config) -> WorkerProfile {
+    let hostname = config.hostname.clone().or_else(|| {
+        std::env::var("HOSTNAME")
+            .ok()
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|host| host.trim().to_string())
+            .filter(|host| !host.is_empty())
+    });
+    WorkerProfile {
+        hostname,
+        region: config.region.clone(),
+        egress: config.egress.clone(),
+        labels: config.labels.iter().cloned().collect(),
+        version: Some(env!("CARGO_PKG_VERSION").to_string()),
+    }
+}
+
+/// 按配置创建探测上游的HTTP客户端
+fn build_http_client(config: &Config) -> reqwest::Result<reqwest::Client> {
+    let builder = http_client_builder(config);
+    if !config.dns_servers.is_empty() {
+        info!("使用自定义DNS服务器: {:?}", config.dns_servers);
+        let resolver = CustomResolver::udp(config.dns_servers.clone());
+        return builder.dns_resolver(Arc::new(resolver)).build();
+    }
+    if let Some(url) = &config.doh {
+        info!("使用 DNS over HTTPS: {}", url);
+        // 访问DoH服务本身使用系统DNS（或固定解析）
+        let resolver = CustomResolver::doh(url.clone(), http_client_builder(config).build()?);
+        return builder.dns_resolver(Arc::new(resolver)).build();
+    }
+    builder.build()
+}
+
+/// 超时、连接池和固定解析等公共设置
+fn http_client_builder(config: &Config) -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.request_timeout))
+        .connect_timeout(Duration::from_secs(config.connect_timeout))
+        .pool_idle_timeout(
+            (config.pool_idle_timeout > 0).then(|| Duration::from_secs(config.pool_idle_timeout)),
+        )
+        .tcp_keepalive(config.tcp_keepalive.map(Duration::from_secs));
+    if let Some(max_idle) = config.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if config.http2 {
+        builder = builder.http2_prior_knowledge();
+    } else if config.http1_only {
+        builder = builder.http1_only();
+    }
+
+    // 同一个主机名可以固定到多个IP
+    let mut pins: BTreeMap<&str, Vec<SocketAddr>> = BTreeMap::new();
+    for pin in &config.resolve {
+        pins.entry(&pin.host)
+            .or_default()
+            .push(SocketAddr::new(pin.addr, 0));
+    }
+    for (host, addrs) in pins {
+        info!("固定解析: {} -> {:?}", host, addrs);
+        builder = builder.resolve_to_addrs(host, &addrs);
+    }
+    builder
+}
+
+/// 设置信号处理器
+async fn setup_signal_handler(state: &Arc<WorkerState>) {
+    let mut first_signal = true;
+
+    loop {
+        tokio::signal::ctrl_c().await.expect("无法监听ctrl+c信号");
+
+        if first_signal {
+            first_signal = false;
+            info!("收到第一次 ctrl+c，准备优雅退出...");
+            info!("再次按 ctrl+c 将强制退出并释放当前任务");
+            state.shutdown_requested.store(true, Ordering::SeqCst);
+        } else {
+            warn!("收到第二次 ctrl+c，强制退出！");
+            state.force_shutdown.store(true, Ordering::SeqCst);
+
+            // 释放正在执行的任务
+            let task_ids = state.running_tasks.lock().expect("任务列表锁中毒").clone();
+            for task_id in task_ids {
+                info!("正在释放任务 {}...", task_id);
+                if let Err(e) = release_task(state, task_id).await {
+                    error!("释放任务 {} 失败: {}", task_id, e);
+                } else {
+                    info!("任务 {} 已释放", task_id);
+                }
+            }
+
+            std::process::exit(1);
+        }
+    }
+}
+
+/// 向Master释放任务
+async fn release_task(
+    state: &Arc<WorkerState>,
+    task_id: i32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = ReleaseTaskRequest::new(task_id, state.worker_id.clone());
+
+    let _: String = state
+        .master
+        .post_with(
+            routes::task::RELEASE,
+            &request,
+            CallOptions::default().idempotent(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// 执行一个任务并提交结果，返回结果是否被Master接受（任务被收回或已重新分配时为 false）
+async fn run_task(
+    config: &Config,
+    state: &Arc<WorkerState>,
+    task: AcquireTaskResponse,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    // 1. 任务信息
+    if task.is_list() {
+        info!(
+            "任务已获取: task_id={}, 显式ID列表 {} 个（范围 [{}, {}]）",
+            task.task_id,
+            task.ids.len(),
+            task.start_id,
+            task.end_id
+        );
+    } else if let Some(secs) = task.scan_for_secs {
+        info!(
+            "任务已获取: task_id={}, 限时任务，从 {} 起扫描 {} 秒（最多到 {}）",
+            task.task_id, task.start_id, secs, task.end_id
+        );
+    } else {
+        info!(
+            "任务已获取: task_id={}, 范围=[{}, {}]",
+            task.task_id, task.start_id, task.end_id
+        );
+    }
+
+    // 本任务已完成探测的ID数，心跳据此计算实时速度
+    let probed_ids = Arc::new(AtomicU64::new(0));
+    update_rate_limit(state, task.rate_limit);
+
+    // 2. 启动后台心跳任务（Master通知任务已被收回时结束）
+    let mut heartbeat_handle = {
+        let config = config.clone();
+        let state = Arc::clone(state);
+        let task_id = task.task_id;
+        let probed_ids = Arc::clone(&probed_ids);
+
+        tokio::spawn(
+            async move {
+                heartbeat_loop(&config, &state, task_id, &probed_ids).await;
+            }
+            .instrument(info_span!("heartbeat", task_id)),
+        )
+    };
+
+    // 启用提前上报时，后台定期把新发现的有效ID发给Master
+    let hits = (config.stream_interval > 0).then(|| Arc::new(HitBuffer::default()));
+    let stream_handle = hits.as_ref().map(|hits| {
+        let config = config.clone();
+        let state = Arc::clone(state);
+        let task = task.clone();
+        let task_id = task.task_id;
+        let hits = Arc::clone(hits);
+
+        tokio::spawn(
+            async move {
+                stream_results_loop(&config, &state, &task, &hits).await;
+            }
+            .instrument(info_span!("stream", task_id)),
+        )
+    });
+
+    // 3. 执行任务，任务被收回时立即停止扫描，不再提交
+    let start_time = Instant::now();
+    let scan = execute_task(config, state, &task, hits.as_deref(), &probed_ids)
+        .instrument(info_span!("scan", task_id = task.task_id));
+    let scanned = tokio::select! {
+        result = scan => Some(result),
+        _ = &mut heartbeat_handle => None,
+    };
+    let elapsed = start_time.elapsed();
+
+    // 4. 停止心跳和提前上报任务（剩余的有效ID随最终提交一起发送）
+    heartbeat_handle.abort();
+    if let Some(handle) = stream_handle {
+        handle.abort();
+    }
+    let Some(mut output) = scanned else {
+        warn!("任务 {} 已不属于本Worker，停止扫描", task.task_id);
+        return Ok(false);
+    };
+
+    // 检查是否被强制退出
+    if state.force_shutdown.load(Ordering::SeqCst) {
+        return Err("强制退出".into());
+    }
+
+    // 5. 计算并更新处理速度（限时任务按实际扫描到的位置计算）
+    let total_ids = output
+        .scanned_to
+        .map_or(task.id_count(), |scanned_to| scanned_to - task.start_id + 1)
+        as u32;
+    let new_speed = if elapsed.as_secs() > 0 {
+        total_ids / elapsed.as_secs() as u32
+    } else {
+        total_ids
+    };
+
+    {
+        let mut speed = state.current_speed.write().await;
+        *speed = new_speed;
+    }
+
+    info!(
+        "任务完成: task_id={}, 总ID数={}, 有效ID数={}, 耗时={:.2}s, 速度={} req/s",
+        task.task_id,
+        total_ids,
+        output.valid_ids.len(),
+        elapsed.as_secs_f32(),
+        new_speed
+    );
+
+    // 6. 上传原始响应，再提交结果
+    let responses = std::mem::take(&mut output.responses);
+    if !responses.is_empty() {
+        upload_responses(state, &task, responses)
+            .instrument(info_span!("archive", task_id = task.task_id))
+            .await;
+    }
+    submit_result(config, state, &task, output)
+        .instrument(info_span!("submit", task_id = task.task_id))
+        .await
+}
+
+/// 从Master获取最多 `max_tasks` 个任务（旧版本Master只分配一个）
+async fn acquire_task(
+    config: &Config,
+    state: &Arc<WorkerState>,
+    max_tasks: u32,
+) -> Result<Vec<AcquireTaskResponse>, Box<dyn std::error::Error>> {
+    // 获取当前处理速度
+    let current_speed = *state.current_speed.read().await;
+
+    // 有每日请求预算时，请求的范围合计不超过剩余预算
+    let remaining = state.budget.as_ref().map(|budget| {
+        let per_task = budget.remaining() / u64::from(max_tasks.max(1));
+        i64::try_from(per_task.max(1)).unwrap_or(i64::MAX)
+    });
+    let max_batch_size = match (config.max_batch_size, remaining) {
+        (Some(max), Some(remaining)) => Some(max.min(remaining)),
+        (max, remaining) => max.or(remaining),
+    };
+
+    let request = AcquireTaskRequest::new(state.worker_id.clone())
+        .with_performance(current_speed)
+        .with_error_rate(*state.last_error_rate.read().await)
+        .with_max_batch_size(max_batch_size)
+        .with_profile(state.profile.clone())
+        .with_max_tasks(max_tasks);
+
+    // 重复获取会分配到新的任务，只在连接失败时重试
+    let task: AcquireTaskResponse = state.master.post(routes::task::ACQUIRE, &request).await?;
+    Ok(task.into_tasks())
+}
+
+/// 定期发送心跳，Master通知任务已不属于本Worker时返回
+async fn heartbeat_loop(
+    config: &Config,
+    state: &Arc<WorkerState>,
+    task_id: i32,
+    probed_ids: &AtomicU64,
+) {
+    let interval = Duration::from_secs(config.heartbeat_interval);
+    let mut last = (Instant::now(), probed_ids.load(Ordering::Relaxed));
+
+    loop {
+        sleep(interval).await;
+
+        // 自上次心跳以来的实际速度
+        let now = (Instant::now(), probed_ids.load(Ordering::Relaxed));
+        let elapsed = now.0.duration_since(last.0).as_secs_f64();
+        let speed = (elapsed > 0.0).then(|| now.1.saturating_sub(last.1) as f64 / elapsed);
+        last = now;
+
+        let request = HeartbeatRequest::new(task_id, state.worker_id.clone())
+            .with_speed(speed)
+            .with_system(config.report_system.then(system::sample).flatten());
+
+        let heartbeat = state
+            .master
+            .post_with::<_, HeartbeatResponse>(
+                routes::task::HEARTBEAT,
+                &request,
+                CallOptions::default().idempotent(),
+            )
+            .await;
+        match heartbeat {
+            Ok(heartbeat) if heartbeat.abort => {
+                warn!(
+                    "Master通知任务 {} 已被收回: {}",
+                    task_id,
+                    heartbeat.reason.unwrap_or_default()
+                );
+                return;
+            }
+            Ok(heartbeat) => {
+                info!("任务 {} 的心跳已发送", task_id);
+                update_rate_limit(state, heartbeat.rate_limit);
+            }
+            // 维护期间Master不会因为缺少心跳收回任务
+            Err(e) if e.is_maintenance() => debug!("Master维护中，心跳未发送"),
+            Err(e) => warn!("心跳发送失败: {}", e),
+        }
+    }
+}
+
+/// 定期把新发现的有效ID上报给Master，任务结束时由调用方中止
+async fn stream_results_loop(
+    config: &Config,
+    state: &Arc<WorkerState>,
+    task: &AcquireTaskResponse,
+    hits: &HitBuffer,
+) {
+    let interval = Duration::from_secs(config.stream_interval);
+
+    loop {
+        sleep(interval).await;
+
+        let valid_ids = hits.take();
+        if valid_ids.is_empty() {
+            continue;
+        }
+
+        let request = PartialResultRequest::new(task, state.worker_id.clone(), valid_ids);
+        // 重复上报的有效ID会被Master去重
+        let reported = state
+            .master
+            .post_with::<_, String>(
+                routes::task::RESULTS,
+                &request,
+                CallOptions::default().idempotent(),
+            )
+            .await;
+        match reported {
+            Ok(_) => info!(
+                "已提前上报任务 {} 的 {} 个有效ID",
+                task.task_id,
+                request.valid_ids.len()
+            ),
+            Err(e) => {
+                if e.is_maintenance() {
+                    debug!("Master维护中，有效ID下次再上报");
+                } else {
+                    warn!("提前上报有效ID失败: {}，下次重试", e);
+                }
+                hits.restore(request.valid_ids);
+            }
+        }
+    }
+}
+
+/// 应用Master分配的请求速度上限
+fn update_rate_limit(state: &WorkerState, rate_limit: Option<f64>) {
+    if state.pacer.rate() == rate_limit {
+        return;
+    }
+    match rate_limit {
+        Some(rate) => info!("Master分配的请求速度上限: {:.2} req/s", rate),
+        None => info!("Master取消了请求速度上限"),
+    }
+    state.pacer.set_rate(rate_limit);
+}
+
+/// 执行扫描任务
+///
+/// 分为三段，之间用有界通道连接，下游处理不过来时上游自然等待：
+/// 1. 生产者按顺序产出范围内的ID
+/// 2. 固定数量的探测协程从通道取ID探测，共用同一个HTTP客户端（同时执行的任务共用探测并发，见 [`ProbeSlots`]）
+/// 3. 收集者记录探测覆盖、汇总有效ID
+async fn execute_task(
+    config: &Config,
+    state: &Arc<WorkerState>,
+    task: &AcquireTaskResponse,
+    hits: Option<&HitBuffer>,
+    probed_ids: &AtomicU64,
+) -> ScanOutput {
+    // 任务级别的重试计数器和探测延迟
+    let task_retry_count = AtomicU32::new(0);
+    let latency = LatencyRecorder::default();
+
+    if let Some(target) = &task.target {
+        info!(
+            "本任务探测目标: {} ({} {})，ID类型: {}",
+            target.label(),
+            target.method,
+            target.url,
+            task.id_kind
+        );
+    }
+
+    // Master的建议针对整个Worker，调整所有任务共用的并发上限
+    let concurrency = state.probe_slots.set_suggested(task.suggested_concurrency);
+    if concurrency < config.concurrency {
+        info!(
+            "按Master建议将并发数从 {} 降为 {}",
+            config.concurrency, concurrency
+        );
+    }
+
+    let (id_tx, id_rx) = mpsc::channel::<i64>(concurrency * PIPELINE_DEPTH);
+    let id_rx = Mutex::new(id_rx);
+    let (result_tx, mut result_rx) = mpsc::channel::<(i64, Probed)>(concurrency * PIPELINE_DEPTH);
+
+    // 1. 生产者：收到强制退出信号时停止产出；限时任务到时后停止产出。返回最后产出的ID
+    let deadline = task
+        .scan_for_secs
+        .map(|secs| Instant::now() + Duration::from_secs(secs));
+    let producer = async move {
+        let mut last = None;
+        for id in task.scan_ids() {
+            // 至少产出一个ID，限时任务的扫描位置不会落在范围之前
+            if last.is_some() && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            if state.force_shutdown.load(Ordering::SeqCst) || id_tx.send(id).await.is_err() {
+                break;
+            }
+            last = Some(id);
+        }
+        last
+    };
+
+    // 2. 探测协程：通道关闭（生产者结束）后退出
+    let probers = futures::future::join_all((0..concurrency).map(|_| {
+        let result_tx = result_tx.clone();
+        let id_rx = &id_rx;
+        let task_retry_count = &task_retry_count;
+        let latency = &latency;
+        async move {
+            loop {
+                let Some(id) = id_rx.lock().await.recv().await else {
+                    break;
+                };
+                let slot = state.probe_slots.acquire().await;
+                let probed = probe_with_retry(
+                    state,
+                    task,
+                    config.max_id_retries,
+                    task_retry_count,
+                    latency,
+                    id,
+                )
+                .await;
+                drop(slot);
+                let Some(probed) = probed else {
+                    // 强制退出：关闭ID通道，避免生产者阻塞在发送上
+                    id_rx.lock().await.close();
+                    break;
+                };
+                if result_tx.send((id, probed)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }));
+    // 只保留探测协程持有的发送端，全部退出后收集者才能结束
+    drop(result_tx);
+
+    // 3. 收集者：记录实际探测过的ID和有效ID
+    let collector = async {
+        let mut valid_ids = Vec::new();
+        let mut marked = Vec::new();
+        let mut responses = Vec::new();
+        let mut coverage = Coverage::default();
+        while let Some((id, probed)) = result_rx.recv().await {
+            let status = probed.status;
+            coverage.record(id, status);
+            if let Some(label) = probed.marker {
+                info!("ID {} 命中响应标记: {}", id, label);
+                marked.push(MarkedId { id, label });
+            }
+            if let Some(raw) = probed.response.filter(|_| config.archive_responses) {
+                match archive::encode(id, &raw) {
+                    Ok(response) => responses.push(response),
+                    Err(e) => warn!("压缩ID {} 的原始响应失败: {}", id, e),
+                }
+            }
+            probed_ids.fetch_add(1, Ordering::Relaxed);
+            if status == IdStatus::Valid {
+                info!("发现有效ID: {}", id);
+                valid_ids.push(id);
+                if let Some(hits) = hits {
+                    hits.push(id);
+                }
+            }
+        }
+        (valid_ids, marked, responses, coverage)
+    };
+
+    // 4. 定期输出进度，扫描结束时随之结束
+    let scan = async { tokio::join!(producer, probers, collector) };
+    let (last_sent, _, (valid_ids, marked, responses, coverage)) = if config.progress_interval > 0 {
+        let total = task.id_count() as u64;
+        // 同时执行多个任务时进度条会互相覆盖，改为输出日志
+        let bar = config.progress_bar && config.concurrent_tasks.get() == 1;
+        let mut progress = Progress::new(task.task_id, total, bar);
+        let report = async {
+            let interval = Duration::from_secs(config.progress_interval);
+            loop {
+                sleep(interval).await;
+                progress.report(
+                    probed_ids.load(Ordering::Relaxed),
+                    task_retry_count.load(Ordering::Relaxed),
+                );
+            }
+        };
+        let result = tokio::select! {
+            result = scan => result,
+            _ = report => unreachable!("进度输出不会结束"),
+        };
+        progress.finish();
+        result
+    } else {
+        scan.await
+    };
+
+    // 输出任务总重试次数，并记录错误率供下次获取任务时上报
+    let total_retries = task_retry_count.load(Ordering::SeqCst);
+    if total_retries > 0 {
+        info!("任务 {} 完成，总重试次数: {}", task.task_id, total_retries);
+    }
+    if coverage.unchecked() > 0 {
+        warn!(
+            "任务 {} 中有 {} 个ID没有得到结论（重试耗尽 {} 个，被上游拒绝 {} 个）",
+            task.task_id,
+            coverage.unchecked(),
+            coverage.unknown,
+            coverage.blocked
+        );
+    }
+    let requests = coverage.probed() + u64::from(total_retries);
+    if requests > 0 {
+        *state.last_error_rate.write().await = Some(f64::from(total_retries) / requests as f64);
+    }
+
+    let latency = latency.stats();
+    if let Some(latency) = &latency {
+        info!(
+            "任务 {} 探测延迟: p50={}ms, p90={}ms, p99={}ms, max={}ms",
+            task.task_id, latency.p50_ms, latency.p90_ms, latency.p99_ms, latency.max_ms
+        );
+    }
+
+    // 产出的ID都已探测完，限时任务扫描到最后产出的ID
+    let scanned_to = deadline.and(last_sent);
+    if let Some(scanned_to) = scanned_to {
+        info!(
+            "限时任务 {} 扫描到 {}（预留到 {}）",
+            task.task_id, scanned_to, task.end_id
+        );
+    }
+
+    ScanOutput {
+        valid_ids,
+        marked,
+        responses,
+        coverage,
+        latency,
+        scanned_to,
+    }
+}
+
+/// 探测单个ID，appId 不匹配、超时或被上游拒绝时重试；收到强制退出信号时返回 None
+///
+/// 任务携带探测目标时按目标和ID类型探测，否则探测内置的上游。
+/// 重试 `max_retries` 次（0 表示不限）仍没有结论时，按最后一次的结果记为被拒绝或未确认。
+/// 命中响应标记的ID记为无效，同时返回标记名称；有效ID同时返回原始响应体。
+async fn probe_with_retry(
+    state: &WorkerState,
+    task: &AcquireTaskResponse,
+    max_retries: u32,
+    task_retry_count: &AtomicU32,
+    latency: &LatencyRecorder,
+    id: i64,
+) -> Option<Probed> {
+    // 单个ID的重试计数
+    let mut id_retry_count: u32 = 0;
+
+    loop {
+        if state.force_shutdown.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        state.pacer.wait().await;
+        if let Some(budget) = &state.budget {
+            budget.record();
+        }
+        let sent_at = Instant::now();
+        let result = match &task.target {
+            Some(target) => {
+                let formatted = task.id_kind.format(id);
+                probe::check_target(&state.client, target, &formatted, id).await
+            }
+            None => probe::check_id(&state.client, id).await,
+        };
+        latency.record(sent_at.elapsed());
+        let status = match result {
+            Outcome::Valid(raw) => {
+                return Some(Probed {
+                    response: Some(raw),
+                    ..Probed::new(IdStatus::Valid)
+                })
+            }
+            Outcome::Invalid => return Some(Probed::new(IdStatus::Invalid)),
+            Outcome::Marked(label) => {
+                return Some(Probed {
+                    marker: Some(label),
+                    ..Probed::new(IdStatus::Invalid)
+                })
+            }
+            Outcome::Retry => IdStatus::Unknown,
+            Outcome::Blocked => IdStatus::Blocked,
+        };
+        if max_retries > 0 && id_retry_count >= max_retries {
+            warn!(
+                "ID {} 重试 {} 次仍没有结论，记为{}",
+                id,
+                id_retry_count,
+                if status == IdStatus::Blocked {
+                    "被上游拒绝"
+                } else {
+                    "未确认"
+                }
+            );
+            return Some(Probed::new(status));
+        }
+        match status {
+            IdStatus::Blocked => {
+                // 被上游拒绝，稍等后再重试
+                id_retry_count += 1;
+                task_retry_count.fetch_add(1, Ordering::SeqCst);
+                warn!(
+                    "ID {} 检查时被上游拒绝，第 {} 次重试...",
+                    id, id_retry_count
+                );
+                sleep(BLOCKED_BACKOFF).await;
+            }
+            _ => {
+                // appId 不匹配或请求超时，需要重试
+                id_retry_count += 1;
+                task_retry_count.fetch_add(1, Ordering::SeqCst);
+                warn!(
+                    "ID {} 检查时 appId 不匹配或超时，第 {} 次重试...",
+                    id, id_retry_count
+                );
+            }
+        }
+    }
+}
+
+/// 按批上传有效ID的原始响应，失败只记录日志
+async fn upload_responses(
+    state: &Arc<WorkerState>,
+    task: &AcquireTaskResponse,
+    responses: Vec<RawResponse>,
+) {
+    let total = responses.len();
+    let mut uploaded = 0;
+    for batch in archive::batches(responses) {
+        let request = ArchiveResponsesRequest::new(task, state.worker_id.clone(), batch);
+        // 同一个ID重复上传只会覆盖原来的存档
+        let result = wait_out_maintenance(|| {
+            state.master.post_with::<_, String>(
+                routes::task::RESPONSES,
+                &request,
+                CallOptions::default().idempotent(),
+            )
+        })
+        .await;
+        match result {
+            Ok(_) => uploaded += request.responses.len(),
+            Err(ClientError::Api {
+                code: Some(ErrorCode::NotFound),
+                message,
+                ..
+            }) => {
+                warn!("Master没有接受原始响应: {}", message);
+                return;
+            }
+            Err(e) => warn!(
+                "上传任务 {} 的 {} 个原始响应失败: {}",
+                task.task_id,
+                request.responses.len(),
+                e
+            ),
+        }
+    }
+    info!(
+        "已上传任务 {} 的原始响应 {}/{} 个",
+        task.task_id, uploaded, total
+    );
+}
+
+/// 调用Master，维护期间按 `Retry-After` 等待后重试，直到维护结束
+///
+/// 维护期间任务租约会被顺延，已完成的任务等维护结束后再上传和提交，不需要重新扫描。
+async fn wait_out_maintenance<T, F, Fut>(mut call: F) -> Result<T, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, ClientError>>,
+{
+    let mut waited = false;
+    loop {
+        match call().await {
+            Err(e) if e.is_maintenance() => {
+                let wait = e.retry_after().unwrap_or(MAINTENANCE_RETRY);
+                if waited {
+                    debug!("Master仍在维护，{} 秒后重试", wait.as_secs());
+                } else {
+                    info!(
+                        "{}，等待维护结束后继续，每 {} 秒重试一次",
+                        e,
+                        wait.as_secs()
+                    );
+                    waited = true;
+                }
+                sleep(wait).await;
+            }
+            result => {
+                if waited {
+                    info!("Master维护已结束");
+                }
+                return result;
+            }
+        }
+    }
+}
+
+/// 向Master提交结果，返回结果是否被接受（任务已归其他Worker时为 false）
+async fn submit_result(
+    config: &Config,
+    state: &Arc<WorkerState>,
+    task: &AcquireTaskResponse,
+    output: ScanOutput,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let request = SubmitResultRequest::new(task, state.worker_id.clone(), output.valid_ids)
+        .with_coverage(output.coverage)
+        .with_latency(output.latency)
+        // 重试时使用同一个幂等键，首次提交其实已成功时Master会返回原结果
+        .with_idempotency_key(uuid::Uuid::new_v4().to_string())
+        .with_marked(output.marked)
+        .with_scanned_to(output.scanned_to);
+
+    // 带幂等键，可以安全重试
+    let retry = RetryPolicy {
+        max_attempts: SUBMIT_ATTEMPTS,
+        base_delay: Duration::from_secs(config.retry_interval),
+        max_delay: Duration::from_secs(config.retry_interval).max(SUBMIT_MAX_DELAY),
+    };
+    let options = CallOptions::default().idempotent().retry(retry);
+    let submitted = wait_out_maintenance(|| {
+        state
+            .master
+            .post_with::<_, String>(routes::task::SUBMIT, &request, options)
+    })
+    .await;
+    match submitted {
+        Ok(_) => {}
+        Err(ClientError::Api {
+            code: Some(ErrorCode::TaskConflict),
+            message,
+            ..
+        }) => {
+            // 任务已归其他Worker，本次结果作废，重试也不会成功
+            warn!("任务 {} 的结果未被接受: {}", task.task_id, message);
+            return Ok(false);
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    info!("任务 {} 提交成功", task.task_id);
+    Ok(true)
+}
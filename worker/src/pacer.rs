@@ -0,0 +1,51 @@
+//! 上游请求限速
+//!
+//! Master按全局速度上限给每个Worker分配份额（获取任务和心跳响应中的 `rate_limit`），
+//! 这里把并发的探测请求均匀地排开，使本Worker的请求速度不超过份额。
+
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// 请求节拍器
+#[derive(Default)]
+pub struct Pacer {
+    state: Mutex<PacerState>,
+}
+
+#[derive(Default)]
+struct PacerState {
+    /// 速度上限（req/s），None 表示不限速
+    rate: Option<f64>,
+    /// 下一个请求最早的发出时间
+    next: Option<Instant>,
+}
+
+impl Pacer {
+    /// 更新速度上限（req/s），None 或非正数表示不限速
+    pub fn set_rate(&self, rate: Option<f64>) {
+        let rate = rate.filter(|rate| rate.is_finite() && *rate > 0.0);
+        self.state.lock().expect("限速锁中毒").rate = rate;
+    }
+
+    /// 当前的速度上限
+    pub fn rate(&self) -> Option<f64> {
+        self.state.lock().expect("限速锁中毒").rate
+    }
+
+    /// 等到可以发出下一个请求
+    pub async fn wait(&self) {
+        let slot = {
+            let mut state = self.state.lock().expect("限速锁中毒");
+            let Some(rate) = state.rate else {
+                return;
+            };
+            let interval = Duration::from_secs_f64(1.0 / rate);
+            let now = Instant::now();
+            let slot = state.next.map_or(now, |next| next.max(now));
+            state.next = Some(slot + interval);
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
@@ -0,0 +1,118 @@
+//! 所有任务共用的探测并发
+//!
+//! 同时执行多个任务（`--concurrent-tasks`）时，`-c` 是所有任务合计的探测并发数。
+//! Master给出的建议并发数（`suggested_concurrency`）针对整个Worker，同样作用于合计的上限，
+//! 而不是每个任务各自的上限。上限调低时正在使用的并发位在归还时收回。
+
+use std::sync::Mutex;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// 探测并发位
+pub struct ProbeSlots {
+    semaphore: Semaphore,
+    /// `-c` 设置的上限
+    max: usize,
+    state: Mutex<SlotsState>,
+}
+
+struct SlotsState {
+    /// 当前的上限
+    limit: usize,
+    /// 上限调低时还没有收回的并发位数（正在使用，归还时收回）
+    debt: usize,
+}
+
+impl ProbeSlots {
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            semaphore: Semaphore::new(max),
+            max,
+            state: Mutex::new(SlotsState {
+                limit: max,
+                debt: 0,
+            }),
+        }
+    }
+
+    /// 按Master的建议调整上限（不超过 `-c`，None 表示恢复到 `-c`），返回调整后的上限
+    pub fn set_suggested(&self, suggested: Option<usize>) -> usize {
+        let limit = suggested.map_or(self.max, |suggested| suggested.clamp(1, self.max));
+        let mut state = self.state.lock().expect("探测并发锁中毒");
+        if limit > state.limit {
+            let grow = limit - state.limit;
+            let repaid = grow.min(state.debt);
+            state.debt -= repaid;
+            self.semaphore.add_permits(grow - repaid);
+        } else if limit < state.limit {
+            let shrink = state.limit - limit;
+            let forgotten = self.semaphore.forget_permits(shrink);
+            state.debt += shrink - forgotten;
+        }
+        state.limit = limit;
+        limit
+    }
+
+    /// 等到有空闲的并发位
+    pub async fn acquire(&self) -> ProbeSlot<'_> {
+        let permit = self.semaphore.acquire().await.expect("探测并发不会关闭");
+        ProbeSlot {
+            slots: self,
+            permit: Some(permit),
+        }
+    }
+}
+
+/// 占用中的并发位，释放时归还（上限已调低时收回）
+pub struct ProbeSlot<'a> {
+    slots: &'a ProbeSlots,
+    permit: Option<SemaphorePermit<'a>>,
+}
+
+impl Drop for ProbeSlot<'_> {
+    fn drop(&mut self) {
+        let mut state = self.slots.state.lock().expect("探测并发锁中毒");
+        let Some(permit) = self.permit.take() else {
+            return;
+        };
+        if state.debt > 0 {
+            state.debt -= 1;
+            permit.forget();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn suggestion_limits_shared_slots() {
+        let slots = ProbeSlots::new(4);
+        assert_eq!(slots.set_suggested(Some(2)), 2);
+        assert_eq!(slots.semaphore.available_permits(), 2);
+        assert_eq!(slots.set_suggested(Some(100)), 4);
+        assert_eq!(slots.semaphore.available_permits(), 4);
+        assert_eq!(slots.set_suggested(Some(0)), 1);
+        assert_eq!(slots.set_suggested(None), 4);
+        assert_eq!(slots.semaphore.available_permits(), 4);
+    }
+
+    #[tokio::test]
+    async fn slots_in_use_are_reclaimed_on_release() {
+        let slots = ProbeSlots::new(3);
+        let held: Vec<_> = futures::future::join_all((0..3).map(|_| slots.acquire())).await;
+        assert_eq!(slots.set_suggested(Some(1)), 1);
+        drop(held);
+        assert_eq!(slots.semaphore.available_permits(), 1);
+
+        // 还没收回时又调高上限，先抵消未收回的数量
+        let held: Vec<_> = futures::future::join_all((0..1).map(|_| slots.acquire())).await;
+        slots.set_suggested(Some(3));
+        let more: Vec<_> = futures::future::join_all((0..2).map(|_| slots.acquire())).await;
+        slots.set_suggested(Some(1));
+        slots.set_suggested(Some(2));
+        drop((held, more));
+        assert_eq!(slots.semaphore.available_permits(), 2);
+    }
+}
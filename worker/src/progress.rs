@@ -0,0 +1,90 @@
+//! 任务内的进度输出
+//!
+//! 一个五万ID的任务可能要跑好几分钟，期间除了发现有效ID外没有任何输出。
+//! 扫描时每隔 `--progress-interval` 秒输出一次进度：已探测/总数、最近的速度、预计剩余时间和重试次数。
+//! 指定 `--progress-bar` 且标准错误是终端时，改为在同一行刷新的进度条。
+
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// 进度条的宽度（字符数）
+const BAR_WIDTH: usize = 30;
+
+/// 一个任务的进度
+pub struct Progress {
+    task_id: i32,
+    total: u64,
+    /// 是否在终端中绘制进度条
+    bar: bool,
+    /// 上一次输出时的时间和已探测数
+    last: (Instant, u64),
+}
+
+impl Progress {
+    /// `bar` 为 true 且标准错误是终端时绘制进度条，否则输出日志
+    pub fn new(task_id: i32, total: u64, bar: bool) -> Self {
+        Self {
+            task_id,
+            total,
+            bar: bar && std::io::stderr().is_terminal(),
+            last: (Instant::now(), 0),
+        }
+    }
+
+    /// 输出当前进度
+    pub fn report(&mut self, probed: u64, retries: u32) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last.0).as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            probed.saturating_sub(self.last.1) as f64 / elapsed
+        } else {
+            0.0
+        };
+        self.last = (now, probed);
+
+        let remaining = self.total.saturating_sub(probed);
+        let eta = if rate > 0.0 {
+            humantime::format_duration(Duration::from_secs((remaining as f64 / rate) as u64))
+                .to_string()
+        } else {
+            "-".to_string()
+        };
+        let percent = if self.total > 0 {
+            probed as f64 * 100.0 / self.total as f64
+        } else {
+            100.0
+        };
+
+        if self.bar {
+            let filled = (percent / 100.0 * BAR_WIDTH as f64) as usize;
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(
+                stderr,
+                "\r任务 {} [{}{}] {:5.1}% {}/{} {:.1}/s 剩余 {} 重试 {}\x1b[K",
+                self.task_id,
+                "#".repeat(filled.min(BAR_WIDTH)),
+                ".".repeat(BAR_WIDTH - filled.min(BAR_WIDTH)),
+                percent,
+                probed,
+                self.total,
+                rate,
+                eta,
+                retries
+            );
+            let _ = stderr.flush();
+        } else {
+            info!(
+                "任务 {} 进度: {}/{} ({:.1}%)，速度 {:.1}/s，预计剩余 {}，重试 {} 次",
+                self.task_id, probed, self.total, percent, rate, eta, retries
+            );
+        }
+    }
+
+    /// 任务结束，进度条换行
+    pub fn finish(&self) {
+        if self.bar {
+            eprintln!();
+        }
+    }
+}
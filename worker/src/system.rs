@@ -0,0 +1,71 @@
+//! 主机系统状态采样
+//!
+//! 启用 `--report-system` 后，每次心跳从 `/proc` 读取负载、内存、交换空间和网卡错误计数随心跳上报，
+//! Master据此在集群概览中标出负载过高、开始使用交换空间或网卡持续出错的机器。
+//! 非 Linux 系统读取不到 `/proc` 时不上报。
+
+use common::SystemSample;
+
+/// 采样当前的系统状态，读取失败时返回 None
+pub fn sample() -> Option<SystemSample> {
+    let load1 = std::fs::read_to_string("/proc/loadavg")
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let (net_errors, net_drops) = std::fs::read_to_string("/proc/net/dev")
+        .map(|dev| net_counters(&dev))
+        .unwrap_or_default();
+
+    Some(SystemSample {
+        load1,
+        cpus: std::thread::available_parallelism()
+            .map(|n| n.get() as u32)
+            .unwrap_or(1),
+        mem_total_kb: meminfo_kb(&meminfo, "MemTotal")?,
+        mem_available_kb: meminfo_kb(&meminfo, "MemAvailable")?,
+        swap_used_kb: meminfo_kb(&meminfo, "SwapTotal")?
+            .saturating_sub(meminfo_kb(&meminfo, "SwapFree")?),
+        net_errors,
+        net_drops,
+    })
+}
+
+/// 读取 /proc/meminfo 中的一项（单位 KB）
+fn meminfo_kb(meminfo: &str, key: &str) -> Option<u64> {
+    meminfo.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name != key {
+            return None;
+        }
+        value.split_whitespace().next()?.parse().ok()
+    })
+}
+
+/// 汇总 /proc/net/dev 中除 lo 以外所有网卡的（收发错误数，丢包数）
+///
+/// 每行格式：`iface: rx_bytes rx_packets rx_errs rx_drop ... tx_bytes tx_packets tx_errs tx_drop ...`
+fn net_counters(dev: &str) -> (u64, u64) {
+    let mut errors = 0;
+    let mut drops = 0;
+    for line in dev.lines().skip(2) {
+        let Some((iface, counters)) = line.split_once(':') else {
+            continue;
+        };
+        if iface.trim() == "lo" {
+            continue;
+        }
+        let counters: Vec<u64> = counters
+            .split_whitespace()
+            .map(|value| value.parse().unwrap_or(0))
+            .collect();
+        if counters.len() < 12 {
+            continue;
+        }
+        errors += counters[2] + counters[10];
+        drops += counters[3] + counters[11];
+    }
+    (errors, drops)
+}